@@ -27,6 +27,7 @@ use tokio::{runtime, runtime::Runtime};
 
 use tari_common::{CommsTransport, GlobalConfig, SocksAuthentication, TorControlAuthentication};
 use tari_comms::{
+    connection_manager::ConnectionManagerError,
     connectivity::ConnectivityError,
     peer_manager::{NodeId, PeerManagerError},
     protocol::rpc::RpcError,
@@ -38,7 +39,10 @@ use tari_comms::{
     utils::multiaddr::multiaddr_to_socketaddr,
 };
 use tari_core::tari_utilities::hex::Hex;
-use tari_p2p::transport::{TorConfig, TransportType};
+use tari_p2p::{
+    transport::{TorConfig, TransportType},
+    DnsClientError,
+};
 
 use crate::identity_management::load_from_json;
 use tari_common_types::emoji::EmojiId;
@@ -76,6 +80,14 @@ pub enum ExitCodes {
     NoPassword,
     #[error("Tor connection is offline")]
     TorOffline,
+    #[error("Unable to resolve DNS seeds: {0}")]
+    DnsResolutionError(String),
+    #[error("No peers could be reached: {0}")]
+    NoPeersReachable(String),
+    #[error("Unable to bind the network transport: {0}")]
+    TransportBindError(String),
+    #[error("The database migration failed: {0}")]
+    MigrationError(String),
 }
 
 impl ExitCodes {
@@ -94,6 +106,74 @@ impl ExitCodes {
             Self::ConversionError(_) => 111,
             Self::IncorrectPassword | Self::NoPassword => 112,
             Self::TorOffline => 113,
+            Self::DnsResolutionError(_) => 114,
+            Self::NoPeersReachable(_) => 115,
+            Self::TransportBindError(_) => 116,
+            Self::MigrationError(_) => 118,
+        }
+    }
+
+    /// Returns true if the application may succeed if it is restarted without any changes, so that a supervisor can
+    /// decide whether to restart it automatically.
+    ///
+    /// Network, Tor, DNS, IO (including database) and unknown errors may be transient and are recoverable.
+    /// Configuration, password, input, command and database migration errors, as well as errors binding the listener
+    /// or gRPC server, require human intervention and are not recoverable.
+    pub fn is_recoverable(&self) -> bool {
+        // Every variant is listed so that new variants require a deliberate choice
+        match self {
+            Self::UnknownError |
+            Self::WalletError(_) |
+            Self::IOError(_) |
+            Self::NetworkError(_) |
+            Self::TorOffline |
+            Self::DnsResolutionError(_) |
+            Self::NoPeersReachable(_) => true,
+            Self::ConfigError(_) |
+            Self::InterfaceError |
+            Self::GrpcError(_) |
+            Self::InputError(_) |
+            Self::CommandError(_) |
+            Self::RecoveryError(_) |
+            Self::ConversionError(_) |
+            Self::IncorrectPassword |
+            Self::NoPassword |
+            Self::TransportBindError(_) |
+            Self::MigrationError(_) => false,
+        }
+    }
+
+    /// Prints the error to stderr, followed by a hint on how to resolve it where one is available
+    pub fn eprint_details(&self) {
+        use ExitCodes::*;
+        eprintln!("{}", self);
+        match self {
+            DnsResolutionError(_) => {
+                eprintln!(
+                    "Please check that you are connected to the internet and that the configured DNS name server is \
+                     reachable. Alternatively, configure static peer seeds."
+                );
+            },
+            NoPeersReachable(_) => {
+                eprintln!(
+                    "Please check your internet connection and, if you are using Tor, that the Tor proxy is running. \
+                     Your peer seeds may also be out of date."
+                );
+            },
+            TransportBindError(_) => {
+                eprintln!(
+                    "Please check that the configured listener address is valid and that the port is not already in \
+                     use, for example by another instance of this application."
+                );
+            },
+            MigrationError(_) => {
+                eprintln!(
+                    "The database could not be upgraded to the schema expected by this version of the application. \
+                     Please check that this version is compatible with your database. If you have a backup of the \
+                     database, you can restore it and roll back to the version of the application that created it."
+                );
+            },
+            _ => {},
         }
     }
 }
@@ -108,7 +188,27 @@ impl From<tari_common::ConfigError> for ExitCodes {
 impl From<ConnectivityError> for ExitCodes {
     fn from(err: ConnectivityError) -> Self {
         error!(target: LOG_TARGET, "{}", err);
-        Self::NetworkError(err.to_string())
+        match err {
+            ConnectivityError::OnlineWaitTimeout(_) => Self::NoPeersReachable(err.to_string()),
+            err => Self::NetworkError(err.to_string()),
+        }
+    }
+}
+
+impl From<ConnectionManagerError> for ExitCodes {
+    fn from(err: ConnectionManagerError) -> Self {
+        error!(target: LOG_TARGET, "{}", err);
+        match err {
+            ConnectionManagerError::ListenerBindFailed(err) => Self::TransportBindError(err),
+            err => Self::NetworkError(err.to_string()),
+        }
+    }
+}
+
+impl From<DnsClientError> for ExitCodes {
+    fn from(err: DnsClientError) -> Self {
+        error!(target: LOG_TARGET, "{}", err);
+        Self::DnsResolutionError(err.to_string())
     }
 }
 
@@ -147,6 +247,7 @@ mod wallet {
             match err {
                 NoPasswordError => ExitCodes::NoPassword,
                 IncorrectPassword => ExitCodes::IncorrectPassword,
+                DatabaseMigrationError(err) => ExitCodes::MigrationError(err),
                 e => ExitCodes::WalletError(e.to_string()),
             }
         }
@@ -307,3 +408,87 @@ pub fn either_to_node_id(either: Either<CommsPublicKey, NodeId>) -> NodeId {
         Either::Right(n) => n,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use trust_dns_client::proto::error::ProtoError;
+
+    #[test]
+    fn dns_client_error_maps_to_dns_resolution_error() {
+        let exit_code = ExitCodes::from(DnsClientError::from(ProtoError::from("no connections available")));
+        assert!(matches!(exit_code, ExitCodes::DnsResolutionError(_)));
+        assert_eq!(exit_code.as_i32(), 114);
+    }
+
+    #[test]
+    fn online_wait_timeout_maps_to_no_peers_reachable() {
+        let exit_code = ExitCodes::from(ConnectivityError::OnlineWaitTimeout(0));
+        assert!(matches!(exit_code, ExitCodes::NoPeersReachable(_)));
+        assert_eq!(exit_code.as_i32(), 115);
+
+        let exit_code = ExitCodes::from(ConnectivityError::ActorDisconnected);
+        assert!(matches!(exit_code, ExitCodes::NetworkError(_)));
+        assert_eq!(exit_code.as_i32(), 110);
+    }
+
+    #[test]
+    fn listener_bind_error_maps_to_transport_bind_error() {
+        let exit_code = ExitCodes::from(ConnectionManagerError::ListenerBindFailed(
+            "Address already in use (os error 98)".to_string(),
+        ));
+        assert!(matches!(exit_code, ExitCodes::TransportBindError(ref err) if err.contains("Address already in use")));
+        assert_eq!(exit_code.as_i32(), 116);
+
+        // Other transport errors, e.g. a failed dial, are recoverable network errors
+        let exit_code = ExitCodes::from(ConnectionManagerError::TransportError("Connection refused".to_string()));
+        assert!(matches!(exit_code, ExitCodes::NetworkError(_)));
+        assert!(exit_code.is_recoverable());
+
+        let exit_code = ExitCodes::from(ConnectionManagerError::PeerBanned);
+        assert!(matches!(exit_code, ExitCodes::NetworkError(_)));
+        assert_eq!(exit_code.as_i32(), 110);
+    }
+
+    #[cfg(feature = "wallet")]
+    #[test]
+    fn wallet_migration_error_maps_to_migration_error() {
+        use tari_wallet::error::WalletStorageError;
+
+        let exit_code = ExitCodes::from(WalletStorageError::DatabaseMigrationError(
+            "Database migration failed".to_string(),
+        ));
+        assert!(matches!(exit_code, ExitCodes::MigrationError(ref err) if err == "Database migration failed"));
+        assert_eq!(exit_code.as_i32(), 118);
+
+        let exit_code = ExitCodes::from(WalletStorageError::NoPasswordError);
+        assert!(matches!(exit_code, ExitCodes::NoPassword));
+    }
+
+    #[test]
+    fn recoverable_classification() {
+        let cases = [
+            (ExitCodes::ConfigError(String::new()), false),
+            (ExitCodes::UnknownError, true),
+            (ExitCodes::InterfaceError, false),
+            (ExitCodes::WalletError(String::new()), true),
+            (ExitCodes::GrpcError(String::new()), false),
+            (ExitCodes::InputError(String::new()), false),
+            (ExitCodes::CommandError(String::new()), false),
+            (ExitCodes::IOError(String::new()), true),
+            (ExitCodes::RecoveryError(String::new()), false),
+            (ExitCodes::NetworkError(String::new()), true),
+            (ExitCodes::ConversionError(String::new()), false),
+            (ExitCodes::IncorrectPassword, false),
+            (ExitCodes::NoPassword, false),
+            (ExitCodes::TorOffline, true),
+            (ExitCodes::DnsResolutionError(String::new()), true),
+            (ExitCodes::NoPeersReachable(String::new()), true),
+            (ExitCodes::TransportBindError(String::new()), false),
+            (ExitCodes::MigrationError(String::new()), false),
+        ];
+        for (exit_code, is_recoverable) in &cases {
+            assert_eq!(exit_code.is_recoverable(), *is_recoverable, "{:?}", exit_code);
+        }
+    }
+}
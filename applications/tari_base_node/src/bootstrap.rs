@@ -142,6 +142,7 @@ where B: BlockchainBackend + 'static
                 mempool_config,
                 self.mempool.clone(),
                 peer_message_subscriptions.clone(),
+                self.rules.clone(),
             ))
             .add_initializer(mempool_sync)
             .add_initializer(LivenessInitializer::new(
@@ -32,7 +32,7 @@ use tari_core::{
     base_node::{state_machine_service::states::StatusInfo, LocalNodeCommsInterface, StateMachineHandle},
     chain_storage::{create_lmdb_database, BlockchainDatabase, BlockchainDatabaseConfig, LMDBDatabase, Validators},
     consensus::ConsensusManager,
-    mempool::{service::LocalMempoolService, Mempool, MempoolConfig},
+    mempool::{service::LocalMempoolService, Mempool, MempoolBuilder, MempoolConfig},
     proof_of_work::randomx_factory::RandomXFactory,
     transactions::CryptoFactories,
     validation::{
@@ -65,6 +65,7 @@ pub struct BaseNodeContext {
     base_node_comms: CommsNode,
     base_node_dht: Dht,
     base_node_handles: ServiceHandles,
+    mempool: Mempool,
 }
 
 impl BaseNodeContext {
@@ -96,6 +97,11 @@ impl BaseNodeContext {
         self.base_node_handles.expect_handle()
     }
 
+    /// Returns the Mempool
+    pub fn mempool(&self) -> Mempool {
+        self.mempool.clone()
+    }
+
     /// Returns the CommsNode.
     pub fn base_node_comms(&self) -> &CommsNode {
         &self.base_node_comms
@@ -157,6 +163,7 @@ impl BaseNodeContext {
 /// Sets up and initializes the base node, creating the context and database
 /// ## Parameters
 /// `config` - The configuration for the base node
+/// `mempool_config` - The configuration for the mempool
 /// `node_identity` - The node identity information of the base node
 /// `wallet_node_identity` - The node identity information of the base node's wallet
 /// `interrupt_signal` - The signal used to stop the application
@@ -164,6 +171,7 @@ impl BaseNodeContext {
 /// Result containing the NodeContainer, String will contain the reason on error
 pub async fn configure_and_initialize_node(
     config: Arc<GlobalConfig>,
+    mempool_config: MempoolConfig,
     node_identity: Arc<NodeIdentity>,
     interrupt_signal: ShutdownSignal,
     cleanup_orphans_at_startup: bool,
@@ -188,6 +196,7 @@ pub async fn configure_and_initialize_node(
                 backend,
                 node_identity,
                 config,
+                mempool_config,
                 interrupt_signal,
                 cleanup_orphans_at_startup,
             )
@@ -205,6 +214,7 @@ pub async fn configure_and_initialize_node(
 /// `base_node_identity` - The node identity information of the base node
 /// `wallet_node_identity` - The node identity information of the base node's wallet
 /// `config` - The configuration for the base node
+/// `mempool_config` - The configuration for the mempool
 /// `interrupt_signal` - The signal used to stop the application
 /// ## Returns
 /// Result containing the BaseNodeContext, String will contain the reason on error
@@ -212,6 +222,7 @@ async fn build_node_context(
     backend: LMDBDatabase,
     base_node_identity: Arc<NodeIdentity>,
     config: Arc<GlobalConfig>,
+    mempool_config: MempoolConfig,
     interrupt_signal: ShutdownSignal,
     cleanup_orphans_at_startup: bool,
 ) -> Result<BaseNodeContext, anyhow::Error> {
@@ -250,7 +261,10 @@ async fn build_node_context(
         Box::new(TxInputAndMaturityValidator::new(blockchain_db.clone())),
         Box::new(TxConsensusValidator::new(blockchain_db.clone())),
     ]);
-    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let mempool = MempoolBuilder::new(mempool_config, Arc::new(mempool_validator))
+        .with_reorg_marker_store(Arc::new(blockchain_db.clone()))
+        .with_orphan_store(Arc::new(blockchain_db.clone()))
+        .build();
 
     //---------------------------------- Base Node  --------------------------------------------//
     debug!(target: LOG_TARGET, "Creating base node state machine.");
@@ -259,7 +273,7 @@ async fn build_node_context(
         config: &config,
         node_identity: base_node_identity,
         db: blockchain_db.clone(),
-        mempool,
+        mempool: mempool.clone(),
         rules: rules.clone(),
         factories: factories.clone(),
         interrupt_signal: interrupt_signal.clone(),
@@ -277,5 +291,6 @@ async fn build_node_context(
         base_node_comms,
         base_node_dht,
         base_node_handles,
+        mempool,
     })
 }
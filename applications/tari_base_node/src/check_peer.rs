@@ -0,0 +1,121 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::time::{Duration, Instant};
+use tari_comms::{
+    connectivity::{ConnectivityError, ConnectivityRequester},
+    peer_manager::NodeId,
+    protocol::{rpc::RpcError, ProtocolId},
+};
+use tari_core::base_node::sync::rpc::BaseNodeSyncRpcClient;
+use thiserror::Error;
+
+/// The result of a successful `check-peer`
+#[derive(Debug, Clone)]
+pub struct CheckPeerReport {
+    /// The time taken to dial the peer, or to fetch an existing connection
+    pub dial_time: Duration,
+    /// The RPC protocol negotiated with the peer
+    pub protocol: ProtocolId,
+    /// The round trip time of an RPC ping
+    pub rtt: Duration,
+}
+
+/// A `check-peer` failure, labelled with the stage at which the check failed
+#[derive(Debug, Error)]
+pub enum CheckPeerError {
+    #[error("Dial failed: {0}")]
+    Dial(#[from] ConnectivityError),
+    #[error("RPC negotiation failed: {0}")]
+    Negotiate(RpcError),
+    #[error("RPC call failed: {0}")]
+    Call(RpcError),
+}
+
+impl CheckPeerError {
+    /// Returns the name of the stage at which the check failed
+    pub fn stage(&self) -> &'static str {
+        match self {
+            CheckPeerError::Dial(_) => "dial",
+            CheckPeerError::Negotiate(_) => "negotiate",
+            CheckPeerError::Call(_) => "call",
+        }
+    }
+}
+
+/// Dials the peer, establishes a base node sync RPC session and pings it. If the check created the connection, it is
+/// disconnected once the check is complete, whether or not it succeeded. An existing connection is left open as it is
+/// in use by other services.
+pub async fn check_peer(
+    connectivity: &ConnectivityRequester,
+    node_id: NodeId,
+) -> Result<CheckPeerReport, CheckPeerError> {
+    let start = Instant::now();
+    let was_connected = connectivity.get_connection(node_id.clone()).await?.is_some();
+    let mut conn = connectivity.dial_peer(node_id).await?;
+    let dial_time = start.elapsed();
+
+    let result = async {
+        let protocol = conn
+            .select_rpc_protocol::<BaseNodeSyncRpcClient>()
+            .await
+            .map_err(|err| CheckPeerError::Negotiate(err.into()))?;
+        let mut client = conn
+            .connect_rpc::<BaseNodeSyncRpcClient>()
+            .await
+            .map_err(CheckPeerError::Negotiate)?;
+        let rtt = client.ping().await.map_err(CheckPeerError::Call)?;
+        client.close().await;
+        Ok(CheckPeerReport {
+            dial_time,
+            protocol,
+            rtt,
+        })
+    }
+    .await;
+
+    if !was_connected {
+        // Can ignore the error here, the connection is already closed if the disconnect fails
+        let _ = conn.disconnect().await;
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_reports_the_failed_stage() {
+        let err = CheckPeerError::from(ConnectivityError::ActorDisconnected);
+        assert_eq!(err.stage(), "dial");
+        assert!(err.to_string().starts_with("Dial failed"));
+
+        let err = CheckPeerError::Negotiate(RpcError::ClientClosed);
+        assert_eq!(err.stage(), "negotiate");
+        assert!(err.to_string().starts_with("RPC negotiation failed"));
+
+        let err = CheckPeerError::Call(RpcError::InvalidPingResponse);
+        assert_eq!(err.stage(), "call");
+        assert!(err.to_string().starts_with("RPC call failed"));
+    }
+}
@@ -21,13 +21,23 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use super::LOG_TARGET;
-use crate::{builder::BaseNodeContext, status_line::StatusLine, table::Table, utils::format_duration_basic};
+use crate::{
+    builder::BaseNodeContext,
+    check_peer::check_peer,
+    db_stats_metrics::format_db_stats_prometheus,
+    status_line::StatusLine,
+    table::{OutputFormat, Table},
+    transaction_file::read_transaction_file,
+    utils::format_duration_basic,
+};
 use chrono::{DateTime, Utc};
 use log::*;
 use std::{
     cmp,
+    convert::TryFrom,
     fs::File,
     io::{self, Write},
+    path::PathBuf,
     string::ToString,
     sync::Arc,
     time::{Duration, Instant},
@@ -39,8 +49,8 @@ use tari_common_types::{
     types::{Commitment, HashOutput, Signature},
 };
 use tari_comms::{
-    connectivity::ConnectivityRequester,
-    peer_manager::{NodeId, Peer, PeerFeatures, PeerManager, PeerManagerError, PeerQuery},
+    connectivity::{BannedPeer, ConnectivityError, ConnectivityRequester},
+    peer_manager::{NodeId, PeerFeatures, PeerManager, PeerQuery},
     protocol::rpc::RpcServerHandle,
     NodeIdentity,
 };
@@ -54,9 +64,17 @@ use tari_core::{
     blocks::BlockHeader,
     chain_storage::{async_db::AsyncBlockchainDb, ChainHeader, LMDBDatabase},
     consensus::ConsensusManager,
-    mempool::service::LocalMempoolService,
+    mempool::{
+        service::LocalMempoolService,
+        Mempool,
+        MempoolDifference,
+        MempoolRpcClient,
+        StateResponse,
+        StatsResponse,
+    },
     proof_of_work::PowAlgorithm,
     tari_utilities::{hex::Hex, message_format::MessageFormat},
+    transactions::transaction::Transaction,
 };
 use tari_crypto::{ristretto::RistrettoPublicKey, tari_utilities::Hashable};
 use tari_p2p::{
@@ -87,6 +105,7 @@ pub struct CommandHandler {
     liveness: LivenessHandle,
     node_service: LocalNodeCommsInterface,
     mempool_service: LocalMempoolService,
+    mempool: Mempool,
     state_machine_info: watch::Receiver<StatusInfo>,
     software_updater: SoftwareUpdaterHandle,
 }
@@ -106,6 +125,7 @@ impl CommandHandler {
             liveness: ctx.liveness(),
             node_service: ctx.local_node(),
             mempool_service: ctx.local_mempool(),
+            mempool: ctx.mempool(),
             state_machine_info: ctx.get_state_machine_info_channel(),
             software_updater: ctx.software_updater(),
         }
@@ -115,7 +135,6 @@ impl CommandHandler {
         let state_info = self.state_machine_info.clone();
         let mut node = self.node_service.clone();
         let mut mempool = self.mempool_service.clone();
-        let peer_manager = self.peer_manager.clone();
         let mut connectivity = self.connectivity.clone();
         let mut metrics = self.dht_metrics_collector.clone();
         let mut rpc_server = self.rpc_server.clone();
@@ -162,7 +181,7 @@ impl CommandHandler {
 
             let conns = connectivity.get_active_connections().await.unwrap();
             status_line.add_field("Connections", conns.len());
-            let banned_peers = fetch_banned_peers(&peer_manager).await.unwrap();
+            let banned_peers = fetch_banned_peers(&mut connectivity).await.unwrap();
             status_line.add_field("Banned", banned_peers.len());
 
             let num_messages = metrics
@@ -356,11 +375,11 @@ impl CommandHandler {
     }
 
     /// Function to process the get-mempool-stats command
-    pub fn get_mempool_stats(&self) {
+    pub fn get_mempool_stats(&self, format: OutputFormat) {
         let mut handler = self.mempool_service.clone();
         self.executor.spawn(async move {
             match handler.get_mempool_stats().await {
-                Ok(stats) => println!("{}", stats),
+                Ok(stats) => mempool_stats_table(&stats).print_stdout_as(format),
                 Err(err) => {
                     println!("Failed to retrieve mempool stats: {:?}", err);
                     warn!(target: LOG_TARGET, "Error communicating with local mempool: {:?}", err,);
@@ -383,6 +402,175 @@ impl CommandHandler {
         });
     }
 
+    /// Function to process the mempool list command
+    pub fn list_mempool_transactions(&self) {
+        let mut txs = match self.mempool.snapshot_with_metadata() {
+            Ok(txs) => txs,
+            Err(err) => {
+                println!("Failed to retrieve mempool transactions: {}", err);
+                warn!(target: LOG_TARGET, "Error reading the local mempool: {}", err);
+                return;
+            },
+        };
+        txs.sort_by(|a, b| b.fee_per_gram().cmp(&a.fee_per_gram()));
+
+        println!();
+        let mut table = Table::new();
+        table.set_titles(vec!["Excess Sig", "Fee", "Weight", "Fee/g", "Age", "Info"]);
+        for tx in &txs {
+            table.add_row(vec![
+                short_excess_sig(&tx.transaction),
+                tx.transaction.body.get_total_fee().to_string(),
+                tx.weight.to_string(),
+                tx.fee_per_gram().to_string(),
+                format_duration_basic(tx.age),
+                if tx.is_local { "LOCAL" } else { "" }.to_string(),
+            ]);
+        }
+        table.print_stdout();
+        println!();
+        println!("{} unconfirmed transaction(s)", txs.len());
+    }
+
+    /// Function to process the next-block-preview command
+    pub fn next_block_preview(&self) {
+        let db = self.blockchain_db.clone();
+        let mempool = self.mempool.clone();
+        let network = self.config.network;
+        self.executor.spawn(async move {
+            let tip_header = try_or_print!(db.fetch_tip_header().await);
+            let consensus_rules = ConsensusManager::builder(network).build();
+            let max_weight = consensus_rules
+                .consensus_constants(tip_header.height() + 1)
+                .get_max_block_weight_excluding_coinbase();
+            let preview = match mempool.next_block_preview(max_weight) {
+                Ok(preview) => preview,
+                Err(err) => {
+                    println!("Failed to retrieve transactions for the next block: {}", err);
+                    warn!(target: LOG_TARGET, "Error reading the local mempool: {}", err);
+                    return;
+                },
+            };
+
+            println!();
+            let mut table = Table::new();
+            table.set_titles(vec!["Excess Sig", "Fee", "Weight"]);
+            for tx in &preview.transactions {
+                table.add_row(row![
+                    short_excess_sig(tx),
+                    tx.body.get_total_fee(),
+                    tx.calculate_weight()
+                ]);
+            }
+            table.print_stdout();
+            println!();
+            println!(
+                "{} transaction(s), total weight: {} of {}, total fees: {}",
+                preview.transactions.len(),
+                preview.total_weight,
+                max_weight,
+                preview.total_fees
+            );
+        });
+    }
+
+    /// Function to process the mempool clear command
+    pub fn clear_mempool(&self) {
+        match self.mempool.clear() {
+            Ok(num_removed) => println!("Removed {} transaction(s) from the mempool", num_removed),
+            Err(err) => {
+                println!("Failed to clear the mempool: {}", err);
+                warn!(target: LOG_TARGET, "Error clearing the local mempool: {}", err);
+            },
+        }
+    }
+
+    /// Function to process the mempool-diff command
+    pub fn mempool_diff(&self, node_id: NodeId) {
+        let connectivity = self.connectivity.clone();
+        let mempool = self.mempool.clone();
+        self.executor.spawn(async move {
+            println!("Fetching the mempool of peer {}...", node_id);
+            let remote_excess_sigs = match fetch_mempool_excess_sigs(&connectivity, node_id.clone()).await {
+                Ok(sigs) => sigs,
+                Err(err) => {
+                    println!("Failed to fetch the mempool of peer {}: {}", node_id, err);
+                    return;
+                },
+            };
+            match mempool.difference(&remote_excess_sigs) {
+                Ok(diff) => {
+                    if let Err(err) = render_mempool_diff(&diff, &mut io::stdout()) {
+                        println!("Failed to print the mempool diff: {}", err);
+                    }
+                },
+                Err(err) => {
+                    println!("Failed to compare mempools: {}", err);
+                    warn!(target: LOG_TARGET, "Error reading the local mempool: {}", err);
+                },
+            }
+        });
+    }
+
+    /// Function to process the mempool-replay command
+    pub fn mempool_replay(&self, from: u64, to: u64) {
+        let db = self.blockchain_db.clone();
+        let mempool = self.mempool.clone();
+        self.executor.spawn(async move {
+            let blocks = try_or_print!(db.fetch_blocks(from..=to).await);
+            let blocks = try_or_print!(blocks
+                .into_iter()
+                .map(|block| block.try_into_block().map(Arc::new))
+                .collect::<Result<Vec<_>, _>>());
+            let num_blocks = blocks.len();
+            let stats = match mempool.replay_blocks(blocks) {
+                Ok(stats) => stats,
+                Err(err) => {
+                    println!("Failed to replay blocks through the mempool: {}", err);
+                    warn!(
+                        target: LOG_TARGET,
+                        "Error replaying blocks through the mempool: {}", err
+                    );
+                    return;
+                },
+            };
+
+            println!(
+                "Replayed {} block(s) from height {} to {} through a copy of the mempool",
+                num_blocks, from, to
+            );
+            mempool_stats_table(&stats).print_stdout();
+        });
+    }
+
+    /// Function to process the submit-tx command
+    pub fn submit_transaction(&self, path: PathBuf) {
+        let tx = match read_transaction_file(&path) {
+            Ok(tx) => tx,
+            Err(err) => {
+                println!("Failed to read transaction from '{}': {}", path.display(), err);
+                return;
+            },
+        };
+        let excess_sig = match tx.first_kernel_excess_sig() {
+            Some(sig) => sig.get_signature().to_hex(),
+            None => {
+                println!("Transaction in '{}' does not contain any kernels", path.display());
+                return;
+            },
+        };
+        let mut handler = self.mempool_service.clone();
+        self.executor.spawn(async move {
+            match handler.submit_transaction(tx).await {
+                Ok(response) => println!("Transaction {}: {}", excess_sig, response),
+                Err(err) => {
+                    println!("Failed to submit transaction: {:?}", err);
+                    warn!(target: LOG_TARGET, "Error communicating with local mempool: {:?}", err,);
+                },
+            };
+        });
+    }
+
     pub fn discover_peer(&self, dest_pubkey: Box<RistrettoPublicKey>) {
         let mut dht = self.discovery_service.clone();
 
@@ -408,6 +596,7 @@ impl CommandHandler {
 
     pub fn get_peer(&self, partial: Vec<u8>, original_str: String) {
         let peer_manager = self.peer_manager.clone();
+        let mut connectivity = self.connectivity.clone();
 
         self.executor.spawn(async move {
             match peer_manager.find_all_starts_with(&partial).await {
@@ -430,6 +619,24 @@ impl CommandHandler {
                     peer.supported_protocols.iter().for_each(|p| {
                         println!("- {}", String::from_utf8_lossy(p));
                     });
+                    // The protocols the peer advertised when the current connection was established may differ from
+                    // the stored protocols if the peer has since upgraded
+                    if let Ok(Some(mut conn)) = connectivity.get_connection(peer.node_id.clone()).await {
+                        match conn.their_supported_protocols().await {
+                            Ok(protocols) => {
+                                println!("Protocols advertised on the current connection:");
+                                protocols.iter().for_each(|p| {
+                                    println!("- {}", String::from_utf8_lossy(p));
+                                });
+                            },
+                            Err(err) => {
+                                println!(
+                                    "Failed to get the protocols advertised on the current connection: {}",
+                                    err
+                                );
+                            },
+                        }
+                    }
                     if let Some(dt) = peer.banned_until() {
                         println!("Banned until {}, reason: {}", dt, peer.banned_reason);
                     }
@@ -595,6 +802,24 @@ impl CommandHandler {
         }));
     }
 
+    pub fn check_peer(&self, dest_node_id: NodeId) {
+        let connectivity = self.connectivity.clone();
+
+        self.executor.spawn(async move {
+            println!("🩺 Checking peer...");
+            match check_peer(&connectivity, dest_node_id).await {
+                Ok(report) => {
+                    println!("⚡️ Peer connected in {}ms", report.dial_time.as_millis());
+                    println!("Protocol: {}", String::from_utf8_lossy(&report.protocol));
+                    println!("🏓 RPC ping round trip time is {}ms", report.rtt.as_millis());
+                },
+                Err(err) => {
+                    println!("❌ Check failed at the {} stage. {}", err.stage(), err);
+                },
+            }
+        });
+    }
+
     pub fn ban_peer(&self, node_id: NodeId, duration: Duration, must_ban: bool) {
         if self.base_node_identity.node_id() == &node_id {
             println!("Cannot ban our own node");
@@ -660,37 +885,46 @@ impl CommandHandler {
         });
     }
 
-    pub fn list_banned_peers(&self) {
-        let peer_manager = self.peer_manager.clone();
+    pub fn list_banned_peers(&self, format: OutputFormat) {
+        let mut connectivity = self.connectivity.clone();
         self.executor.spawn(async move {
-            match fetch_banned_peers(&peer_manager).await {
+            match fetch_banned_peers(&mut connectivity).await {
+                Ok(banned) if banned.is_empty() && format == OutputFormat::Table => {
+                    println!("No peers banned from node.");
+                },
                 Ok(banned) => {
-                    if banned.is_empty() {
-                        println!("No peers banned from node.")
-                    } else {
-                        println!("Peers banned from node ({}):", banned.len());
-                        for peer in banned {
-                            println!("{}", peer);
-                        }
+                    let mut table = Table::new();
+                    table.set_titles(vec!["NodeId", "Remaining", "Reason"]);
+                    for peer in &banned {
+                        table.add_row(row![peer.node_id, format_duration_basic(peer.remaining), peer.reason]);
                     }
+                    table.print_stdout_as(format);
+                    if format == OutputFormat::Table {
+                        println!("{} banned peer(s)", banned.len());
+                    }
+                },
+                Err(err) => {
+                    println!("Failed to retrieve banned peers: {:?}", err);
+                    error!(target: LOG_TARGET, "Could not retrieve banned peers: {:?}", err);
                 },
-                Err(e) => println!("Error listing peers: {}", e),
             }
         });
     }
 
     /// Function to process the list-connections command
-    pub fn list_connections(&self) {
+    pub fn list_connections(&self, format: OutputFormat) {
         let mut connectivity = self.connectivity.clone();
         let peer_manager = self.peer_manager.clone();
 
         self.executor.spawn(async move {
             match connectivity.get_active_connections().await {
-                Ok(conns) if conns.is_empty() => {
+                Ok(conns) if conns.is_empty() && format == OutputFormat::Table => {
                     println!("No active peer connections.");
                 },
                 Ok(conns) => {
-                    println!();
+                    if format == OutputFormat::Table {
+                        println!();
+                    }
                     let num_connections = conns.len();
                     let mut table = Table::new();
                     table.set_titles(vec![
@@ -703,7 +937,7 @@ impl CommandHandler {
                         "User Agent",
                         "Info",
                     ]);
-                    for conn in conns {
+                    for mut conn in conns {
                         let peer = peer_manager
                             .find_by_node_id(conn.peer_node_id())
                             .await
@@ -713,6 +947,13 @@ impl CommandHandler {
                             .get_metadata(1)
                             .and_then(|v| bincode::deserialize::<PeerMetadata>(v).ok())
                             .map(|metadata| format!("height: {}", metadata.metadata.height_of_longest_chain()));
+                        let byte_counts = conn
+                            .get_byte_counters()
+                            .await
+                            .map(|counts| {
+                                format!(", sent: {} B, received: {} B", counts.bytes_sent, counts.bytes_received)
+                            })
+                            .unwrap_or_default();
 
                         table.add_row(row![
                             peer.node_id,
@@ -731,16 +972,20 @@ impl CommandHandler {
                                 .map(|ua| if ua.is_empty() { "<unknown>".to_string() } else { ua })
                                 .unwrap(),
                             format!(
-                                "substreams: {}{}",
+                                "substreams: {}{}{}{}",
                                 conn.substream_count(),
-                                chain_height.map(|s| format!(", {}", s)).unwrap_or_default()
+                                byte_counts,
+                                chain_height.map(|s| format!(", {}", s)).unwrap_or_default(),
+                                conn.purpose().map(|p| format!(", purpose: {}", p)).unwrap_or_default()
                             ),
                         ]);
                     }
 
-                    table.print_stdout();
+                    table.print_stdout_as(format);
 
-                    println!("{} active connection(s)", num_connections);
+                    if format == OutputFormat::Table {
+                        println!("{} active connection(s)", num_connections);
+                    }
                 },
                 Err(err) => {
                     println!("Failed to list connections: {:?}", err);
@@ -750,6 +995,32 @@ impl CommandHandler {
         });
     }
 
+    pub fn connectivity_history(&self, format: OutputFormat) {
+        let mut connectivity = self.connectivity.clone();
+        self.executor.spawn(async move {
+            match connectivity.get_status_history().await {
+                Ok(history) if history.is_empty() && format == OutputFormat::Table => {
+                    println!("No connectivity status transitions have been recorded.");
+                },
+                Ok(history) => {
+                    let mut table = Table::new();
+                    table.set_titles(vec!["Timestamp", "From", "To"]);
+                    for transition in &history {
+                        table.add_row(row![transition.timestamp.to_rfc2822(), transition.from, transition.to]);
+                    }
+                    table.print_stdout_as(format);
+                    if format == OutputFormat::Table {
+                        println!("{} transition(s)", history.len());
+                    }
+                },
+                Err(err) => {
+                    println!("Failed to retrieve the connectivity history: {:?}", err);
+                    error!(target: LOG_TARGET, "Could not retrieve connectivity history: {:?}", err);
+                },
+            }
+        });
+    }
+
     pub fn reset_offline_peers(&self) {
         let peer_manager = self.peer_manager.clone();
         self.executor.spawn(async move {
@@ -1116,7 +1387,7 @@ impl CommandHandler {
         self.software_updater.clone()
     }
 
-    pub fn get_blockchain_db_stats(&self) {
+    pub fn get_blockchain_db_stats(&self, format: OutputFormat) {
         const BYTES_PER_MB: usize = 1024 * 1024;
 
         let db = self.blockchain_db.clone();
@@ -1149,16 +1420,18 @@ impl CommandHandler {
                         ]);
                     });
 
-                    table.print_stdout();
-                    println!();
-                    println!(
-                        "{} databases, {:.2} MiB used ({:.2}%), page size: {} bytes, env_info = ({})",
-                        stats.root().entries,
-                        total_db_size as f32 / BYTES_PER_MB as f32,
-                        (total_db_size as f32 / stats.env_info().mapsize as f32) * 100.0,
-                        stats.root().psize as usize,
-                        stats.env_info()
-                    );
+                    table.print_stdout_as(format);
+                    if format == OutputFormat::Table {
+                        println!();
+                        println!(
+                            "{} databases, {:.2} MiB used ({:.2}%), page size: {} bytes, env_info = ({})",
+                            stats.root().entries,
+                            total_db_size as f32 / BYTES_PER_MB as f32,
+                            (total_db_size as f32 / stats.env_info().mapsize as f32) * 100.0,
+                            stats.root().psize as usize,
+                            stats.env_info()
+                        );
+                    }
                     total_db_size
                 },
                 Err(err) => {
@@ -1167,12 +1440,16 @@ impl CommandHandler {
                 },
             };
 
-            println!();
-            println!("Totalling DB entry sizes. This may take a few seconds...");
-            println!();
+            if format == OutputFormat::Table {
+                println!();
+                println!("Totalling DB entry sizes. This may take a few seconds...");
+                println!();
+            }
             match db.fetch_total_size_stats().await {
                 Ok(stats) => {
-                    println!();
+                    if format == OutputFormat::Table {
+                        println!();
+                    }
                     let mut table = Table::new();
                     table.set_titles(vec![
                         "Name",
@@ -1192,13 +1469,15 @@ impl CommandHandler {
                             format!("{:.2}%", (size.total() as f32 / total_data_size as f32) * 100.0)
                         ])
                     });
-                    table.print_stdout();
-                    println!();
-                    println!(
-                        "Total blockchain data size: {:.2} MiB ({:.2} % of LMDB map size)",
-                        total_data_size as f32 / BYTES_PER_MB as f32,
-                        (total_data_size as f32 / total_db_size as f32) * 100.0
-                    );
+                    table.print_stdout_as(format);
+                    if format == OutputFormat::Table {
+                        println!();
+                        println!(
+                            "Total blockchain data size: {:.2} MiB ({:.2} % of LMDB map size)",
+                            total_data_size as f32 / BYTES_PER_MB as f32,
+                            (total_data_size as f32 / total_db_size as f32) * 100.0
+                        );
+                    }
                 },
                 Err(err) => {
                     println!("{}", err);
@@ -1206,11 +1485,24 @@ impl CommandHandler {
             }
         });
     }
+
+    /// Prints the blockchain database stats in Prometheus text exposition format
+    pub fn get_blockchain_db_stats_prometheus(&self) {
+        let db = self.blockchain_db.clone();
+
+        self.executor.spawn(async move {
+            let stats = try_or_print!(db.get_stats().await);
+            let sizes = try_or_print!(db.fetch_total_size_stats().await);
+            print!("{}", format_db_stats_prometheus(stats.db_stats(), sizes.sizes()));
+        });
+    }
 }
 
-async fn fetch_banned_peers(pm: &PeerManager) -> Result<Vec<Peer>, PeerManagerError> {
-    let query = PeerQuery::new().select_where(|p| p.is_banned());
-    pm.perform_query(query).await
+/// Returns the active peer bans, soonest to expire first
+async fn fetch_banned_peers(connectivity: &mut ConnectivityRequester) -> Result<Vec<BannedPeer>, ConnectivityError> {
+    let mut banned = connectivity.get_banned_peers().await?;
+    banned.sort_by_key(|b| b.remaining);
+    Ok(banned)
 }
 
 pub enum Format {
@@ -1232,3 +1524,108 @@ pub enum Format {
 //     }
 //     del_arg_vec
 // }
+
+/// Dials the given peer and returns the excess signatures of the transactions in its unconfirmed pool
+async fn fetch_mempool_excess_sigs(
+    connectivity: &ConnectivityRequester,
+    node_id: NodeId,
+) -> Result<Vec<Signature>, anyhow::Error> {
+    let mut conn = connectivity.dial_peer(node_id).await?;
+    let mut client = conn
+        .connect_rpc_using_builder(MempoolRpcClient::builder().with_deadline(Duration::from_secs(30)))
+        .await?;
+    let state = StateResponse::try_from(client.get_state().await?).map_err(anyhow::Error::msg)?;
+    Ok(state
+        .unconfirmed_pool
+        .iter()
+        .filter_map(|tx| tx.first_kernel_excess_sig().cloned())
+        .collect())
+}
+
+/// Returns a table with the given mempool stats as its only row
+fn mempool_stats_table(stats: &StatsResponse) -> Table<'static, 'static> {
+    let mut table = Table::new();
+    table.set_titles(vec![
+        "Total Txs",
+        "Unconfirmed Txs",
+        "Published Txs",
+        "Local Txs",
+        "Total Weight",
+        "Max Dependency Depth",
+    ]);
+    table.add_row(row![
+        stats.total_txs,
+        stats.unconfirmed_txs,
+        stats.reorg_txs,
+        stats.local_txs,
+        stats.total_weight,
+        stats.max_dependency_depth,
+    ]);
+    table
+}
+
+/// Returns the first 16 hex characters of the excess signature of the transaction's first kernel, or `--` if the
+/// transaction has no kernels
+fn short_excess_sig(tx: &Transaction) -> String {
+    tx.first_kernel_excess_sig()
+        .map(|sig| {
+            let sig = sig.get_signature().to_hex();
+            sig[..cmp::min(16, sig.len())].to_string()
+        })
+        .unwrap_or_else(|| "--".to_string())
+}
+
+/// Renders the transactions that only the local or only the remote mempool has as two tables
+fn render_mempool_diff<W: Write>(diff: &MempoolDifference, out: &mut W) -> io::Result<()> {
+    let sections = [
+        ("We have, they don't", &diff.local_only),
+        ("They have, we don't", &diff.remote_only),
+    ];
+    for (title, sigs) in sections.iter() {
+        writeln!(out)?;
+        writeln!(out, "{} ({})", title, sigs.len())?;
+        let mut table = Table::new();
+        table.set_titles(vec!["Excess Sig"]);
+        for sig in sigs.iter() {
+            table.add_row(vec![sig.get_signature().to_hex()]);
+        }
+        table.render(out)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tari_common_types::types::{PrivateKey, PublicKey};
+
+    fn excess_sig(n: u64) -> Signature {
+        Signature::new(PublicKey::default(), PrivateKey::from(n))
+    }
+
+    #[test]
+    fn it_renders_mempool_diff() {
+        let diff = MempoolDifference {
+            local_only: vec![excess_sig(1)],
+            remote_only: vec![excess_sig(4)],
+        };
+
+        let mut buf = io::Cursor::new(Vec::new());
+        render_mempool_diff(&diff, &mut buf).unwrap();
+        let output = String::from_utf8(buf.into_inner()).unwrap();
+        let ours_at = output.find("We have, they don't (1)").unwrap();
+        let theirs_at = output.find("They have, we don't (1)").unwrap();
+        let sig1 = output.find(&excess_sig(1).get_signature().to_hex()).unwrap();
+        let sig4 = output.find(&excess_sig(4).get_signature().to_hex()).unwrap();
+        assert!(ours_at < sig1 && sig1 < theirs_at && theirs_at < sig4);
+    }
+
+    #[test]
+    fn it_renders_empty_mempool_diff() {
+        let mut buf = io::Cursor::new(Vec::new());
+        render_mempool_diff(&MempoolDifference::default(), &mut buf).unwrap();
+        let output = String::from_utf8(buf.into_inner()).unwrap();
+        assert!(output.contains("We have, they don't (0)"));
+        assert!(output.contains("They have, we don't (0)"));
+    }
+}
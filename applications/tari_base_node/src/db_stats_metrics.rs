@@ -0,0 +1,194 @@
+// Copyright 2020, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use std::fmt::Write;
+use tari_core::chain_storage::{DbSize, DbStat};
+
+const METRIC_PREFIX: &str = "tari_db";
+
+/// Formats the blockchain database stats as Prometheus text exposition format lines
+pub fn format_db_stats_prometheus(stats: &[DbStat], sizes: &[DbSize]) -> String {
+    let mut buf = String::new();
+    write_metric(&mut buf, "entries", "Number of entries in the database", stats, |s| {
+        s.entries as u64
+    });
+    write_metric(&mut buf, "depth", "Depth of the database B-tree", stats, |s| {
+        u64::from(s.depth)
+    });
+    write_metric(
+        &mut buf,
+        "branch_pages",
+        "Number of internal (non-leaf) pages",
+        stats,
+        |s| s.branch_pages as u64,
+    );
+    write_metric(&mut buf, "leaf_pages", "Number of leaf pages", stats, |s| {
+        s.leaf_pages as u64
+    });
+    write_metric(&mut buf, "overflow_pages", "Number of overflow pages", stats, |s| {
+        s.overflow_pages as u64
+    });
+    write_metric(
+        &mut buf,
+        "page_size_bytes",
+        "Total size in bytes of all database pages",
+        stats,
+        |s| s.total_page_size() as u64,
+    );
+    write_metric(
+        &mut buf,
+        "key_size_bytes",
+        "Total size in bytes of all keys in the database",
+        sizes,
+        |s| s.total_key_size,
+    );
+    write_metric(
+        &mut buf,
+        "value_size_bytes",
+        "Total size in bytes of all values in the database",
+        sizes,
+        |s| s.total_value_size,
+    );
+    buf
+}
+
+trait NamedStat {
+    fn db_name(&self) -> &str;
+}
+
+impl NamedStat for DbStat {
+    fn db_name(&self) -> &str {
+        self.name
+    }
+}
+
+impl NamedStat for DbSize {
+    fn db_name(&self) -> &str {
+        self.name
+    }
+}
+
+fn write_metric<T: NamedStat, F: Fn(&T) -> u64>(buf: &mut String, name: &str, help: &str, stats: &[T], value: F) {
+    if stats.is_empty() {
+        return;
+    }
+    // Writing to a String cannot fail
+    let _ = writeln!(buf, "# HELP {}_{} {}", METRIC_PREFIX, name, help);
+    let _ = writeln!(buf, "# TYPE {}_{} gauge", METRIC_PREFIX, name);
+    for stat in stats {
+        let _ = writeln!(
+            buf,
+            "{}_{}{{db=\"{}\"}} {}",
+            METRIC_PREFIX,
+            name,
+            sanitize_label_value(stat.db_name()),
+            value(stat)
+        );
+    }
+}
+
+/// Replaces any character that is not alphanumeric or an underscore, so that the value never needs escaping
+fn sanitize_label_value(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn is_valid_metric_name(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars
+            .next()
+            .map(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+            .unwrap_or(false) &&
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+    }
+
+    fn assert_valid_prometheus_text(text: &str) {
+        for line in text.lines() {
+            if let Some(comment) = line.strip_prefix("# ") {
+                let mut parts = comment.splitn(3, ' ');
+                let kind = parts.next().unwrap();
+                assert!(kind == "HELP" || kind == "TYPE", "Unexpected comment '{}'", line);
+                assert!(is_valid_metric_name(parts.next().unwrap()));
+                assert!(parts.next().is_some());
+                continue;
+            }
+            let (series, value) = line.rsplit_once(' ').unwrap();
+            value.parse::<f64>().unwrap();
+            let (name, labels) = series.split_once('{').unwrap();
+            assert!(is_valid_metric_name(name), "Invalid metric name '{}'", name);
+            let labels = labels.strip_suffix('}').unwrap();
+            let (label_name, label_value) = labels.split_once('=').unwrap();
+            assert_eq!(label_name, "db");
+            let label_value = label_value.strip_prefix('"').unwrap().strip_suffix('"').unwrap();
+            assert!(!label_value.contains(|c| c == '"' || c == '\\' || c == '\n'));
+        }
+    }
+
+    #[test]
+    fn it_formats_valid_prometheus_text() {
+        let stats = [
+            DbStat {
+                name: "utxos",
+                psize: 4096,
+                depth: 3,
+                branch_pages: 2,
+                leaf_pages: 10,
+                overflow_pages: 0,
+                entries: 12345,
+            },
+            DbStat {
+                name: "block \"hashes\"",
+                psize: 4096,
+                depth: 1,
+                branch_pages: 0,
+                leaf_pages: 1,
+                overflow_pages: 1,
+                entries: 2,
+            },
+        ];
+        let sizes = [DbSize {
+            name: "utxos",
+            num_entries: 12345,
+            total_key_size: 100,
+            total_value_size: 200,
+        }];
+
+        let text = format_db_stats_prometheus(&stats, &sizes);
+        assert_valid_prometheus_text(&text);
+        assert!(text.contains("tari_db_entries{db=\"utxos\"} 12345\n"));
+        assert!(text.contains("tari_db_page_size_bytes{db=\"utxos\"} 49152\n"));
+        assert!(text.contains("tari_db_entries{db=\"block__hashes_\"} 2\n"));
+        assert!(text.contains("tari_db_value_size_bytes{db=\"utxos\"} 200\n"));
+    }
+
+    #[test]
+    fn it_omits_metrics_without_stats() {
+        let text = format_db_stats_prometheus(&[], &[]);
+        assert!(text.is_empty());
+    }
+}
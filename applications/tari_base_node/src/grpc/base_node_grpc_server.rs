@@ -516,7 +516,7 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
         );
 
         let mut handler = self.mempool_service.clone();
-        let res = handler.submit_transaction(txn).await.map_err(|e| {
+        let res = handler.submit_local_transaction(txn).await.map_err(|e| {
             error!(target: LOG_TARGET, "Error submitting:{}", e);
             Status::internal(e.to_string())
         })?;
@@ -531,7 +531,10 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
             },
             TxStorageResponse::NotStored |
             TxStorageResponse::NotStoredOrphan |
-            TxStorageResponse::NotStoredTimeLocked => tari_rpc::SubmitTransactionResponse {
+            TxStorageResponse::NotStoredTimeLocked |
+            TxStorageResponse::NotStoredDuplicateOutput |
+            TxStorageResponse::NotStoredFeeTooLow(_) |
+            TxStorageResponse::NotStoredZeroConfChainTooLong => tari_rpc::SubmitTransactionResponse {
                 result: tari_rpc::SubmitTransactionResult::Rejected.into(),
             },
         };
@@ -597,7 +600,10 @@ impl tari_rpc::base_node_server::BaseNode for BaseNodeGrpcServer {
             },
             TxStorageResponse::NotStored |
             TxStorageResponse::NotStoredOrphan |
-            TxStorageResponse::NotStoredTimeLocked => tari_rpc::TransactionStateResponse {
+            TxStorageResponse::NotStoredTimeLocked |
+            TxStorageResponse::NotStoredDuplicateOutput |
+            TxStorageResponse::NotStoredFeeTooLow(_) |
+            TxStorageResponse::NotStoredZeroConfChainTooLong => tari_rpc::TransactionStateResponse {
                 result: tari_rpc::TransactionLocation::NotStored.into(),
             },
         };
@@ -86,12 +86,15 @@ mod macros;
 
 mod bootstrap;
 mod builder;
+mod check_peer;
 mod cli;
 mod command_handler;
+mod db_stats_metrics;
 mod grpc;
 mod parser;
 mod recovery;
 mod status_line;
+mod transaction_file;
 mod utils;
 
 use crate::command_handler::{CommandHandler, StatusOutput};
@@ -113,8 +116,14 @@ use tari_app_utilities::{
     initialization::init_configuration,
     utilities::{setup_runtime, ExitCodes},
 };
-use tari_common::{configuration::bootstrap::ApplicationType, ConfigBootstrap, GlobalConfig};
-use tari_comms::{peer_manager::PeerFeatures, tor::HiddenServiceControllerError};
+use tari_common::{configuration::bootstrap::ApplicationType, ConfigBootstrap, DefaultConfigLoader, GlobalConfig};
+use tari_comms::{
+    connection_manager::ConnectionManagerError,
+    peer_manager::PeerFeatures,
+    tor::HiddenServiceControllerError,
+};
+use tari_core::mempool::MempoolConfig;
+use tari_p2p::DnsClientError;
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use tokio::{
     runtime,
@@ -128,7 +137,7 @@ const LOG_TARGET: &str = "base_node::app";
 /// Application entry point
 fn main() {
     if let Err(exit_code) = main_inner() {
-        eprintln!("{:?}", exit_code);
+        exit_code.eprint_details();
         error!(
             target: LOG_TARGET,
             "Exiting with code ({}): {:?}",
@@ -140,9 +149,12 @@ fn main() {
 }
 
 fn main_inner() -> Result<(), ExitCodes> {
-    let (bootstrap, node_config, _) = init_configuration(ApplicationType::BaseNode)?;
+    let (bootstrap, node_config, cfg) = init_configuration(ApplicationType::BaseNode)?;
+    let mempool_config = <MempoolConfig as DefaultConfigLoader>::load_from(&cfg)
+        .map_err(|err| ExitCodes::ConfigError(format!("Invalid mempool configuration: {}", err)))?;
 
     debug!(target: LOG_TARGET, "Using configuration: {:?}", node_config);
+    debug!(target: LOG_TARGET, "Using mempool configuration: {:?}", mempool_config);
 
     // Set up the Tokio runtime
     let rt = setup_runtime(&node_config).map_err(|e| {
@@ -150,14 +162,18 @@ fn main_inner() -> Result<(), ExitCodes> {
         ExitCodes::UnknownError
     })?;
 
-    rt.block_on(run_node(node_config.into(), bootstrap))?;
+    rt.block_on(run_node(node_config.into(), mempool_config, bootstrap))?;
     // Shutdown and send any traces
     global::shutdown_tracer_provider();
     Ok(())
 }
 
 /// Sets up the base node and runs the cli_loop
-async fn run_node(node_config: Arc<GlobalConfig>, bootstrap: ConfigBootstrap) -> Result<(), ExitCodes> {
+async fn run_node(
+    node_config: Arc<GlobalConfig>,
+    mempool_config: MempoolConfig,
+    bootstrap: ConfigBootstrap,
+) -> Result<(), ExitCodes> {
     if bootstrap.tracing_enabled {
         enable_tracing();
     }
@@ -198,6 +214,7 @@ async fn run_node(node_config: Arc<GlobalConfig>, bootstrap: ConfigBootstrap) ->
     // Build, node, build!
     let ctx = builder::configure_and_initialize_node(
         node_config.clone(),
+        mempool_config,
         node_identity,
         shutdown.to_signal(),
         bootstrap.clean_orphans_db,
@@ -221,6 +238,15 @@ async fn run_node(node_config: Arc<GlobalConfig>, bootstrap: ConfigBootstrap) ->
                 return ExitCodes::TorOffline;
             }
 
+            if let Some(err) = boxed_error.downcast_ref::<DnsClientError>() {
+                return ExitCodes::DnsResolutionError(err.to_string());
+            }
+
+            // The connection manager fails during initialization if the listener could not be bound
+            if let Some(err) = boxed_error.downcast_ref::<ConnectionManagerError>() {
+                return ExitCodes::from(err.clone());
+            }
+
             // todo: find a better way to do this
             if boxed_error.to_string().contains("Invalid force sync peer") {
                 println!("Please check your force sync peers configuration");
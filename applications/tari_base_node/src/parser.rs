@@ -21,7 +21,10 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use super::LOG_TARGET;
-use crate::command_handler::{CommandHandler, Format, StatusOutput};
+use crate::{
+    command_handler::{CommandHandler, Format, StatusOutput},
+    table::OutputFormat,
+};
 use futures::future::Either;
 use log::*;
 use rustyline::{
@@ -32,7 +35,14 @@ use rustyline::{
     Context,
 };
 use rustyline_derive::{Helper, Highlighter, Validator};
-use std::{str::FromStr, string::ToString, sync::Arc, time::Duration};
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+    string::ToString,
+    sync::Arc,
+    time::Duration,
+};
 use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, EnumString};
 use tari_app_utilities::utilities::{
@@ -49,6 +59,9 @@ use tari_core::{
 use tari_crypto::tari_utilities::hex;
 use tari_shutdown::Shutdown;
 
+/// The maximum number of blocks that the mempool-replay command fetches and replays at once
+const MAX_MEMPOOL_REPLAY_BLOCKS: u64 = 100;
+
 /// Enum representing commands used by the basenode
 #[derive(Clone, Copy, PartialEq, Debug, Display, EnumIter, EnumString)]
 #[strum(serialize_all = "kebab_case")]
@@ -63,6 +76,7 @@ pub enum BaseNodeCommand {
     ListPeers,
     DialPeer,
     PingPeer,
+    CheckPeer,
     ResetOfflinePeers,
     RewindBlockchain,
     BanPeer,
@@ -70,6 +84,7 @@ pub enum BaseNodeCommand {
     UnbanAllPeers,
     ListBannedPeers,
     ListConnections,
+    ConnectivityHistory,
     ListHeaders,
     CheckDb,
     PeriodStats,
@@ -82,6 +97,11 @@ pub enum BaseNodeCommand {
     SearchKernel,
     GetMempoolStats,
     GetMempoolState,
+    Mempool,
+    MempoolDiff,
+    MempoolReplay,
+    NextBlockPreview,
+    SubmitTx,
     Whoami,
     GetStateInfo,
     Quit,
@@ -192,7 +212,12 @@ impl Parser {
                 self.command_handler.get_chain_meta();
             },
             GetDbStats => {
-                self.command_handler.get_blockchain_db_stats();
+                let args = args.collect::<Vec<_>>();
+                if args.first() == Some(&"--prometheus") {
+                    self.command_handler.get_blockchain_db_stats_prometheus();
+                } else if let Some(format) = self.parse_output_format(GetDbStats, &args) {
+                    self.command_handler.get_blockchain_db_stats(format);
+                }
             },
             DialPeer => {
                 self.process_dial_peer(args);
@@ -200,6 +225,9 @@ impl Parser {
             PingPeer => {
                 self.process_ping_peer(args);
             },
+            CheckPeer => {
+                self.process_check_peer(args);
+            },
             DiscoverPeer => {
                 self.process_discover_peer(args);
             },
@@ -234,10 +262,19 @@ impl Parser {
                 self.command_handler.unban_all_peers();
             },
             ListBannedPeers => {
-                self.command_handler.list_banned_peers();
+                if let Some(format) = self.parse_output_format(ListBannedPeers, &args.collect::<Vec<_>>()) {
+                    self.command_handler.list_banned_peers(format);
+                }
             },
             ListConnections => {
-                self.command_handler.list_connections();
+                if let Some(format) = self.parse_output_format(ListConnections, &args.collect::<Vec<_>>()) {
+                    self.command_handler.list_connections(format);
+                }
+            },
+            ConnectivityHistory => {
+                if let Some(format) = self.parse_output_format(ConnectivityHistory, &args.collect::<Vec<_>>()) {
+                    self.command_handler.connectivity_history(format);
+                }
             },
             ListHeaders => {
                 self.process_list_headers(args);
@@ -255,11 +292,28 @@ impl Parser {
                 self.process_search_kernel(args);
             },
             GetMempoolStats => {
-                self.command_handler.get_mempool_stats();
+                if let Some(format) = self.parse_output_format(GetMempoolStats, &args.collect::<Vec<_>>()) {
+                    self.command_handler.get_mempool_stats(format);
+                }
             },
             GetMempoolState => {
                 self.command_handler.get_mempool_state();
             },
+            Mempool => {
+                self.process_mempool(args);
+            },
+            MempoolDiff => {
+                self.process_mempool_diff(args);
+            },
+            MempoolReplay => {
+                self.process_mempool_replay(args);
+            },
+            NextBlockPreview => {
+                self.command_handler.next_block_preview();
+            },
+            SubmitTx => {
+                self.process_submit_tx(args);
+            },
             Whoami => {
                 self.command_handler.whoami();
             },
@@ -300,6 +354,9 @@ impl Parser {
             },
             GetDbStats => {
                 println!("Gets your base node database stats");
+                println!("get-db-stats [--prometheus | --format {{table,json,csv}}]");
+                println!("[--prometheus] Optional. Prints the stats in Prometheus text exposition format.");
+                println!("[--format] Optional. The format in which the stats are printed. Defaults to 'table'.");
             },
             DialPeer => {
                 println!("Attempt to connect to a known peer");
@@ -307,6 +364,16 @@ impl Parser {
             PingPeer => {
                 println!("Send a ping to a known peer and wait for a pong reply");
             },
+            CheckPeer => {
+                println!(
+                    "Dial a peer, open an RPC session and ping it, then disconnect if the peer was not already \
+                     connected"
+                );
+                println!(
+                    "Reports the RPC round trip time or the stage (dial, negotiate or call) at which the check failed"
+                );
+                println!("check-peer [hex public key or emoji id]");
+            },
             DiscoverPeer => {
                 println!("Attempt to discover a peer on the Tari network");
             },
@@ -334,7 +401,8 @@ impl Parser {
                 println!("Unbans all peers");
             },
             ListBannedPeers => {
-                println!("Lists peers that have been banned by the node or wallet");
+                println!("Lists all active peer bans with the time remaining on each ban and the reason for it");
+                println!("list-banned-peers [--format {{table,json,csv}}]");
             },
             CheckDb => {
                 println!("Checks the blockchain database for missing blocks and headers");
@@ -361,6 +429,11 @@ impl Parser {
             },
             ListConnections => {
                 println!("Lists the peer connections currently held by this node");
+                println!("list-connections [--format {{table,json,csv}}]");
+            },
+            ConnectivityHistory => {
+                println!("Lists the most recent connectivity status transitions, e.g. from online to degraded");
+                println!("connectivity-history [--format {{table,json,csv}}]");
             },
             ListHeaders => {
                 println!("List the amount of headers, can be called in the following two ways: ");
@@ -400,10 +473,41 @@ impl Parser {
             },
             GetMempoolStats => {
                 println!("Retrieves your mempools stats");
+                println!("get-mempool-stats [--format {{table,json,csv}}]");
             },
             GetMempoolState => {
                 println!("Retrieves your mempools state");
             },
+            Mempool => {
+                println!("Lists or removes the unconfirmed transactions in your mempool");
+                println!("mempool list");
+                println!("mempool clear [--yes]");
+            },
+            MempoolDiff => {
+                println!("Compares the unconfirmed transactions in your mempool with those of a peer");
+                println!("mempool-diff [hex public key or emoji id]");
+            },
+            MempoolReplay => {
+                println!(
+                    "Replays a range of blocks through a fresh copy of your mempool and prints the resulting stats. \
+                     Only available in debug builds"
+                );
+                println!(
+                    "At most {} blocks can be replayed at once. Transactions are re-validated against the current \
+                     tip, so replaying blocks far below the tip gives inaccurate results",
+                    MAX_MEMPOOL_REPLAY_BLOCKS
+                );
+                println!("mempool-replay [from height] [to height]");
+            },
+            NextBlockPreview => {
+                println!(
+                    "Previews the unconfirmed transactions that this node would include in the next block it mines"
+                );
+            },
+            SubmitTx => {
+                println!("Submits a JSON or protobuf serialized transaction from a file to the mempool");
+                println!("submit-tx [path]");
+            },
             Whoami => {
                 println!(
                     "Display identity information about this node, including: public key, node ID and the public \
@@ -416,6 +520,23 @@ impl Parser {
         }
     }
 
+    /// Parses the optional `--format {table,json,csv}` option shared by the stats commands. Returns None and prints the
+    /// command help if the format is not recognised.
+    fn parse_output_format(&self, command: BaseNodeCommand, args: &[&str]) -> Option<OutputFormat> {
+        let pos = match args.iter().position(|arg| *arg == "--format") {
+            Some(pos) => pos,
+            None => return Some(OutputFormat::default()),
+        };
+        match args.get(pos + 1).and_then(|v| v.to_ascii_lowercase().parse().ok()) {
+            Some(format) => Some(format),
+            None => {
+                println!("Unrecognized output format. Supported formats are 'table', 'json' and 'csv'.");
+                self.print_help(command);
+                None
+            },
+        }
+    }
+
     /// Function to process the get-block command
     fn process_get_block<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
         let height_or_hash = match args.next() {
@@ -586,6 +707,24 @@ impl Parser {
         self.command_handler.ping_peer(dest_node_id)
     }
 
+    /// Function to process the check-peer command
+    fn process_check_peer<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I) {
+        let dest_node_id = match args
+            .next()
+            .and_then(parse_emoji_id_or_public_key_or_node_id)
+            .map(either_to_node_id)
+        {
+            Some(n) => n,
+            None => {
+                println!("Please enter a valid destination public key or emoji id");
+                println!("check-peer [hex public key or emoji id]");
+                return;
+            },
+        };
+
+        self.command_handler.check_peer(dest_node_id)
+    }
+
     /// Function to process the ban-peer command
     fn process_ban_peer<'a, I: Iterator<Item = &'a str>>(&mut self, mut args: I, must_ban: bool) {
         let node_id = match args
@@ -707,6 +846,62 @@ impl Parser {
             .save_header_stats(start_height, end_height, filename, algo)
     }
 
+    fn process_submit_tx<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let path = try_or_print!(args.next().ok_or("path argument required"));
+        self.command_handler.submit_transaction(PathBuf::from(path));
+    }
+
+    fn process_mempool<'a, I: Iterator<Item = &'a str>>(&self, args: I) {
+        match parse_mempool_command(args) {
+            Ok(MempoolCommand::List) => self.command_handler.list_mempool_transactions(),
+            Ok(MempoolCommand::Clear { confirmed }) => {
+                if confirmed || confirm("Remove all unconfirmed transactions from the mempool?") {
+                    self.command_handler.clear_mempool();
+                } else {
+                    println!("Mempool not cleared");
+                }
+            },
+            Err(err) => {
+                println!("{}", err);
+                self.print_help(BaseNodeCommand::Mempool);
+            },
+        }
+    }
+
+    fn process_mempool_diff<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        let node_id = match args
+            .next()
+            .and_then(parse_emoji_id_or_public_key_or_node_id)
+            .map(either_to_node_id)
+        {
+            Some(n) => n,
+            None => {
+                println!("Please enter a valid peer public key, node id or emoji id");
+                println!("mempool-diff [hex public key or emoji id]");
+                return;
+            },
+        };
+        self.command_handler.mempool_diff(node_id);
+    }
+
+    fn process_mempool_replay<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
+        if !cfg!(debug_assertions) {
+            println!("mempool-replay is only available in debug builds");
+            return;
+        }
+        let from = args.next().map(u64::from_str).map(Result::ok).flatten();
+        let to = args.next().map(u64::from_str).map(Result::ok).flatten();
+        match (from, to) {
+            (Some(from), Some(to)) if from <= to && to - from < MAX_MEMPOOL_REPLAY_BLOCKS => {
+                self.command_handler.mempool_replay(from, to)
+            },
+            _ => {
+                println!("Please enter a valid block height range");
+                self.print_help(BaseNodeCommand::MempoolReplay);
+            },
+        }
+    }
+
     fn process_rewind_blockchain<'a, I: Iterator<Item = &'a str>>(&self, mut args: I) {
         let new_height = try_or_print!(args
             .next()
@@ -715,3 +910,62 @@ impl Parser {
         self.command_handler.rewind_blockchain(new_height);
     }
 }
+
+/// Subcommands of the `mempool` command
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MempoolCommand {
+    List,
+    /// `confirmed` is true if the `--yes` flag was given, in which case the user is not prompted
+    Clear {
+        confirmed: bool,
+    },
+}
+
+fn parse_mempool_command<'a, I: Iterator<Item = &'a str>>(mut args: I) -> Result<MempoolCommand, String> {
+    let command = match args.next() {
+        Some("list") => MempoolCommand::List,
+        Some("clear") => MempoolCommand::Clear { confirmed: false },
+        Some(other) => return Err(format!("Unknown mempool command '{}'", other)),
+        None => return Err("A mempool command is required".to_string()),
+    };
+    match (command, args.next()) {
+        (command, None) => Ok(command),
+        (MempoolCommand::Clear { .. }, Some("--yes")) => Ok(MempoolCommand::Clear { confirmed: true }),
+        (_, Some(arg)) => Err(format!("Unexpected argument '{}'", arg)),
+    }
+}
+
+/// Prompts the user with a yes/no question, returning true if the user answered yes
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_parses_mempool_commands() {
+        assert_eq!(
+            parse_mempool_command("list".split_whitespace()),
+            Ok(MempoolCommand::List)
+        );
+        assert_eq!(
+            parse_mempool_command("clear".split_whitespace()),
+            Ok(MempoolCommand::Clear { confirmed: false })
+        );
+        assert_eq!(
+            parse_mempool_command("clear --yes".split_whitespace()),
+            Ok(MempoolCommand::Clear { confirmed: true })
+        );
+        assert!(parse_mempool_command("list --yes".split_whitespace()).is_err());
+        assert!(parse_mempool_command("flush".split_whitespace()).is_err());
+        assert!(parse_mempool_command("".split_whitespace()).is_err());
+    }
+}
@@ -20,7 +20,24 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
+use serde_json::{Map, Value};
 use std::{cmp, io, io::Write};
+use strum_macros::{Display, EnumString};
+
+/// The format in which the output of the stats commands is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "snake_case")]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self {
+        OutputFormat::Table
+    }
+}
 
 /// Basic ASCII table implementation that is easy to put in a spreadsheet.
 pub struct Table<'t, 's> {
@@ -62,6 +79,51 @@ impl<'t, 's> Table<'t, 's> {
         self.render(&mut io::stdout()).unwrap();
     }
 
+    /// Renders the table in the given format
+    pub fn render_as<T: Write>(&self, format: OutputFormat, out: &mut T) -> io::Result<()> {
+        match format {
+            OutputFormat::Table => self.render(out),
+            OutputFormat::Json => writeln!(out, "{}", self.to_json()),
+            OutputFormat::Csv => out.write_all(self.to_csv().as_bytes()),
+        }
+    }
+
+    pub fn print_stdout_as(&self, format: OutputFormat) {
+        self.render_as(format, &mut io::stdout()).unwrap();
+    }
+
+    /// Returns the rows as a JSON array. Each row is an object keyed by the column titles, or an array if the table has
+    /// no titles.
+    pub fn to_json(&self) -> String {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| match self.titles.as_ref() {
+                Some(titles) => Value::Object(
+                    titles
+                        .iter()
+                        .zip(row)
+                        .map(|(title, value)| (title.to_string(), Value::String(value.clone())))
+                        .collect::<Map<_, _>>(),
+                ),
+                None => Value::Array(row.iter().cloned().map(Value::String).collect()),
+            })
+            .collect();
+        Value::Array(rows).to_string()
+    }
+
+    /// Returns the titles (if any) and rows as comma-separated values, one line per row
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        if let Some(titles) = self.titles.as_ref() {
+            csv.push_str(&csv_line(titles));
+        }
+        for row in &self.rows {
+            csv.push_str(&csv_line(row));
+        }
+        csv
+    }
+
     fn col_width(&self, idx: usize) -> usize {
         let title_width = self.titles.as_ref().map(|titles| titles[idx].len()).unwrap_or(0);
         let rows_width = self.rows.iter().fold(0, |max, r| {
@@ -127,6 +189,23 @@ impl<'t, 's> Table<'t, 's> {
     }
 }
 
+fn csv_line<S: AsRef<str>>(fields: &[S]) -> String {
+    let mut line = fields
+        .iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains(|c: char| matches!(c, ',' | '"' | '\n' | '\r')) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    line.push('\n');
+    line
+}
+
 macro_rules! row {
     ($($s:expr),*$(,)?) => {
         vec![$($s.to_string()),*]
@@ -136,6 +215,7 @@ macro_rules! row {
 #[cfg(test)]
 mod test {
     use super::*;
+    use serde_json::json;
 
     #[test]
     fn renders_titles() {
@@ -164,4 +244,50 @@ mod test {
              ------------------ \nTrevor  | 132 | +123 12323223    | Pith Helmet        \n\nHatless | 2   \n"
         );
     }
+
+    fn sample_table() -> Table<'static, 'static> {
+        let mut table = Table::new();
+        table.set_titles(vec!["Name", "Entries", "Notes"]);
+        table.add_row(row!["headers", 12, "Size, in MiB"]);
+        table.add_row(row!["utxos", 3, "Said \"hi\""]);
+        table
+    }
+
+    fn render_as_string(table: &Table<'_, '_>, format: OutputFormat) -> String {
+        let mut buf = io::Cursor::new(Vec::new());
+        table.render_as(format, &mut buf).unwrap();
+        String::from_utf8(buf.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn renders_same_data_in_all_formats() {
+        let table = sample_table();
+        assert_eq!(
+            render_as_string(&table, OutputFormat::Table),
+            "Name    | Entries | Notes        \n------- | ------- | ------------ \nheaders | 12      | Size, in MiB \
+             \nutxos   | 3       | Said \"hi\"    \n"
+        );
+        let json = render_as_string(&table, OutputFormat::Json);
+        assert!(json.ends_with('\n'));
+        assert_eq!(
+            serde_json::from_str::<Value>(&json).unwrap(),
+            json!([
+                {"Name": "headers", "Entries": "12", "Notes": "Size, in MiB"},
+                {"Name": "utxos", "Entries": "3", "Notes": "Said \"hi\""},
+            ])
+        );
+        assert_eq!(
+            render_as_string(&table, OutputFormat::Csv),
+            "Name,Entries,Notes\nheaders,12,\"Size, in MiB\"\nutxos,3,\"Said \"\"hi\"\"\"\n"
+        );
+    }
+
+    #[test]
+    fn parses_output_format() {
+        assert_eq!("table".parse::<OutputFormat>().unwrap(), OutputFormat::Table);
+        assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!("csv".parse::<OutputFormat>().unwrap(), OutputFormat::Csv);
+        assert!("xml".parse::<OutputFormat>().is_err());
+        assert_eq!(OutputFormat::default(), OutputFormat::Table);
+    }
 }
@@ -0,0 +1,88 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+use prost::Message;
+use std::{convert::TryFrom, fs, io, path::Path};
+use tari_core::{proto, transactions::transaction::Transaction};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TransactionFileError {
+    #[error("Could not read transaction file: {0}")]
+    Io(#[from] io::Error),
+    #[error("File does not contain a valid JSON transaction: {0}")]
+    MalformedJson(String),
+    #[error("File does not contain a valid protobuf transaction: {0}")]
+    MalformedProtobuf(String),
+}
+
+/// Reads a serialized `Transaction` from a file. The file may contain either a JSON or a protobuf encoded transaction.
+pub fn read_transaction_file<P: AsRef<Path>>(path: P) -> Result<Transaction, TransactionFileError> {
+    let bytes = fs::read(path)?;
+    parse_transaction(&bytes)
+}
+
+/// Parses a JSON or protobuf encoded `Transaction`. Input that begins with a `{` is parsed as JSON.
+pub fn parse_transaction(bytes: &[u8]) -> Result<Transaction, TransactionFileError> {
+    let is_json = bytes
+        .iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .map(|b| *b == b'{')
+        .unwrap_or(false);
+    if is_json {
+        serde_json::from_slice(bytes).map_err(|err| TransactionFileError::MalformedJson(err.to_string()))
+    } else {
+        let tx = proto::types::Transaction::decode(bytes)
+            .map_err(|err| TransactionFileError::MalformedProtobuf(err.to_string()))?;
+        Transaction::try_from(tx).map_err(TransactionFileError::MalformedProtobuf)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tari_core::{transactions::tari_amount::MicroTari, tx};
+
+    #[test]
+    fn it_parses_json_transactions() {
+        let tx = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let json = serde_json::to_vec_pretty(&tx).unwrap();
+        assert_eq!(parse_transaction(&json).unwrap(), tx);
+    }
+
+    #[test]
+    fn it_parses_protobuf_transactions() {
+        let tx = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let mut bytes = Vec::new();
+        proto::types::Transaction::from(tx.clone()).encode(&mut bytes).unwrap();
+        assert_eq!(parse_transaction(&bytes).unwrap(), tx);
+    }
+
+    #[test]
+    fn it_errors_on_malformed_input() {
+        let err = parse_transaction(b"{\"not\": \"a transaction\"}").unwrap_err();
+        assert!(matches!(err, TransactionFileError::MalformedJson(_)));
+        let err = parse_transaction(&[0xff, 0xff, 0xff]).unwrap_err();
+        assert!(matches!(err, TransactionFileError::MalformedProtobuf(_)));
+        let err = read_transaction_file("/this/file/does/not/exist").unwrap_err();
+        assert!(matches!(err, TransactionFileError::Io(_)));
+    }
+}
@@ -45,7 +45,7 @@ fn main() {
     match main_inner() {
         Ok(_) => process::exit(0),
         Err(exit_code) => {
-            eprintln!("{:?}", exit_code);
+            exit_code.eprint_details();
             error!(
                 target: LOG_TARGET,
                 "Exiting with code ({}): {:?}",
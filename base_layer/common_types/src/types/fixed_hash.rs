@@ -0,0 +1,102 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    fmt::{Display, Formatter},
+    ops::Deref,
+};
+use tari_crypto::tari_utilities::hex::{from_hex, to_hex, Hex, HexError};
+
+pub const FIXED_HASH_SIZE: usize = 32;
+
+/// A 32-byte hash, e.g. the output of `HashDigest`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct FixedHash([u8; FIXED_HASH_SIZE]);
+
+impl FixedHash {
+    /// Returns a hash of all zeros
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; FIXED_HASH_SIZE]> for FixedHash {
+    fn from(hash: [u8; FIXED_HASH_SIZE]) -> Self {
+        Self(hash)
+    }
+}
+
+impl TryFrom<&[u8]> for FixedHash {
+    type Error = FixedHashSizeError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() != FIXED_HASH_SIZE {
+            return Err(FixedHashSizeError(bytes.len()));
+        }
+        let mut hash = [0u8; FIXED_HASH_SIZE];
+        hash.copy_from_slice(bytes);
+        Ok(Self(hash))
+    }
+}
+
+impl Deref for FixedHash {
+    type Target = [u8; FIXED_HASH_SIZE];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Hex for FixedHash {
+    fn from_hex(hex: &str) -> Result<Self, HexError> {
+        let bytes = from_hex(hex)?;
+        Self::try_from(bytes.as_slice()).map_err(|_| HexError::HexConversionError)
+    }
+
+    fn to_hex(&self) -> String {
+        to_hex(&self.0)
+    }
+}
+
+impl Display for FixedHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+/// Returned when converting a byte slice of the wrong length into a `FixedHash`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedHashSizeError(pub usize);
+
+impl Display for FixedHashSizeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Expected {} bytes for a FixedHash, got {}", FIXED_HASH_SIZE, self.0)
+    }
+}
+
+impl std::error::Error for FixedHashSizeError {}
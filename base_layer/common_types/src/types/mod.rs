@@ -34,8 +34,10 @@ use tari_crypto::{
 use tari_crypto::ristretto::dalek_range_proof::DalekRangeProofService;
 
 mod bullet_rangeproofs;
+mod fixed_hash;
 
 pub use bullet_rangeproofs::BulletRangeProof;
+pub use fixed_hash::{FixedHash, FixedHashSizeError, FIXED_HASH_SIZE};
 
 pub const BLOCK_HASH_LENGTH: usize = 32;
 pub type BlockHash = Vec<u8>;
@@ -362,11 +362,14 @@ where T: BlockchainBackend + 'static
                     request.max_weight
                 };
 
-                let transactions = async_mempool::retrieve(self.mempool.clone(), asking_weight)
-                    .await?
-                    .into_iter()
-                    .map(|tx| Arc::try_unwrap(tx).unwrap_or_else(|tx| (*tx).clone()))
-                    .collect::<Vec<_>>();
+                // The asking weight already excludes the coinbase
+                let transactions =
+                    async_mempool::transactions_for_block_template(self.mempool.clone(), asking_weight, 0)
+                        .await?
+                        .transactions
+                        .into_iter()
+                        .map(|tx| Arc::try_unwrap(tx).unwrap_or_else(|tx| (*tx).clone()))
+                        .collect::<Vec<_>>();
 
                 debug!(
                     target: LOG_TARGET,
@@ -23,6 +23,7 @@
 use crate::{
     crypto::tari_utilities::ByteArrayError,
     proto::{base_node as proto, types},
+    transactions::tari_amount::MicroTari,
 };
 
 use serde::{Deserialize, Serialize};
@@ -47,6 +48,9 @@ pub enum TxSubmissionRejectionReason {
     Orphan,
     TimeLocked,
     ValidationFailed,
+    /// The mempool is full and the fee was too low. Contains the minimum fee per gram that would currently be
+    /// accepted.
+    FeeTooLow(MicroTari),
 }
 
 impl Display for TxSubmissionRejectionReason {
@@ -57,6 +61,9 @@ impl Display for TxSubmissionRejectionReason {
             TxSubmissionRejectionReason::Orphan => "Orphan",
             TxSubmissionRejectionReason::TimeLocked => "Time Locked",
             TxSubmissionRejectionReason::ValidationFailed => "Validation Failed",
+            TxSubmissionRejectionReason::FeeTooLow(min_fee_per_gram) => {
+                return write!(fmt, "Fee Too Low (minimum fee per gram: {})", min_fee_per_gram);
+            },
             TxSubmissionRejectionReason::None => "None",
         };
         fmt.write_str(response)
@@ -75,6 +82,7 @@ impl TryFrom<proto::TxSubmissionRejectionReason> for TxSubmissionRejectionReason
             Orphan => TxSubmissionRejectionReason::Orphan,
             TimeLocked => TxSubmissionRejectionReason::TimeLocked,
             ValidationFailed => TxSubmissionRejectionReason::ValidationFailed,
+            FeeTooLow => return Err("`FeeTooLow` rejection reason requires a minimum fee per gram".to_string()),
         })
     }
 }
@@ -89,6 +97,7 @@ impl From<TxSubmissionRejectionReason> for proto::TxSubmissionRejectionReason {
             Orphan => proto::TxSubmissionRejectionReason::Orphan,
             TimeLocked => proto::TxSubmissionRejectionReason::TimeLocked,
             ValidationFailed => proto::TxSubmissionRejectionReason::ValidationFailed,
+            FeeTooLow(_) => proto::TxSubmissionRejectionReason::FeeTooLow,
         }
     }
 }
@@ -97,12 +106,17 @@ impl TryFrom<proto::TxSubmissionResponse> for TxSubmissionResponse {
     type Error = String;
 
     fn try_from(value: proto::TxSubmissionResponse) -> Result<Self, Self::Error> {
+        let rejection_reason = proto::TxSubmissionRejectionReason::from_i32(value.rejection_reason)
+            .ok_or_else(|| "Invalid or unrecognised `TxSubmissionRejectionReason` enum".to_string())?;
+        let rejection_reason = match rejection_reason {
+            proto::TxSubmissionRejectionReason::FeeTooLow => {
+                TxSubmissionRejectionReason::FeeTooLow(MicroTari::from(value.min_fee_per_gram))
+            },
+            reason => TxSubmissionRejectionReason::try_from(reason)?,
+        };
         Ok(Self {
             accepted: value.accepted,
-            rejection_reason: TxSubmissionRejectionReason::try_from(
-                proto::TxSubmissionRejectionReason::from_i32(value.rejection_reason)
-                    .ok_or_else(|| "Invalid or unrecognised `TxSubmissionRejectionReason` enum".to_string())?,
-            )?,
+            rejection_reason,
             is_synced: value.is_synced,
         })
     }
@@ -110,10 +124,15 @@ impl TryFrom<proto::TxSubmissionResponse> for TxSubmissionResponse {
 
 impl From<TxSubmissionResponse> for proto::TxSubmissionResponse {
     fn from(value: TxSubmissionResponse) -> Self {
+        let min_fee_per_gram = match value.rejection_reason {
+            TxSubmissionRejectionReason::FeeTooLow(min_fee_per_gram) => min_fee_per_gram.as_u64(),
+            _ => 0,
+        };
         Self {
             accepted: value.accepted,
             rejection_reason: proto::TxSubmissionRejectionReason::from(value.rejection_reason) as i32,
             is_synced: value.is_synced,
+            min_fee_per_gram,
         }
     }
 }
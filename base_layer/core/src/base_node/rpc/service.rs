@@ -130,6 +130,9 @@ impl<B: BlockchainBackend + 'static> BaseNodeWalletRpcService<B> {
             TxStorageResponse::NotStoredOrphan |
             TxStorageResponse::NotStoredTimeLocked |
             TxStorageResponse::NotStoredAlreadySpent |
+            TxStorageResponse::NotStoredDuplicateOutput |
+            TxStorageResponse::NotStoredFeeTooLow(_) |
+            TxStorageResponse::NotStoredZeroConfChainTooLong |
             TxStorageResponse::NotStored => TxQueryResponse {
                 location: TxLocation::NotStored as i32,
                 block_hash: None,
@@ -162,7 +165,7 @@ impl<B: BlockchainBackend + 'static> BaseNodeWalletService for BaseNodeWalletRpc
         };
 
         let response = match mempool
-            .submit_transaction(transaction.clone())
+            .submit_local_transaction(transaction.clone())
             .await
             .map_err(RpcStatus::log_internal_error(LOG_TARGET))?
         {
@@ -170,23 +173,35 @@ impl<B: BlockchainBackend + 'static> BaseNodeWalletService for BaseNodeWalletRpc
                 accepted: true,
                 rejection_reason: TxSubmissionRejectionReason::None.into(),
                 is_synced,
+                min_fee_per_gram: 0,
             },
 
             TxStorageResponse::NotStoredOrphan => TxSubmissionResponse {
                 accepted: false,
                 rejection_reason: TxSubmissionRejectionReason::Orphan.into(),
                 is_synced,
+                min_fee_per_gram: 0,
             },
             TxStorageResponse::NotStoredTimeLocked => TxSubmissionResponse {
                 accepted: false,
                 rejection_reason: TxSubmissionRejectionReason::TimeLocked.into(),
                 is_synced,
+                min_fee_per_gram: 0,
             },
 
-            TxStorageResponse::NotStored => TxSubmissionResponse {
+            TxStorageResponse::NotStoredFeeTooLow(min_fee_per_gram) => TxSubmissionResponse {
+                accepted: false,
+                rejection_reason: TxSubmissionRejectionReason::FeeTooLow.into(),
+                is_synced,
+                min_fee_per_gram: min_fee_per_gram.as_u64(),
+            },
+            TxStorageResponse::NotStored |
+            TxStorageResponse::NotStoredDuplicateOutput |
+            TxStorageResponse::NotStoredZeroConfChainTooLong => TxSubmissionResponse {
                 accepted: false,
                 rejection_reason: TxSubmissionRejectionReason::ValidationFailed.into(),
                 is_synced,
+                min_fee_per_gram: 0,
             },
             TxStorageResponse::NotStoredAlreadySpent | TxStorageResponse::ReorgPool => {
                 // Is this transaction a double spend or has this transaction been mined?
@@ -195,6 +210,7 @@ impl<B: BlockchainBackend + 'static> BaseNodeWalletService for BaseNodeWalletRpc
                         accepted: false,
                         rejection_reason: TxSubmissionRejectionReason::DoubleSpend.into(),
                         is_synced,
+                        min_fee_per_gram: 0,
                     },
                     Some(s) => {
                         // Check to see if the kernel exists in the blockchain db in which case this exact transaction
@@ -209,11 +225,13 @@ impl<B: BlockchainBackend + 'static> BaseNodeWalletService for BaseNodeWalletRpc
                                 accepted: false,
                                 rejection_reason: TxSubmissionRejectionReason::DoubleSpend.into(),
                                 is_synced,
+                                min_fee_per_gram: 0,
                             },
                             Some(_) => TxSubmissionResponse {
                                 accepted: false,
                                 rejection_reason: TxSubmissionRejectionReason::AlreadyMined.into(),
                                 is_synced,
+                                min_fee_per_gram: 0,
                             },
                         }
                     },
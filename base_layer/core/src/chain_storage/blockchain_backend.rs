@@ -16,7 +16,7 @@ use crate::{
         HorizonData,
         MmrTree,
     },
-    transactions::transaction::{TransactionInput, TransactionKernel},
+    transactions::transaction::{Transaction, TransactionInput, TransactionKernel},
 };
 use croaring::Bitmap;
 use tari_common_types::{
@@ -161,6 +161,12 @@ pub trait BlockchainBackend: Send + Sync {
 
     fn fetch_horizon_data(&self) -> Result<Option<HorizonData>, ChainStorageError>;
 
+    /// Returns the height and hash of the chain tip of the last reorg that was applied to the mempool, if any
+    fn fetch_mempool_reorg_marker(&self) -> Result<Option<(u64, HashOutput)>, ChainStorageError>;
+
+    /// Returns the orphan transactions that were persisted by the mempool, in order of arrival
+    fn fetch_mempool_orphan_txs(&self) -> Result<Vec<Transaction>, ChainStorageError>;
+
     /// Returns basic database stats for each internal database, such as number of entries and page sizes. This call may
     /// not apply to every database implementation.
     fn get_stats(&self) -> Result<DbBasicStats, ChainStorageError>;
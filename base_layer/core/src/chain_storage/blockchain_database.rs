@@ -48,7 +48,7 @@ use crate::{
     consensus::{chain_strength_comparer::ChainStrengthComparer, ConsensusConstants, ConsensusManager},
     proof_of_work::{monero_rx::MoneroPowData, PowAlgorithm, TargetDifficultyWindow},
     tari_utilities::epoch_time::EpochTime,
-    transactions::transaction::TransactionKernel,
+    transactions::transaction::{Transaction, TransactionKernel},
     validation::{
         helpers::calc_median_timestamp,
         DifficultyCalculator,
@@ -903,6 +903,46 @@ where B: BlockchainBackend
         db.fetch_horizon_data()
     }
 
+    /// Returns the height and hash of the chain tip of the last reorg that was applied to the mempool, if any
+    pub fn fetch_mempool_reorg_marker(&self) -> Result<Option<(u64, HashOutput)>, ChainStorageError> {
+        let db = self.db_read_access()?;
+        db.fetch_mempool_reorg_marker()
+    }
+
+    /// Persists the height and hash of the chain tip of the last reorg that was applied to the mempool
+    pub fn set_mempool_reorg_marker(&self, height: u64, hash: HashOutput) -> Result<(), ChainStorageError> {
+        let mut txn = DbTransaction::new();
+        txn.set_mempool_reorg_marker(height, hash);
+        self.write(txn)
+    }
+
+    /// Returns the orphan transactions that were persisted by the mempool, in order of arrival
+    pub fn fetch_mempool_orphan_txs(&self) -> Result<Vec<Transaction>, ChainStorageError> {
+        let db = self.db_read_access()?;
+        db.fetch_mempool_orphan_txs()
+    }
+
+    /// Persists the orphan transactions that were newly held by the mempool and deletes the persisted orphan
+    /// transactions with the `removed` excess signatures in a single write. `received_at` is stored with the inserted
+    /// transactions to order them when they are fetched.
+    pub fn update_mempool_orphan_txs(
+        &self,
+        inserted: Vec<Transaction>,
+        removed: Vec<Signature>,
+        received_at: u64,
+    ) -> Result<(), ChainStorageError> {
+        let mut txn = DbTransaction::new();
+        for excess_sig in removed {
+            txn.delete_mempool_orphan_tx(excess_sig);
+        }
+        for tx in inserted {
+            if let Some(excess_sig) = tx.first_kernel_excess_sig().cloned() {
+                txn.insert_mempool_orphan_tx(excess_sig, tx, received_at);
+            }
+        }
+        self.write(txn)
+    }
+
     pub fn fetch_complete_deleted_bitmap_at(
         &self,
         hash: HashOutput,
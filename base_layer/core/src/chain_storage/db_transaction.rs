@@ -22,7 +22,7 @@
 use crate::{
     blocks::{Block, BlockHeader},
     chain_storage::{error::ChainStorageError, ChainBlock, ChainHeader, MmrTree},
-    transactions::transaction::{TransactionKernel, TransactionOutput},
+    transactions::transaction::{Transaction, TransactionKernel, TransactionOutput},
 };
 use croaring::Bitmap;
 use std::{
@@ -30,7 +30,7 @@ use std::{
     fmt::{Display, Error, Formatter},
     sync::Arc,
 };
-use tari_common_types::types::{BlockHash, Commitment, HashOutput};
+use tari_common_types::types::{BlockHash, Commitment, HashOutput, Signature};
 use tari_crypto::tari_utilities::{
     hex::{to_hex, Hex},
     Hashable,
@@ -261,6 +261,30 @@ impl DbTransaction {
         self.operations
             .push(WriteOperation::InsertMoneroSeedHeight(monero_seed, height));
     }
+
+    /// Stores the height and hash of the chain tip of the last reorg that was applied to the mempool.
+    pub fn set_mempool_reorg_marker(&mut self, height: u64, hash: HashOutput) -> &mut Self {
+        self.operations
+            .push(WriteOperation::SetMempoolReorgMarker { height, hash });
+        self
+    }
+
+    /// Stores an orphan transaction held by the mempool under its excess signature. `received_at` is used to order the
+    /// orphan transactions when they are fetched.
+    pub fn insert_mempool_orphan_tx(&mut self, excess_sig: Signature, tx: Transaction, received_at: u64) -> &mut Self {
+        self.operations.push(WriteOperation::InsertMempoolOrphanTx {
+            excess_sig,
+            tx: Box::new(tx),
+            received_at,
+        });
+        self
+    }
+
+    /// Deletes the mempool orphan transaction with the given excess signature, if it is stored.
+    pub fn delete_mempool_orphan_tx(&mut self, excess_sig: Signature) -> &mut Self {
+        self.operations.push(WriteOperation::DeleteMempoolOrphanTx(excess_sig));
+        self
+    }
 }
 
 #[derive(Debug)]
@@ -331,6 +355,16 @@ pub enum WriteOperation {
         kernel_sum: Commitment,
         utxo_sum: Commitment,
     },
+    SetMempoolReorgMarker {
+        height: u64,
+        hash: HashOutput,
+    },
+    InsertMempoolOrphanTx {
+        excess_sig: Signature,
+        tx: Box<Transaction>,
+        received_at: u64,
+    },
+    DeleteMempoolOrphanTx(Signature),
 }
 
 impl fmt::Display for WriteOperation {
@@ -432,6 +466,19 @@ impl fmt::Display for WriteOperation {
             ),
             SetPruningHorizonConfig(pruning_horizon) => write!(f, "Set config: pruning horizon to {}", pruning_horizon),
             SetPrunedHeight { height, .. } => write!(f, "Set pruned height to {}", height),
+            SetMempoolReorgMarker { height, hash } => {
+                write!(f, "Set mempool reorg marker to height: {} ({})", height, hash.to_hex())
+            },
+            InsertMempoolOrphanTx { excess_sig, .. } => write!(
+                f,
+                "Insert mempool orphan transaction {}",
+                excess_sig.get_signature().to_hex()
+            ),
+            DeleteMempoolOrphanTx(excess_sig) => write!(
+                f,
+                "Delete mempool orphan transaction {}",
+                excess_sig.get_signature().to_hex()
+            ),
             DeleteHeader(height) => write!(f, "Delete header at height: {}", height),
             DeleteOrphan(hash) => write!(f, "Delete orphan with hash: {}", hash.to_hex()),
         }
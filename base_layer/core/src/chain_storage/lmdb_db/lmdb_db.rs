@@ -60,6 +60,7 @@ use crate::{
             LMDB_DB_KERNEL_EXCESS_INDEX,
             LMDB_DB_KERNEL_EXCESS_SIG_INDEX,
             LMDB_DB_KERNEL_MMR_SIZE_INDEX,
+            LMDB_DB_MEMPOOL_ORPHAN_TXS,
             LMDB_DB_METADATA,
             LMDB_DB_MONERO_SEED_HEIGHT,
             LMDB_DB_ORPHANS,
@@ -84,7 +85,7 @@ use crate::{
     crypto::tari_utilities::hex::to_hex,
     transactions::{
         aggregated_body::AggregateBody,
-        transaction::{TransactionInput, TransactionKernel, TransactionOutput},
+        transaction::{Transaction, TransactionInput, TransactionKernel, TransactionOutput},
     },
 };
 use croaring::Bitmap;
@@ -146,6 +147,7 @@ pub struct LMDBDatabase {
     orphan_header_accumulated_data_db: DatabaseRef,
     orphan_chain_tips_db: DatabaseRef,
     orphan_parent_map_index: DatabaseRef,
+    mempool_orphan_txs_db: DatabaseRef,
     _file_lock: Arc<File>,
 }
 
@@ -173,6 +175,7 @@ impl LMDBDatabase {
             monero_seed_height_db: get_database(&store, LMDB_DB_MONERO_SEED_HEIGHT)?,
             orphan_chain_tips_db: get_database(&store, LMDB_DB_ORPHAN_CHAIN_TIPS)?,
             orphan_parent_map_index: get_database(&store, LMDB_DB_ORPHAN_PARENT_MAP_INDEX)?,
+            mempool_orphan_txs_db: get_database(&store, LMDB_DB_MEMPOOL_ORPHAN_TXS)?,
             env,
             env_config: store.env_config(),
             _file_lock: Arc::new(file_lock),
@@ -369,6 +372,36 @@ impl LMDBDatabase {
                         MetadataValue::HorizonData(HorizonData::new(kernel_sum.clone(), utxo_sum.clone())),
                     )?;
                 },
+                SetMempoolReorgMarker { height, hash } => {
+                    self.set_metadata(
+                        &write_txn,
+                        MetadataKey::MempoolReorgMarker,
+                        MetadataValue::MempoolReorgMarker(*height, hash.clone()),
+                    )?;
+                },
+                InsertMempoolOrphanTx {
+                    excess_sig,
+                    tx,
+                    received_at,
+                } => {
+                    lmdb_replace(
+                        &write_txn,
+                        &self.mempool_orphan_txs_db,
+                        excess_sig_key(excess_sig).as_slice(),
+                        &(*received_at, tx.as_ref()),
+                    )?;
+                },
+                DeleteMempoolOrphanTx(excess_sig) => {
+                    let key = excess_sig_key(excess_sig);
+                    if lmdb_exists(&write_txn, &self.mempool_orphan_txs_db, key.as_slice())? {
+                        lmdb_delete(
+                            &write_txn,
+                            &self.mempool_orphan_txs_db,
+                            key.as_slice(),
+                            "mempool_orphan_txs_db",
+                        )?;
+                    }
+                },
             }
         }
         write_txn.commit()?;
@@ -376,7 +409,7 @@ impl LMDBDatabase {
         Ok(())
     }
 
-    fn all_dbs(&self) -> [(&'static str, &DatabaseRef); 19] {
+    fn all_dbs(&self) -> [(&'static str, &DatabaseRef); 20] {
         [
             ("metadata_db", &self.metadata_db),
             ("headers_db", &self.headers_db),
@@ -400,6 +433,7 @@ impl LMDBDatabase {
             ("monero_seed_height_db", &self.monero_seed_height_db),
             ("orphan_chain_tips_db", &self.orphan_chain_tips_db),
             ("orphan_parent_map_index", &self.orphan_parent_map_index),
+            ("mempool_orphan_txs_db", &self.mempool_orphan_txs_db),
         ]
     }
 
@@ -1280,6 +1314,7 @@ pub fn create_lmdb_database<P: AsRef<Path>>(path: P, config: LMDBConfig) -> Resu
         .add_database(LMDB_DB_MONERO_SEED_HEIGHT, flags)
         .add_database(LMDB_DB_ORPHAN_CHAIN_TIPS, flags)
         .add_database(LMDB_DB_ORPHAN_PARENT_MAP_INDEX, flags | db::DUPSORT)
+        .add_database(LMDB_DB_MEMPOOL_ORPHAN_TXS, flags)
         .build()
         .map_err(|err| ChainStorageError::CriticalError(format!("Could not create LMDB store:{}", err)))?;
     LMDBDatabase::new(lmdb_store, file_lock)
@@ -2098,6 +2133,20 @@ impl BlockchainBackend for LMDBDatabase {
         fetch_horizon_data(&txn, &self.metadata_db)
     }
 
+    fn fetch_mempool_reorg_marker(&self) -> Result<Option<(u64, HashOutput)>, ChainStorageError> {
+        let txn = self.read_transaction()?;
+        fetch_mempool_reorg_marker(&txn, &self.metadata_db)
+    }
+
+    fn fetch_mempool_orphan_txs(&self) -> Result<Vec<Transaction>, ChainStorageError> {
+        let txn = self.read_transaction()?;
+        let mut txs = lmdb_filter_map_values(&txn, &self.mempool_orphan_txs_db, |row: (u64, Transaction)| {
+            Ok(Some(row))
+        })?;
+        txs.sort_by_key(|(received_at, _)| *received_at);
+        Ok(txs.into_iter().map(|(_, tx)| tx).collect())
+    }
+
     fn get_stats(&self) -> Result<DbBasicStats, ChainStorageError> {
         let global = self.env.stat()?;
         let env_info = self.env.info()?;
@@ -2175,6 +2224,30 @@ fn fetch_horizon_data(txn: &ConstTransaction<'_>, db: &Database) -> Result<Optio
         }),
     }
 }
+// Fetches the mempool reorg marker from the provided metadata db.
+fn fetch_mempool_reorg_marker(
+    txn: &ConstTransaction<'_>,
+    db: &Database,
+) -> Result<Option<(u64, HashOutput)>, ChainStorageError> {
+    let k = MetadataKey::MempoolReorgMarker;
+    let val: Option<MetadataValue> = lmdb_get(txn, db, &k.as_u32())?;
+    match val {
+        Some(MetadataValue::MempoolReorgMarker(height, hash)) => Ok(Some((height, hash))),
+        None => Ok(None),
+        _ => Err(ChainStorageError::ValueNotFound {
+            entity: "ChainMetadata",
+            field: "MempoolReorgMarker",
+            value: "".to_string(),
+        }),
+    }
+}
+// Returns the key of a kernel excess signature, the public nonce followed by the signature.
+fn excess_sig_key(excess_sig: &Signature) -> Vec<u8> {
+    let mut key = Vec::<u8>::new();
+    key.extend(excess_sig.get_public_nonce().as_bytes());
+    key.extend(excess_sig.get_signature().as_bytes());
+    key
+}
 // Fetches the best block hash from the provided metadata db.
 fn fetch_best_block(txn: &ConstTransaction<'_>, db: &Database) -> Result<BlockHash, ChainStorageError> {
     let k = MetadataKey::BestBlock;
@@ -2244,6 +2317,7 @@ enum MetadataKey {
     PrunedHeight,
     HorizonData,
     DeletedBitmap,
+    MempoolReorgMarker,
 }
 
 impl MetadataKey {
@@ -2263,6 +2337,7 @@ impl fmt::Display for MetadataKey {
             MetadataKey::BestBlock => f.write_str("Chain tip block hash"),
             MetadataKey::HorizonData => f.write_str("Database info"),
             MetadataKey::DeletedBitmap => f.write_str("Deleted bitmap"),
+            MetadataKey::MempoolReorgMarker => f.write_str("Mempool reorg marker"),
         }
     }
 }
@@ -2277,6 +2352,7 @@ enum MetadataValue {
     PrunedHeight(u64),
     HorizonData(HorizonData),
     DeletedBitmap(DeletedBitmap),
+    MempoolReorgMarker(u64, BlockHash),
 }
 
 impl fmt::Display for MetadataValue {
@@ -2291,6 +2367,9 @@ impl fmt::Display for MetadataValue {
             MetadataValue::DeletedBitmap(deleted) => {
                 write!(f, "Deleted Bitmap ({} indexes)", deleted.bitmap().cardinality())
             },
+            MetadataValue::MempoolReorgMarker(height, hash) => {
+                write!(f, "Mempool reorg marker at height {} ({})", height, hash.to_hex())
+            },
         }
     }
 }
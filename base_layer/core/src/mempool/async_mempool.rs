@@ -22,7 +22,15 @@
 
 use crate::{
     blocks::Block,
-    mempool::{error::MempoolError, Mempool, StateResponse, StatsResponse, TxStorageResponse},
+    mempool::{
+        error::MempoolError,
+        BlockProcessSummary,
+        BlockTemplateTxs,
+        Mempool,
+        StateResponse,
+        StatsResponse,
+        TxStorageResponse,
+    },
     transactions::transaction::Transaction,
 };
 use std::sync::Arc;
@@ -60,10 +68,13 @@ macro_rules! make_async {
 }
 
 make_async!(insert(tx: Arc<Transaction>) -> TxStorageResponse);
-make_async!(process_published_block(published_block: Arc<Block>) -> ());
+make_async!(insert_local(tx: Arc<Transaction>) -> TxStorageResponse);
+make_async!(local_transactions() -> Vec<Arc<Transaction>>);
+make_async!(process_published_block(published_block: Arc<Block>) -> BlockProcessSummary);
 make_async!(process_reorg(removed_blocks: Vec<Arc<Block>>, new_blocks: Vec<Arc<Block>>) -> ());
 make_async!(snapshot() -> Vec<Arc<Transaction>>);
 make_async!(retrieve(total_weight: u64) -> Vec<Arc<Transaction>>);
+make_async!(transactions_for_block_template(max_weight: u64, coinbase_weight: u64) -> BlockTemplateTxs);
 make_async!(has_tx_with_excess_sig(excess_sig: Signature) -> TxStorageResponse);
 make_async!(stats() -> StatsResponse);
 make_async!(state() -> StateResponse);
@@ -25,11 +25,79 @@ use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tari_common::{configuration::seconds, NetworkConfigPath};
 
+/// Determines when orphan transactions, i.e. transactions that spend outputs which are not yet known to the mempool,
+/// are promoted to the unconfirmed pool
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanPromotionMode {
+    /// Orphans are promoted as soon as an inserted transaction provides the outputs they spend
+    Eager,
+    /// Orphans are only promoted by an explicit call to `Mempool::promote_orphans` or at the end of
+    /// `Mempool::insert_many`. This avoids re-scanning the orphans after every insert of a large batch.
+    Batched,
+}
+
+impl Default for OrphanPromotionMode {
+    fn default() -> Self {
+        OrphanPromotionMode::Eager
+    }
+}
+
+/// Selects the `TransactionWeightPolicy` that calculates the weight of the transactions held by the mempool
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightPolicyKind {
+    /// The consensus transaction weight, see `DefaultWeightPolicy`
+    Consensus,
+}
+
+impl Default for WeightPolicyKind {
+    fn default() -> Self {
+        WeightPolicyKind::Consensus
+    }
+}
+
 /// Configuration for the Mempool.
 #[derive(Clone, Copy, Deserialize, Serialize)]
 pub struct MempoolConfig {
     pub unconfirmed_pool: UnconfirmedPoolConfig,
     pub reorg_pool: ReorgPoolConfig,
+    /// The maximum number of transactions that are validated concurrently. Further inserts wait for a validation
+    /// slot to become available. Default: 4
+    pub validation_concurrency: usize,
+    /// Re-validate transactions against the current tip when they are retrieved for a block template, omitting any
+    /// that are no longer valid. This adds a validation per retrieved transaction. Default: false
+    pub revalidate_on_retrieve: bool,
+    /// The interval at which fee per gram estimates are computed and published to `Mempool::fee_estimate_receiver`
+    /// subscribers. Default: 30s
+    #[serde(with = "seconds")]
+    pub fee_estimate_interval: Duration,
+    /// Hold orphan transactions, i.e. transactions that spend outputs which are not yet known, until the outputs they
+    /// spend arrive instead of rejecting them. Default: false
+    pub hold_orphans: bool,
+    /// The maximum number of held orphan transactions. The oldest orphan is dropped when the pool is full.
+    /// Default: 1,000
+    pub orphan_pool_capacity: usize,
+    /// The time after which a held orphan transaction is dropped if its inputs have not arrived. Default: 300s
+    #[serde(with = "seconds")]
+    pub orphan_tx_ttl: Duration,
+    /// Determines when held orphan transactions are promoted to the unconfirmed pool. Default: eager
+    pub orphan_promotion_mode: OrphanPromotionMode,
+    /// Persist the held orphan transactions, if `hold_orphans` is set and the mempool was created with an orphan
+    /// store, so that they can still be promoted after a restart. Default: false
+    pub persist_orphans: bool,
+    /// Only accept transactions that spend or create a commitment in the watch set set with `Mempool::set_watch_set`.
+    /// This is intended for light and pruned nodes that are not interested in the full mempool. Default: false
+    pub watch_only: bool,
+    /// The number of recent admission decisions that are kept in the audit log returned by
+    /// `Mempool::recent_admissions`. The audit log is disabled if this is 0. Default: 0
+    pub admission_audit_log_size: usize,
+    /// The maximum number of unconfirmed ancestors a transaction may have in the unconfirmed pool. Transactions that
+    /// would extend a zero-conf chain beyond this length are rejected regardless of their fee. Default: 25
+    pub max_zero_conf_chain_length: usize,
+    /// The policy used to calculate the weight of transactions, which determines their priority, their selection for
+    /// a block and the mempool stats. Default: consensus
+    pub weight_policy: WeightPolicyKind,
 }
 
 impl Default for MempoolConfig {
@@ -37,6 +105,18 @@ impl Default for MempoolConfig {
         Self {
             unconfirmed_pool: UnconfirmedPoolConfig::default(),
             reorg_pool: ReorgPoolConfig::default(),
+            validation_concurrency: consts::MEMPOOL_VALIDATION_CONCURRENCY,
+            revalidate_on_retrieve: false,
+            fee_estimate_interval: consts::MEMPOOL_FEE_ESTIMATE_INTERVAL,
+            hold_orphans: false,
+            orphan_pool_capacity: consts::MEMPOOL_ORPHAN_POOL_CAPACITY,
+            orphan_tx_ttl: consts::MEMPOOL_ORPHAN_TX_TTL,
+            orphan_promotion_mode: OrphanPromotionMode::default(),
+            persist_orphans: false,
+            watch_only: false,
+            admission_audit_log_size: 0,
+            max_zero_conf_chain_length: consts::MEMPOOL_MAX_ZERO_CONF_CHAIN_LENGTH,
+            weight_policy: WeightPolicyKind::default(),
         }
     }
 }
@@ -58,6 +138,10 @@ pub struct MempoolServiceConfig {
     pub initial_sync_num_peers: usize,
     /// The maximum number of transactions to sync in a single sync session Default: 10_000
     pub initial_sync_max_transactions: usize,
+    /// The number of peers that outbound requests (e.g. `get_stats`, `get_tx_state_by_excess_sig`) are sent to. The
+    /// first successful response is returned, so an unresponsive peer does not cause the request to fail. Each
+    /// additional peer adds a request to the network traffic. Default: 1
+    pub outbound_request_num_peers: usize,
 }
 
 impl Default for MempoolServiceConfig {
@@ -66,6 +150,7 @@ impl Default for MempoolServiceConfig {
             request_timeout: consts::MEMPOOL_SERVICE_REQUEST_TIMEOUT,
             initial_sync_num_peers: 2,
             initial_sync_max_transactions: 10_000,
+            outbound_request_num_peers: 1,
         }
     }
 }
@@ -79,8 +164,17 @@ impl NetworkConfigPath for MempoolServiceConfig {
 #[cfg(test)]
 mod test {
     use super::{
-        consts::{MEMPOOL_REORG_POOL_CACHE_TTL, MEMPOOL_REORG_POOL_STORAGE_CAPACITY},
+        consts::{
+            MEMPOOL_FEE_ESTIMATE_INTERVAL,
+            MEMPOOL_MAX_ZERO_CONF_CHAIN_LENGTH,
+            MEMPOOL_ORPHAN_POOL_CAPACITY,
+            MEMPOOL_ORPHAN_TX_TTL,
+            MEMPOOL_REORG_POOL_CACHE_TTL,
+            MEMPOOL_REORG_POOL_STORAGE_CAPACITY,
+            MEMPOOL_VALIDATION_CONCURRENCY,
+        },
         MempoolConfig,
+        OrphanPromotionMode,
     };
     use config::Config;
     use tari_common::DefaultConfigLoader;
@@ -102,6 +196,17 @@ mod test {
         );
         // [ ] mempool.mainnet, [ ]  mempool, [X] Default = 10s
         assert_eq!(my_config.reorg_pool.tx_ttl, MEMPOOL_REORG_POOL_CACHE_TTL);
+        assert_eq!(my_config.validation_concurrency, MEMPOOL_VALIDATION_CONCURRENCY);
+        assert!(!my_config.revalidate_on_retrieve);
+        assert_eq!(my_config.fee_estimate_interval, MEMPOOL_FEE_ESTIMATE_INTERVAL);
+        assert!(!my_config.hold_orphans);
+        assert_eq!(my_config.orphan_pool_capacity, MEMPOOL_ORPHAN_POOL_CAPACITY);
+        assert_eq!(my_config.orphan_tx_ttl, MEMPOOL_ORPHAN_TX_TTL);
+        assert_eq!(my_config.orphan_promotion_mode, OrphanPromotionMode::Eager);
+        assert!(!my_config.persist_orphans);
+        assert!(!my_config.watch_only);
+        assert_eq!(my_config.admission_audit_log_size, 0);
+        assert_eq!(my_config.max_zero_conf_chain_length, MEMPOOL_MAX_ZERO_CONF_CHAIN_LENGTH);
 
         config
             .set("mempool.mainnet.unconfirmed_pool.storage_capacity", 20)
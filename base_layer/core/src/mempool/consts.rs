@@ -33,5 +33,29 @@ pub const MEMPOOL_REORG_POOL_STORAGE_CAPACITY: usize = 5_000;
 /// The time-to-live duration used for transactions stored in the ReorgPool
 pub const MEMPOOL_REORG_POOL_CACHE_TTL: Duration = Duration::from_secs(300);
 
+/// The maximum number of transactions that are held until the outputs they spend mature
+pub const MEMPOOL_PENDING_MATURITY_CAPACITY: usize = 1_000;
+
+/// The number of recently processed blocks that are remembered to recognise a block that is delivered again or that
+/// builds on an earlier processed block
+pub const MEMPOOL_PROCESSED_BLOCKS_CAPACITY: usize = 10;
+
+/// The maximum number of orphan transactions that are held until the outputs they spend are known
+pub const MEMPOOL_ORPHAN_POOL_CAPACITY: usize = 1_000;
+/// The time-to-live duration used for orphan transactions
+pub const MEMPOOL_ORPHAN_TX_TTL: Duration = Duration::from_secs(300);
+
+/// The maximum number of unconfirmed ancestors a transaction may have in the unconfirmed pool
+pub const MEMPOOL_MAX_ZERO_CONF_CHAIN_LENGTH: usize = 25;
+
+/// The maximum number of transactions that can be validated concurrently before being admitted to the mempool
+pub const MEMPOOL_VALIDATION_CONCURRENCY: usize = 4;
+
+/// The interval at which fee per gram estimates are published
+pub const MEMPOOL_FEE_ESTIMATE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The number of blocks for which fee per gram estimates are published
+pub const MEMPOOL_FEE_ESTIMATE_NUM_BLOCKS: usize = 3;
+
 /// The allocated waiting time for a request waiting for service responses from the mempools of remote base nodes.
 pub const MEMPOOL_SERVICE_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
@@ -25,6 +25,8 @@ use crate::{
     mempool::{reorg_pool::ReorgPoolError, unconfirmed_pool::UnconfirmedPoolError},
     transactions::transaction::TransactionError,
 };
+use tari_common_types::types::BlockHash;
+use tari_crypto::tari_utilities::hex::Hex;
 use tari_service_framework::reply_channel::TransportChannelError;
 use thiserror::Error;
 
@@ -44,6 +46,17 @@ pub enum MempoolError {
     BlockingTaskSpawnError(String),
     #[error("A problem has been encountered with the storage backend: `{0}`")]
     BackendError(String),
+    #[error("Mempool snapshot could not be serialized or deserialized: `{0}`")]
+    SnapshotSerializationError(String),
     #[error("Internal reply channel error: `{0}`")]
     TransportChannelError(#[from] TransportChannelError),
+    #[error(
+        "Block does not build on the last processed block. Expected previous block `{}`, got `{}`",
+        .expected_prev.to_hex(),
+        .got_prev.to_hex()
+    )]
+    UnexpectedBlock {
+        expected_prev: BlockHash,
+        got_prev: BlockHash,
+    },
 }
@@ -23,18 +23,43 @@
 use crate::{
     blocks::Block,
     mempool::{
+        consts::MEMPOOL_FEE_ESTIMATE_NUM_BLOCKS,
         error::MempoolError,
         mempool_storage::MempoolStorage,
+        orphan_store::OrphanStore,
+        reorg_marker::ReorgMarkerStore,
+        AdmissionRecord,
+        BlockProcessSummary,
+        BlockTemplateTxs,
+        FeeCursor,
+        FeePerGramStat,
         MempoolConfig,
+        MempoolDifference,
+        MempoolHealth,
+        MempoolTransactionInfo,
+        NextBlockPreview,
+        OrphanPromotionMode,
         StateResponse,
         StatsResponse,
+        TransactionWeightPolicy,
         TxStorageResponse,
+        ValidationOutcome,
     },
-    transactions::transaction::Transaction,
+    transactions::{tari_amount::MicroTari, transaction::Transaction},
     validation::MempoolTransactionValidation,
 };
-use std::sync::{Arc, RwLock};
-use tari_common_types::types::Signature;
+use chrono::{DateTime, Utc};
+use log::*;
+use std::{
+    collections::HashSet,
+    sync::{Arc, Condvar, Mutex, RwLock},
+    time::Duration,
+};
+use tari_common_types::types::{Commitment, FixedHash, Signature};
+use tari_shutdown::ShutdownSignal;
+use tokio::{sync::watch, time};
+
+const LOG_TARGET: &str = "c::mp::mempool";
 
 /// The Mempool consists of an Unconfirmed Transaction Pool, Pending Pool, Orphan Pool and Reorg Pool and is responsible
 /// for managing and maintaining all unconfirmed transactions have not yet been included in a block, and transactions
@@ -42,31 +67,198 @@ use tari_common_types::types::Signature;
 #[derive(Clone)]
 pub struct Mempool {
     pool_storage: Arc<RwLock<MempoolStorage>>,
+    validator: Arc<dyn MempoolTransactionValidation>,
+    validation_permits: Arc<ValidationPermits>,
+    fee_estimate_interval: Duration,
+    fee_estimate_publisher: Arc<watch::Sender<Vec<FeePerGramStat>>>,
+    fee_estimate_receiver: watch::Receiver<Vec<FeePerGramStat>>,
+    min_fee_publisher: Arc<watch::Sender<MicroTari>>,
+    min_fee_receiver: watch::Receiver<MicroTari>,
 }
 
 impl Mempool {
-    /// Create a new Mempool with an UnconfirmedPool, OrphanPool, PendingPool and ReOrgPool.
+    /// Create a new Mempool with an UnconfirmedPool, OrphanPool, PendingPool and ReOrgPool. Transactions are weighed
+    /// with the policy selected by `MempoolConfig::weight_policy`. Use `MempoolBuilder` to persist the reorg marker and
+    /// the orphan transactions.
     pub fn new(config: MempoolConfig, validator: Arc<dyn MempoolTransactionValidation>) -> Self {
+        MempoolBuilder::new(config, validator).build()
+    }
+
+    fn with_storage(
+        config: MempoolConfig,
+        validator: Arc<dyn MempoolTransactionValidation>,
+        storage: MempoolStorage,
+    ) -> Self {
+        let (fee_estimate_publisher, fee_estimate_receiver) = watch::channel(Vec::new());
+        let (min_fee_publisher, min_fee_receiver) = watch::channel(storage.min_fee_per_gram());
         Self {
-            pool_storage: Arc::new(RwLock::new(MempoolStorage::new(config, validator))),
+            pool_storage: Arc::new(RwLock::new(storage)),
+            validator,
+            validation_permits: Arc::new(ValidationPermits::new(config.validation_concurrency)),
+            fee_estimate_interval: config.fee_estimate_interval,
+            fee_estimate_publisher: Arc::new(fee_estimate_publisher),
+            fee_estimate_receiver,
+            min_fee_publisher: Arc::new(min_fee_publisher),
+            min_fee_receiver,
         }
     }
 
     /// Insert an unconfirmed transaction into the Mempool. The transaction *MUST* have passed through the validation
     /// pipeline already and will thus always be internally consistent by this stage
+    ///
+    /// Validation is performed without holding the storage lock, and at most `validation_concurrency` transactions
+    /// are validated at a time. This call blocks until a validation slot is available.
     pub fn insert(&self, tx: Arc<Transaction>) -> Result<TxStorageResponse, MempoolError> {
-        self.pool_storage
-            .write()
+        self.insert_with_origin(tx, false)
+    }
+
+    /// Insert a locally-originated transaction, e.g. a transaction broadcast by a wallet connected to this node, into
+    /// the Mempool. Local transactions are exempt from eviction and are re-inserted as local transactions after a
+    /// reorg.
+    pub fn insert_local(&self, tx: Arc<Transaction>) -> Result<TxStorageResponse, MempoolError> {
+        self.insert_with_origin(tx, true)
+    }
+
+    /// Insert a batch of unconfirmed transactions into the Mempool, returning the storage response for each
+    /// transaction. In `OrphanPromotionMode::Batched`, orphans are promoted once after all transactions have been
+    /// inserted.
+    pub fn insert_many(&self, txs: Vec<Arc<Transaction>>) -> Result<Vec<TxStorageResponse>, MempoolError> {
+        let responses = txs
+            .into_iter()
+            .map(|tx| self.insert_with_origin(tx, false))
+            .collect::<Result<Vec<_>, _>>()?;
+        if self.config()?.orphan_promotion_mode == OrphanPromotionMode::Batched {
+            self.promote_orphans()?;
+        }
+        Ok(responses)
+    }
+
+    /// Returns the active configuration.
+    pub fn config(&self) -> Result<MempoolConfig, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .config())
+    }
+
+    /// Replaces the configuration at runtime, returning the number of unconfirmed transactions that were evicted.
+    ///
+    /// A lowered `unconfirmed_pool.storage_capacity` takes effect immediately by evicting the lowest priority
+    /// transactions, local transactions excepted, and a lowered `admission_audit_log_size` immediately discards the
+    /// oldest admission records. The remaining pool and admission settings apply from the next insert, retrieval or
+    /// published block. `reorg_pool`, `validation_concurrency`, `fee_estimate_interval` and `weight_policy` are only
+    /// read when the mempool is created and are not changed.
+    pub fn set_config(&self, config: MempoolConfig) -> Result<usize, MempoolError> {
+        self.update_storage(|storage| storage.set_config(config))
+    }
+
+    /// Promotes the held orphan transactions whose inputs are now in the unconfirmed pool, returning the number of
+    /// promoted transactions. Orphans that have outlived `MempoolConfig::orphan_tx_ttl` are dropped. In
+    /// `OrphanPromotionMode::Eager` the orphans of an inserted transaction are promoted automatically.
+    pub fn promote_orphans(&self) -> Result<usize, MempoolError> {
+        self.update_storage(|storage| storage.promote_orphans())?
+    }
+
+    /// Promotes the held time-locked transactions that are valid at `current_height` to the unconfirmed pool, returning
+    /// the number of promoted transactions. This is done for every processed published block, so it only needs to be
+    /// called when the chain height advances without a published block being processed.
+    pub fn recheck_timelocked(&self, current_height: u64) -> Result<usize, MempoolError> {
+        self.update_storage(|storage| storage.recheck_timelocked(current_height))?
+    }
+
+    /// Returns the number of orphan transactions that are held until the outputs they spend are known.
+    pub fn orphan_count(&self) -> Result<usize, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
             .map_err(|e| MempoolError::BackendError(e.to_string()))?
-            .insert(tx)
+            .orphan_count())
     }
 
-    /// Update the Mempool based on the received published block.
-    pub fn process_published_block(&self, published_block: Arc<Block>) -> Result<(), MempoolError> {
+    /// Validates a transaction against the Mempool's validator and the current chain tip, returning the outcome an
+    /// insert would have had. This is a dry run, the transaction is not stored.
+    pub fn validate_tx(&self, tx: &Transaction) -> Result<ValidationOutcome, MempoolError> {
+        let validation_result = {
+            let _permit = self.validation_permits.acquire()?;
+            self.validator.validate(tx)
+        };
         self.pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .validation_outcome(tx, validation_result)
+    }
+
+    fn insert_with_origin(&self, tx: Arc<Transaction>, is_local: bool) -> Result<TxStorageResponse, MempoolError> {
+        let (state_generation, validation_result) = {
+            let _permit = self.validation_permits.acquire()?;
+            let state_generation = self
+                .pool_storage
+                .read()
+                .map_err(|e| MempoolError::BackendError(e.to_string()))?
+                .state_generation();
+            (state_generation, self.validator.validate(&tx))
+        };
+
+        self.update_storage(|storage| {
+            if storage.state_generation() == state_generation {
+                storage.insert_validated(tx, validation_result, is_local)
+            } else if is_local {
+                // The chain state changed while the transaction was being validated, the result may be stale
+                storage.insert_local(tx)
+            } else {
+                storage.insert(tx)
+            }
+        })?
+    }
+
+    /// Applies `f` to the storage while holding the write lock and publishes the minimum fee per gram to
+    /// `subscribe_min_fee` subscribers if it changed as a result.
+    fn update_storage<T, F>(&self, f: F) -> Result<T, MempoolError>
+    where F: FnOnce(&mut MempoolStorage) -> T {
+        let mut storage = self
+            .pool_storage
             .write()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?;
+        let result = f(&mut storage);
+        let min_fee_per_gram = storage.min_fee_per_gram();
+        if *self.min_fee_receiver.borrow() != min_fee_per_gram {
+            let _ = self.min_fee_publisher.send(min_fee_per_gram);
+        }
+        Ok(result)
+    }
+
+    /// Evicts the unconfirmed transactions that spend outputs which no longer exist at the current chain tip, for e.g.
+    /// after a deep reorg, along with the transactions that depend on them. Returns the number of evicted transactions.
+    pub fn evict_by_missing_inputs(&self) -> Result<usize, MempoolError> {
+        self.update_storage(|storage| storage.evict_by_missing_inputs())?
+    }
+
+    /// Update the Mempool based on the received published block, returning a summary of the effects the block had on
+    /// the pool. A block that was already processed is skipped. Returns `MempoolError::UnexpectedBlock` if the block
+    /// does not build on a recently processed block.
+    pub fn process_published_block(&self, published_block: Arc<Block>) -> Result<BlockProcessSummary, MempoolError> {
+        self.update_storage(|storage| storage.process_published_block(published_block))?
+    }
+
+    /// Replays the blocks, in order, through `process_published_block` on a fresh in-memory copy of the Mempool and
+    /// returns the stats of the copy. This is a debugging aid to tell whether an accounting discrepancy is caused by
+    /// the processing logic or by how block events were delivered. The Mempool itself is not modified and is only
+    /// locked while it is copied.
+    ///
+    /// Transactions that are re-validated during the replay, e.g. held time-locked transactions, are validated against
+    /// the current chain tip rather than the chain state at the replayed block, so the replay is only accurate for
+    /// blocks close to the tip.
+    pub fn replay_blocks(&self, blocks: Vec<Arc<Block>>) -> Result<StatsResponse, MempoolError> {
+        let mut replay = self
+            .pool_storage
+            .read()
             .map_err(|e| MempoolError::BackendError(e.to_string()))?
-            .process_published_block(published_block)
+            .replay_copy();
+        for block in blocks {
+            replay.process_published_block(block)?;
+        }
+        replay.stats()
     }
 
     /// In the event of a ReOrg, resubmit all ReOrged transactions into the Mempool and process each newly introduced
@@ -76,10 +268,7 @@ impl Mempool {
         removed_blocks: Vec<Arc<Block>>,
         new_blocks: Vec<Arc<Block>>,
     ) -> Result<(), MempoolError> {
-        self.pool_storage
-            .write()
-            .map_err(|e| MempoolError::BackendError(e.to_string()))?
-            .process_reorg(removed_blocks, new_blocks)
+        self.update_storage(|storage| storage.process_reorg(removed_blocks, new_blocks))?
     }
 
     /// Returns all unconfirmed transaction stored in the Mempool, except the transactions stored in the ReOrgPool.
@@ -91,13 +280,102 @@ impl Mempool {
             .snapshot()
     }
 
+    /// Returns all unconfirmed transactions stored in the Mempool along with their weight, age and origin, except the
+    /// transactions stored in the ReOrgPool.
+    pub fn snapshot_with_metadata(&self) -> Result<Vec<MempoolTransactionInfo>, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .snapshot_with_metadata())
+    }
+
+    /// Returns up to `limit` unconfirmed transactions following `cursor`, in descending order of fee priority, along
+    /// with the cursor for the next page. The returned cursor is None once the last page has been reached.
+    pub fn retrieve_page(
+        &self,
+        cursor: Option<FeeCursor>,
+        limit: usize,
+    ) -> Result<(Vec<MempoolTransactionInfo>, Option<FeeCursor>), MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .retrieve_page(cursor, limit))
+    }
+
+    /// Removes all unconfirmed transactions from the Mempool, returning the number of transactions that were removed.
+    pub fn clear(&self) -> Result<usize, MempoolError> {
+        self.update_storage(|storage| storage.clear())
+    }
+
+    /// Serializes the unconfirmed transactions in the Mempool so that they can be persisted and restored using
+    /// `import_snapshot`, e.g. across a node restart.
+    pub fn export_snapshot(&self) -> Result<Vec<u8>, MempoolError> {
+        self.pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .export_snapshot()
+    }
+
+    /// Re-validates and inserts the transactions contained in a snapshot created by `export_snapshot`. Transactions
+    /// that are no longer valid against the current tip are dropped. Returns the number of restored transactions.
+    pub fn import_snapshot(&self, bytes: &[u8]) -> Result<usize, MempoolError> {
+        self.update_storage(|storage| storage.import_snapshot(bytes))?
+    }
+
+    /// Reconstructs the internal output and output commitment indexes from the current pool contents. This should be
+    /// called after bulk loads so that duplicate output and zero-conf input detection remain consistent.
+    pub fn rebuild_indexes(&self) -> Result<(), MempoolError> {
+        self.update_storage(|storage| storage.rebuild_indexes())
+    }
+
     /// Returns a list of transaction ranked by transaction priority up to a given weight.
     /// Only transactions that fit into a block will be returned
     pub fn retrieve(&self, total_weight: u64) -> Result<Vec<Arc<Transaction>>, MempoolError> {
+        self.update_storage(|storage| storage.retrieve(total_weight))?
+    }
+
+    /// Previews the transactions that would be included in a block of `total_weight`, i.e. the transactions selected by
+    /// `transactions_for_block_template`, along with their total weight and fees.
+    pub fn next_block_preview(&self, total_weight: u64) -> Result<NextBlockPreview, MempoolError> {
         self.pool_storage
-            .write()
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .next_block_preview(total_weight)
+    }
+
+    /// Returns the transactions to include in a new block template of `max_weight`, of which `coinbase_weight` is
+    /// reserved for the coinbase, in the order selected by `retrieve`. The totals needed to build the template, i.e.
+    /// the fees, weight and offset sums, are returned with them.
+    pub fn transactions_for_block_template(
+        &self,
+        max_weight: u64,
+        coinbase_weight: u64,
+    ) -> Result<BlockTemplateTxs, MempoolError> {
+        self.pool_storage
+            .read()
             .map_err(|e| MempoolError::BackendError(e.to_string()))?
-            .retrieve(total_weight)
+            .transactions_for_block_template(max_weight, coinbase_weight)
+    }
+
+    /// Returns the total fees of the transactions that `transactions_for_block_template` selects for a block of
+    /// `max_block_weight`, i.e. the fees a miner could currently collect from the Mempool in a single block.
+    pub fn collectable_fees(&self, max_block_weight: u64, coinbase_weight: u64) -> Result<MicroTari, MempoolError> {
+        Ok(self
+            .transactions_for_block_template(max_block_weight, coinbase_weight)?
+            .total_fees)
+    }
+
+    /// Returns a list of transactions that fit into a block of `max_weight`. Unlike `retrieve`, which is strictly
+    /// ordered by fee priority, `min_reserved_weight_for_low_fee` of the block weight is reserved for the oldest
+    /// transactions that would not otherwise be selected, so that low fee transactions are not starved indefinitely.
+    pub fn retrieve_fair(
+        &self,
+        max_weight: u64,
+        min_reserved_weight_for_low_fee: u64,
+    ) -> Result<Vec<Arc<Transaction>>, MempoolError> {
+        self.update_storage(|storage| storage.retrieve_fair(max_weight, min_reserved_weight_for_low_fee))?
     }
 
     /// Check if the specified transaction is stored in the Mempool.
@@ -108,6 +386,131 @@ impl Mempool {
             .has_tx_with_excess_sig(excess_sig)
     }
 
+    /// Returns the time at which the specified transaction was first seen by the local Mempool, or None if the
+    /// transaction is not in the unconfirmed pool.
+    pub fn first_seen(&self, excess_sig: &Signature) -> Result<Option<DateTime<Utc>>, MempoolError> {
+        self.pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .first_seen(excess_sig)
+    }
+
+    /// Returns the number of unconfirmed transactions in each age bucket, where a bucket is given by the minimum age of
+    /// its transactions and the largest bucket holds all older transactions. Many old transactions indicate that the
+    /// pool is stalled.
+    pub fn age_histogram(&self, buckets: &[Duration]) -> Result<Vec<(Duration, usize)>, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .age_histogram(buckets))
+    }
+
+    /// Returns the locally-originated transactions stored in the unconfirmed pool.
+    pub fn local_transactions(&self) -> Result<Vec<Arc<Transaction>>, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .local_transactions())
+    }
+
+    /// Returns the unconfirmed transactions that create an output with the given commitment. This allows a wallet to
+    /// detect an incoming payment before it is mined.
+    pub fn txs_creating_commitment(&self, commitment: &Commitment) -> Result<Vec<Arc<Transaction>>, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .txs_creating_commitment(commitment))
+    }
+
+    /// Boosts the priority of the unconfirmed transaction with the given excess signature as if it paid an additional
+    /// `priority_boost` fee per gram, e.g. to prioritize an operator's own transactions. The boost applies to the
+    /// ordering used by `retrieve` and to eviction, and is kept until the transaction leaves the Mempool. It only
+    /// affects which transactions this node includes in the blocks it builds, not consensus. Returns false if the
+    /// transaction is not in the unconfirmed pool.
+    pub fn set_priority_override(
+        &self,
+        excess_sig: Signature,
+        priority_boost: MicroTari,
+    ) -> Result<bool, MempoolError> {
+        self.update_storage(|storage| storage.set_priority_override(&excess_sig, priority_boost))?
+    }
+
+    /// Returns the commitments of the inputs spent by the unconfirmed transactions in the Mempool. This allows the
+    /// inputs of a block to be checked for conflicts with the Mempool without retrieving the transactions.
+    pub fn spent_commitments(&self) -> Result<HashSet<Commitment>, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .spent_commitments())
+    }
+
+    /// Replaces the set of commitments that a transaction must spend or create to be accepted by the Mempool when
+    /// `MempoolConfig::watch_only` is set. Transactions that are already stored are not affected.
+    pub fn set_watch_set(&self, commitments: Vec<Commitment>) -> Result<(), MempoolError> {
+        self.pool_storage
+            .write()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .set_watch_set(commitments);
+        Ok(())
+    }
+
+    /// Returns up to `limit` of the most recent admission decisions, oldest first. This is empty unless
+    /// `MempoolConfig::admission_audit_log_size` is set.
+    pub fn recent_admissions(&self, limit: usize) -> Result<Vec<AdmissionRecord>, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .recent_admissions(limit))
+    }
+
+    /// Returns the length of the longest chain of unconfirmed ancestors of the specified transaction, which a wallet
+    /// can use to assess the risk of accepting a zero-conf transaction. Returns None if the transaction is not in
+    /// the unconfirmed pool.
+    pub fn dependency_depth(&self, excess_sig: &Signature) -> Result<Option<usize>, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .dependency_depth(excess_sig))
+    }
+
+    /// Returns the excess signature of the transaction that would be evicted if one more transaction was accepted, i.e.
+    /// the lowest priority transaction that did not originate locally. Returns None if the unconfirmed pool is below
+    /// capacity.
+    pub fn next_eviction_candidate(&self) -> Result<Option<Signature>, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .next_eviction_candidate())
+    }
+
+    /// Returns an order-independent fingerprint of the unconfirmed transactions in the Mempool, computed from their
+    /// excess signatures. Two mempools that hold the same unconfirmed transactions have the same content hash, so it
+    /// can be used to cheaply compare the mempools of different nodes.
+    pub fn content_hash(&self) -> Result<FixedHash, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .content_hash())
+    }
+
+    /// Compares the unconfirmed transactions in the Mempool against the excess signatures of the unconfirmed
+    /// transactions of a remote mempool, returning the transactions that only one of the two has.
+    pub fn difference(&self, remote_excess_sigs: &[Signature]) -> Result<MempoolDifference, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .difference(remote_excess_sigs))
+    }
+
     /// Gathers and returns the stats of the Mempool.
     pub fn stats(&self) -> Result<StatsResponse, MempoolError> {
         self.pool_storage
@@ -116,6 +519,16 @@ impl Mempool {
             .stats()
     }
 
+    /// Returns the transactions that were rejected because they spend outputs that have not matured yet, e.g. a
+    /// coinbase. These are promoted to the unconfirmed pool on the first published block at which they are valid.
+    pub fn pending_coinbase_maturity(&self) -> Result<Vec<Arc<Transaction>>, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .pending_coinbase_maturity())
+    }
+
     /// Gathers and returns a breakdown of all the transaction in the Mempool.
     pub fn state(&self) -> Result<StateResponse, MempoolError> {
         self.pool_storage
@@ -123,4 +536,678 @@ impl Mempool {
             .map_err(|e| MempoolError::BackendError(e.to_string()))?
             .state()
     }
+
+    /// Returns the number of unconfirmed transactions with a fee per gram in the half-open range `[min, max)`.
+    pub fn count_in_fee_range(&self, min: MicroTari, max: MicroTari) -> Result<usize, MempoolError> {
+        Ok(self.txs_in_fee_range(min, max)?.len())
+    }
+
+    /// Returns the unconfirmed transactions with a fee per gram in the half-open range `[min, max)`, in order of fee
+    /// priority from highest to lowest.
+    pub fn txs_in_fee_range(&self, min: MicroTari, max: MicroTari) -> Result<Vec<Arc<Transaction>>, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .txs_in_fee_range(min, max))
+    }
+
+    /// Returns the fee per gram statistics of up to `count` blocks of `target_block_weight`, if blocks were filled
+    /// with the unconfirmed transactions in order of fee priority.
+    pub fn fee_per_gram_stats(
+        &self,
+        count: usize,
+        target_block_weight: u64,
+    ) -> Result<Vec<FeePerGramStat>, MempoolError> {
+        Ok(self
+            .pool_storage
+            .read()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?
+            .fee_per_gram_stats(count, target_block_weight))
+    }
+
+    /// Returns a receiver of the fee per gram estimates that are periodically published by `run_fee_estimator`.
+    pub fn fee_estimate_receiver(&self) -> watch::Receiver<Vec<FeePerGramStat>> {
+        self.fee_estimate_receiver.clone()
+    }
+
+    /// Returns a receiver of the minimum fee per gram a transaction needs to pay to be stored in the Mempool. The value
+    /// is zero until the unconfirmed pool reaches capacity and is updated whenever it changes, e.g. when a low fee
+    /// transaction is evicted or a block frees up space.
+    pub fn subscribe_min_fee(&self) -> watch::Receiver<MicroTari> {
+        self.min_fee_receiver.clone()
+    }
+
+    /// Computes the fee per gram estimates for the next few blocks of `target_block_weight` every
+    /// `fee_estimate_interval` and publishes them to `fee_estimate_receiver` subscribers if they have changed. Runs
+    /// until the shutdown signal is triggered.
+    pub async fn run_fee_estimator(self, target_block_weight: u64, mut shutdown: ShutdownSignal) {
+        let mut interval = time::interval(self.fee_estimate_interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    match self.fee_per_gram_stats(MEMPOOL_FEE_ESTIMATE_NUM_BLOCKS, target_block_weight) {
+                        Ok(stats) => {
+                            if *self.fee_estimate_receiver.borrow() != stats {
+                                let _ = self.fee_estimate_publisher.send(stats);
+                            }
+                        },
+                        Err(err) => warn!(target: LOG_TARGET, "Failed to compute fee per gram estimates: {}", err),
+                    }
+                },
+                _ = shutdown.wait() => {
+                    debug!(target: LOG_TARGET, "Fee estimator shutting down");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Returns a readiness report of the Mempool. The Mempool is reported as unresponsive if its storage cannot be
+    /// accessed.
+    pub fn health_check(&self) -> MempoolHealth {
+        self.pool_storage
+            .read()
+            .ok()
+            .and_then(|storage| storage.health_check().ok())
+            .unwrap_or(MempoolHealth {
+                is_responsive: false,
+                unconfirmed_txs: 0,
+                unconfirmed_pool_capacity: 0,
+                reorg_txs: 0,
+                reorg_pool_capacity: 0,
+                orphan_txs: 0,
+                orphan_pool_capacity: 0,
+                time_since_last_block: None,
+            })
+    }
+}
+
+/// Creates a `Mempool` with optional persistent stores and an optional custom weight policy.
+pub struct MempoolBuilder {
+    config: MempoolConfig,
+    validator: Arc<dyn MempoolTransactionValidation>,
+    weight_policy: Option<Arc<dyn TransactionWeightPolicy>>,
+    reorg_marker_store: Option<Arc<dyn ReorgMarkerStore>>,
+    orphan_store: Option<Arc<dyn OrphanStore>>,
+}
+
+impl MempoolBuilder {
+    pub fn new(config: MempoolConfig, validator: Arc<dyn MempoolTransactionValidation>) -> Self {
+        Self {
+            config,
+            validator,
+            weight_policy: None,
+            reorg_marker_store: None,
+            orphan_store: None,
+        }
+    }
+
+    /// Use `weight_policy` instead of the policy selected by `MempoolConfig::weight_policy` to calculate the weight of
+    /// transactions, which determines their priority, their selection for a block and the mempool stats.
+    pub fn with_weight_policy(mut self, weight_policy: Arc<dyn TransactionWeightPolicy>) -> Self {
+        self.weight_policy = Some(weight_policy);
+        self
+    }
+
+    /// Persist the marker of the last applied reorg in `reorg_marker_store`, so that a reorg that is delivered again
+    /// after a restart is detected and not applied twice.
+    pub fn with_reorg_marker_store(mut self, reorg_marker_store: Arc<dyn ReorgMarkerStore>) -> Self {
+        self.reorg_marker_store = Some(reorg_marker_store);
+        self
+    }
+
+    /// Persist the orphan transactions in `orphan_store` if `MempoolConfig::hold_orphans` and
+    /// `MempoolConfig::persist_orphans` are set. Previously persisted orphans are reloaded when the mempool is built.
+    pub fn with_orphan_store(mut self, orphan_store: Arc<dyn OrphanStore>) -> Self {
+        self.orphan_store = Some(orphan_store);
+        self
+    }
+
+    pub fn build(self) -> Mempool {
+        let mut storage = MempoolStorage::new(self.config, self.validator.clone());
+        if let Some(weight_policy) = self.weight_policy {
+            storage = storage.with_weight_policy(weight_policy);
+        }
+        if let Some(reorg_marker_store) = self.reorg_marker_store {
+            storage = storage.with_reorg_marker_store(reorg_marker_store);
+        }
+        // Orphans are reloaded by the orphan store, so it is set once the weight policy is final
+        if let Some(orphan_store) = self.orphan_store {
+            storage = storage.with_orphan_store(orphan_store);
+        }
+        Mempool::with_storage(self.config, self.validator, storage)
+    }
+}
+
+/// A counting semaphore that limits the number of transactions that are validated concurrently.
+struct ValidationPermits {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+impl ValidationPermits {
+    fn new(num_permits: usize) -> Self {
+        Self {
+            available: Mutex::new(num_permits.max(1)),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Blocks until a permit is available. The permit is returned when the `ValidationPermit` is dropped.
+    fn acquire(&self) -> Result<ValidationPermit<'_>, MempoolError> {
+        let mut available = self
+            .available
+            .lock()
+            .map_err(|e| MempoolError::BackendError(e.to_string()))?;
+        while *available == 0 {
+            available = self
+                .released
+                .wait(available)
+                .map_err(|e| MempoolError::BackendError(e.to_string()))?;
+        }
+        *available -= 1;
+        Ok(ValidationPermit { permits: self })
+    }
+}
+
+struct ValidationPermit<'a> {
+    permits: &'a ValidationPermits,
+}
+
+impl Drop for ValidationPermit<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut available) = self.permits.available.lock() {
+            *available += 1;
+            self.permits.released.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        consensus::ConsensusManagerBuilder,
+        mempool::{reorg_pool::ReorgPoolConfig, unconfirmed_pool::UnconfirmedPoolConfig, AdmissionSource},
+        test_helpers::create_orphan_block,
+        tx,
+        validation::{mocks::MockValidator, ValidationError},
+    };
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+    };
+    use tari_common::configuration::Network;
+    use tari_shutdown::Shutdown;
+    use tokio::task;
+
+    fn create_mempool(unconfirmed_capacity: usize, reorg_capacity: usize) -> Mempool {
+        let config = MempoolConfig {
+            unconfirmed_pool: UnconfirmedPoolConfig {
+                storage_capacity: unconfirmed_capacity,
+                ..Default::default()
+            },
+            reorg_pool: ReorgPoolConfig {
+                storage_capacity: reorg_capacity,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        Mempool::new(config, Arc::new(MockValidator::new(true)))
+    }
+
+    #[test]
+    fn health_check() {
+        let mempool = create_mempool(2, 1);
+        let health = mempool.health_check();
+        assert!(health.is_healthy());
+        assert!(!health.is_unconfirmed_pool_saturated());
+        assert!(!health.is_reorg_pool_saturated());
+        assert!(!health.is_orphan_pool_saturated());
+        assert!(health.time_since_last_block.is_none());
+
+        let tx1 = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let tx2 = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        mempool.insert(Arc::new(tx1.clone())).unwrap();
+        mempool.insert(Arc::new(tx2.clone())).unwrap();
+        let health = mempool.health_check();
+        assert_eq!(health.unconfirmed_txs, 2);
+        assert!(health.is_unconfirmed_pool_saturated());
+        assert!(!health.is_healthy());
+
+        let consensus = ConsensusManagerBuilder::new(Network::LocalNet).build();
+        let block = create_orphan_block(1, vec![tx1, tx2], &consensus);
+        mempool.process_published_block(Arc::new(block)).unwrap();
+        let health = mempool.health_check();
+        assert_eq!(health.unconfirmed_txs, 0);
+        assert!(health.is_reorg_pool_saturated());
+        assert!(health.time_since_last_block.is_some());
+        assert!(health.is_healthy());
+    }
+
+    struct UnknownInputsValidator;
+
+    impl MempoolTransactionValidation for UnknownInputsValidator {
+        fn validate(&self, _transaction: &Transaction) -> Result<(), ValidationError> {
+            Err(ValidationError::UnknownInputs(vec![vec![0u8; 32]]))
+        }
+    }
+
+    #[test]
+    fn health_check_reports_orphan_pool_saturation() {
+        let config = MempoolConfig {
+            hold_orphans: true,
+            orphan_pool_capacity: 2,
+            ..Default::default()
+        };
+        let mempool = Mempool::new(config, Arc::new(UnknownInputsValidator));
+        let health = mempool.health_check();
+        assert_eq!(health.orphan_pool_capacity, 2);
+        assert!(!health.is_orphan_pool_saturated());
+
+        for _ in 0..3 {
+            let tx = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+            assert_eq!(
+                mempool.insert(Arc::new(tx)).unwrap(),
+                TxStorageResponse::NotStoredOrphan
+            );
+        }
+        let health = mempool.health_check();
+        assert_eq!(health.orphan_txs, 2);
+        assert!(health.is_orphan_pool_saturated());
+        assert!(health.is_healthy());
+    }
+
+    #[test]
+    fn first_seen() {
+        let mempool = create_mempool(10, 10);
+        let tx = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let excess_sig = tx.first_kernel_excess_sig().unwrap().clone();
+        assert!(mempool.first_seen(&excess_sig).unwrap().is_none());
+
+        let before = Utc::now();
+        mempool.insert(Arc::new(tx)).unwrap();
+        let first_seen = mempool.first_seen(&excess_sig).unwrap().unwrap();
+        let tolerance = chrono::Duration::seconds(1);
+        assert!(first_seen >= before - tolerance);
+        assert!(first_seen <= Utc::now() + tolerance);
+
+        // Re-inserting the transaction keeps the time it was first seen
+        thread::sleep(Duration::from_millis(20));
+        mempool.process_reorg(vec![], vec![]).unwrap();
+        assert_eq!(mempool.first_seen(&excess_sig).unwrap(), Some(first_seen));
+    }
+
+    #[test]
+    fn export_import_snapshot() {
+        let mempool = create_mempool(10, 10);
+        let tx1 = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let tx2 = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        mempool.insert(Arc::new(tx1.clone())).unwrap();
+        mempool.insert(Arc::new(tx2.clone())).unwrap();
+        let snapshot = mempool.export_snapshot().unwrap();
+
+        // Transactions that are no longer valid are dropped
+        let validator = MockValidator::new(false);
+        let mempool = Mempool::new(MempoolConfig::default(), Arc::new(validator));
+        assert_eq!(mempool.import_snapshot(&snapshot).unwrap(), 0);
+        assert!(mempool.snapshot().unwrap().is_empty());
+
+        let mempool = create_mempool(10, 10);
+        assert!(mempool.snapshot().unwrap().is_empty());
+        assert_eq!(mempool.import_snapshot(&snapshot).unwrap(), 2);
+        let restored = mempool.snapshot().unwrap();
+        assert_eq!(restored.len(), 2);
+        assert!(restored.iter().any(|tx| **tx == tx1));
+        assert!(restored.iter().any(|tx| **tx == tx2));
+
+        assert!(mempool.import_snapshot(&[1, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn local_txs_are_not_evicted() {
+        let mempool = create_mempool(2, 10);
+        let local_tx = tx!(MicroTari(10_000), fee: MicroTari(5), inputs: 2, outputs: 1).0;
+        let foreign_tx = tx!(MicroTari(10_000), fee: MicroTari(10), inputs: 2, outputs: 1).0;
+        let high_fee_tx = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let local_sig = local_tx.first_kernel_excess_sig().unwrap().clone();
+        let foreign_sig = foreign_tx.first_kernel_excess_sig().unwrap().clone();
+        let high_fee_sig = high_fee_tx.first_kernel_excess_sig().unwrap().clone();
+
+        mempool.insert_local(Arc::new(local_tx)).unwrap();
+        mempool.insert(Arc::new(foreign_tx)).unwrap();
+        assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 2);
+        assert_eq!(mempool.stats().unwrap().local_txs, 1);
+
+        // The pool is full, the lowest priority foreign tx is evicted rather than the (lower priority) local tx
+        mempool.insert(Arc::new(high_fee_tx)).unwrap();
+        assert!(mempool.has_tx_with_excess_sig(local_sig.clone()).unwrap().is_stored());
+        assert!(mempool.has_tx_with_excess_sig(high_fee_sig).unwrap().is_stored());
+        assert!(!mempool.has_tx_with_excess_sig(foreign_sig).unwrap().is_stored());
+
+        let local_txs = mempool.local_transactions().unwrap();
+        assert_eq!(local_txs.len(), 1);
+        assert_eq!(local_txs[0].first_kernel_excess_sig(), Some(&local_sig));
+        assert_eq!(mempool.stats().unwrap().local_txs, 1);
+    }
+
+    #[test]
+    fn low_fee_rejection_returns_min_fee_per_gram() {
+        let mempool = create_mempool(2, 10);
+        let low_priority_tx = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        let high_priority_tx = tx!(MicroTari(10_000), fee: MicroTari(30), inputs: 2, outputs: 1).0;
+        let rejected_tx = tx!(MicroTari(10_000), fee: MicroTari(5), inputs: 2, outputs: 1).0;
+        let expected_min_fee_per_gram =
+            MicroTari::from(low_priority_tx.calculate_ave_fee_per_gram().floor() as u64 + 1);
+
+        mempool.insert(Arc::new(low_priority_tx)).unwrap();
+        mempool.insert(Arc::new(high_priority_tx)).unwrap();
+        assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 2);
+
+        let response = mempool.insert(Arc::new(rejected_tx)).unwrap();
+        assert_eq!(
+            response,
+            TxStorageResponse::NotStoredFeeTooLow(expected_min_fee_per_gram)
+        );
+        assert!(!response.is_stored());
+        assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 2);
+    }
+
+    #[test]
+    fn watch_only_accepts_txs_touching_the_watch_set() {
+        let config = MempoolConfig {
+            watch_only: true,
+            ..Default::default()
+        };
+        let mempool = Mempool::new(config, Arc::new(MockValidator::new(true)));
+        let spends_watched = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let creates_watched = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let unrelated = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+
+        // Nothing is accepted until a watch set is set
+        let response = mempool.insert(Arc::new(spends_watched.clone())).unwrap();
+        assert_eq!(response, TxStorageResponse::NotStored);
+
+        mempool
+            .set_watch_set(vec![
+                spends_watched.body.inputs()[1].commitment.clone(),
+                creates_watched.body.outputs()[0].commitment.clone(),
+            ])
+            .unwrap();
+        let response = mempool.insert(Arc::new(unrelated)).unwrap();
+        assert_eq!(response, TxStorageResponse::NotStored);
+        let response = mempool.insert(Arc::new(spends_watched)).unwrap();
+        assert_eq!(response, TxStorageResponse::UnconfirmedPool);
+        let response = mempool.insert(Arc::new(creates_watched)).unwrap();
+        assert_eq!(response, TxStorageResponse::UnconfirmedPool);
+        assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 2);
+    }
+
+    #[test]
+    fn recent_admissions_are_recorded() {
+        let config = MempoolConfig {
+            unconfirmed_pool: UnconfirmedPoolConfig {
+                storage_capacity: 2,
+                ..Default::default()
+            },
+            admission_audit_log_size: 3,
+            ..Default::default()
+        };
+        let mempool = Mempool::new(config, Arc::new(MockValidator::new(true)));
+        let local_tx = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        let foreign_tx = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        let low_fee_tx = tx!(MicroTari(10_000), fee: MicroTari(5), inputs: 2, outputs: 1).0;
+        let high_fee_tx = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let excess_sigs = [&foreign_tx, &local_tx, &low_fee_tx, &high_fee_tx]
+            .iter()
+            .map(|tx| tx.first_kernel_excess_sig().cloned())
+            .collect::<Vec<_>>();
+
+        let before = Utc::now();
+        mempool.insert(Arc::new(foreign_tx)).unwrap();
+        mempool.insert_local(Arc::new(local_tx)).unwrap();
+        mempool.insert(Arc::new(low_fee_tx)).unwrap();
+        mempool.insert(Arc::new(high_fee_tx)).unwrap();
+
+        // Only the 3 most recent decisions are retained
+        let admissions = mempool.recent_admissions(10).unwrap();
+        assert_eq!(admissions.len(), 3);
+        assert_eq!(
+            admissions.iter().map(|a| a.excess_sig.clone()).collect::<Vec<_>>(),
+            excess_sigs[1..].to_vec()
+        );
+        assert_eq!(admissions[0].outcome, TxStorageResponse::UnconfirmedPool);
+        assert!(matches!(
+            admissions[1].outcome,
+            TxStorageResponse::NotStoredFeeTooLow(_)
+        ));
+        assert_eq!(admissions[2].outcome, TxStorageResponse::UnconfirmedPool);
+        assert_eq!(admissions.iter().map(|a| a.source).collect::<Vec<_>>(), vec![
+            AdmissionSource::Local,
+            AdmissionSource::Gossip,
+            AdmissionSource::Gossip
+        ]);
+        assert!(admissions.iter().all(|a| a.timestamp >= before));
+
+        let admissions = mempool.recent_admissions(1).unwrap();
+        assert_eq!(admissions.len(), 1);
+        assert_eq!(admissions[0].excess_sig, excess_sigs[3]);
+
+        // The audit log is disabled by default
+        let mempool = create_mempool(10, 10);
+        let tx = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        mempool.insert_local(Arc::new(tx)).unwrap();
+        assert!(mempool.recent_admissions(10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn priority_override_is_retrieved_first() {
+        let mempool = create_mempool(10, 10);
+        let low_fee_tx = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        let high_fee_tx1 = tx!(MicroTari(10_000), fee: MicroTari(30), inputs: 2, outputs: 1).0;
+        let high_fee_tx2 = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let low_fee_sig = low_fee_tx.first_kernel_excess_sig().unwrap().clone();
+        let weight = low_fee_tx.calculate_weight();
+
+        mempool.insert(Arc::new(low_fee_tx)).unwrap();
+        mempool.insert(Arc::new(high_fee_tx1)).unwrap();
+        mempool.insert(Arc::new(high_fee_tx2)).unwrap();
+        let retrieved = mempool.retrieve(weight).unwrap();
+        assert_eq!(retrieved.len(), 1);
+        assert_ne!(retrieved[0].first_kernel_excess_sig(), Some(&low_fee_sig));
+
+        assert!(mempool
+            .set_priority_override(low_fee_sig.clone(), MicroTari(1_000))
+            .unwrap());
+        let retrieved = mempool.retrieve(weight).unwrap();
+        assert_eq!(retrieved.len(), 1);
+        assert_eq!(retrieved[0].first_kernel_excess_sig(), Some(&low_fee_sig));
+
+        // The override is removed along with the transaction
+        mempool.clear().unwrap();
+        assert!(!mempool.set_priority_override(low_fee_sig, MicroTari(1_000)).unwrap());
+    }
+
+    #[test]
+    fn next_eviction_candidate_is_lowest_fee_per_gram() {
+        let mempool = create_mempool(3, 10);
+        let tx1 = tx!(MicroTari(10_000), fee: MicroTari(30), inputs: 2, outputs: 1).0;
+        let tx2 = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        let tx3 = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let tx2_sig = tx2.first_kernel_excess_sig().unwrap().clone();
+
+        mempool.insert(Arc::new(tx1)).unwrap();
+        mempool.insert(Arc::new(tx2)).unwrap();
+        assert_eq!(mempool.next_eviction_candidate().unwrap(), None);
+
+        mempool.insert(Arc::new(tx3)).unwrap();
+        assert_eq!(mempool.next_eviction_candidate().unwrap(), Some(tx2_sig));
+    }
+
+    #[test]
+    fn collectable_fees_are_the_fees_of_the_selected_txs() {
+        let mempool = create_mempool(10, 10);
+        assert_eq!(mempool.collectable_fees(u64::MAX, 1_000).unwrap(), MicroTari(0));
+
+        let tx1 = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        let tx2 = tx!(MicroTari(10_000), fee: MicroTari(30), inputs: 2, outputs: 1).0;
+        let tx3 = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let weight = tx1.calculate_weight();
+        let coinbase_weight = 1_000;
+        for tx in vec![tx1, tx2, tx3] {
+            mempool.insert(Arc::new(tx)).unwrap();
+        }
+
+        // Only the two highest priority transactions fit in the block
+        let max_block_weight = 2 * weight + coinbase_weight;
+        let selected = mempool.retrieve(max_block_weight - coinbase_weight).unwrap();
+        assert_eq!(selected.len(), 2);
+        let expected = selected.iter().map(|tx| tx.body.get_total_fee()).sum::<MicroTari>();
+        assert_eq!(
+            mempool.collectable_fees(max_block_weight, coinbase_weight).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn set_config_evicts_to_fit_lowered_capacity() {
+        let mempool = create_mempool(10, 10);
+        let tx1 = tx!(MicroTari(10_000), fee: MicroTari(30), inputs: 2, outputs: 1).0;
+        let tx2 = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        let tx3 = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let sigs = vec![
+            tx1.first_kernel_excess_sig().unwrap().clone(),
+            tx2.first_kernel_excess_sig().unwrap().clone(),
+            tx3.first_kernel_excess_sig().unwrap().clone(),
+        ];
+        for tx in vec![tx1, tx2, tx3] {
+            mempool.insert(Arc::new(tx)).unwrap();
+        }
+
+        let mut config = mempool.config().unwrap();
+        config.unconfirmed_pool.storage_capacity = 1;
+        config.reorg_pool.storage_capacity = 1;
+        assert_eq!(mempool.set_config(config).unwrap(), 2);
+        assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 1);
+        assert_eq!(
+            mempool.has_tx_with_excess_sig(sigs[2].clone()).unwrap(),
+            TxStorageResponse::UnconfirmedPool
+        );
+        assert_eq!(
+            mempool.has_tx_with_excess_sig(sigs[0].clone()).unwrap(),
+            TxStorageResponse::NotStored
+        );
+        assert_eq!(
+            mempool.has_tx_with_excess_sig(sigs[1].clone()).unwrap(),
+            TxStorageResponse::NotStored
+        );
+
+        let config = mempool.config().unwrap();
+        assert_eq!(config.unconfirmed_pool.storage_capacity, 1);
+        // The reorg pool is only configured when the mempool is created
+        assert_eq!(config.reorg_pool.storage_capacity, 10);
+    }
+
+    #[test]
+    fn min_fee_changes_are_published() {
+        let mempool = create_mempool(2, 10);
+        let min_fee = mempool.subscribe_min_fee();
+        assert_eq!(*min_fee.borrow(), MicroTari(0));
+
+        let low_priority_tx = tx!(MicroTari(10_000), fee: MicroTari(20), inputs: 2, outputs: 1).0;
+        let high_priority_tx = tx!(MicroTari(10_000), fee: MicroTari(30), inputs: 2, outputs: 1).0;
+        let highest_priority_tx = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let low_min_fee = MicroTari::from(low_priority_tx.calculate_ave_fee_per_gram().floor() as u64 + 1);
+        let high_min_fee = MicroTari::from(high_priority_tx.calculate_ave_fee_per_gram().floor() as u64 + 1);
+
+        mempool.insert(Arc::new(low_priority_tx)).unwrap();
+        assert_eq!(*min_fee.borrow(), MicroTari(0));
+
+        // The pool is now full, so the lowest priority transaction sets the minimum
+        mempool.insert(Arc::new(high_priority_tx)).unwrap();
+        assert_eq!(*min_fee.borrow(), low_min_fee);
+
+        // Evicting the lowest priority transaction pushes the minimum up
+        mempool.insert(Arc::new(highest_priority_tx)).unwrap();
+        assert_eq!(*min_fee.borrow(), high_min_fee);
+
+        mempool.clear().unwrap();
+        assert_eq!(*min_fee.borrow(), MicroTari(0));
+    }
+
+    #[tokio::test]
+    async fn fee_estimates_are_published() {
+        let config = MempoolConfig {
+            fee_estimate_interval: Duration::from_millis(10),
+            ..Default::default()
+        };
+        let mempool = Mempool::new(config, Arc::new(MockValidator::new(true)));
+        let mut fee_estimates = mempool.fee_estimate_receiver();
+        assert!(fee_estimates.borrow().is_empty());
+        let shutdown = Shutdown::new();
+        task::spawn(mempool.clone().run_fee_estimator(u64::MAX, shutdown.to_signal()));
+
+        let tx = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+        let fee_per_gram = MicroTari::from(tx.body.get_total_fee().as_u64() / tx.calculate_weight());
+        mempool.insert(Arc::new(tx)).unwrap();
+
+        time::timeout(Duration::from_secs(5), fee_estimates.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        let estimates = fee_estimates.borrow().clone();
+        assert_eq!(
+            estimates,
+            mempool
+                .fee_per_gram_stats(MEMPOOL_FEE_ESTIMATE_NUM_BLOCKS, u64::MAX)
+                .unwrap()
+        );
+        assert_eq!(estimates.len(), 1);
+        assert_eq!(estimates[0].order, 0);
+        assert_eq!(estimates[0].min_fee_per_gram, fee_per_gram);
+        assert_eq!(estimates[0].avg_fee_per_gram, fee_per_gram);
+        assert_eq!(estimates[0].max_fee_per_gram, fee_per_gram);
+    }
+
+    #[derive(Default)]
+    struct ConcurrencyTrackingValidator {
+        current: AtomicUsize,
+        max_observed: AtomicUsize,
+    }
+
+    impl MempoolTransactionValidation for ConcurrencyTrackingValidator {
+        fn validate(&self, _: &Transaction) -> Result<(), ValidationError> {
+            let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_observed.fetch_max(current, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20));
+            self.current.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn concurrent_inserts_respect_validation_concurrency() {
+        let validator = Arc::new(ConcurrencyTrackingValidator::default());
+        let config = MempoolConfig {
+            validation_concurrency: 2,
+            ..Default::default()
+        };
+        let mempool = Mempool::new(config, validator.clone());
+
+        let handles = (0..8)
+            .map(|_| {
+                let mempool = mempool.clone();
+                let tx = tx!(MicroTari(10_000), fee: MicroTari(50), inputs: 2, outputs: 1).0;
+                thread::spawn(move || mempool.insert(Arc::new(tx)).unwrap())
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), TxStorageResponse::UnconfirmedPool);
+        }
+
+        assert!(validator.max_observed.load(Ordering::SeqCst) <= 2);
+        assert_eq!(validator.current.load(Ordering::SeqCst), 0);
+        assert_eq!(mempool.snapshot().unwrap().len(), 8);
+    }
 }
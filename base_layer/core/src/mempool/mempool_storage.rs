@@ -23,102 +23,829 @@
 use crate::{
     blocks::Block,
     mempool::{
+        config::OrphanPromotionMode,
+        consts::{MEMPOOL_PENDING_MATURITY_CAPACITY, MEMPOOL_PROCESSED_BLOCKS_CAPACITY},
         error::MempoolError,
+        orphan_pool::OrphanPool,
+        orphan_store::OrphanStore,
+        pending_maturity_pool::PendingMaturityPool,
+        reorg_marker::{ReorgMarker, ReorgMarkerStore},
         reorg_pool::ReorgPool,
         unconfirmed_pool::UnconfirmedPool,
+        AdmissionRecord,
+        AdmissionSource,
+        BlockProcessSummary,
+        BlockTemplateTxs,
+        FeeCursor,
+        FeePerGramStat,
         MempoolConfig,
+        MempoolDifference,
+        MempoolHealth,
+        MempoolTransactionInfo,
+        NextBlockPreview,
+        RejectReason,
         StateResponse,
         StatsResponse,
+        TransactionWeightPolicy,
         TxStorageResponse,
+        ValidationOutcome,
     },
-    transactions::transaction::Transaction,
+    transactions::{tari_amount::MicroTari, transaction::Transaction},
     validation::{MempoolTransactionValidation, ValidationError},
 };
+use chrono::{DateTime, Utc};
 use log::*;
-use std::sync::Arc;
-use tari_common_types::types::Signature;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    slice,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tari_common_types::types::{BlockHash, Commitment, FixedHash, HashOutput, Signature};
 use tari_crypto::tari_utilities::{hex::Hex, Hashable};
 
 pub const LOG_TARGET: &str = "c::mp::mempool_storage";
 
+/// The decision whether to store a validated transaction, see `MempoolStorage::check_admission`
+enum Admission {
+    /// The transaction can be stored in the unconfirmed pool, depending on the given unconfirmed outputs
+    Accept(Option<Vec<HashOutput>>),
+    /// The transaction spends the given outputs, which are not known
+    Orphan(Vec<HashOutput>),
+    /// The transaction spends immature outputs or has a kernel lock height in the future
+    TimeLocked,
+    /// The transaction failed validation
+    Invalid(ValidationError),
+    /// The transaction is valid but is not stored, for the given reason
+    Rejected(TxStorageResponse),
+}
+
 /// The Mempool consists of an Unconfirmed Transaction Pool and Reorg Pool and is responsible
 /// for managing and maintaining all unconfirmed transactions have not yet been included in a block, and transactions
 /// that have recently been included in a block.
 pub struct MempoolStorage {
+    config: MempoolConfig,
     unconfirmed_pool: UnconfirmedPool,
     reorg_pool: ReorgPool,
     validator: Arc<dyn MempoolTransactionValidation>,
+    last_block_processed_at: Option<Instant>,
+    /// The hashes of the most recently processed blocks, the last processed block last
+    processed_blocks: VecDeque<BlockHash>,
+    state_generation: u64,
+    local_txs: HashSet<Signature>,
+    first_seen: HashMap<Signature, DateTime<Utc>>,
+    pending_maturity: PendingMaturityPool,
+    orphans: OrphanPool,
+    reorg_marker_store: Option<Arc<dyn ReorgMarkerStore>>,
+    orphan_store: Option<Arc<dyn OrphanStore>>,
+    persisted_orphans: HashSet<Signature>,
+    watch_set: HashSet<Commitment>,
+    admissions: VecDeque<AdmissionRecord>,
+    weight_policy: Arc<dyn TransactionWeightPolicy>,
+    reject_reasons: HashMap<RejectReason, usize>,
 }
 
 impl MempoolStorage {
-    /// Create a new Mempool with an UnconfirmedPool and ReOrgPool.
+    /// Create a new Mempool with an UnconfirmedPool and ReOrgPool that weighs transactions with the policy selected
+    /// by `MempoolConfig::weight_policy`.
     pub fn new(config: MempoolConfig, validators: Arc<dyn MempoolTransactionValidation>) -> Self {
+        let weight_policy = config.weight_policy.build();
         Self {
-            unconfirmed_pool: UnconfirmedPool::new(config.unconfirmed_pool),
+            config,
+            unconfirmed_pool: UnconfirmedPool::new(config.unconfirmed_pool).with_weight_policy(weight_policy.clone()),
             reorg_pool: ReorgPool::new(config.reorg_pool),
             validator: validators,
+            last_block_processed_at: None,
+            processed_blocks: VecDeque::new(),
+            state_generation: 0,
+            local_txs: HashSet::new(),
+            first_seen: HashMap::new(),
+            pending_maturity: PendingMaturityPool::new(MEMPOOL_PENDING_MATURITY_CAPACITY),
+            orphans: OrphanPool::new(config.orphan_pool_capacity),
+            reorg_marker_store: None,
+            orphan_store: None,
+            persisted_orphans: HashSet::new(),
+            watch_set: HashSet::new(),
+            admissions: VecDeque::new(),
+            weight_policy,
+            reject_reasons: HashMap::new(),
+        }
+    }
+
+    /// Returns the active configuration.
+    pub fn config(&self) -> MempoolConfig {
+        self.config
+    }
+
+    /// Replaces the configuration, see `Mempool::set_config`. The reorg pool configuration and the weight policy are
+    /// not changed. Returns the number of unconfirmed transactions that were evicted to fit a lowered storage
+    /// capacity.
+    pub fn set_config(&mut self, mut config: MempoolConfig) -> usize {
+        config.reorg_pool = self.config.reorg_pool;
+        config.weight_policy = self.config.weight_policy;
+        self.config = config;
+        let evicted = self.unconfirmed_pool.set_config(config.unconfirmed_pool);
+        for tx in &evicted {
+            if let Some(sig) = tx.first_kernel_excess_sig() {
+                self.local_txs.remove(sig);
+            }
+        }
+        if self.orphans.set_capacity(config.orphan_pool_capacity) > 0 {
+            self.persist_orphans();
         }
+        let capacity = config.admission_audit_log_size;
+        while self.admissions.len() > capacity {
+            self.admissions.pop_front();
+        }
+        if !evicted.is_empty() {
+            debug!(
+                target: LOG_TARGET,
+                "Evicted {} unconfirmed transaction(s) to fit the new storage capacity of {}",
+                evicted.len(),
+                config.unconfirmed_pool.storage_capacity
+            );
+        }
+        self.state_generation += 1;
+        evicted.len()
+    }
+
+    /// Use the given policy to calculate transaction weights instead of the one selected by
+    /// `MempoolConfig::weight_policy`. This must be set before any transactions are inserted.
+    pub fn with_weight_policy(mut self, weight_policy: Arc<dyn TransactionWeightPolicy>) -> Self {
+        self.unconfirmed_pool =
+            UnconfirmedPool::new(self.config.unconfirmed_pool).with_weight_policy(weight_policy.clone());
+        self.weight_policy = weight_policy;
+        self
+    }
+
+    /// Persist the marker of the last applied reorg in the given store, so that a reorg that is delivered again after a
+    /// restart is not applied twice.
+    pub fn with_reorg_marker_store(mut self, reorg_marker_store: Arc<dyn ReorgMarkerStore>) -> Self {
+        self.reorg_marker_store = Some(reorg_marker_store);
+        self
+    }
+
+    /// Persist the orphan transactions in the given store if `MempoolConfig::hold_orphans` and
+    /// `MempoolConfig::persist_orphans` are set. Orphans that were persisted previously are re-validated and reloaded,
+    /// up to the orphan pool capacity, and are promoted once the outputs they spend become known.
+    pub fn with_orphan_store(mut self, orphan_store: Arc<dyn OrphanStore>) -> Self {
+        if self.config.hold_orphans && self.config.persist_orphans {
+            match orphan_store.fetch_orphans() {
+                Ok(orphans) => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Reloaded {} persisted orphan transaction(s)",
+                        orphans.len()
+                    );
+                    self.persisted_orphans = orphans
+                        .iter()
+                        .filter_map(|tx| tx.first_kernel_excess_sig().cloned())
+                        .collect();
+                    for tx in orphans {
+                        let tx = Arc::new(tx);
+                        let validation_result = self.validator.validate(&tx);
+                        if let Err(err) = self.store_validated(tx, validation_result, false) {
+                            warn!(
+                                target: LOG_TARGET,
+                                "Failed to reload persisted orphan transaction: {}", err
+                            );
+                        }
+                    }
+                },
+                Err(err) => warn!(
+                    target: LOG_TARGET,
+                    "Failed to reload persisted orphan transactions: {}", err
+                ),
+            }
+        }
+        self.orphan_store = Some(orphan_store);
+        // Removes the reloaded orphans that were not held again, e.g. because they are now valid
+        self.persist_orphans();
+        self
     }
 
     /// Insert an unconfirmed transaction into the Mempool. The transaction *MUST* have passed through the validation
     /// pipeline already and will thus always be internally consistent by this stage
     pub fn insert(&mut self, tx: Arc<Transaction>) -> Result<TxStorageResponse, MempoolError> {
+        let validation_result = self.validator.validate(&tx);
+        self.insert_validated(tx, validation_result, false)
+    }
+
+    /// Insert a locally-originated transaction into the Mempool. Local transactions are exempt from eviction and are
+    /// re-inserted as local transactions after a reorg.
+    pub fn insert_local(&mut self, tx: Arc<Transaction>) -> Result<TxStorageResponse, MempoolError> {
+        let validation_result = self.validator.validate(&tx);
+        self.insert_validated(tx, validation_result, true)
+    }
+
+    /// Insert an unconfirmed transaction into the Mempool using the result of a validation that was performed without
+    /// holding the storage lock. The caller is responsible for re-validating the transaction if the
+    /// `state_generation` has changed since the validation was performed.
+    pub fn insert_validated(
+        &mut self,
+        tx: Arc<Transaction>,
+        validation_result: Result<(), ValidationError>,
+        is_local: bool,
+    ) -> Result<TxStorageResponse, MempoolError> {
+        let excess_sig = tx.first_kernel_excess_sig().cloned();
+        let validation_failure = match &validation_result {
+            Err(ValidationError::MaxTransactionWeightExceeded) => Some(RejectReason::ExceedsWeight),
+            Err(_) => Some(RejectReason::Invalid),
+            Ok(()) => None,
+        };
+        let response = self.admit_validated(tx, validation_result, is_local)?;
+        // Transactions that are held until they can be promoted have not been rejected
+        let is_held = excess_sig
+            .as_ref()
+            .map(|sig| self.orphans.contains(sig) || self.pending_maturity.contains(sig))
+            .unwrap_or(false);
+        self.record_admission(excess_sig, &response, is_local);
+        let reject_reason = match &response {
+            TxStorageResponse::NotStoredOrphan => Some(RejectReason::Orphan),
+            TxStorageResponse::NotStoredTimeLocked => Some(RejectReason::TimeLocked),
+            TxStorageResponse::NotStoredAlreadySpent => Some(RejectReason::DoubleSpend),
+            TxStorageResponse::NotStoredDuplicateOutput => Some(RejectReason::DuplicateOutput),
+            TxStorageResponse::NotStoredZeroConfChainTooLong => Some(RejectReason::ZeroConfChainTooLong),
+            TxStorageResponse::NotStoredFeeTooLow(_) => Some(RejectReason::FeeTooLow),
+            TxStorageResponse::NotStored => validation_failure,
+            TxStorageResponse::UnconfirmedPool | TxStorageResponse::ReorgPool => None,
+        };
+        if let Some(reason) = reject_reason.filter(|_| !is_held) {
+            *self.reject_reasons.entry(reason).or_insert(0) += 1;
+        }
+        Ok(response)
+    }
+
+    fn admit_validated(
+        &mut self,
+        tx: Arc<Transaction>,
+        validation_result: Result<(), ValidationError>,
+        is_local: bool,
+    ) -> Result<TxStorageResponse, MempoolError> {
+        let num_expired = self.orphans.remove_expired(self.config.orphan_tx_ttl);
+        // Only the orphans waiting for the outputs of this transaction can be promoted by inserting it
+        let output_hashes =
+            if self.config.orphan_promotion_mode == OrphanPromotionMode::Eager && !self.orphans.is_empty() {
+                tx.body.outputs().iter().map(|output| output.hash()).collect()
+            } else {
+                Vec::new()
+            };
+        let response = self.store_validated(tx, validation_result, is_local)?;
+        let num_promoted = if response == TxStorageResponse::UnconfirmedPool && !output_hashes.is_empty() {
+            let resolved = self.resolved_orphans_spending(&output_hashes);
+            self.promote_resolved_orphans(resolved)?
+        } else {
+            0
+        };
+        if num_expired > 0 || num_promoted > 0 || response == TxStorageResponse::NotStoredOrphan {
+            self.persist_orphans();
+        }
+        Ok(response)
+    }
+
+    fn store_validated(
+        &mut self,
+        tx: Arc<Transaction>,
+        validation_result: Result<(), ValidationError>,
+        is_local: bool,
+    ) -> Result<TxStorageResponse, MempoolError> {
+        let excess_sig = tx.first_kernel_excess_sig().cloned();
         debug!(
             target: LOG_TARGET,
-            "Inserting tx into mempool: {}",
-            tx.body
-                .kernels()
-                .first()
-                .map(|k| k.excess_sig.get_signature().to_hex())
+            "Inserting {}tx into mempool: {}",
+            if is_local { "local " } else { "" },
+            excess_sig
+                .as_ref()
+                .map(|sig| sig.get_signature().to_hex())
                 .unwrap_or_else(|| "None".into())
         );
-        match self.validator.validate(&tx) {
-            Ok(()) => {
-                self.unconfirmed_pool.insert(tx, None)?;
-                Ok(TxStorageResponse::UnconfirmedPool)
+        let response = match self.check_admission(&tx, validation_result, is_local)? {
+            Admission::Accept(dependent_outputs) => {
+                self.insert_into_unconfirmed_pool(tx, dependent_outputs, is_local)?
             },
-            Err(ValidationError::UnknownInputs(dependent_outputs)) => {
-                if self.unconfirmed_pool.verify_outputs_exist(&dependent_outputs) {
-                    self.unconfirmed_pool.insert(tx, Some(dependent_outputs))?;
-                    Ok(TxStorageResponse::UnconfirmedPool)
-                } else {
-                    warn!(target: LOG_TARGET, "Validation failed due to unknown inputs");
-                    Ok(TxStorageResponse::NotStoredOrphan)
+            Admission::Orphan(dependent_outputs) => {
+                warn!(target: LOG_TARGET, "Validation failed due to unknown inputs");
+                if self.config.hold_orphans {
+                    self.orphans.insert(tx, is_local, dependent_outputs);
                 }
+                TxStorageResponse::NotStoredOrphan
             },
-            Err(ValidationError::ContainsSTxO) => {
-                warn!(target: LOG_TARGET, "Validation failed due to already spent output");
-                Ok(TxStorageResponse::NotStoredAlreadySpent)
-            },
-            Err(ValidationError::MaturityError) => {
+            Admission::TimeLocked => {
                 warn!(target: LOG_TARGET, "Validation failed due to maturity error");
-                Ok(TxStorageResponse::NotStoredTimeLocked)
+                // Only transactions waiting on immature inputs (e.g. a coinbase) are held, transactions with a kernel
+                // lock height in the future must be re-submitted
+                if tx.max_input_maturity() > tx.max_kernel_timelock() {
+                    self.pending_maturity.insert(tx, is_local);
+                }
+                TxStorageResponse::NotStoredTimeLocked
+            },
+            Admission::Invalid(ValidationError::ContainsSTxO) => {
+                warn!(target: LOG_TARGET, "Validation failed due to already spent output");
+                TxStorageResponse::NotStoredAlreadySpent
             },
-            Err(e) => {
+            Admission::Invalid(e) => {
                 warn!(target: LOG_TARGET, "Validation failed due to error:{}", e);
-                Ok(TxStorageResponse::NotStored)
+                TxStorageResponse::NotStored
+            },
+            Admission::Rejected(response) => {
+                debug!(target: LOG_TARGET, "Transaction not stored: {}", response);
+                response
+            },
+        };
+
+        if let Some(excess_sig) = excess_sig.as_ref() {
+            // The first insertion is kept when a transaction is re-inserted, promoted or restored after a reorg
+            if response.is_stored() || self.orphans.contains(excess_sig) || self.pending_maturity.contains(excess_sig) {
+                self.first_seen.entry(excess_sig.clone()).or_insert_with(Utc::now);
+            }
+        }
+
+        if let Some(excess_sig) = excess_sig.filter(|_| is_local) {
+            if response.is_stored() {
+                self.local_txs.insert(excess_sig);
+            } else {
+                warn!(
+                    target: LOG_TARGET,
+                    "Locally-originated transaction {} was not stored ({})",
+                    excess_sig.get_signature().to_hex(),
+                    response
+                );
+                self.local_txs.remove(&excess_sig);
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Classifies the result of validating a transaction in the same way as an insert would, without storing it.
+    pub fn validation_outcome(
+        &self,
+        tx: &Transaction,
+        validation_result: Result<(), ValidationError>,
+    ) -> Result<ValidationOutcome, MempoolError> {
+        Ok(match self.check_admission(tx, validation_result, false)? {
+            Admission::Accept(_) => ValidationOutcome::Valid,
+            Admission::Orphan(_) => ValidationOutcome::Orphan,
+            Admission::TimeLocked => ValidationOutcome::TimeLocked,
+            Admission::Invalid(e) => ValidationOutcome::Invalid(e.to_string()),
+            Admission::Rejected(response) => ValidationOutcome::Invalid(response.to_string()),
+        })
+    }
+
+    /// Decides whether a transaction that was validated with the given result would be stored, without storing it.
+    /// This is shared by inserts and `validation_outcome` so that a dry run applies the same admission checks.
+    fn check_admission(
+        &self,
+        tx: &Transaction,
+        validation_result: Result<(), ValidationError>,
+        is_local: bool,
+    ) -> Result<Admission, MempoolError> {
+        if self.config.watch_only && !self.is_watched(tx) {
+            trace!(
+                target: LOG_TARGET,
+                "Transaction not stored, it does not spend or create a watched commitment"
+            );
+            return Ok(Admission::Rejected(TxStorageResponse::NotStored));
+        }
+        match validation_result {
+            Ok(()) => self.check_unconfirmed_pool_admission(tx, None, is_local),
+            Err(ValidationError::UnknownInputs(dependent_outputs)) => {
+                if self.unconfirmed_pool.verify_outputs_exist(&dependent_outputs) {
+                    self.check_unconfirmed_pool_admission(tx, Some(dependent_outputs), is_local)
+                } else {
+                    Ok(Admission::Orphan(dependent_outputs))
+                }
             },
+            Err(ValidationError::MaturityError) => Ok(Admission::TimeLocked),
+            Err(e) => Ok(Admission::Invalid(e)),
         }
     }
 
+    fn check_unconfirmed_pool_admission(
+        &self,
+        tx: &Transaction,
+        dependent_outputs: Option<Vec<HashOutput>>,
+        is_local: bool,
+    ) -> Result<Admission, MempoolError> {
+        if self.unconfirmed_pool.has_duplicate_output_commitment(tx) {
+            return Ok(Admission::Rejected(TxStorageResponse::NotStoredDuplicateOutput));
+        }
+        if let Some(dependent_outputs) = dependent_outputs.as_ref() {
+            let depth = self.unconfirmed_pool.zero_conf_depth(dependent_outputs);
+            if depth > self.config.max_zero_conf_chain_length {
+                debug!(
+                    target: LOG_TARGET,
+                    "Transaction has {} unconfirmed ancestors (maximum: {})",
+                    depth,
+                    self.config.max_zero_conf_chain_length
+                );
+                return Ok(Admission::Rejected(TxStorageResponse::NotStoredZeroConfChainTooLong));
+            }
+        }
+        // A transaction that is already stored is accepted again, even if the pool is full
+        if !self.unconfirmed_pool.would_store(tx, is_local)? {
+            return Ok(Admission::Rejected(self.fee_too_low_response()));
+        }
+        Ok(Admission::Accept(dependent_outputs))
+    }
+
+    fn fee_too_low_response(&self) -> TxStorageResponse {
+        match self.unconfirmed_pool.min_fee_per_gram() {
+            Some(min_fee_per_gram) => TxStorageResponse::NotStoredFeeTooLow(min_fee_per_gram),
+            None => TxStorageResponse::NotStored,
+        }
+    }
+
+    fn insert_into_unconfirmed_pool(
+        &mut self,
+        tx: Arc<Transaction>,
+        dependent_outputs: Option<Vec<HashOutput>>,
+        is_local: bool,
+    ) -> Result<TxStorageResponse, MempoolError> {
+        let is_stored = if is_local {
+            self.unconfirmed_pool.insert_local(tx, dependent_outputs)?
+        } else {
+            self.unconfirmed_pool.insert(tx, dependent_outputs)?
+        };
+        if is_stored {
+            Ok(TxStorageResponse::UnconfirmedPool)
+        } else {
+            Ok(self.fee_too_low_response())
+        }
+    }
+
+    fn record_admission(&mut self, excess_sig: Option<Signature>, outcome: &TxStorageResponse, is_local: bool) {
+        let capacity = self.config.admission_audit_log_size;
+        if capacity == 0 {
+            return;
+        }
+        if self.admissions.len() >= capacity {
+            self.admissions.pop_front();
+        }
+        self.admissions.push_back(AdmissionRecord {
+            excess_sig,
+            outcome: outcome.clone(),
+            timestamp: Utc::now(),
+            source: if is_local {
+                AdmissionSource::Local
+            } else {
+                AdmissionSource::Gossip
+            },
+        });
+    }
+
+    /// Returns up to `limit` of the most recent admission decisions, oldest first.
+    pub fn recent_admissions(&self, limit: usize) -> Vec<AdmissionRecord> {
+        let skip = self.admissions.len().saturating_sub(limit);
+        self.admissions.iter().skip(skip).cloned().collect()
+    }
+
+    /// Replaces the set of commitments that transactions must spend or create to be accepted when
+    /// `MempoolConfig::watch_only` is set.
+    pub fn set_watch_set(&mut self, commitments: Vec<Commitment>) {
+        self.watch_set = commitments.into_iter().collect();
+    }
+
+    fn is_watched(&self, tx: &Transaction) -> bool {
+        tx.body
+            .inputs()
+            .iter()
+            .map(|input| &input.commitment)
+            .chain(tx.body.outputs().iter().map(|output| &output.commitment))
+            .any(|commitment| self.watch_set.contains(commitment))
+    }
+
+    /// Evicts the transactions waiting for their inputs to mature that spend an input of the published block. A
+    /// transaction that spends an output that has been spent on-chain, e.g. by a conflicting transaction, can never
+    /// become valid and is evicted rather than held until it matures.
+    fn evict_pending_spent_in_block(&mut self, block: &Block) {
+        let evicted = self
+            .pending_maturity
+            .remove_spending(block.body.inputs().iter().map(|input| &input.commitment));
+        for tx in &evicted {
+            debug!(
+                target: LOG_TARGET,
+                "Evicted pending transaction {} because an output it spends has been spent on-chain",
+                tx.first_kernel_excess_sig()
+                    .map(|sig| sig.get_signature().to_hex())
+                    .unwrap_or_else(|| "None".into())
+            );
+        }
+    }
+
+    /// Re-validates the held transactions that are spendable in the block following `current_height`, promoting those
+    /// that are now valid to the unconfirmed pool. Those that are still immature are held and the rest are discarded.
+    /// This is called for every published block and can also be called when the chain height advances without a
+    /// published block being processed. Returns the number of promoted transactions.
+    pub fn recheck_timelocked(&mut self, current_height: u64) -> Result<usize, MempoolError> {
+        let mut num_promoted = 0;
+        let mut num_evicted = 0;
+        for (tx, is_local) in self.pending_maturity.take_spendable(current_height) {
+            let excess_sig = tx.first_kernel_excess_sig().cloned();
+            let validation_result = self.validator.validate(&tx);
+            match self.store_validated(tx, validation_result, is_local)? {
+                TxStorageResponse::UnconfirmedPool => num_promoted += 1,
+                TxStorageResponse::NotStoredAlreadySpent => {
+                    num_evicted += 1;
+                    debug!(
+                        target: LOG_TARGET,
+                        "Evicted pending transaction {} because an output it spends has been spent on-chain",
+                        excess_sig
+                            .map(|sig| sig.get_signature().to_hex())
+                            .unwrap_or_else(|| "None".into())
+                    );
+                },
+                _ => {},
+            }
+        }
+        if num_promoted > 0 {
+            debug!(
+                target: LOG_TARGET,
+                "Promoted {} time-locked transaction(s) at height {}", num_promoted, current_height
+            );
+        }
+        if num_evicted > 0 {
+            debug!(
+                target: LOG_TARGET,
+                "Evicted {} pending transaction(s) that can never become valid", num_evicted
+            );
+        }
+        Ok(num_promoted)
+    }
+
+    /// Promotes the held orphan transactions whose inputs are now all in the unconfirmed pool, after dropping the
+    /// orphans that have outlived `MempoolConfig::orphan_tx_ttl`. Orphans are only re-validated once all of their
+    /// missing inputs are known, and chains of orphans are resolved at once. Returns the number of promoted
+    /// transactions.
+    pub fn promote_orphans(&mut self) -> Result<usize, MempoolError> {
+        let num_expired = self.orphans.remove_expired(self.config.orphan_tx_ttl);
+        let unconfirmed_pool = &self.unconfirmed_pool;
+        let resolved = self
+            .orphans
+            .all_resolved(|missing_outputs| unconfirmed_pool.verify_outputs_exist(missing_outputs));
+        let num_promoted = self.promote_resolved_orphans(resolved)?;
+        if num_expired > 0 || num_promoted > 0 {
+            self.persist_orphans();
+        }
+        Ok(num_promoted)
+    }
+
+    fn resolved_orphans_spending(&self, output_hashes: &[HashOutput]) -> Vec<Signature> {
+        let unconfirmed_pool = &self.unconfirmed_pool;
+        self.orphans.resolved_by(output_hashes, |missing_outputs| {
+            unconfirmed_pool.verify_outputs_exist(missing_outputs)
+        })
+    }
+
+    /// Re-validates and stores the given orphans, followed by the orphans that the promoted orphans resolve in turn.
+    fn promote_resolved_orphans(&mut self, mut resolved: Vec<Signature>) -> Result<usize, MempoolError> {
+        let mut num_promoted = 0;
+        while !resolved.is_empty() {
+            let mut output_hashes = Vec::new();
+            for excess_sig in resolved {
+                if let Some((tx, is_local)) = self.orphans.remove(&excess_sig) {
+                    let tx_output_hashes = tx.body.outputs().iter().map(|output| output.hash()).collect::<Vec<_>>();
+                    let validation_result = self.validator.validate(&tx);
+                    if self.store_validated(tx, validation_result, is_local)? == TxStorageResponse::UnconfirmedPool {
+                        num_promoted += 1;
+                        output_hashes.extend(tx_output_hashes);
+                    }
+                }
+            }
+            resolved = self.resolved_orphans_spending(&output_hashes);
+        }
+        if num_promoted > 0 {
+            debug!(
+                target: LOG_TARGET,
+                "Promoted {} orphan transaction(s) to the unconfirmed pool", num_promoted
+            );
+        }
+        Ok(num_promoted)
+    }
+
+    /// Writes the orphans that were held and deletes the orphans that were removed since the orphans were last
+    /// persisted. Orphans that are unchanged are not rewritten.
+    fn persist_orphans(&mut self) {
+        if !self.config.hold_orphans || !self.config.persist_orphans {
+            return;
+        }
+        if let Some(store) = self.orphan_store.as_ref() {
+            let removed = self
+                .persisted_orphans
+                .iter()
+                .filter(|sig| !self.orphans.contains(sig))
+                .cloned()
+                .collect::<Vec<_>>();
+            let persisted_orphans = &self.persisted_orphans;
+            let inserted = self
+                .orphans
+                .transactions()
+                .filter(|tx| {
+                    tx.first_kernel_excess_sig()
+                        .map(|sig| !persisted_orphans.contains(sig))
+                        .unwrap_or(false)
+                })
+                .map(|tx| tx.as_ref().clone())
+                .collect::<Vec<_>>();
+            if removed.is_empty() && inserted.is_empty() {
+                return;
+            }
+            match store.update_orphans(inserted, removed) {
+                Ok(()) => self.persisted_orphans = self.orphans.excess_sigs().cloned().collect(),
+                Err(err) => warn!(target: LOG_TARGET, "Failed to persist orphan transactions: {}", err),
+            }
+        }
+    }
+
+    /// Returns the number of orphan transactions that are held until the outputs they spend are known.
+    pub fn orphan_count(&self) -> usize {
+        self.orphans.len()
+    }
+
+    /// Returns the transactions that are held until the outputs they spend mature.
+    pub fn pending_coinbase_maturity(&self) -> Vec<Arc<Transaction>> {
+        self.pending_maturity.transactions().cloned().collect()
+    }
+
+    /// Returns a counter that changes every time the chain state that transactions are validated against changes,
+    /// i.e. when a block is published or a reorg is processed.
+    pub fn state_generation(&self) -> u64 {
+        self.state_generation
+    }
+
     // Insert a set of new transactions into the UTxPool.
     fn insert_txs(&mut self, txs: Vec<Arc<Transaction>>) -> Result<(), MempoolError> {
         for tx in txs {
-            self.insert(tx)?;
+            let is_local = tx
+                .first_kernel_excess_sig()
+                .map(|sig| self.local_txs.contains(sig))
+                .unwrap_or(false);
+            let validation_result = self.validator.validate(&tx);
+            self.store_validated(tx, validation_result, is_local)?;
         }
         Ok(())
     }
 
     /// Update the Mempool based on the received published block.
-    pub fn process_published_block(&mut self, published_block: Arc<Block>) -> Result<(), MempoolError> {
+    pub fn process_published_block(
+        &mut self,
+        published_block: Arc<Block>,
+    ) -> Result<BlockProcessSummary, MempoolError> {
         trace!(target: LOG_TARGET, "Mempool processing new block: {}", published_block);
+        let block_hash = published_block.hash();
+        if self.processed_blocks.contains(&block_hash) {
+            debug!(
+                target: LOG_TARGET,
+                "Block {} ({}) has already been processed, skipping",
+                published_block.header.height,
+                block_hash.to_hex()
+            );
+            return Ok(BlockProcessSummary::default());
+        }
+        if let Some(last_block_hash) = self.processed_blocks.back() {
+            if *last_block_hash != published_block.header.prev_hash {
+                // Processing a block that does not build on a processed block would corrupt the pool accounting. A
+                // block that builds on an earlier processed block replaces the blocks processed after
+                // its parent, the transactions mined in those blocks are kept in the reorg pool.
+                if !self.processed_blocks.contains(&published_block.header.prev_hash) {
+                    return Err(MempoolError::UnexpectedBlock {
+                        expected_prev: last_block_hash.clone(),
+                        got_prev: published_block.header.prev_hash.clone(),
+                    });
+                }
+                debug!(
+                    target: LOG_TARGET,
+                    "Block {} ({}) builds on an earlier processed block, processing it without revalidating the pool",
+                    published_block.header.height,
+                    block_hash.to_hex()
+                );
+                self.rewind_processed_blocks(&published_block.header.prev_hash);
+            }
+        }
         // Move published txs to ReOrgPool and discard double spends
-        self.reorg_pool.insert_txs(
-            self.unconfirmed_pool
-                .remove_published_and_discard_deprecated_transactions(&published_block),
-        )?;
+        let removed_txs = self
+            .unconfirmed_pool
+            .remove_published_and_discard_deprecated_transactions(&published_block);
+        let block_kernel_sigs = published_block
+            .body
+            .kernels()
+            .iter()
+            .map(|kernel| &kernel.excess_sig)
+            .collect::<HashSet<_>>();
+        let mined_from_pool = removed_txs
+            .iter()
+            .filter(|tx| {
+                tx.first_kernel_excess_sig()
+                    .map(|sig| block_kernel_sigs.contains(sig))
+                    .unwrap_or(false)
+            })
+            .count();
+        self.reorg_pool.insert_txs(removed_txs)?;
+        self.last_block_processed_at = Some(Instant::now());
+        self.processed_blocks.push_back(block_hash);
+        if self.processed_blocks.len() > MEMPOOL_PROCESSED_BLOCKS_CAPACITY {
+            self.processed_blocks.pop_front();
+        }
+        self.state_generation += 1;
+        self.evict_pending_spent_in_block(&published_block);
+        let newly_timelocked_promoted = self.recheck_timelocked(published_block.header.height)?;
+        let orphans_promoted = self.promote_orphans_spending_block(&published_block)?;
+        // Local transactions are remembered for as long as they may be re-inserted after a reorg
+        let unconfirmed_pool = &self.unconfirmed_pool;
+        let reorg_pool = &self.reorg_pool;
+        self.local_txs.retain(|sig| {
+            unconfirmed_pool.has_tx_with_excess_sig(sig) || reorg_pool.has_tx_with_excess_sig(sig).unwrap_or(false)
+        });
+        let orphans = &self.orphans;
+        let pending_maturity = &self.pending_maturity;
+        self.first_seen.retain(|sig| {
+            unconfirmed_pool.has_tx_with_excess_sig(sig) ||
+                orphans.contains(sig) ||
+                pending_maturity.contains(sig) ||
+                reorg_pool.has_tx_with_excess_sig(sig).unwrap_or(false)
+        });
 
-        Ok(())
+        Ok(BlockProcessSummary {
+            mined_from_pool,
+            newly_timelocked_promoted,
+            orphans_promoted,
+        })
+    }
+
+    /// Promotes the held orphan transactions that spend outputs created in the given block, once all of their other
+    /// missing inputs are known. Returns the number of promoted transactions.
+    fn promote_orphans_spending_block(&mut self, block: &Block) -> Result<usize, MempoolError> {
+        if self.orphans.is_empty() {
+            return Ok(0);
+        }
+        let block_outputs = block
+            .body
+            .outputs()
+            .iter()
+            .map(|output| output.hash())
+            .collect::<HashSet<_>>();
+        let unconfirmed_pool = &self.unconfirmed_pool;
+        let resolved = self.orphans.resolved_by(&block_outputs, |missing_outputs| {
+            missing_outputs.iter().all(|hash| {
+                block_outputs.contains(hash) || unconfirmed_pool.verify_outputs_exist(slice::from_ref(hash))
+            })
+        });
+        let num_promoted = self.promote_resolved_orphans(resolved)?;
+        if num_promoted > 0 {
+            self.persist_orphans();
+        }
+        Ok(num_promoted)
+    }
+
+    /// Forgets the processed blocks after `block_hash`, so that `block_hash` is the last processed block.
+    fn rewind_processed_blocks(&mut self, block_hash: &BlockHash) {
+        while self
+            .processed_blocks
+            .back()
+            .map(|hash| hash != block_hash)
+            .unwrap_or(false)
+        {
+            self.processed_blocks.pop_back();
+        }
+        if self.processed_blocks.is_empty() {
+            self.processed_blocks.push_back(block_hash.clone());
+        }
+    }
+
+    /// Re-checks the inputs of every unconfirmed transaction against the current chain state and evicts the
+    /// transactions that spend outputs which no longer exist, along with the unconfirmed transactions that depend on
+    /// them. Returns the number of evicted transactions.
+    pub fn evict_by_missing_inputs(&mut self) -> Result<usize, MempoolError> {
+        let mut num_evicted = 0;
+        // Evicting a transaction removes the outputs its descendants spend, so repeat until nothing more is evicted
+        loop {
+            let missing_inputs = self
+                .unconfirmed_pool
+                .snapshot()
+                .into_iter()
+                .filter(|tx| match self.validator.validate(tx) {
+                    Err(ValidationError::UnknownInputs(dependent_outputs)) => {
+                        !self.unconfirmed_pool.verify_outputs_exist(&dependent_outputs)
+                    },
+                    Err(ValidationError::ContainsSTxO) => true,
+                    _ => false,
+                })
+                .filter_map(|tx| tx.first_kernel_excess_sig().cloned())
+                .collect::<Vec<_>>();
+            let num_removed = self.unconfirmed_pool.remove_txs(&missing_inputs).len();
+            // Stop once nothing is removed, otherwise transactions that cannot be removed would be found again forever
+            if num_removed == 0 {
+                break;
+            }
+            num_evicted += num_removed;
+        }
+        if num_evicted > 0 {
+            debug!(
+                target: LOG_TARGET,
+                "Evicted {} transaction(s) spending outputs that no longer exist", num_evicted
+            );
+        }
+        Ok(num_evicted)
     }
 
     /// In the event of a ReOrg, resubmit all ReOrged transactions into the Mempool and process each newly introduced
@@ -146,8 +873,30 @@ impl MempoolStorage {
             );
         }
 
+        let reorg_marker = ReorgMarker::from_reorg(&removed_blocks, &new_blocks);
+        if let Some(marker) = reorg_marker.as_ref() {
+            if self.is_last_applied_reorg(marker) {
+                debug!(
+                    target: LOG_TARGET,
+                    "Reorg to tip {} ({}) has already been applied, skipping",
+                    marker.height,
+                    marker.tip_hash.to_hex()
+                );
+                return Ok(());
+            }
+        }
+
+        self.state_generation += 1;
         let previous_tip = removed_blocks.last().map(|block| block.header.height);
         let new_tip = new_blocks.last().map(|block| block.header.height);
+        // The new blocks build on the fork point rather than on the last processed block
+        let fork_hash = new_blocks
+            .first()
+            .or_else(|| removed_blocks.iter().min_by_key(|block| block.header.height))
+            .map(|block| block.header.prev_hash.clone());
+        if let Some(fork_hash) = fork_hash {
+            self.rewind_processed_blocks(&fork_hash);
+        }
 
         // Clear out all transactions from the unconfirmed pool and re-submit them to the unconfirmed mempool for
         // validation. This is important as invalid transactions that have not been mined yet may remain in the mempool
@@ -185,9 +934,38 @@ impl MempoolStorage {
             }
         }
 
+        if let Some(marker) = reorg_marker {
+            self.set_last_applied_reorg(marker);
+        }
+
         Ok(())
     }
 
+    fn is_last_applied_reorg(&self, marker: &ReorgMarker) -> bool {
+        match self.reorg_marker_store.as_ref().map(|store| store.fetch_reorg_marker()) {
+            Some(Ok(last_applied)) => last_applied.as_ref() == Some(marker),
+            Some(Err(err)) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Failed to fetch the last applied reorg marker: {}", err
+                );
+                false
+            },
+            None => false,
+        }
+    }
+
+    fn set_last_applied_reorg(&self, marker: ReorgMarker) {
+        if let Some(store) = self.reorg_marker_store.as_ref() {
+            if let Err(err) = store.set_reorg_marker(marker) {
+                warn!(
+                    target: LOG_TARGET,
+                    "Failed to persist the last applied reorg marker: {}", err
+                );
+            }
+        }
+    }
+
     /// Returns all unconfirmed transaction stored in the Mempool, except the transactions stored in the ReOrgPool.
     // TODO: Investigate returning an iterator rather than a large vector of transactions
     pub fn snapshot(&self) -> Result<Vec<Arc<Transaction>>, MempoolError> {
@@ -195,12 +973,158 @@ impl MempoolStorage {
         Ok(txs)
     }
 
+    /// Returns all transactions in the unconfirmed pool along with their weight, age and origin.
+    pub fn snapshot_with_metadata(&self) -> Vec<MempoolTransactionInfo> {
+        self.unconfirmed_pool.snapshot_with_metadata()
+    }
+
+    /// Returns up to `limit` unconfirmed transactions after the given cursor, in descending order of fee priority,
+    /// together with the cursor of the next page, if any.
+    pub fn retrieve_page(
+        &self,
+        cursor: Option<FeeCursor>,
+        limit: usize,
+    ) -> (Vec<MempoolTransactionInfo>, Option<FeeCursor>) {
+        let (txs, next) = self.unconfirmed_pool.page(cursor.as_ref().map(|c| &c.0), limit);
+        (txs, next.map(FeeCursor))
+    }
+
+    /// Removes all transactions from the unconfirmed pool, including local transactions, orphans and transactions
+    /// waiting for their inputs to mature, returning the number of unconfirmed transactions that were removed. The
+    /// reorg pool is left intact so that published transactions can still be restored in the event of a reorg.
+    pub fn clear(&mut self) -> usize {
+        let num_removed = self.unconfirmed_pool.drain_all_mempool_transactions().len();
+        self.pending_maturity.clear();
+        self.orphans.clear();
+        self.persist_orphans();
+        self.local_txs.clear();
+        self.state_generation += 1;
+        num_removed
+    }
+
+    /// Serializes the transactions in the unconfirmed pool so that they can be restored after a restart. The reorg pool
+    /// is not included as it can be rebuilt from the blockchain.
+    pub fn export_snapshot(&self) -> Result<Vec<u8>, MempoolError> {
+        let txs = self
+            .unconfirmed_pool
+            .snapshot()
+            .iter()
+            .map(|tx| tx.as_ref().clone())
+            .collect::<Vec<_>>();
+        bincode::serialize(&txs).map_err(|e| MempoolError::SnapshotSerializationError(e.to_string()))
+    }
+
+    /// Restores the transactions from a snapshot created by `export_snapshot`. Each transaction is re-validated against
+    /// the current tip before being inserted and transactions that are no longer valid are dropped. Returns the number
+    /// of transactions that were restored.
+    pub fn import_snapshot(&mut self, bytes: &[u8]) -> Result<usize, MempoolError> {
+        let txs: Vec<Transaction> =
+            bincode::deserialize(bytes).map_err(|e| MempoolError::SnapshotSerializationError(e.to_string()))?;
+        let num_txs = txs.len();
+        let mut num_restored = 0;
+        for tx in txs {
+            if let TxStorageResponse::UnconfirmedPool = self.insert(Arc::new(tx))? {
+                num_restored += 1;
+            }
+        }
+        debug!(
+            target: LOG_TARGET,
+            "Restored {} of {} transaction(s) from mempool snapshot", num_restored, num_txs
+        );
+        Ok(num_restored)
+    }
+
+    /// Reconstructs the indexes of the unconfirmed pool from the stored transactions, see
+    /// `UnconfirmedPool::rebuild_indexes`.
+    pub fn rebuild_indexes(&mut self) {
+        self.unconfirmed_pool.rebuild_indexes();
+    }
+
     /// Returns a list of transaction ranked by transaction priority up to a given weight.
     /// Will only return transactions that will fit into a block
     pub fn retrieve(&mut self, total_weight: u64) -> Result<Vec<Arc<Transaction>>, MempoolError> {
         let results = self.unconfirmed_pool.highest_priority_txs(total_weight)?;
         self.insert_txs(results.transactions_to_insert)?;
-        Ok(results.retrieved_transactions)
+        Ok(self.revalidate_retrieved(results.retrieved_transactions))
+    }
+
+    /// Returns the transactions that `transactions_for_block_template` selects for a block of `total_weight`, along
+    /// with their totals.
+    pub fn next_block_preview(&self, total_weight: u64) -> Result<NextBlockPreview, MempoolError> {
+        Ok(self.transactions_for_block_template(total_weight, 0)?.into())
+    }
+
+    /// Returns the transactions that `retrieve` selects for a block template of `max_weight` less the
+    /// `coinbase_weight`, along with the totals needed to build the template. The pool is not modified, transactions
+    /// that `retrieve` would remove to be re-evaluated are skipped.
+    pub fn transactions_for_block_template(
+        &self,
+        max_weight: u64,
+        coinbase_weight: u64,
+    ) -> Result<BlockTemplateTxs, MempoolError> {
+        let transactions = self
+            .unconfirmed_pool
+            .peek_highest_priority_txs(max_weight.saturating_sub(coinbase_weight))?;
+        let transactions = self.revalidate_retrieved(transactions);
+        let total_weight = transactions
+            .iter()
+            .map(|tx| self.weight_policy.calculate_weight(tx))
+            .sum();
+        Ok(BlockTemplateTxs::new(transactions, total_weight))
+    }
+
+    /// Returns a list of transactions that fit into a block of `total_weight`, where `reserved_weight` is reserved for
+    /// the oldest transactions that would not otherwise be selected by fee priority.
+    pub fn retrieve_fair(
+        &mut self,
+        total_weight: u64,
+        reserved_weight: u64,
+    ) -> Result<Vec<Arc<Transaction>>, MempoolError> {
+        let results = self
+            .unconfirmed_pool
+            .highest_priority_txs_fair(total_weight, reserved_weight)?;
+        self.insert_txs(results.transactions_to_insert)?;
+        Ok(self.revalidate_retrieved(results.retrieved_transactions))
+    }
+
+    /// If `revalidate_on_retrieve` is enabled, validates the retrieved transactions against the current tip and omits
+    /// (and logs) any that no longer validate. Transactions that spend the outputs of other unconfirmed transactions
+    /// are only kept if those transactions are kept.
+    fn revalidate_retrieved(&self, txs: Vec<Arc<Transaction>>) -> Vec<Arc<Transaction>> {
+        if !self.config.revalidate_on_retrieve {
+            return txs;
+        }
+
+        let mut valid = Vec::with_capacity(txs.len());
+        let mut dependent = Vec::new();
+        for tx in txs {
+            match self.validator.validate(&tx) {
+                Ok(()) => valid.push(tx),
+                Err(ValidationError::UnknownInputs(dependent_outputs)) => dependent.push((tx, dependent_outputs)),
+                Err(err) => log_omitted_transaction(&tx, &err),
+            }
+        }
+
+        loop {
+            let available_outputs = valid
+                .iter()
+                .flat_map(|tx| tx.body.outputs().iter().map(|output| output.hash()))
+                .collect::<HashSet<_>>();
+            let (resolved, unresolved) = dependent.into_iter().partition::<Vec<_>, _>(|(_, dependent_outputs)| {
+                dependent_outputs.iter().all(|hash| available_outputs.contains(hash))
+            });
+            dependent = unresolved;
+            if resolved.is_empty() {
+                break;
+            }
+            valid.extend(resolved.into_iter().map(|(tx, _)| tx));
+        }
+
+        for (tx, dependent_outputs) in dependent {
+            log_omitted_transaction(&tx, &ValidationError::UnknownInputs(dependent_outputs));
+        }
+
+        valid
     }
 
     /// Check if the specified transaction is stored in the Mempool.
@@ -214,11 +1138,38 @@ impl MempoolStorage {
         }
     }
 
+    /// Returns the time at which the transaction was first stored or held by the mempool, or None if it is not stored
+    /// in the unconfirmed pool. Re-inserting the transaction, e.g. after a reorg, does not reset the time.
+    pub fn first_seen(&self, excess_sig: &Signature) -> Result<Option<DateTime<Utc>>, MempoolError> {
+        if !self.unconfirmed_pool.has_tx_with_excess_sig(excess_sig) {
+            return Ok(None);
+        }
+        Ok(self.first_seen.get(excess_sig).copied())
+    }
+
+    /// Returns the number of unconfirmed transactions in each age bucket. See `UnconfirmedPool::age_histogram`.
+    pub fn age_histogram(&self, buckets: &[Duration]) -> Vec<(Duration, usize)> {
+        self.unconfirmed_pool.age_histogram(buckets, Instant::now())
+    }
+
     // Returns the total number of transactions in the Mempool.
     fn len(&self) -> Result<usize, MempoolError> {
         Ok(self.unconfirmed_pool.len())
     }
 
+    /// Returns a fresh copy of the mempool to replay blocks on, seeded with the unconfirmed, time-locked and orphan
+    /// transactions of this mempool and with an empty reorg pool. The copy does not persist anything.
+    pub fn replay_copy(&self) -> MempoolStorage {
+        let mut replay = MempoolStorage::new(self.config, self.validator.clone());
+        replay.unconfirmed_pool = self.unconfirmed_pool.clone();
+        replay.weight_policy = self.weight_policy.clone();
+        replay.local_txs = self.local_txs.clone();
+        replay.pending_maturity = self.pending_maturity.clone();
+        replay.orphans = self.orphans.clone();
+        replay.reject_reasons = self.reject_reasons.clone();
+        replay
+    }
+
     /// Gathers and returns the stats of the Mempool.
     pub fn stats(&self) -> Result<StatsResponse, MempoolError> {
         Ok(StatsResponse {
@@ -226,9 +1177,56 @@ impl MempoolStorage {
             unconfirmed_txs: self.unconfirmed_pool.len(),
             reorg_txs: self.reorg_pool.len()?,
             total_weight: self.unconfirmed_pool.calculate_weight(),
+            txs_by_kernel_feature: self.unconfirmed_pool.txs_by_kernel_feature(),
+            local_txs: self.unconfirmed_pool.local_txs_count(),
+            max_dependency_depth: self.unconfirmed_pool.max_dependency_depth(),
+            reject_reasons: self.reject_reasons.clone(),
         })
     }
 
+    /// Returns the locally-originated transactions stored in the unconfirmed pool.
+    pub fn local_transactions(&self) -> Vec<Arc<Transaction>> {
+        self.unconfirmed_pool.local_transactions()
+    }
+
+    /// Returns the unconfirmed transactions that create an output with the given commitment.
+    pub fn txs_creating_commitment(&self, commitment: &Commitment) -> Vec<Arc<Transaction>> {
+        self.unconfirmed_pool.txs_creating_commitment(commitment)
+    }
+
+    /// Boosts the retrieval priority of the unconfirmed transaction with the given excess signature by
+    /// `priority_boost` fee per gram. Returns false if the transaction is not in the unconfirmed pool.
+    pub fn set_priority_override(
+        &mut self,
+        excess_sig: &Signature,
+        priority_boost: MicroTari,
+    ) -> Result<bool, MempoolError> {
+        Ok(self
+            .unconfirmed_pool
+            .set_priority_override(excess_sig, priority_boost)?)
+    }
+
+    /// Returns the commitments of the inputs spent by the unconfirmed transactions.
+    pub fn spent_commitments(&self) -> HashSet<Commitment> {
+        self.unconfirmed_pool.spent_commitments()
+    }
+
+    /// Returns the length of the longest chain of unconfirmed ancestors of the transaction with the given excess
+    /// signature, or None if the transaction is not in the unconfirmed pool.
+    pub fn dependency_depth(&self, excess_sig: &Signature) -> Option<usize> {
+        self.unconfirmed_pool.dependency_depth(excess_sig)
+    }
+
+    /// Returns an order-independent hash of the transactions in the unconfirmed pool
+    pub fn content_hash(&self) -> FixedHash {
+        self.unconfirmed_pool.content_hash()
+    }
+
+    /// Compares the unconfirmed pool against the excess signatures of the transactions in a remote mempool.
+    pub fn difference(&self, remote_excess_sigs: &[Signature]) -> MempoolDifference {
+        self.unconfirmed_pool.difference(remote_excess_sigs)
+    }
+
     /// Gathers and returns a breakdown of all the transaction in the Mempool.
     pub fn state(&self) -> Result<StateResponse, MempoolError> {
         let unconfirmed_pool = self
@@ -248,4 +1246,52 @@ impl MempoolStorage {
             reorg_pool,
         })
     }
+
+    /// Returns the minimum fee per gram a transaction currently needs to pay to be stored in the unconfirmed pool. This
+    /// is zero unless the pool is at capacity and a transaction would have to be evicted to make room.
+    pub fn min_fee_per_gram(&self) -> MicroTari {
+        self.unconfirmed_pool
+            .min_fee_per_gram()
+            .unwrap_or_else(|| MicroTari::from(0))
+    }
+
+    /// Returns the transaction that would be evicted from the unconfirmed pool to make room for the next transaction.
+    pub fn next_eviction_candidate(&self) -> Option<Signature> {
+        self.unconfirmed_pool.next_eviction_candidate()
+    }
+
+    /// Returns the unconfirmed transactions with a fee per gram in the half-open range `[min, max)`.
+    pub fn txs_in_fee_range(&self, min: MicroTari, max: MicroTari) -> Vec<Arc<Transaction>> {
+        self.unconfirmed_pool.txs_in_fee_range(min, max)
+    }
+
+    /// Returns the fee per gram statistics of up to `count` blocks of `target_block_weight`.
+    pub fn fee_per_gram_stats(&self, count: usize, target_block_weight: u64) -> Vec<FeePerGramStat> {
+        self.unconfirmed_pool.fee_per_gram_stats(count, target_block_weight)
+    }
+
+    /// Gathers and returns a readiness report of the Mempool.
+    pub fn health_check(&self) -> Result<MempoolHealth, MempoolError> {
+        Ok(MempoolHealth {
+            is_responsive: true,
+            unconfirmed_txs: self.unconfirmed_pool.len(),
+            unconfirmed_pool_capacity: self.config.unconfirmed_pool.storage_capacity,
+            reorg_txs: self.reorg_pool.len()?,
+            reorg_pool_capacity: self.config.reorg_pool.storage_capacity,
+            orphan_txs: self.orphans.len(),
+            orphan_pool_capacity: self.orphans.capacity(),
+            time_since_last_block: self.last_block_processed_at.map(|t| t.elapsed()),
+        })
+    }
+}
+
+fn log_omitted_transaction(tx: &Transaction, err: &ValidationError) {
+    warn!(
+        target: LOG_TARGET,
+        "Omitting transaction {} from retrieved transactions because it no longer validates: {}",
+        tx.first_kernel_excess_sig()
+            .map(|sig| sig.get_signature().to_hex())
+            .unwrap_or_else(|| "None".into()),
+        err
+    );
 }
@@ -35,8 +35,20 @@ mod mempool;
 #[cfg(feature = "base_node")]
 mod mempool_storage;
 #[cfg(feature = "base_node")]
+mod orphan_pool;
+#[cfg(feature = "base_node")]
+mod orphan_store;
+#[cfg(feature = "base_node")]
+pub use orphan_store::OrphanStore;
+#[cfg(feature = "base_node")]
+mod pending_maturity_pool;
+#[cfg(feature = "base_node")]
 mod priority;
 #[cfg(feature = "base_node")]
+mod reorg_marker;
+#[cfg(feature = "base_node")]
+pub use reorg_marker::{ReorgMarker, ReorgMarkerStore};
+#[cfg(feature = "base_node")]
 mod reorg_pool;
 #[cfg(feature = "base_node")]
 mod rpc;
@@ -46,6 +58,10 @@ pub use rpc::create_mempool_rpc_service;
 pub use rpc::{MempoolRpcClient, MempoolRpcServer, MempoolRpcService, MempoolService};
 #[cfg(feature = "base_node")]
 mod unconfirmed_pool;
+#[cfg(feature = "base_node")]
+mod weight_policy;
+#[cfg(feature = "base_node")]
+pub use weight_policy::{DefaultWeightPolicy, TransactionWeightPolicy};
 
 // public modules
 #[cfg(feature = "base_node")]
@@ -53,11 +69,11 @@ pub mod async_mempool;
 
 // Public re-exports
 #[cfg(feature = "base_node")]
-pub use self::config::{MempoolConfig, MempoolServiceConfig};
+pub use self::config::{MempoolConfig, MempoolServiceConfig, OrphanPromotionMode, WeightPolicyKind};
 #[cfg(feature = "base_node")]
 pub use error::MempoolError;
 #[cfg(feature = "base_node")]
-pub use mempool::Mempool;
+pub use mempool::{Mempool, MempoolBuilder};
 
 #[cfg(any(feature = "base_node", feature = "mempool_proto"))]
 pub mod proto;
@@ -72,10 +88,15 @@ mod sync_protocol;
 #[cfg(feature = "base_node")]
 pub use sync_protocol::MempoolSyncInitializer;
 
-use crate::transactions::transaction::Transaction;
+use crate::transactions::{
+    tari_amount::MicroTari,
+    transaction::{KernelFeatures, Transaction},
+};
+use chrono::{DateTime, Utc};
 use core::fmt::{Display, Error, Formatter};
 use serde::{Deserialize, Serialize};
-use tari_common_types::types::Signature;
+use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
+use tari_common_types::types::{BlindingFactor, Signature};
 use tari_crypto::tari_utilities::hex::Hex;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -84,6 +105,17 @@ pub struct StatsResponse {
     pub unconfirmed_txs: usize,
     pub reorg_txs: usize,
     pub total_weight: u64,
+    /// The number of unconfirmed transactions that contain a kernel with the given features
+    pub txs_by_kernel_feature: HashMap<KernelFeatures, usize>,
+    /// The number of locally-originated unconfirmed transactions, which are exempt from eviction
+    pub local_txs: usize,
+    /// The length of the longest chain of unconfirmed ancestors of any unconfirmed transaction (0 if all unconfirmed
+    /// transactions only spend mined outputs)
+    pub max_dependency_depth: usize,
+    /// The number of submitted transactions that were rejected since startup, by reason. Transactions that are held
+    /// until they can be promoted, i.e. held orphans and transactions waiting for their inputs to mature, are not
+    /// counted.
+    pub reject_reasons: HashMap<RejectReason, usize>,
 }
 
 impl Display for StatsResponse {
@@ -96,6 +128,260 @@ impl Display for StatsResponse {
     }
 }
 
+/// The reason a transaction submitted to the mempool was not stored
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RejectReason {
+    /// The mempool is full and the fee per gram is too low to evict a stored transaction
+    FeeTooLow,
+    /// The transaction spends outputs that are not known
+    Orphan,
+    /// The transaction spends an output that has already been spent
+    DoubleSpend,
+    /// The transaction creates an output commitment that already exists in the unconfirmed pool
+    DuplicateOutput,
+    /// The transaction exceeds the maximum transaction weight
+    ExceedsWeight,
+    /// The transaction spends immature outputs or has a kernel lock height in the future
+    TimeLocked,
+    /// The transaction would extend a chain of unconfirmed transactions beyond the configured maximum length
+    ZeroConfChainTooLong,
+    /// The transaction failed validation for any other reason
+    Invalid,
+}
+
+impl RejectReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RejectReason::FeeTooLow => "fee_too_low",
+            RejectReason::Orphan => "orphan",
+            RejectReason::DoubleSpend => "double_spend",
+            RejectReason::DuplicateOutput => "duplicate_output",
+            RejectReason::ExceedsWeight => "exceeds_weight",
+            RejectReason::TimeLocked => "time_locked",
+            RejectReason::ZeroConfChainTooLong => "zero_conf_chain_too_long",
+            RejectReason::Invalid => "invalid",
+        }
+    }
+}
+
+impl Display for RejectReason {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        fmt.write_str(self.as_str())
+    }
+}
+
+impl FromStr for RejectReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fee_too_low" => Ok(RejectReason::FeeTooLow),
+            "orphan" => Ok(RejectReason::Orphan),
+            "double_spend" => Ok(RejectReason::DoubleSpend),
+            "duplicate_output" => Ok(RejectReason::DuplicateOutput),
+            "exceeds_weight" => Ok(RejectReason::ExceedsWeight),
+            "time_locked" => Ok(RejectReason::TimeLocked),
+            "zero_conf_chain_too_long" => Ok(RejectReason::ZeroConfChainTooLong),
+            "invalid" => Ok(RejectReason::Invalid),
+            s => Err(format!("Invalid reject reason: {}", s)),
+        }
+    }
+}
+
+/// The unconfirmed transactions that would be included in the next block, i.e. a view over the `BlockTemplateTxs`
+/// selected by `Mempool::transactions_for_block_template`
+#[derive(Clone, Debug)]
+pub struct NextBlockPreview {
+    pub transactions: Vec<Arc<Transaction>>,
+    pub total_weight: u64,
+    pub total_fees: MicroTari,
+}
+
+impl From<BlockTemplateTxs> for NextBlockPreview {
+    fn from(template_txs: BlockTemplateTxs) -> Self {
+        Self {
+            transactions: template_txs.transactions,
+            total_weight: template_txs.total_weight,
+            total_fees: template_txs.total_fees,
+        }
+    }
+}
+
+/// The effects of processing a published block on the mempool, as returned by `Mempool::process_published_block`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockProcessSummary {
+    /// The number of unconfirmed transactions that were removed because they were mined in the block
+    pub mined_from_pool: usize,
+    /// The number of time-locked transactions that were promoted to the unconfirmed pool
+    pub newly_timelocked_promoted: usize,
+    /// The number of orphan transactions that were promoted to the unconfirmed pool because the block created the
+    /// outputs they spend
+    pub orphans_promoted: usize,
+}
+
+impl Display for BlockProcessSummary {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            fmt,
+            "{} mined from pool, {} time-locked promoted, {} orphan(s) promoted",
+            self.mined_from_pool, self.newly_timelocked_promoted, self.orphans_promoted
+        )
+    }
+}
+
+/// The transactions selected for a new block template, in block order, along with the totals needed to build the
+/// template
+#[derive(Clone, Debug)]
+pub struct BlockTemplateTxs {
+    pub transactions: Vec<Arc<Transaction>>,
+    pub total_fees: MicroTari,
+    pub total_weight: u64,
+    /// The sum of the kernel offsets of the transactions
+    pub total_kernel_offset: BlindingFactor,
+    /// The sum of the script offsets of the transactions
+    pub total_script_offset: BlindingFactor,
+}
+
+impl BlockTemplateTxs {
+    pub fn new(transactions: Vec<Arc<Transaction>>, total_weight: u64) -> Self {
+        let total_fees = transactions.iter().map(|tx| tx.body.get_total_fee()).sum();
+        let total_kernel_offset = transactions
+            .iter()
+            .fold(BlindingFactor::default(), |sum, tx| sum + &tx.offset);
+        let total_script_offset = transactions
+            .iter()
+            .fold(BlindingFactor::default(), |sum, tx| sum + &tx.script_offset);
+        Self {
+            transactions,
+            total_fees,
+            total_weight,
+            total_kernel_offset,
+            total_script_offset,
+        }
+    }
+}
+
+/// Fee per gram statistics of the transactions that would be included in a block, if blocks were filled with the
+/// unconfirmed transactions in order of fee priority
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeePerGramStat {
+    /// The position of the block, 0 being the next block to be mined
+    pub order: u64,
+    pub min_fee_per_gram: MicroTari,
+    pub avg_fee_per_gram: MicroTari,
+    pub max_fee_per_gram: MicroTari,
+}
+
+/// An unconfirmed transaction together with the metadata that the unconfirmed pool keeps for it
+#[derive(Clone, Debug)]
+pub struct MempoolTransactionInfo {
+    pub transaction: Arc<Transaction>,
+    pub weight: u64,
+    /// The time elapsed since the transaction entered the unconfirmed pool
+    pub age: Duration,
+    pub is_local: bool,
+}
+
+impl MempoolTransactionInfo {
+    /// Returns the fee per gram of the transaction
+    pub fn fee_per_gram(&self) -> MicroTari {
+        let fee = self.transaction.body.get_total_fee();
+        MicroTari::from(fee.as_u64() / self.weight.max(1))
+    }
+}
+
+/// The excess signatures of the unconfirmed transactions that differ between the local mempool and a remote mempool
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MempoolDifference {
+    /// Transactions in the local unconfirmed pool that the remote mempool does not have
+    pub local_only: Vec<Signature>,
+    /// Transactions in the remote mempool that the local unconfirmed pool does not have
+    pub remote_only: Vec<Signature>,
+}
+
+/// The origin of a transaction that was submitted to the mempool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdmissionSource {
+    /// The transaction originated locally, e.g. from a wallet connected to this node
+    Local,
+    /// The transaction was received from a peer
+    Gossip,
+}
+
+/// An admission decision made by the mempool, as recorded in the audit log returned by `Mempool::recent_admissions`
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdmissionRecord {
+    /// The excess signature of the first kernel of the transaction
+    pub excess_sig: Option<Signature>,
+    pub outcome: TxStorageResponse,
+    pub timestamp: DateTime<Utc>,
+    pub source: AdmissionSource,
+}
+
+/// A position in the fee-ordered listing of the unconfirmed pool, as returned by `Mempool::retrieve_page`. The cursor
+/// refers to the fee priority of the last transaction that was returned, which embeds the excess signature of the
+/// transaction, so paging remains stable if transactions are added or removed between pages.
+#[cfg(feature = "base_node")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeeCursor(priority::FeePriority);
+
+/// A readiness report of the Mempool, intended to be used by liveness and readiness probes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MempoolHealth {
+    /// False if the mempool storage could not be accessed
+    pub is_responsive: bool,
+    pub unconfirmed_txs: usize,
+    pub unconfirmed_pool_capacity: usize,
+    pub reorg_txs: usize,
+    pub reorg_pool_capacity: usize,
+    /// The number of held orphan transactions, see `MempoolConfig::hold_orphans`
+    pub orphan_txs: usize,
+    pub orphan_pool_capacity: usize,
+    /// The time elapsed since the last published block was processed, or None if no block has been processed
+    pub time_since_last_block: Option<Duration>,
+}
+
+impl MempoolHealth {
+    /// Returns true if the unconfirmed pool has reached its storage capacity
+    pub fn is_unconfirmed_pool_saturated(&self) -> bool {
+        self.unconfirmed_txs >= self.unconfirmed_pool_capacity
+    }
+
+    /// Returns true if the reorg pool has reached its storage capacity
+    pub fn is_reorg_pool_saturated(&self) -> bool {
+        self.reorg_txs >= self.reorg_pool_capacity
+    }
+
+    /// Returns true if the orphan pool has reached its capacity, i.e. further orphans displace the oldest held orphans
+    pub fn is_orphan_pool_saturated(&self) -> bool {
+        self.orphan_txs >= self.orphan_pool_capacity
+    }
+
+    /// Returns true if the mempool is responsive and has space to accept new transactions
+    pub fn is_healthy(&self) -> bool {
+        self.is_responsive && !self.is_unconfirmed_pool_saturated()
+    }
+}
+
+impl Display for MempoolHealth {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            fmt,
+            "Mempool health: Responsive: {}, Unconfirmed: {}/{}, Reorg: {}/{}, Orphans: {}/{}, Last block: {}",
+            self.is_responsive,
+            self.unconfirmed_txs,
+            self.unconfirmed_pool_capacity,
+            self.reorg_txs,
+            self.reorg_pool_capacity,
+            self.orphan_txs,
+            self.orphan_pool_capacity,
+            self.time_since_last_block
+                .map(|t| format!("{:.0?} ago", t))
+                .unwrap_or_else(|| "never".to_string())
+        )
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct StateResponse {
     pub unconfirmed_pool: Vec<Transaction>,
@@ -133,6 +419,14 @@ pub enum TxStorageResponse {
     NotStoredOrphan,
     NotStoredTimeLocked,
     NotStoredAlreadySpent,
+    /// The transaction creates an output with the same commitment as a transaction in the unconfirmed pool
+    NotStoredDuplicateOutput,
+    /// The mempool is full and the transaction's fee per gram is too low to evict any stored transaction. Contains the
+    /// minimum fee per gram a transaction currently needs to pay to be accepted.
+    NotStoredFeeTooLow(MicroTari),
+    /// The transaction would extend a chain of unconfirmed transactions beyond
+    /// `MempoolConfig::max_zero_conf_chain_length`
+    NotStoredZeroConfChainTooLong,
     NotStored,
 }
 
@@ -150,12 +444,34 @@ impl Display for TxStorageResponse {
             TxStorageResponse::NotStoredOrphan => "Not stored orphan transaction",
             TxStorageResponse::NotStoredTimeLocked => "Not stored time locked transaction",
             TxStorageResponse::NotStoredAlreadySpent => "Not stored output already spent",
+            TxStorageResponse::NotStoredDuplicateOutput => "Not stored duplicate output commitment",
+            TxStorageResponse::NotStoredFeeTooLow(min_fee_per_gram) => {
+                return write!(
+                    fmt,
+                    "Not stored, fee too low (minimum fee per gram: {})",
+                    min_fee_per_gram
+                );
+            },
+            TxStorageResponse::NotStoredZeroConfChainTooLong => "Not stored zero-conf chain too long",
             TxStorageResponse::NotStored => "Not stored",
         };
         fmt.write_str(storage)
     }
 }
 
+/// The outcome of validating a transaction against the Mempool without inserting it, see `Mempool::validate_tx`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationOutcome {
+    /// The transaction would be accepted into the unconfirmed pool
+    Valid,
+    /// The transaction is valid but cannot be included in a block until the chain has progressed further
+    TimeLocked,
+    /// The transaction spends outputs that are unknown to both the blockchain and the unconfirmed pool
+    Orphan,
+    /// The transaction is invalid for the given reason
+    Invalid(String),
+}
+
 /// Events that can be published on state changes of the Mempool
 #[derive(Debug, Clone)]
 pub enum MempoolStateEvent {
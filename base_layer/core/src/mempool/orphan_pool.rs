@@ -0,0 +1,269 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::transactions::transaction::Transaction;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tari_common_types::types::{HashOutput, Signature};
+
+/// Holds orphan transactions, i.e. transactions that spend outputs which are not yet known, until the outputs they
+/// spend arrive. Orphans are indexed by the hashes of their missing outputs so that an arriving transaction only
+/// requires its own orphans to be re-validated. The oldest orphan is dropped once the capacity is reached.
+#[derive(Clone)]
+pub struct OrphanPool {
+    capacity: usize,
+    orphans: HashMap<Signature, HeldOrphan>,
+    by_missing_output: HashMap<HashOutput, HashSet<Signature>>,
+    arrival_order: BTreeMap<u64, Signature>,
+    next_seq: u64,
+}
+
+#[derive(Clone)]
+struct HeldOrphan {
+    tx: Arc<Transaction>,
+    is_local: bool,
+    missing_outputs: Vec<HashOutput>,
+    held_at: Instant,
+    seq: u64,
+}
+
+impl OrphanPool {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            orphans: HashMap::new(),
+            by_missing_output: HashMap::new(),
+            arrival_order: BTreeMap::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Holds the transaction until the `missing_outputs` arrive. Returns false if the transaction is already held or
+    /// has no kernel.
+    pub fn insert(&mut self, tx: Arc<Transaction>, is_local: bool, missing_outputs: Vec<HashOutput>) -> bool {
+        let excess_sig = match tx.first_kernel_excess_sig() {
+            Some(sig) => sig.clone(),
+            None => return false,
+        };
+        if self.capacity == 0 || self.orphans.contains_key(&excess_sig) {
+            return false;
+        }
+        if self.orphans.len() >= self.capacity {
+            if let Some(oldest) = self.arrival_order.values().next().cloned() {
+                self.remove(&oldest);
+            }
+        }
+        for hash in &missing_outputs {
+            self.by_missing_output
+                .entry(hash.clone())
+                .or_insert_with(HashSet::new)
+                .insert(excess_sig.clone());
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.arrival_order.insert(seq, excess_sig.clone());
+        self.orphans.insert(excess_sig, HeldOrphan {
+            tx,
+            is_local,
+            missing_outputs,
+            held_at: Instant::now(),
+            seq,
+        });
+        true
+    }
+
+    /// Changes the capacity, dropping the oldest orphans that no longer fit. Returns the number of dropped orphans.
+    pub fn set_capacity(&mut self, capacity: usize) -> usize {
+        self.capacity = capacity;
+        let excess = self.orphans.len().saturating_sub(capacity);
+        let oldest = self.arrival_order.values().take(excess).cloned().collect::<Vec<_>>();
+        for sig in &oldest {
+            self.remove(sig);
+        }
+        excess
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn contains(&self, excess_sig: &Signature) -> bool {
+        self.orphans.contains_key(excess_sig)
+    }
+
+    /// Removes the orphan with the given excess signature, returning the transaction and whether it is local.
+    pub fn remove(&mut self, excess_sig: &Signature) -> Option<(Arc<Transaction>, bool)> {
+        let orphan = self.orphans.remove(excess_sig)?;
+        self.arrival_order.remove(&orphan.seq);
+        for hash in &orphan.missing_outputs {
+            if let Some(sigs) = self.by_missing_output.get_mut(hash) {
+                sigs.remove(excess_sig);
+                if sigs.is_empty() {
+                    self.by_missing_output.remove(hash);
+                }
+            }
+        }
+        Some((orphan.tx, orphan.is_local))
+    }
+
+    /// Returns the excess signatures of the orphans that are waiting for any of the given outputs, and for which
+    /// `is_resolved` returns true for all of their missing outputs.
+    pub fn resolved_by<'a, I, F>(&self, outputs: I, is_resolved: F) -> Vec<Signature>
+    where
+        I: IntoIterator<Item = &'a HashOutput>,
+        F: Fn(&[HashOutput]) -> bool,
+    {
+        let candidates = outputs
+            .into_iter()
+            .filter_map(|hash| self.by_missing_output.get(hash))
+            .flatten()
+            .collect::<HashSet<_>>();
+        self.resolved(candidates, is_resolved)
+    }
+
+    /// Returns the excess signatures of all orphans for which `is_resolved` returns true for all of their missing
+    /// outputs, in order of arrival.
+    pub fn all_resolved<F>(&self, is_resolved: F) -> Vec<Signature>
+    where F: Fn(&[HashOutput]) -> bool {
+        self.resolved(self.arrival_order.values(), is_resolved)
+    }
+
+    fn resolved<'a, I, F>(&self, excess_sigs: I, is_resolved: F) -> Vec<Signature>
+    where
+        I: IntoIterator<Item = &'a Signature>,
+        F: Fn(&[HashOutput]) -> bool,
+    {
+        excess_sigs
+            .into_iter()
+            .filter(|sig| {
+                self.orphans
+                    .get(sig)
+                    .map(|orphan| is_resolved(&orphan.missing_outputs))
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Drops the orphans that have been held for longer than `ttl`, returning the number of dropped orphans.
+    pub fn remove_expired(&mut self, ttl: Duration) -> usize {
+        let expired = self
+            .arrival_order
+            .values()
+            .take_while(|sig| {
+                self.orphans
+                    .get(sig)
+                    .map(|orphan| orphan.held_at.elapsed() >= ttl)
+                    .unwrap_or(true)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        for sig in &expired {
+            self.remove(sig);
+        }
+        expired.len()
+    }
+
+    /// Returns the held transactions in order of arrival
+    pub fn transactions(&self) -> impl Iterator<Item = &Arc<Transaction>> {
+        self.arrival_order
+            .values()
+            .filter_map(move |sig| self.orphans.get(sig))
+            .map(|orphan| &orphan.tx)
+    }
+
+    /// Returns the excess signatures of the held transactions
+    pub fn excess_sigs(&self) -> impl Iterator<Item = &Signature> {
+        self.orphans.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.orphans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orphans.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.orphans.clear();
+        self.by_missing_output.clear();
+        self.arrival_order.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{transactions::tari_amount::MicroTari, tx};
+
+    fn create_orphan() -> Arc<Transaction> {
+        Arc::new(tx!(MicroTari(5000), fee: MicroTari(50)).0)
+    }
+
+    #[test]
+    fn only_orphans_waiting_for_the_outputs_are_resolved() {
+        let mut orphans = OrphanPool::new(10);
+        let (output_a, output_b, output_c) = (vec![1u8; 32], vec![2u8; 32], vec![3u8; 32]);
+        let tx1 = create_orphan();
+        let tx2 = create_orphan();
+        assert!(orphans.insert(tx1.clone(), false, vec![output_a.clone()]));
+        assert!(orphans.insert(tx2.clone(), false, vec![output_b.clone(), output_c.clone()]));
+        assert!(!orphans.insert(tx1.clone(), false, vec![output_a.clone()]));
+
+        let resolved = orphans.resolved_by(&[output_a.clone()], |_| true);
+        assert_eq!(resolved, vec![tx1.first_kernel_excess_sig().unwrap().clone()]);
+        // tx2 is still missing output_c
+        let resolved = orphans.resolved_by(&[output_b], |missing| !missing.contains(&output_c));
+        assert!(resolved.is_empty());
+        assert_eq!(orphans.all_resolved(|_| true).len(), 2);
+
+        orphans.remove(tx1.first_kernel_excess_sig().unwrap()).unwrap();
+        assert!(orphans.resolved_by(&[output_a], |_| true).is_empty());
+        assert_eq!(orphans.len(), 1);
+    }
+
+    #[test]
+    fn oldest_orphan_is_dropped_at_capacity() {
+        let mut orphans = OrphanPool::new(2);
+        let txs = vec![create_orphan(), create_orphan(), create_orphan()];
+        for tx in &txs {
+            orphans.insert(tx.clone(), false, vec![vec![1u8; 32]]);
+        }
+        assert_eq!(orphans.len(), 2);
+        assert_eq!(orphans.transactions().cloned().collect::<Vec<_>>(), txs[1..].to_vec());
+    }
+
+    #[test]
+    fn expired_orphans_are_removed() {
+        let mut orphans = OrphanPool::new(10);
+        orphans.insert(create_orphan(), false, vec![vec![1u8; 32]]);
+        orphans.insert(create_orphan(), false, vec![vec![1u8; 32]]);
+        assert_eq!(orphans.remove_expired(Duration::from_secs(60)), 0);
+        assert_eq!(orphans.remove_expired(Duration::from_secs(0)), 2);
+        assert!(orphans.is_empty());
+        assert!(orphans.resolved_by(&[vec![1u8; 32]], |_| true).is_empty());
+    }
+}
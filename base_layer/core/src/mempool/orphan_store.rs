@@ -0,0 +1,51 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    chain_storage::{BlockchainBackend, BlockchainDatabase},
+    mempool::MempoolError,
+    transactions::transaction::Transaction,
+};
+use chrono::Utc;
+use tari_common_types::types::Signature;
+
+/// Persists the orphan transactions held by the mempool, so that they can still be promoted after a restart once the
+/// outputs they spend become known.
+pub trait OrphanStore: Send + Sync {
+    /// Returns the persisted orphan transactions in order of arrival
+    fn fetch_orphans(&self) -> Result<Vec<Transaction>, MempoolError>;
+    /// Persists the newly held orphan transactions, each under its own key, and deletes the persisted orphan
+    /// transactions with the `removed` excess signatures. The other persisted orphan transactions are not rewritten.
+    fn update_orphans(&self, inserted: Vec<Transaction>, removed: Vec<Signature>) -> Result<(), MempoolError>;
+}
+
+impl<B: BlockchainBackend> OrphanStore for BlockchainDatabase<B> {
+    fn fetch_orphans(&self) -> Result<Vec<Transaction>, MempoolError> {
+        Ok(self.fetch_mempool_orphan_txs()?)
+    }
+
+    fn update_orphans(&self, inserted: Vec<Transaction>, removed: Vec<Signature>) -> Result<(), MempoolError> {
+        let received_at = Utc::now().timestamp_millis() as u64;
+        self.update_mempool_orphan_txs(inserted, removed, received_at)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::transactions::transaction::Transaction;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+use tari_common_types::types::{Commitment, Signature};
+
+/// Holds transactions that spend outputs which have not matured yet, indexed by the height at which they become
+/// spendable so that only the transactions that may have matured need to be re-validated when the chain advances. The
+/// transactions are also indexed by the commitments they spend, so that they can be evicted without re-validation when
+/// a block spends one of their inputs. Once the capacity is reached, the transaction that matures last is dropped.
+#[derive(Clone)]
+pub struct PendingMaturityPool {
+    capacity: usize,
+    txs: HashMap<Signature, PendingTx>,
+    by_spendable_height: BTreeMap<(u64, u64), Signature>,
+    by_input: HashMap<Commitment, HashSet<Signature>>,
+    next_seq: u64,
+}
+
+#[derive(Clone)]
+struct PendingTx {
+    tx: Arc<Transaction>,
+    is_local: bool,
+    key: (u64, u64),
+}
+
+impl PendingMaturityPool {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            txs: HashMap::new(),
+            by_spendable_height: BTreeMap::new(),
+            by_input: HashMap::new(),
+            next_seq: 0,
+        }
+    }
+
+    /// Holds the transaction until it matures. Returns false if the transaction is already held, has no kernel or
+    /// would mature after every held transaction while the pool is full.
+    pub fn insert(&mut self, tx: Arc<Transaction>, is_local: bool) -> bool {
+        let excess_sig = match tx.first_kernel_excess_sig() {
+            Some(sig) => sig.clone(),
+            None => return false,
+        };
+        if self.capacity == 0 || self.txs.contains_key(&excess_sig) {
+            return false;
+        }
+        let key = (tx.min_spendable_height(), self.next_seq);
+        if self.txs.len() >= self.capacity {
+            let last = self
+                .by_spendable_height
+                .iter()
+                .next_back()
+                .map(|(k, sig)| (*k, sig.clone()));
+            if let Some((last_key, last)) = last {
+                if key > last_key {
+                    return false;
+                }
+                self.remove(&last);
+            }
+        }
+        for input in tx.body.inputs() {
+            self.by_input
+                .entry(input.commitment.clone())
+                .or_insert_with(HashSet::new)
+                .insert(excess_sig.clone());
+        }
+        self.next_seq += 1;
+        self.by_spendable_height.insert(key, excess_sig.clone());
+        self.txs.insert(excess_sig, PendingTx { tx, is_local, key });
+        true
+    }
+
+    pub fn contains(&self, excess_sig: &Signature) -> bool {
+        self.txs.contains_key(excess_sig)
+    }
+
+    fn remove(&mut self, excess_sig: &Signature) -> Option<(Arc<Transaction>, bool)> {
+        let pending = self.txs.remove(excess_sig)?;
+        self.by_spendable_height.remove(&pending.key);
+        for input in pending.tx.body.inputs() {
+            if let Some(sigs) = self.by_input.get_mut(&input.commitment) {
+                sigs.remove(excess_sig);
+                if sigs.is_empty() {
+                    self.by_input.remove(&input.commitment);
+                }
+            }
+        }
+        Some((pending.tx, pending.is_local))
+    }
+
+    /// Removes and returns the transactions that may be included in the block following `height`, i.e. the
+    /// transactions that are spendable at `height + 1`.
+    pub fn take_spendable(&mut self, height: u64) -> Vec<(Arc<Transaction>, bool)> {
+        let spendable = self
+            .by_spendable_height
+            .range(..(height.saturating_add(2), 0))
+            .map(|(_, sig)| sig.clone())
+            .collect::<Vec<_>>();
+        spendable.iter().filter_map(|sig| self.remove(sig)).collect()
+    }
+
+    /// Removes the transactions that spend any of the given commitments, returning the removed transactions.
+    pub fn remove_spending<'a, I>(&mut self, commitments: I) -> Vec<Arc<Transaction>>
+    where I: IntoIterator<Item = &'a Commitment> {
+        let spending = commitments
+            .into_iter()
+            .filter_map(|commitment| self.by_input.get(commitment))
+            .flatten()
+            .cloned()
+            .collect::<HashSet<_>>();
+        spending
+            .iter()
+            .filter_map(|sig| self.remove(sig))
+            .map(|(tx, _)| tx)
+            .collect()
+    }
+
+    /// Returns the held transactions in the order in which they become spendable
+    pub fn transactions(&self) -> impl Iterator<Item = &Arc<Transaction>> {
+        self.by_spendable_height
+            .values()
+            .filter_map(move |sig| self.txs.get(sig))
+            .map(|pending| &pending.tx)
+    }
+
+    pub fn clear(&mut self) {
+        self.txs.clear();
+        self.by_spendable_height.clear();
+        self.by_input.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{transactions::tari_amount::MicroTari, tx};
+
+    fn create_tx(lock_height: u64) -> Arc<Transaction> {
+        Arc::new(tx!(MicroTari(5000), fee: MicroTari(50), lock: lock_height, inputs: 1, outputs: 1).0)
+    }
+
+    #[test]
+    fn only_spendable_txs_are_taken() {
+        let mut pool = PendingMaturityPool::new(10);
+        let tx5 = create_tx(5);
+        let tx10 = create_tx(10);
+        assert!(pool.insert(tx10.clone(), false));
+        assert!(pool.insert(tx5.clone(), true));
+        assert!(!pool.insert(tx5.clone(), true));
+
+        assert!(pool.take_spendable(3).is_empty());
+        let spendable = pool.take_spendable(4);
+        assert_eq!(spendable, vec![(tx5, true)]);
+        assert_eq!(pool.transactions().cloned().collect::<Vec<_>>(), vec![tx10]);
+    }
+
+    #[test]
+    fn txs_spending_a_commitment_are_removed() {
+        let mut pool = PendingMaturityPool::new(10);
+        let tx1 = create_tx(5);
+        let tx2 = create_tx(5);
+        pool.insert(tx1.clone(), false);
+        pool.insert(tx2.clone(), false);
+
+        let removed = pool.remove_spending(vec![&tx1.body.inputs()[0].commitment]);
+        assert_eq!(removed, vec![tx1]);
+        assert_eq!(pool.transactions().cloned().collect::<Vec<_>>(), vec![tx2]);
+    }
+
+    #[test]
+    fn last_maturing_tx_is_dropped_at_capacity() {
+        let mut pool = PendingMaturityPool::new(2);
+        let tx5 = create_tx(5);
+        let tx10 = create_tx(10);
+        let tx7 = create_tx(7);
+        pool.insert(tx5.clone(), false);
+        pool.insert(tx10, false);
+        pool.insert(tx7.clone(), false);
+        assert_eq!(pool.transactions().cloned().collect::<Vec<_>>(), vec![tx5, tx7]);
+    }
+
+    #[test]
+    fn tx_maturing_last_is_rejected_at_capacity() {
+        let mut pool = PendingMaturityPool::new(2);
+        let tx5 = create_tx(5);
+        let tx7 = create_tx(7);
+        assert!(pool.insert(tx5.clone(), false));
+        assert!(pool.insert(tx7.clone(), false));
+        assert!(!pool.insert(create_tx(10), false));
+        assert!(!pool.insert(create_tx(7), false));
+        assert_eq!(pool.transactions().cloned().collect::<Vec<_>>(), vec![tx5, tx7]);
+    }
+}
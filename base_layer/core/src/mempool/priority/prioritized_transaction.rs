@@ -20,8 +20,11 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::{mempool::priority::PriorityError, transactions::transaction::Transaction};
-use std::sync::Arc;
+use crate::{
+    mempool::priority::PriorityError,
+    transactions::{tari_amount::MicroTari, transaction::Transaction},
+};
+use std::{sync::Arc, time::Instant};
 use tari_common_types::types::HashOutput;
 use tari_crypto::tari_utilities::message_format::MessageFormat;
 
@@ -33,8 +36,16 @@ pub struct FeePriority(Vec<u8>);
 
 impl FeePriority {
     pub fn try_from(transaction: &Transaction) -> Result<Self, PriorityError> {
+        Self::new(transaction, transaction.calculate_weight(), MicroTari::from(0))
+    }
+
+    /// Calculates the priority of the transaction from its fee per gram of the given `weight`, as if it paid an
+    /// additional `boost` fee per gram
+    pub fn new(transaction: &Transaction, weight: u64, boost: MicroTari) -> Result<Self, PriorityError> {
         // The weights have been normalised, so the fee priority is now equal to the fee per gram ± a few pct points
-        let fee_per_byte = (transaction.calculate_ave_fee_per_gram() * 1000.0) as usize; // Include 3 decimal places before flooring
+        let fee_per_gram =
+            transaction.body.get_total_fee().as_u64() as f64 / weight.max(1) as f64 + boost.as_u64() as f64;
+        let fee_per_byte = (fee_per_gram * 1000.0) as usize; // Include 3 decimal places before flooring
         let mut fee_priority = fee_per_byte.to_binary()?;
         fee_priority.reverse(); // Requires Big-endian for BtreeMap sorting
 
@@ -61,11 +72,18 @@ pub struct PrioritizedTransaction {
     pub priority: FeePriority,
     pub weight: u64,
     pub depended_output_hashes: Vec<HashOutput>,
+    /// The time at which the transaction was prioritized, i.e. when it entered the pool
+    pub inserted_at: Instant,
+    /// True if the transaction was submitted locally. Locally-originated transactions are never evicted to make space
+    /// for other transactions.
+    pub is_local: bool,
 }
 
 impl PrioritizedTransaction {
+    /// Prioritizes the transaction by its fee per gram of the given `weight`
     pub fn convert_from_transaction(
         transaction: Transaction,
+        weight: u64,
         dependent_outputs: Option<Vec<HashOutput>>,
     ) -> Result<PrioritizedTransaction, PriorityError> {
         let depended_output_hashes = match dependent_outputs {
@@ -73,10 +91,12 @@ impl PrioritizedTransaction {
             None => Vec::new(),
         };
         Ok(Self {
-            priority: FeePriority::try_from(&transaction)?,
-            weight: transaction.calculate_weight(),
+            priority: FeePriority::new(&transaction, weight, MicroTari::from(0))?,
+            weight,
             transaction: Arc::new(transaction),
             depended_output_hashes,
+            inserted_at: Instant::now(),
+            is_local: false,
         })
     }
 }
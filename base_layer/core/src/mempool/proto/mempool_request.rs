@@ -53,7 +53,8 @@ impl From<MempoolRequest> for ProtoMempoolRequest {
             GetStats => ProtoMempoolRequest::GetStats(true),
             GetState => ProtoMempoolRequest::GetState(true),
             GetTxStateByExcessSig(excess_sig) => ProtoMempoolRequest::GetTxStateByExcessSig(excess_sig.into()),
-            SubmitTransaction(tx) => ProtoMempoolRequest::SubmitTransaction(tx.into()),
+            // Whether a transaction was submitted locally is only meaningful to the node it was submitted to
+            SubmitTransaction(tx) | SubmitLocalTransaction(tx) => ProtoMempoolRequest::SubmitTransaction(tx.into()),
         }
     }
 }
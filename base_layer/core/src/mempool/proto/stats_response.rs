@@ -20,18 +20,44 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::mempool::{proto::mempool::StatsResponse as ProtoStatsResponse, StatsResponse};
-use std::convert::TryFrom;
+use crate::{
+    mempool::{proto::mempool::StatsResponse as ProtoStatsResponse, StatsResponse},
+    transactions::transaction::KernelFeatures,
+};
+use std::convert::{TryFrom, TryInto};
 
 impl TryFrom<ProtoStatsResponse> for StatsResponse {
     type Error = String;
 
     fn try_from(stats: ProtoStatsResponse) -> Result<Self, Self::Error> {
+        let txs_by_kernel_feature = stats
+            .txs_by_kernel_feature
+            .into_iter()
+            .map(|(features, count)| {
+                let features = features
+                    .try_into()
+                    .ok()
+                    .and_then(KernelFeatures::from_bits)
+                    .ok_or_else(|| format!("Invalid kernel features: {}", features))?;
+                Ok((features, count as usize))
+            })
+            .collect::<Result<_, String>>()?;
+        // Reasons that were added in a newer version are skipped, so that stats can still be exchanged with that
+        // version
+        let reject_reasons = stats
+            .reject_reasons
+            .into_iter()
+            .filter_map(|(reason, count)| reason.parse().ok().map(|reason| (reason, count as usize)))
+            .collect();
         Ok(Self {
             total_txs: stats.total_txs as usize,
             unconfirmed_txs: stats.unconfirmed_txs as usize,
             reorg_txs: stats.reorg_txs as usize,
             total_weight: stats.total_weight,
+            txs_by_kernel_feature,
+            local_txs: stats.local_txs as usize,
+            max_dependency_depth: stats.max_dependency_depth as usize,
+            reject_reasons,
         })
     }
 }
@@ -43,6 +69,47 @@ impl From<StatsResponse> for ProtoStatsResponse {
             unconfirmed_txs: stats.unconfirmed_txs as u64,
             reorg_txs: stats.reorg_txs as u64,
             total_weight: stats.total_weight,
+            txs_by_kernel_feature: stats
+                .txs_by_kernel_feature
+                .into_iter()
+                .map(|(features, count)| (u32::from(features.bits()), count as u64))
+                .collect(),
+            local_txs: stats.local_txs as u64,
+            max_dependency_depth: stats.max_dependency_depth as u64,
+            reject_reasons: stats
+                .reject_reasons
+                .into_iter()
+                .map(|(reason, count)| (reason.to_string(), count as u64))
+                .collect(),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mempool::RejectReason;
+    use std::collections::HashMap;
+
+    #[test]
+    fn unknown_reject_reasons_are_skipped() {
+        let mut reject_reasons = HashMap::new();
+        reject_reasons.insert(RejectReason::Orphan, 2);
+        reject_reasons.insert(RejectReason::FeeTooLow, 1);
+        let stats = StatsResponse {
+            total_txs: 3,
+            unconfirmed_txs: 2,
+            reorg_txs: 1,
+            total_weight: 100,
+            txs_by_kernel_feature: HashMap::new(),
+            local_txs: 1,
+            max_dependency_depth: 0,
+            reject_reasons,
+        };
+
+        let mut proto = ProtoStatsResponse::from(stats.clone());
+        proto.reject_reasons.insert("not_a_known_reason".to_string(), 5);
+        let decoded = StatsResponse::try_from(proto).unwrap();
+        assert_eq!(decoded, stats);
+    }
+}
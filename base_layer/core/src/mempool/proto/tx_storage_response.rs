@@ -47,6 +47,9 @@ impl From<TxStorageResponse> for proto::TxStorageResponse {
             NotStoredOrphan => proto::TxStorageResponse::NotStored,
             NotStoredTimeLocked => proto::TxStorageResponse::NotStored,
             NotStoredAlreadySpent => proto::TxStorageResponse::NotStored,
+            NotStoredDuplicateOutput => proto::TxStorageResponse::NotStored,
+            NotStoredFeeTooLow(_) => proto::TxStorageResponse::NotStored,
+            NotStoredZeroConfChainTooLong => proto::TxStorageResponse::NotStored,
         }
     }
 }
@@ -0,0 +1,79 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{
+    blocks::Block,
+    chain_storage::{BlockchainBackend, BlockchainDatabase},
+    mempool::MempoolError,
+};
+use std::sync::Arc;
+use tari_common_types::types::HashOutput;
+use tari_crypto::tari_utilities::Hashable;
+
+/// Identifies a reorg by the chain tip that resulted from it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReorgMarker {
+    pub height: u64,
+    pub tip_hash: HashOutput,
+}
+
+impl ReorgMarker {
+    /// Returns the marker of the chain tip resulting from removing `removed_blocks` and adding `new_blocks`, or None if
+    /// both are empty.
+    pub fn from_reorg(removed_blocks: &[Arc<Block>], new_blocks: &[Arc<Block>]) -> Option<Self> {
+        match new_blocks.iter().max_by_key(|block| block.header.height) {
+            Some(tip) => Some(Self {
+                height: tip.header.height,
+                tip_hash: tip.header.hash(),
+            }),
+            // A rewind without new blocks leaves the parent of the lowest removed block as the tip
+            None => removed_blocks
+                .iter()
+                .min_by_key(|block| block.header.height)
+                .map(|block| Self {
+                    height: block.header.height.saturating_sub(1),
+                    tip_hash: block.header.prev_hash.clone(),
+                }),
+        }
+    }
+}
+
+/// Persists the marker of the last reorg that was applied to the mempool, so that a reorg that is delivered again, for
+/// instance after a restart, is detected and not applied twice.
+pub trait ReorgMarkerStore: Send + Sync {
+    fn fetch_reorg_marker(&self) -> Result<Option<ReorgMarker>, MempoolError>;
+    fn set_reorg_marker(&self, marker: ReorgMarker) -> Result<(), MempoolError>;
+}
+
+impl<B: BlockchainBackend> ReorgMarkerStore for BlockchainDatabase<B> {
+    fn fetch_reorg_marker(&self) -> Result<Option<ReorgMarker>, MempoolError> {
+        let marker = self
+            .fetch_mempool_reorg_marker()?
+            .map(|(height, tip_hash)| ReorgMarker { height, tip_hash });
+        Ok(marker)
+    }
+
+    fn set_reorg_marker(&self, marker: ReorgMarker) -> Result<(), MempoolError> {
+        self.set_mempool_reorg_marker(marker.height, marker.tip_hash)?;
+        Ok(())
+    }
+}
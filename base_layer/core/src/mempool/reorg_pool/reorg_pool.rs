@@ -39,7 +39,9 @@ use tari_common_types::types::Signature;
 /// Configuration for the ReorgPool
 #[derive(Clone, Copy, Deserialize, Serialize)]
 pub struct ReorgPoolConfig {
-    /// The maximum number of transactions that can be stored in the ReorgPool
+    /// The maximum number of transactions that can be stored in the ReorgPool. Once the pool is full, the transactions
+    /// that were mined the longest ago are evicted first, as they are the least likely to be needed for a reorg.
+    /// Default: 5,000
     pub storage_capacity: usize,
     /// The Time-to-live for each stored transaction
     #[serde(with = "seconds")]
@@ -52,6 +52,10 @@ mod get_stats {
 
             reorg_txs: 5,
             total_weight: 6,
+            txs_by_kernel_feature: Default::default(),
+            local_txs: 0,
+            max_dependency_depth: 0,
+            reject_reasons: Default::default(),
         };
         mempool.set_get_stats_response(expected_stats.clone()).await;
 
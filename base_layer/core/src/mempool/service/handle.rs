@@ -80,4 +80,19 @@ impl MempoolHandle {
             _ => panic!("Incorrect response"),
         }
     }
+
+    /// Submits a transaction from a local wallet or client, see `Mempool::insert_local`
+    pub async fn submit_local_transaction(
+        &mut self,
+        transaction: Transaction,
+    ) -> Result<TxStorageResponse, MempoolServiceError> {
+        match self
+            .inner
+            .call(MempoolRequest::SubmitLocalTransaction(transaction))
+            .await??
+        {
+            MempoolResponse::TxStorage(resp) => Ok(resp),
+            _ => panic!("Incorrect response"),
+        }
+    }
 }
@@ -22,11 +22,13 @@
 
 use crate::{
     base_node::comms_interface::BlockEvent,
+    blocks::Block,
     chain_storage::BlockAddResult,
     mempool::{
         async_mempool,
         service::{MempoolRequest, MempoolResponse, MempoolServiceError, OutboundMempoolServiceInterface},
         Mempool,
+        MempoolError,
         MempoolStateEvent,
         TxStorageResponse,
     },
@@ -83,7 +85,19 @@ impl MempoolInboundHandlers {
                     "Transaction ({}) submitted using request.",
                     tx.body.kernels()[0].excess_sig.get_signature().to_hex(),
                 );
-                Ok(MempoolResponse::TxStorage(self.submit_transaction(tx, vec![]).await?))
+                Ok(MempoolResponse::TxStorage(
+                    self.submit_transaction(tx, vec![], false).await?,
+                ))
+            },
+            SubmitLocalTransaction(tx) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Local transaction ({}) submitted using request.",
+                    tx.body.kernels()[0].excess_sig.get_signature().to_hex(),
+                );
+                Ok(MempoolResponse::TxStorage(
+                    self.submit_transaction(tx, vec![], true).await?,
+                ))
             },
         }
     }
@@ -104,14 +118,16 @@ impl MempoolInboundHandlers {
                 .unwrap_or_else(|| "local services".to_string())
         );
         let exclude_peers = source_peer.into_iter().collect();
-        self.submit_transaction(tx, exclude_peers).await.map(|_| ())
+        self.submit_transaction(tx, exclude_peers, false).await.map(|_| ())
     }
 
-    // Submits a transaction to the mempool and propagate valid transactions.
+    // Submits a transaction to the mempool and propagate valid transactions. Local transactions are inserted with
+    // `insert_local`.
     async fn submit_transaction(
         &mut self,
         tx: Transaction,
         exclude_peers: Vec<NodeId>,
+        is_local: bool,
     ) -> Result<TxStorageResponse, MempoolServiceError> {
         trace!(target: LOG_TARGET, "submit_transaction: {}.", tx);
         let tx_storage =
@@ -126,7 +142,12 @@ impl MempoolInboundHandlers {
             );
             return Ok(tx_storage);
         }
-        match async_mempool::insert(self.mempool.clone(), Arc::new(tx.clone())).await {
+        let result = if is_local {
+            async_mempool::insert_local(self.mempool.clone(), Arc::new(tx.clone())).await
+        } else {
+            async_mempool::insert(self.mempool.clone(), Arc::new(tx.clone())).await
+        };
+        match result {
             Ok(tx_storage) => {
                 debug!(
                     target: LOG_TARGET,
@@ -151,7 +172,7 @@ impl MempoolInboundHandlers {
         use BlockEvent::*;
         match block_event {
             ValidBlockAdded(block, BlockAddResult::Ok(_), broadcast) => {
-                async_mempool::process_published_block(self.mempool.clone(), block.clone()).await?;
+                self.process_published_block(block.clone()).await?;
                 if broadcast.is_true() {
                     let _ = self.event_publisher.send(MempoolStateEvent::Updated);
                 }
@@ -163,6 +184,7 @@ impl MempoolInboundHandlers {
                     added.iter().map(|b| b.to_arc_block()).collect(),
                 )
                 .await?;
+                self.propagate_local_transactions().await?;
                 if broadcast.is_true() {
                     let _ = self.event_publisher.send(MempoolStateEvent::Updated);
                 }
@@ -174,10 +196,11 @@ impl MempoolInboundHandlers {
                     vec![],
                 )
                 .await?;
+                self.propagate_local_transactions().await?;
                 let _ = self.event_publisher.send(MempoolStateEvent::Updated);
             },
             BlockSyncComplete(tip_block) => {
-                async_mempool::process_published_block(self.mempool.clone(), tip_block.to_arc_block()).await?;
+                self.process_published_block(tip_block.to_arc_block()).await?;
                 let _ = self.event_publisher.send(MempoolStateEvent::Updated);
             },
             _ => {},
@@ -185,4 +208,61 @@ impl MempoolInboundHandlers {
 
         Ok(())
     }
+
+    // Processes a block added to the tip of the chain. A block that was already processed, or that builds on an earlier
+    // processed block, is handled by the mempool without revalidating the pool. If the block does not build on any
+    // block the mempool processed, e.g. after a block sync or if block events were missed, the pool is reconciled with
+    // the new tip.
+    async fn process_published_block(&mut self, block: Arc<Block>) -> Result<(), MempoolServiceError> {
+        match async_mempool::process_published_block(self.mempool.clone(), block.clone()).await {
+            Ok(summary) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Mempool processed block {}: {}", block.header.height, summary
+                );
+                Ok(())
+            },
+            Err(MempoolError::UnexpectedBlock {
+                expected_prev,
+                got_prev,
+            }) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Block {} does not build on the last processed block (expected previous block {}, got {}). \
+                     Revalidating the mempool against the new tip.",
+                    block.header.height,
+                    expected_prev.to_hex(),
+                    got_prev.to_hex()
+                );
+                async_mempool::process_reorg(self.mempool.clone(), vec![], vec![block]).await?;
+                Ok(())
+            },
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // Re-broadcasts locally-originated transactions after a reorg, as peers may have dropped them
+    async fn propagate_local_transactions(&mut self) -> Result<(), MempoolServiceError> {
+        let local_txs = async_mempool::local_transactions(self.mempool.clone()).await?;
+        if !local_txs.is_empty() {
+            debug!(
+                target: LOG_TARGET,
+                "Propagating {} local transaction(s) after reorg",
+                local_txs.len()
+            );
+        }
+        for tx in local_txs {
+            if let Err(err) = self.outbound_nmi.propagate_tx((*tx).clone(), vec![]).await {
+                warn!(
+                    target: LOG_TARGET,
+                    "Failed to propagate local transaction ({}): {}",
+                    tx.first_kernel_excess_sig()
+                        .map(|sig| sig.get_signature().to_hex())
+                        .unwrap_or_else(|| "None".into()),
+                    err
+                );
+            }
+        }
+        Ok(())
+    }
 }
@@ -22,6 +22,7 @@
 
 use crate::{
     base_node::{comms_interface::LocalNodeCommsInterface, StateMachineHandle},
+    consensus::ConsensusManager,
     mempool::{
         mempool::Mempool,
         proto as mempool_proto,
@@ -35,9 +36,9 @@ use crate::{
         MempoolServiceConfig,
     },
     proto,
-    transactions::transaction::Transaction,
+    transactions::{fee::Fee, transaction::Transaction},
 };
-use futures::{Stream, StreamExt};
+use futures::{future, Stream, StreamExt};
 use log::*;
 use std::{convert::TryFrom, sync::Arc};
 use tari_comms_dht::Dht;
@@ -54,7 +55,10 @@ use tari_service_framework::{
     ServiceInitializer,
     ServiceInitializerContext,
 };
-use tokio::sync::{broadcast, mpsc};
+use tokio::{
+    sync::{broadcast, mpsc},
+    task,
+};
 
 const LOG_TARGET: &str = "c::bn::mempool_service::initializer";
 const SUBSCRIPTION_LABEL: &str = "Mempool";
@@ -64,6 +68,7 @@ pub struct MempoolServiceInitializer {
     inbound_message_subscription_factory: Arc<SubscriptionFactory>,
     mempool: Mempool,
     config: MempoolServiceConfig,
+    max_transaction_weight: u64,
 }
 
 impl MempoolServiceInitializer {
@@ -72,11 +77,16 @@ impl MempoolServiceInitializer {
         config: MempoolServiceConfig,
         mempool: Mempool,
         inbound_message_subscription_factory: Arc<SubscriptionFactory>,
+        consensus_manager: ConsensusManager,
     ) -> Self {
         Self {
             inbound_message_subscription_factory,
             mempool,
             config,
+            // A transaction that does not fit into a block can never be mined
+            max_transaction_weight: consensus_manager
+                .consensus_constants(u64::MAX)
+                .get_max_block_transaction_weight(),
         }
     }
 
@@ -98,13 +108,14 @@ impl MempoolServiceInitializer {
 
     /// Create a stream of 'New Transaction` messages
     fn inbound_transaction_stream(&self) -> impl Stream<Item = DomainMessage<Transaction>> {
+        let max_transaction_weight = self.max_transaction_weight;
         self.inbound_message_subscription_factory
             .get_subscription(TariMessageType::NewTransaction, SUBSCRIPTION_LABEL)
-            .filter_map(extract_transaction)
+            .filter_map(move |msg| future::ready(extract_transaction(msg, max_transaction_weight)))
     }
 }
 
-async fn extract_transaction(msg: Arc<PeerMessage>) -> Option<DomainMessage<Transaction>> {
+fn extract_transaction(msg: Arc<PeerMessage>, max_transaction_weight: u64) -> Option<DomainMessage<Transaction>> {
     match msg.decode_message::<proto::types::Transaction>() {
         Err(e) => {
             warn!(
@@ -115,6 +126,19 @@ async fn extract_transaction(msg: Arc<PeerMessage>) -> Option<DomainMessage<Tran
             None
         },
         Ok(tx) => {
+            // Reject oversized transactions before any further (expensive) conversion and validation takes place
+            let weight = proto_transaction_weight(&tx);
+            if weight > max_transaction_weight {
+                warn!(
+                    target: LOG_TARGET,
+                    "Peer {} sent a transaction with weight {} that exceeds the maximum transaction weight of {}. The \
+                     peer may be misbehaving.",
+                    msg.source_peer.node_id.short_str(),
+                    weight,
+                    max_transaction_weight
+                );
+                return None;
+            }
             let tx = match Transaction::try_from(tx) {
                 Err(e) => {
                     warn!(
@@ -135,6 +159,14 @@ async fn extract_transaction(msg: Arc<PeerMessage>) -> Option<DomainMessage<Tran
     }
 }
 
+/// Calculates the weight of a decoded transaction message from its element counts
+fn proto_transaction_weight(tx: &proto::types::Transaction) -> u64 {
+    tx.body
+        .as_ref()
+        .map(|body| Fee::calculate_weight(body.kernels.len(), body.inputs.len(), body.outputs.len()))
+        .unwrap_or(0)
+}
+
 #[async_trait]
 impl ServiceInitializer for MempoolServiceInitializer {
     async fn initialize(&mut self, context: ServiceInitializerContext) -> Result<(), ServiceInitializationError> {
@@ -167,6 +199,12 @@ impl ServiceInitializer for MempoolServiceInitializer {
         context.register_handle(outbound_mp_interface);
         context.register_handle(local_mp_interface);
 
+        task::spawn(
+            self.mempool
+                .clone()
+                .run_fee_estimator(self.max_transaction_weight, context.get_shutdown_signal()),
+        );
+
         context.spawn_until_shutdown(move |handles| {
             let outbound_message_service = handles.expect_handle::<Dht>().outbound_requester();
             let state_machine = handles.expect_handle::<StateMachineHandle>();
@@ -188,3 +226,73 @@ impl ServiceInitializer for MempoolServiceInitializer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::consensus::ConsensusManagerBuilder;
+    use rand::rngs::OsRng;
+    use tari_common::configuration::Network;
+    use tari_comms::{
+        message::{MessageExt, MessageTag},
+        peer_manager::PeerFeatures,
+        NodeIdentity,
+    };
+    use tari_comms_dht::{envelope::DhtMessageHeader, DhtProtocolVersion};
+
+    fn create_peer_message(tx: proto::types::Transaction) -> Arc<PeerMessage> {
+        let node_identity = NodeIdentity::random(
+            &mut OsRng,
+            "/ip4/127.0.0.1/tcp/9000".parse().unwrap(),
+            PeerFeatures::COMMUNICATION_NODE,
+        );
+        Arc::new(PeerMessage {
+            dht_header: DhtMessageHeader {
+                version: DhtProtocolVersion::latest(),
+                ephemeral_public_key: None,
+                origin_mac: Vec::new(),
+                message_type: Default::default(),
+                flags: Default::default(),
+                destination: Default::default(),
+                message_tag: MessageTag::new(),
+                expires: None,
+            },
+            source_peer: node_identity.to_peer(),
+            message_header: Default::default(),
+            authenticated_origin: None,
+            body: tx.to_encoded_bytes(),
+        })
+    }
+
+    #[test]
+    fn it_drops_oversized_transactions_before_conversion() {
+        let consensus_manager = ConsensusManagerBuilder::new(Network::LocalNet).build();
+        let max_weight = consensus_manager
+            .consensus_constants(u64::MAX)
+            .get_max_block_transaction_weight();
+        let tx = proto::types::Transaction {
+            body: Some(proto::types::AggregateBody {
+                inputs: vec![Default::default(); max_weight as usize + 1],
+                outputs: vec![],
+                kernels: vec![Default::default()],
+            }),
+            ..Default::default()
+        };
+        assert!(proto_transaction_weight(&tx) > max_weight);
+        assert!(extract_transaction(create_peer_message(tx), max_weight).is_none());
+    }
+
+    #[test]
+    fn it_calculates_proto_transaction_weight() {
+        let tx = proto::types::Transaction {
+            body: Some(proto::types::AggregateBody {
+                inputs: vec![Default::default(); 2],
+                outputs: vec![Default::default(); 3],
+                kernels: vec![Default::default()],
+            }),
+            ..Default::default()
+        };
+        assert_eq!(proto_transaction_weight(&tx), Fee::calculate_weight(1, 2, 3));
+        assert_eq!(proto_transaction_weight(&Default::default()), 0);
+    }
+}
@@ -99,6 +99,21 @@ impl LocalMempoolService {
         }
     }
 
+    /// Submits a transaction from a local wallet or client, see `Mempool::insert_local`
+    pub async fn submit_local_transaction(
+        &mut self,
+        transaction: Transaction,
+    ) -> Result<TxStorageResponse, MempoolServiceError> {
+        match self
+            .request_sender
+            .call(MempoolRequest::SubmitLocalTransaction(transaction))
+            .await??
+        {
+            MempoolResponse::TxStorage(s) => Ok(s),
+            _ => Err(MempoolServiceError::UnexpectedApiResponse),
+        }
+    }
+
     pub async fn get_transaction_state_by_excess_sig(
         &mut self,
         sig: Signature,
@@ -133,6 +148,10 @@ mod test {
             unconfirmed_txs: 3,
             reorg_txs: 4,
             total_weight: 1000,
+            txs_by_kernel_feature: Default::default(),
+            local_txs: 0,
+            max_dependency_depth: 0,
+            reject_reasons: Default::default(),
         }
     }
 
@@ -34,6 +34,9 @@ pub enum MempoolRequest {
     GetState,
     GetTxStateByExcessSig(Signature),
     SubmitTransaction(Transaction),
+    /// Submit a transaction from a local wallet or client. Local transactions are exempt from eviction and are
+    /// re-broadcast after a reorg.
+    SubmitLocalTransaction(Transaction),
 }
 
 impl Display for MempoolRequest {
@@ -48,6 +51,10 @@ impl Display for MempoolRequest {
                 "SubmitTransaction ({})",
                 tx.body.kernels()[0].excess_sig.get_signature().to_hex()
             )),
+            MempoolRequest::SubmitLocalTransaction(tx) => f.write_str(&format!(
+                "SubmitLocalTransaction ({})",
+                tx.body.kernels()[0].excess_sig.get_signature().to_hex()
+            )),
         }
     }
 }
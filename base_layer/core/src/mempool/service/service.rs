@@ -397,9 +397,10 @@ async fn handle_outbound_request(
         request: Some(request.into()),
     };
 
+    // The request is sent to multiple peers and the first response for the request key is used
     let send_result = outbound_message_service
         .send_random(
-            1,
+            config.outbound_request_num_peers,
             NodeDestination::Unknown,
             OutboundEncryption::ClearText,
             OutboundDomainMessage::new(TariMessageType::MempoolRequest, service_request),
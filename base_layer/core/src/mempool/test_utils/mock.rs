@@ -60,6 +60,10 @@ impl Default for MempoolMockState {
                 unconfirmed_txs: 0,
                 reorg_txs: 0,
                 total_weight: 0,
+                txs_by_kernel_feature: Default::default(),
+                local_txs: 0,
+                max_dependency_depth: 0,
+                reject_reasons: Default::default(),
             })),
             get_state: Arc::new(Mutex::new(StateResponse {
                 unconfirmed_pool: vec![],
@@ -132,7 +136,7 @@ impl MempoolServiceMock {
             GetTxStateByExcessSig(_) => Ok(MempoolResponse::TxStorage(
                 self.state.get_tx_state_by_excess_sig.lock().await.clone(),
             )),
-            SubmitTransaction(_) => Ok(MempoolResponse::TxStorage(
+            SubmitTransaction(_) | SubmitLocalTransaction(_) => Ok(MempoolResponse::TxStorage(
                 self.state.submit_transaction.lock().await.clone(),
             )),
         }
@@ -21,13 +21,16 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet},
+    ops::Bound,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
+use blake2::Digest;
 use log::*;
 use serde::{Deserialize, Serialize};
-use tari_crypto::tari_utilities::{hex::Hex, Hashable};
+use tari_crypto::tari_utilities::{hex::Hex, ByteArray, Hashable};
 
 use crate::{
     blocks::Block,
@@ -35,10 +38,18 @@ use crate::{
         consts::{MEMPOOL_UNCONFIRMED_POOL_STORAGE_CAPACITY, MEMPOOL_UNCONFIRMED_POOL_WEIGHT_TRANSACTION_SKIP_COUNT},
         priority::{FeePriority, PrioritizedTransaction},
         unconfirmed_pool::UnconfirmedPoolError,
+        DefaultWeightPolicy,
+        FeePerGramStat,
+        MempoolDifference,
+        MempoolTransactionInfo,
+        TransactionWeightPolicy,
+    },
+    transactions::{
+        tari_amount::MicroTari,
+        transaction::{KernelFeatures, Transaction},
     },
-    transactions::transaction::Transaction,
 };
-use tari_common_types::types::{HashOutput, Signature};
+use tari_common_types::types::{Commitment, FixedHash, HashDigest, HashOutput, Signature, FIXED_HASH_SIZE};
 
 pub const LOG_TARGET: &str = "c::mp::unconfirmed_pool::unconfirmed_pool_storage";
 
@@ -69,11 +80,14 @@ impl Default for UnconfirmedPoolConfig {
 /// priority. The txs_by_priority BTreeMap makes it easier to select the set of highest priority transactions that can
 /// be included in a block. The excess_sig of a transaction is used a key to uniquely identify a specific transaction in
 /// these containers.
+#[derive(Clone)]
 pub struct UnconfirmedPool {
     config: UnconfirmedPoolConfig,
     txs_by_signature: HashMap<Signature, PrioritizedTransaction>,
     txs_by_priority: BTreeMap<FeePriority, Signature>,
     txs_by_output: HashMap<HashOutput, Vec<Signature>>,
+    txs_by_commitment: HashMap<Commitment, Vec<Signature>>,
+    weight_policy: Arc<dyn TransactionWeightPolicy>,
 }
 
 // helper class to reduce type complexity
@@ -82,6 +96,14 @@ pub struct RetrieveResults {
     pub transactions_to_insert: Vec<Arc<Transaction>>,
 }
 
+// Accumulated state while selecting transactions for a block
+#[derive(Default)]
+struct TxSelection {
+    selected_txs: HashMap<Signature, Arc<Transaction>>,
+    curr_weight: u64,
+    transactions_to_remove_and_recheck: Vec<Arc<Transaction>>,
+}
+
 impl UnconfirmedPool {
     /// Create a new UnconfirmedPool with the specified configuration
     pub fn new(config: UnconfirmedPoolConfig) -> Self {
@@ -90,39 +112,110 @@ impl UnconfirmedPool {
             txs_by_signature: HashMap::new(),
             txs_by_priority: BTreeMap::new(),
             txs_by_output: HashMap::new(),
+            txs_by_commitment: HashMap::new(),
+            weight_policy: Arc::new(DefaultWeightPolicy),
         }
     }
 
-    fn lowest_priority(&self) -> &FeePriority {
-        self.txs_by_priority.iter().next().unwrap().0
+    /// Use the given policy to calculate the weight of the stored transactions
+    pub fn with_weight_policy(mut self, weight_policy: Arc<dyn TransactionWeightPolicy>) -> Self {
+        self.weight_policy = weight_policy;
+        self
+    }
+
+    /// Returns the lowest priority transaction that may be evicted, i.e. that did not originate locally
+    fn lowest_priority_evictable(&self) -> Option<(&FeePriority, &Signature)> {
+        self.txs_by_priority
+            .iter()
+            .find(|(_, sig)| self.txs_by_signature.get(sig).map(|ptx| !ptx.is_local).unwrap_or(false))
+    }
+
+    /// Returns true if a transaction with the given priority can be stored, evicting the lowest priority evictable
+    /// transaction if the pool is at capacity. Local transactions are stored regardless of capacity.
+    fn has_room_for(&self, priority: &FeePriority, is_local: bool) -> bool {
+        if is_local || self.txs_by_signature.len() < self.config.storage_capacity {
+            return true;
+        }
+        self.lowest_priority_evictable()
+            .map(|(lowest_priority, _)| priority >= lowest_priority)
+            .unwrap_or(false)
     }
 
-    fn remove_lowest_priority_tx(&mut self) {
-        if let Some((priority, sig)) = self.txs_by_priority.iter().next().map(|(p, s)| (p.clone(), s.clone())) {
-            self.txs_by_signature.remove(&sig);
-            self.txs_by_priority.remove(&priority);
+    /// Returns true if `insert` (or `insert_local` if `is_local` is set) would store the transaction, i.e. it is
+    /// already stored or there is room for it. The pool is not modified.
+    pub fn would_store(&self, tx: &Transaction, is_local: bool) -> Result<bool, UnconfirmedPoolError> {
+        let tx_key = tx
+            .first_kernel_excess_sig()
+            .ok_or(UnconfirmedPoolError::TransactionNoKernels)?;
+        if self.txs_by_signature.contains_key(tx_key) {
+            return Ok(true);
         }
+        let priority = FeePriority::new(tx, self.weight_policy.calculate_weight(tx), MicroTari::from(0))?;
+        Ok(self.has_room_for(&priority, is_local))
+    }
+
+    /// Replaces the pool configuration. If the storage capacity was lowered, the lowest priority transactions are
+    /// evicted until the pool fits, local transactions excepted. Returns the evicted transactions.
+    pub fn set_config(&mut self, config: UnconfirmedPoolConfig) -> Vec<Arc<Transaction>> {
+        self.config = config;
+        let mut evicted = Vec::new();
+        while self.txs_by_signature.len() > self.config.storage_capacity {
+            let sig = match self.lowest_priority_evictable() {
+                Some((_, sig)) => sig.clone(),
+                None => break,
+            };
+            if let Some(tx) = self.delete_transaction(&sig) {
+                evicted.push(tx);
+            }
+        }
+        evicted
     }
 
     /// Insert a new transaction into the UnconfirmedPool. Low priority transactions will be removed to make space for
     /// higher priority transactions. The lowest priority transactions will be removed when the maximum capacity is
     /// reached and the new transaction has a higher priority than the currently stored lowest priority transaction.
-    #[allow(clippy::map_entry)]
+    ///
+    /// Returns false if the pool is at capacity and the transaction's priority is too low for it to be stored.
     pub fn insert(
         &mut self,
         tx: Arc<Transaction>,
         dependent_outputs: Option<Vec<HashOutput>>,
-    ) -> Result<(), UnconfirmedPoolError> {
+    ) -> Result<bool, UnconfirmedPoolError> {
+        self.insert_with_origin(tx, dependent_outputs, false)
+    }
+
+    /// Insert a locally-originated transaction into the UnconfirmedPool. Local transactions are exempt from eviction
+    /// and are stored even if the pool is at capacity and no other transaction can be evicted.
+    pub fn insert_local(
+        &mut self,
+        tx: Arc<Transaction>,
+        dependent_outputs: Option<Vec<HashOutput>>,
+    ) -> Result<bool, UnconfirmedPoolError> {
+        self.insert_with_origin(tx, dependent_outputs, true)
+    }
+
+    #[allow(clippy::map_entry)]
+    fn insert_with_origin(
+        &mut self,
+        tx: Arc<Transaction>,
+        dependent_outputs: Option<Vec<HashOutput>>,
+        is_local: bool,
+    ) -> Result<bool, UnconfirmedPoolError> {
         let tx_key = tx
             .first_kernel_excess_sig()
             .ok_or(UnconfirmedPoolError::TransactionNoKernels)?;
         if !self.txs_by_signature.contains_key(tx_key) {
-            let prioritized_tx = PrioritizedTransaction::convert_from_transaction((*tx).clone(), dependent_outputs)?;
+            let weight = self.weight_policy.calculate_weight(&tx);
+            let mut prioritized_tx =
+                PrioritizedTransaction::convert_from_transaction((*tx).clone(), weight, dependent_outputs)?;
+            prioritized_tx.is_local = is_local;
             if self.txs_by_signature.len() >= self.config.storage_capacity {
-                if prioritized_tx.priority < *self.lowest_priority() {
-                    return Ok(());
+                if !self.has_room_for(&prioritized_tx.priority, is_local) {
+                    return Ok(false);
+                }
+                if let Some(sig) = self.lowest_priority_evictable().map(|(_, sig)| sig.clone()) {
+                    self.delete_transaction(&sig);
                 }
-                self.remove_lowest_priority_tx();
             }
             self.txs_by_priority
                 .insert(prioritized_tx.priority.clone(), tx_key.clone());
@@ -132,6 +225,10 @@ impl UnconfirmedPool {
                     .entry(output.hash())
                     .or_default()
                     .push(tx_key.clone());
+                self.txs_by_commitment
+                    .entry(output.commitment.clone())
+                    .or_default()
+                    .push(tx_key.clone());
             }
             debug!(
                 target: LOG_TARGET,
@@ -141,11 +238,34 @@ impl UnconfirmedPool {
 
             trace!(target: LOG_TARGET, "{}", tx);
         }
-        Ok(())
+        Ok(true)
+    }
+
+    /// Returns the minimum fee per gram a transaction currently needs to pay to be stored, i.e. just above the fee
+    /// per gram of the lowest priority evictable transaction. Returns None if the pool is not at capacity or there is
+    /// no transaction that can be evicted.
+    pub fn min_fee_per_gram(&self) -> Option<MicroTari> {
+        if self.txs_by_signature.len() < self.config.storage_capacity {
+            return None;
+        }
+        let (_, sig) = self.lowest_priority_evictable()?;
+        let lowest = self.txs_by_signature.get(sig)?;
+        let fee_per_gram = lowest.transaction.body.get_total_fee().as_u64() / lowest.weight.max(1);
+        Some(MicroTari::from(fee_per_gram + 1))
+    }
+
+    /// Returns the transaction that would be evicted if another transaction was inserted, i.e. the lowest priority
+    /// evictable transaction. Returns None if the pool is not at capacity or there is no transaction that can be
+    /// evicted.
+    pub fn next_eviction_candidate(&self) -> Option<Signature> {
+        if self.txs_by_signature.len() < self.config.storage_capacity {
+            return None;
+        }
+        self.lowest_priority_evictable().map(|(_, sig)| sig.clone())
     }
 
     /// TThis will search the unconfirmed pool for the set of outputs and return true if all of them are found
-    pub fn verify_outputs_exist(&mut self, outputs: &[HashOutput]) -> bool {
+    pub fn verify_outputs_exist(&self, outputs: &[HashOutput]) -> bool {
         for hash in outputs {
             if !self.txs_by_output.contains_key(hash) {
                 return false;
@@ -168,14 +288,254 @@ impl UnconfirmedPool {
         self.txs_by_signature.contains_key(excess_sig)
     }
 
+    /// Returns the number of transactions in each age bucket at `now`. A bucket is given by the minimum age of its
+    /// transactions and holds the transactions that are younger than the next larger bucket, so the largest bucket
+    /// holds all older transactions. Transactions younger than the smallest bucket are not counted.
+    pub fn age_histogram(&self, buckets: &[Duration], now: Instant) -> Vec<(Duration, usize)> {
+        let mut histogram = buckets.iter().map(|min_age| (*min_age, 0)).collect::<Vec<_>>();
+        histogram.sort_by_key(|(min_age, _)| *min_age);
+        histogram.dedup_by_key(|(min_age, _)| *min_age);
+        for ptx in self.txs_by_signature.values() {
+            let age = now.saturating_duration_since(ptx.inserted_at);
+            if let Some((_, count)) = histogram.iter_mut().rev().find(|(min_age, _)| age >= *min_age) {
+                *count += 1;
+            }
+        }
+        histogram
+    }
+
+    /// Returns the length of the longest chain of unconfirmed ancestors of the transaction, i.e. 0 if the transaction
+    /// only spends mined outputs. Returns None if the transaction is not in the UnconfirmedPool.
+    pub fn dependency_depth(&self, excess_sig: &Signature) -> Option<usize> {
+        let ptx = self.txs_by_signature.get(excess_sig)?;
+        Some(self.ancestor_depth(ptx, &mut HashMap::new()))
+    }
+
+    /// Returns the length of the longest chain of unconfirmed ancestors of any transaction in the UnconfirmedPool, i.e.
+    /// 0 if all transactions only spend mined outputs.
+    pub fn max_dependency_depth(&self) -> usize {
+        let mut depths = HashMap::new();
+        self.txs_by_signature
+            .values()
+            .map(|ptx| self.ancestor_depth(ptx, &mut depths))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of unconfirmed ancestors a transaction spending the given unconfirmed outputs would have in
+    /// the UnconfirmedPool.
+    pub fn zero_conf_depth(&self, dependent_outputs: &[HashOutput]) -> usize {
+        self.outputs_depth(dependent_outputs, &mut HashMap::new())
+    }
+
+    fn ancestor_depth(&self, ptx: &PrioritizedTransaction, depths: &mut HashMap<Signature, usize>) -> usize {
+        self.outputs_depth(&ptx.depended_output_hashes, depths)
+    }
+
+    fn outputs_depth(&self, output_hashes: &[HashOutput], depths: &mut HashMap<Signature, usize>) -> usize {
+        self.parents_of(output_hashes)
+            .map(|sig| self.tx_depth(sig, depths))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn parents_of<'a>(&'a self, output_hashes: &'a [HashOutput]) -> impl Iterator<Item = &'a Signature> + 'a {
+        output_hashes
+            .iter()
+            .flat_map(move |hash| self.txs_by_output.get(hash).into_iter().flatten())
+    }
+
+    /// Returns the length of the chain of unconfirmed transactions ending in the given transaction. The chains are
+    /// supplied by peers and can be arbitrarily long, so they are walked with an explicit stack instead of recursion.
+    fn tx_depth(&self, excess_sig: &Signature, depths: &mut HashMap<Signature, usize>) -> usize {
+        // A transaction is visited first to queue its parents, and again to calculate its depth from theirs
+        let mut stack = vec![(excess_sig, false)];
+        while let Some((sig, parents_done)) = stack.pop() {
+            let ptx = match self.txs_by_signature.get(sig) {
+                Some(ptx) => ptx,
+                None => {
+                    depths.insert(sig.clone(), 0);
+                    continue;
+                },
+            };
+            if parents_done {
+                let depth = 1 + self
+                    .parents_of(&ptx.depended_output_hashes)
+                    .filter_map(|parent| depths.get(parent).copied())
+                    .max()
+                    .unwrap_or(0);
+                depths.insert(sig.clone(), depth);
+            } else if !depths.contains_key(sig) {
+                // Mark the transaction as visited so that it is never queued twice
+                depths.insert(sig.clone(), 0);
+                stack.push((sig, true));
+                stack.extend(
+                    self.parents_of(&ptx.depended_output_hashes)
+                        .filter(|parent| !depths.contains_key(*parent))
+                        .map(|parent| (parent, false)),
+                );
+            }
+        }
+        depths.get(excess_sig).copied().unwrap_or(0)
+    }
+
+    /// Re-prioritizes the transaction with the given excess signature as if it paid an additional `priority_boost` fee
+    /// per gram. The boost replaces any previous boost and is kept until the transaction leaves the UnconfirmedPool.
+    /// Returns false if the transaction is not in the UnconfirmedPool.
+    pub fn set_priority_override(
+        &mut self,
+        excess_sig: &Signature,
+        priority_boost: MicroTari,
+    ) -> Result<bool, UnconfirmedPoolError> {
+        let ptx = match self.txs_by_signature.get_mut(excess_sig) {
+            Some(ptx) => ptx,
+            None => return Ok(false),
+        };
+        let priority = FeePriority::new(&ptx.transaction, ptx.weight, priority_boost)?;
+        self.txs_by_priority.remove(&ptx.priority);
+        self.txs_by_priority.insert(priority.clone(), excess_sig.clone());
+        ptx.priority = priority;
+        Ok(true)
+    }
+
+    /// Returns the commitments of the inputs spent by the transactions in the UnconfirmedPool
+    pub fn spent_commitments(&self) -> HashSet<Commitment> {
+        self.txs_by_signature
+            .values()
+            .flat_map(|ptx| {
+                ptx.transaction
+                    .body
+                    .inputs()
+                    .iter()
+                    .map(|input| input.commitment.clone())
+            })
+            .collect()
+    }
+
+    /// Returns the transactions in the UnconfirmedPool that create an output with the given commitment
+    pub fn txs_creating_commitment(&self, commitment: &Commitment) -> Vec<Arc<Transaction>> {
+        self.txs_by_commitment
+            .get(commitment)
+            .map(|signatures| {
+                signatures
+                    .iter()
+                    .filter_map(|sig| self.txs_by_signature.get(sig))
+                    .map(|ptx| ptx.transaction.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns an order-independent hash of the excess signatures of the transactions in the UnconfirmedPool, i.e. the
+    /// XOR of the hashes of the excess signatures. Pools with equal hashes almost certainly hold the same transactions.
+    pub fn content_hash(&self) -> FixedHash {
+        let mut content_hash = [0u8; FIXED_HASH_SIZE];
+        for excess_sig in self.txs_by_signature.keys() {
+            let hash = HashDigest::new()
+                .chain(excess_sig.get_public_nonce().as_bytes())
+                .chain(excess_sig.get_signature().as_bytes())
+                .finalize();
+            content_hash.iter_mut().zip(hash.iter()).for_each(|(a, b)| *a ^= b);
+        }
+        content_hash.into()
+    }
+
+    /// Compares the transactions in the UnconfirmedPool against the given excess signatures of a remote pool
+    pub fn difference(&self, remote_excess_sigs: &[Signature]) -> MempoolDifference {
+        let remote_excess_sigs = remote_excess_sigs.iter().collect::<HashSet<_>>();
+        let local_only = self
+            .txs_by_signature
+            .keys()
+            .filter(|sig| !remote_excess_sigs.contains(sig))
+            .cloned()
+            .collect();
+        let remote_only = remote_excess_sigs
+            .into_iter()
+            .filter(|sig| !self.txs_by_signature.contains_key(sig))
+            .cloned()
+            .collect();
+        MempoolDifference {
+            local_only,
+            remote_only,
+        }
+    }
+
+    /// Returns true if the transaction creates an output with the same commitment as an output of another transaction
+    /// in the UnconfirmedPool. Only one of the transactions could be included in a valid block.
+    pub fn has_duplicate_output_commitment(&self, tx: &Transaction) -> bool {
+        let excess_sig = tx.first_kernel_excess_sig();
+        tx.body.outputs().iter().any(|output| {
+            self.txs_by_commitment
+                .get(&output.commitment)
+                .map(|signatures| signatures.iter().any(|sig| Some(sig) != excess_sig))
+                .unwrap_or(false)
+        })
+    }
+
     /// Returns a set of the highest priority unconfirmed transactions, that can be included in a block
     pub fn highest_priority_txs(&mut self, total_weight: u64) -> Result<RetrieveResults, UnconfirmedPoolError> {
-        let mut selected_txs = HashMap::new();
-        let mut curr_weight: u64 = 0;
+        let selection = self.select_highest_priority_txs(total_weight)?;
+        self.finalize_selection(selection)
+    }
+
+    /// Returns the transactions that `highest_priority_txs` retrieves for a block of `total_weight`. Transactions that
+    /// need to be re-evaluated are skipped but, unlike `highest_priority_txs`, not removed from the pool.
+    pub fn peek_highest_priority_txs(&self, total_weight: u64) -> Result<Vec<Arc<Transaction>>, UnconfirmedPoolError> {
+        let selection = self.select_highest_priority_txs(total_weight)?;
+        Ok(selection.selected_txs.into_values().collect())
+    }
+
+    fn select_highest_priority_txs(&self, total_weight: u64) -> Result<TxSelection, UnconfirmedPoolError> {
+        let mut selection = TxSelection::default();
+        self.select_txs(self.txs_by_priority.values().rev(), total_weight, &mut selection)?;
+        Ok(selection)
+    }
+
+    /// Returns a set of unconfirmed transactions that can be included in a block, where `reserved_weight` of the
+    /// block weight is reserved for the oldest transactions that were not selected by fee priority. This prevents low
+    /// fee transactions from being starved indefinitely. The remaining weight is filled by fee priority.
+    pub fn highest_priority_txs_fair(
+        &mut self,
+        total_weight: u64,
+        reserved_weight: u64,
+    ) -> Result<RetrieveResults, UnconfirmedPoolError> {
+        let mut selection = TxSelection::default();
+        let priority_weight = total_weight.saturating_sub(reserved_weight);
+        self.select_txs(self.txs_by_priority.values().rev(), priority_weight, &mut selection)?;
+
+        // Fill the reserved weight with the oldest transactions, favouring lower priority transactions on ties
+        let mut txs_by_age = self
+            .txs_by_priority
+            .values()
+            .map(|tx_key| {
+                self.txs_by_signature
+                    .get(tx_key)
+                    .map(|ptx| (ptx.inserted_at, tx_key))
+                    .ok_or(UnconfirmedPoolError::StorageOutofSync)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        txs_by_age.sort_by_key(|(inserted_at, _)| *inserted_at);
+        self.select_txs(
+            txs_by_age.into_iter().map(|(_, tx_key)| tx_key),
+            total_weight,
+            &mut selection,
+        )?;
+
+        // Any weight not used by the oldest transactions is filled by fee priority
+        self.select_txs(self.txs_by_priority.values().rev(), total_weight, &mut selection)?;
+        self.finalize_selection(selection)
+    }
+
+    // Selects transactions (and their dependencies) in the order given by `tx_keys` that fit into `total_weight`.
+    fn select_txs<'a, I: Iterator<Item = &'a Signature>>(
+        &self,
+        tx_keys: I,
+        total_weight: u64,
+        selection: &mut TxSelection,
+    ) -> Result<(), UnconfirmedPoolError> {
         let mut curr_skip_count: usize = 0;
-        let mut transactions_to_remove_and_recheck = Vec::new();
-        for (_, tx_key) in self.txs_by_priority.iter().rev() {
-            if selected_txs.contains_key(tx_key) {
+        for tx_key in tx_keys {
+            if selection.selected_txs.contains_key(tx_key) {
                 continue;
             }
             let prioritized_transaction = self
@@ -190,20 +550,24 @@ impl UnconfirmedPool {
                 prioritized_transaction,
                 &mut potential_transactions_to_insert,
                 &mut potential_transactions_to_remove_and_recheck,
-                &selected_txs,
+                &selection.selected_txs,
                 &mut total_transaction_weight,
             )?;
-            if curr_weight + total_transaction_weight <= total_weight &&
+            if selection.curr_weight + total_transaction_weight <= total_weight &&
                 potential_transactions_to_remove_and_recheck.is_empty()
             {
-                if !UnconfirmedPool::find_duplicate_input(&selected_txs, &potential_transactions_to_insert) {
-                    curr_weight += total_transaction_weight;
+                if !UnconfirmedPool::find_duplicate_input(&selection.selected_txs, &potential_transactions_to_insert) {
+                    selection.curr_weight += total_transaction_weight;
                     for (key, transaction) in potential_transactions_to_insert {
-                        selected_txs.insert((key).clone(), transaction.transaction.clone());
+                        selection
+                            .selected_txs
+                            .insert((key).clone(), transaction.transaction.clone());
                     }
                 }
             } else {
-                transactions_to_remove_and_recheck.append(&mut potential_transactions_to_remove_and_recheck);
+                selection
+                    .transactions_to_remove_and_recheck
+                    .append(&mut potential_transactions_to_remove_and_recheck);
                 // Check if some the next few txs with slightly lower priority wont fit in the remaining space.
                 curr_skip_count += 1;
                 if curr_skip_count >= self.config.weight_tx_skip_count {
@@ -211,6 +575,22 @@ impl UnconfirmedPool {
                 }
             }
         }
+        Ok(())
+    }
+
+    fn finalize_selection(&mut self, selection: TxSelection) -> Result<RetrieveResults, UnconfirmedPoolError> {
+        let TxSelection {
+            selected_txs,
+            mut transactions_to_remove_and_recheck,
+            ..
+        } = selection;
+        // A transaction may have been marked for re-evaluation more than once
+        let mut removed_keys = HashSet::new();
+        transactions_to_remove_and_recheck.retain(|tx| {
+            tx.first_kernel_excess_sig()
+                .map(|key| removed_keys.insert(key.clone()))
+                .unwrap_or(true)
+        });
         // we need to remove all transactions that need to be rechecked.
         for transaction in &transactions_to_remove_and_recheck {
             let key = transaction
@@ -319,6 +699,33 @@ impl UnconfirmedPool {
         false
     }
 
+    /// Reconstructs the priority, output and output commitment indexes from the stored transactions. This should be
+    /// called after transactions have been loaded in bulk to ensure that conflict detection is consistent with the
+    /// pool contents.
+    pub fn rebuild_indexes(&mut self) {
+        self.txs_by_priority.clear();
+        self.txs_by_output.clear();
+        self.txs_by_commitment.clear();
+        for (tx_key, ptx) in &self.txs_by_signature {
+            self.txs_by_priority.insert(ptx.priority.clone(), tx_key.clone());
+            for output in ptx.transaction.body.outputs() {
+                self.txs_by_output
+                    .entry(output.hash())
+                    .or_default()
+                    .push(tx_key.clone());
+                self.txs_by_commitment
+                    .entry(output.commitment.clone())
+                    .or_default()
+                    .push(tx_key.clone());
+            }
+        }
+        debug!(
+            target: LOG_TARGET,
+            "Rebuilt unconfirmed pool indexes for {} transaction(s)",
+            self.txs_by_signature.len()
+        );
+    }
+
     /// Remove all current mempool transactions from the UnconfirmedPoolStorage, returning that which have been removed
     pub fn drain_all_mempool_transactions(&mut self) -> Vec<Arc<Transaction>> {
         let mempool_txs: Vec<Arc<Transaction>> = self
@@ -328,6 +735,7 @@ impl UnconfirmedPool {
             .collect();
         self.txs_by_priority.clear();
         self.txs_by_output.clear();
+        self.txs_by_commitment.clear();
 
         mempool_txs
     }
@@ -416,6 +824,12 @@ impl UnconfirmedPool {
                         self.txs_by_output.remove(&key);
                     }
                 }
+                if let Some(signatures) = self.txs_by_commitment.get_mut(&output.commitment) {
+                    signatures.retain(|x| x != signature);
+                    if signatures.is_empty() {
+                        self.txs_by_commitment.remove(&output.commitment);
+                    }
+                }
             }
             trace!(
                 target: LOG_TARGET,
@@ -427,6 +841,12 @@ impl UnconfirmedPool {
         None
     }
 
+    /// Remove the transactions with the given excess signatures from the UnconfirmedPool, returning the transactions
+    /// that were removed
+    pub fn remove_txs(&mut self, excess_sigs: &[Signature]) -> Vec<Arc<Transaction>> {
+        self.delete_transactions(excess_sigs)
+    }
+
     /// Remove all unconfirmed transactions that have become time locked. This can happen when the chain height was
     /// reduced on some reorgs.
     pub fn remove_timelocked(&mut self, tip_height: u64) -> Vec<Arc<Transaction>> {
@@ -440,6 +860,20 @@ impl UnconfirmedPool {
         self.delete_transactions(&removed_tx_keys)
     }
 
+    /// Returns the number of locally-originated transactions stored in the UnconfirmedPool.
+    pub fn local_txs_count(&self) -> usize {
+        self.txs_by_signature.values().filter(|ptx| ptx.is_local).count()
+    }
+
+    /// Returns all locally-originated transactions stored in the UnconfirmedPool.
+    pub fn local_transactions(&self) -> Vec<Arc<Transaction>> {
+        self.txs_by_signature
+            .values()
+            .filter(|ptx| ptx.is_local)
+            .map(|ptx| ptx.transaction.clone())
+            .collect()
+    }
+
     /// Returns the total number of unconfirmed transactions stored in the UnconfirmedPool.
     pub fn len(&self) -> usize {
         self.txs_by_signature.len()
@@ -453,11 +887,118 @@ impl UnconfirmedPool {
             .collect()
     }
 
+    /// Returns all transactions stored in the UnconfirmedPool along with their weight, age and origin.
+    pub fn snapshot_with_metadata(&self) -> Vec<MempoolTransactionInfo> {
+        self.txs_by_signature
+            .values()
+            .map(|ptx| MempoolTransactionInfo {
+                transaction: ptx.transaction.clone(),
+                weight: ptx.weight,
+                age: ptx.inserted_at.elapsed(),
+                is_local: ptx.is_local,
+            })
+            .collect()
+    }
+
+    /// Returns up to `limit` transactions with a lower fee priority than `cursor`, in descending order of fee priority,
+    /// together with the priority of the last returned transaction if more transactions remain.
+    pub fn page(
+        &self,
+        cursor: Option<&FeePriority>,
+        limit: usize,
+    ) -> (Vec<MempoolTransactionInfo>, Option<FeePriority>) {
+        let upper = cursor.map_or(Bound::Unbounded, Bound::Excluded);
+        let mut iter = self
+            .txs_by_priority
+            .range::<FeePriority, _>((Bound::Unbounded, upper))
+            .rev()
+            .peekable();
+        let mut txs = Vec::with_capacity(limit);
+        let mut last_priority = None;
+        for (priority, signature) in iter.by_ref().take(limit) {
+            if let Some(ptx) = self.txs_by_signature.get(signature) {
+                txs.push(MempoolTransactionInfo {
+                    transaction: ptx.transaction.clone(),
+                    weight: ptx.weight,
+                    age: ptx.inserted_at.elapsed(),
+                    is_local: ptx.is_local,
+                });
+            }
+            last_priority = Some(priority);
+        }
+        let next = if iter.peek().is_some() {
+            last_priority.cloned()
+        } else {
+            None
+        };
+        (txs, next)
+    }
+
+    /// Returns the number of transactions stored in the pool that contain a kernel with the given features. A
+    /// transaction is counted once for each distinct set of kernel features that it contains.
+    pub fn txs_by_kernel_feature(&self) -> HashMap<KernelFeatures, usize> {
+        let mut counts = HashMap::new();
+        for ptx in self.txs_by_signature.values() {
+            let features = ptx
+                .transaction
+                .body
+                .kernels()
+                .iter()
+                .map(|k| k.features)
+                .collect::<HashSet<_>>();
+            for feature in features {
+                *counts.entry(feature).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
     /// Returns the total weight of all transactions stored in the pool.
     pub fn calculate_weight(&self) -> u64 {
         self.txs_by_signature
             .iter()
-            .fold(0, |weight, (_, ptx)| weight + ptx.transaction.calculate_weight())
+            .fold(0, |weight, (_, ptx)| weight + ptx.weight)
+    }
+
+    /// Returns the stored transactions with a fee per gram in the half-open range `[min, max)`, in order of fee
+    /// priority from highest to lowest.
+    pub fn txs_in_fee_range(&self, min: MicroTari, max: MicroTari) -> Vec<Arc<Transaction>> {
+        self.txs_by_priority
+            .values()
+            .rev()
+            .filter_map(|sig| self.txs_by_signature.get(sig))
+            .filter(|ptx| {
+                let fee_per_gram = ptx.transaction.body.get_total_fee().as_u64() / ptx.weight.max(1);
+                fee_per_gram >= min.as_u64() && fee_per_gram < max.as_u64()
+            })
+            .map(|ptx| ptx.transaction.clone())
+            .collect()
+    }
+
+    /// Returns the fee per gram statistics of up to `count` blocks of `target_block_weight`, filled with the stored
+    /// transactions in order of fee priority. The first entry is the next block to be mined.
+    pub fn fee_per_gram_stats(&self, count: usize, target_block_weight: u64) -> Vec<FeePerGramStat> {
+        let mut stats = Vec::new();
+        let mut bucket = FeePerGramBucket::default();
+        for ptx in self
+            .txs_by_priority
+            .values()
+            .rev()
+            .filter_map(|sig| self.txs_by_signature.get(sig))
+        {
+            if bucket.weight > 0 && bucket.weight + ptx.weight > target_block_weight {
+                stats.push(bucket.to_stat(stats.len() as u64));
+                if stats.len() >= count {
+                    return stats;
+                }
+                bucket = FeePerGramBucket::default();
+            }
+            bucket.add(ptx.transaction.body.get_total_fee().as_u64(), ptx.weight);
+        }
+        if bucket.weight > 0 && stats.len() < count {
+            stats.push(bucket.to_stat(stats.len() as u64));
+        }
+        stats
     }
 
     #[cfg(test)]
@@ -472,6 +1013,33 @@ impl UnconfirmedPool {
     }
 }
 
+#[derive(Default)]
+struct FeePerGramBucket {
+    min_fee_per_gram: Option<u64>,
+    max_fee_per_gram: u64,
+    total_fee: u64,
+    weight: u64,
+}
+
+impl FeePerGramBucket {
+    fn add(&mut self, fee: u64, weight: u64) {
+        let fee_per_gram = fee / weight.max(1);
+        self.min_fee_per_gram = Some(self.min_fee_per_gram.map_or(fee_per_gram, |min| min.min(fee_per_gram)));
+        self.max_fee_per_gram = self.max_fee_per_gram.max(fee_per_gram);
+        self.total_fee += fee;
+        self.weight += weight;
+    }
+
+    fn to_stat(&self, order: u64) -> FeePerGramStat {
+        FeePerGramStat {
+            order,
+            min_fee_per_gram: self.min_fee_per_gram.unwrap_or(0).into(),
+            avg_fee_per_gram: (self.total_fee / self.weight.max(1)).into(),
+            max_fee_per_gram: self.max_fee_per_gram.into(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use tari_common::configuration::Network;
@@ -503,11 +1071,11 @@ mod test {
         tx_pool.insert(tx1.first_kernel_excess_sig().unwrap().clone(), tx1.clone());
         tx1_pool.insert(
             tx1.first_kernel_excess_sig().unwrap().clone(),
-            PrioritizedTransaction::convert_from_transaction((*tx1).clone(), None).unwrap(),
+            PrioritizedTransaction::convert_from_transaction((*tx1).clone(), tx1.calculate_weight(), None).unwrap(),
         );
         tx2_pool.insert(
             tx2.first_kernel_excess_sig().unwrap().clone(),
-            PrioritizedTransaction::convert_from_transaction((*tx2).clone(), None).unwrap(),
+            PrioritizedTransaction::convert_from_transaction((*tx2).clone(), tx2.calculate_weight(), None).unwrap(),
         );
         assert!(
             UnconfirmedPool::find_duplicate_input(&tx_pool, &tx1_pool),
@@ -519,6 +1087,64 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_difference() {
+        let tx1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
+        let tx2 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(20), inputs: 2, outputs: 1).0);
+        let tx3 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(30), inputs: 2, outputs: 1).0);
+        let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig::default());
+        unconfirmed_pool.insert_txs(vec![tx1.clone(), tx2.clone()]).unwrap();
+
+        let remote_excess_sigs = vec![
+            tx2.first_kernel_excess_sig().unwrap().clone(),
+            tx3.first_kernel_excess_sig().unwrap().clone(),
+        ];
+        let diff = unconfirmed_pool.difference(&remote_excess_sigs);
+        assert_eq!(diff.local_only, vec![tx1.first_kernel_excess_sig().unwrap().clone()]);
+        assert_eq!(diff.remote_only, vec![tx3.first_kernel_excess_sig().unwrap().clone()]);
+    }
+
+    #[test]
+    fn test_age_histogram() {
+        let tx1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
+        let tx2 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(20), inputs: 2, outputs: 1).0);
+        let tx3 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(30), inputs: 2, outputs: 1).0);
+        let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig::default());
+        unconfirmed_pool
+            .insert_txs(vec![tx1.clone(), tx2.clone(), tx3.clone()])
+            .unwrap();
+        let now = Instant::now();
+        // tx1 has been in the pool for a while
+        unconfirmed_pool
+            .txs_by_signature
+            .get_mut(tx1.first_kernel_excess_sig().unwrap())
+            .unwrap()
+            .inserted_at = now - Duration::from_secs(700);
+
+        let buckets = [
+            Duration::from_secs(600),
+            Duration::from_secs(0),
+            Duration::from_secs(60),
+        ];
+        let histogram = unconfirmed_pool.age_histogram(&buckets, now);
+        assert_eq!(histogram, vec![
+            (Duration::from_secs(0), 2),
+            (Duration::from_secs(60), 0),
+            (Duration::from_secs(600), 1)
+        ]);
+
+        let histogram = unconfirmed_pool.age_histogram(&buckets, now + Duration::from_secs(120));
+        assert_eq!(histogram, vec![
+            (Duration::from_secs(0), 0),
+            (Duration::from_secs(60), 2),
+            (Duration::from_secs(600), 1)
+        ]);
+
+        // Transactions younger than the smallest bucket are not counted
+        let histogram = unconfirmed_pool.age_histogram(&[Duration::from_secs(60)], now);
+        assert_eq!(histogram, vec![(Duration::from_secs(60), 1)]);
+    }
+
     #[test]
     fn test_insert_and_retrieve_highest_priority_txs() {
         let tx1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
@@ -553,6 +1179,61 @@ mod test {
         assert!(unconfirmed_pool.check_status());
     }
 
+    #[test]
+    fn test_retrieve_fair_includes_oldest_low_fee_txs() {
+        let tx_low = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(5), inputs: 2, outputs: 1).0);
+        let tx_high1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(100), inputs: 2, outputs: 1).0);
+        let tx_high2 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(90), inputs: 2, outputs: 1).0);
+
+        let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
+            storage_capacity: 10,
+            weight_tx_skip_count: 3,
+        });
+        // The low fee transaction has been waiting the longest
+        unconfirmed_pool
+            .insert_txs(vec![tx_low.clone(), tx_high1.clone(), tx_high2.clone()])
+            .unwrap();
+
+        let desired_weight = tx_high1.calculate_weight() + tx_high2.calculate_weight();
+        let results = unconfirmed_pool.highest_priority_txs(desired_weight).unwrap();
+        assert_eq!(results.retrieved_transactions.len(), 2);
+        assert!(results.retrieved_transactions.contains(&tx_high1));
+        assert!(results.retrieved_transactions.contains(&tx_high2));
+
+        let results = unconfirmed_pool
+            .highest_priority_txs_fair(desired_weight, tx_low.calculate_weight())
+            .unwrap();
+        assert_eq!(results.retrieved_transactions.len(), 2);
+        assert!(results.retrieved_transactions.contains(&tx_high1));
+        assert!(results.retrieved_transactions.contains(&tx_low));
+
+        // Nothing reserved is the same as plain retrieval
+        let results = unconfirmed_pool.highest_priority_txs_fair(desired_weight, 0).unwrap();
+        assert!(results.retrieved_transactions.contains(&tx_high1));
+        assert!(results.retrieved_transactions.contains(&tx_high2));
+
+        assert!(unconfirmed_pool.check_status());
+    }
+
+    #[test]
+    fn test_txs_by_kernel_feature() {
+        let tx1 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 2, outputs: 1).0);
+        let tx2 = Arc::new(tx!(MicroTari(5_000), fee: MicroTari(20), inputs: 2, outputs: 1).0);
+        let (mut tx3, _, _) = tx!(MicroTari(5_000), fee: MicroTari(30), inputs: 2, outputs: 1);
+        let mut kernel = tx3.body.kernels()[0].clone();
+        kernel.features = KernelFeatures::COINBASE_KERNEL;
+        tx3.body.set_kernel(kernel);
+
+        let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig::default());
+        assert!(unconfirmed_pool.txs_by_kernel_feature().is_empty());
+        unconfirmed_pool.insert_txs(vec![tx1, tx2, Arc::new(tx3)]).unwrap();
+
+        let counts = unconfirmed_pool.txs_by_kernel_feature();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&KernelFeatures::empty()], 2);
+        assert_eq!(counts[&KernelFeatures::COINBASE_KERNEL], 1);
+    }
+
     #[test]
     fn test_double_spend_inputs() {
         let (tx1, _, _) = tx!(MicroTari(5_000), fee: MicroTari(50), inputs: 1, outputs: 1);
@@ -765,4 +1446,33 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_rebuild_indexes() {
+        let (tx1, _, _) = tx!(MicroTari(150_000), fee: MicroTari(50), inputs:2, outputs:2);
+        let (tx2, _, _) = tx!(MicroTari(250_000), fee: MicroTari(50), inputs:2, outputs:2);
+        // tx3 creates the same outputs as tx1
+        let mut tx3 = tx1.clone();
+        tx3.body.set_kernel(tx2.body.kernels()[0].clone());
+
+        let mut unconfirmed_pool = UnconfirmedPool::new(UnconfirmedPoolConfig {
+            storage_capacity: 10,
+            weight_tx_skip_count: 3,
+        });
+        unconfirmed_pool.insert_txs(vec![Arc::new(tx1.clone())]).unwrap();
+        assert!(unconfirmed_pool.has_duplicate_output_commitment(&tx3));
+
+        // Desync the indexes from the stored transactions
+        unconfirmed_pool.txs_by_priority.clear();
+        unconfirmed_pool.txs_by_output.clear();
+        unconfirmed_pool.txs_by_commitment.clear();
+        assert!(!unconfirmed_pool.check_status());
+        assert!(!unconfirmed_pool.has_duplicate_output_commitment(&tx3));
+
+        unconfirmed_pool.rebuild_indexes();
+        assert!(unconfirmed_pool.check_status());
+        assert!(unconfirmed_pool.has_duplicate_output_commitment(&tx3));
+        let output_hashes = tx1.body.outputs().iter().map(|o| o.hash()).collect::<Vec<_>>();
+        assert!(unconfirmed_pool.verify_outputs_exist(&output_hashes));
+    }
 }
@@ -0,0 +1,50 @@
+// Copyright 2021. The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::{mempool::WeightPolicyKind, transactions::transaction::Transaction};
+use std::sync::Arc;
+
+/// Computes the weight of the transactions held by the mempool. The weight is used to budget the transactions that
+/// are retrieved for a block and is reported in the mempool stats. This allows alternative weightings to be tried out
+/// on test networks. Note that block validation always uses the consensus weight.
+pub trait TransactionWeightPolicy: Send + Sync {
+    fn calculate_weight(&self, tx: &Transaction) -> u64;
+}
+
+/// The consensus transaction weight, as given by `Transaction::calculate_weight`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultWeightPolicy;
+
+impl TransactionWeightPolicy for DefaultWeightPolicy {
+    fn calculate_weight(&self, tx: &Transaction) -> u64 {
+        tx.calculate_weight()
+    }
+}
+
+impl WeightPolicyKind {
+    /// Creates the selected weight policy
+    pub fn build(self) -> Arc<dyn TransactionWeightPolicy> {
+        match self {
+            WeightPolicyKind::Consensus => Arc::new(DefaultWeightPolicy),
+        }
+    }
+}
@@ -50,7 +50,7 @@ use crate::{
     proof_of_work::{AchievedTargetDifficulty, Difficulty, PowAlgorithm},
     test_helpers::BlockSpec,
     transactions::{
-        transaction::{TransactionInput, TransactionKernel, UnblindedOutput},
+        transaction::{Transaction, TransactionInput, TransactionKernel, UnblindedOutput},
         CryptoFactories,
     },
     validation::{
@@ -361,6 +361,14 @@ impl BlockchainBackend for TempDatabase {
         self.db.as_ref().unwrap().fetch_horizon_data()
     }
 
+    fn fetch_mempool_reorg_marker(&self) -> Result<Option<(u64, HashOutput)>, ChainStorageError> {
+        self.db.as_ref().unwrap().fetch_mempool_reorg_marker()
+    }
+
+    fn fetch_mempool_orphan_txs(&self) -> Result<Vec<Transaction>, ChainStorageError> {
+        self.db.as_ref().unwrap().fetch_mempool_orphan_txs()
+    }
+
     fn get_stats(&self) -> Result<DbBasicStats, ChainStorageError> {
         self.db.as_ref().unwrap().get_stats()
     }
@@ -192,10 +192,14 @@ async fn test_base_node_wallet_rpc() {
         .unwrap();
 
     // Check that subitting Tx2 will now be accepted
-    let msg = TransactionProto::from(tx2);
+    let msg = TransactionProto::from(tx2.clone());
     let req = request_mock.request_with_context(Default::default(), msg);
     let resp = service.submit_transaction(req).await.unwrap().into_message();
     assert!(resp.accepted);
+    // Wallet submissions are stored as local transactions
+    let local_txs = base_node.mempool.local_transactions().unwrap();
+    assert_eq!(local_txs.len(), 1);
+    assert_eq!(*local_txs[0], tx2);
 
     // Query Tx2 which should now be in the mempool
     let msg = SignatureProto::from(tx2_sig.clone());
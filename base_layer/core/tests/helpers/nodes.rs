@@ -418,13 +418,14 @@ async fn setup_base_node_services(
             subscription_factory.clone(),
             blockchain_db.clone().into(),
             mempool.clone(),
-            consensus_manager,
+            consensus_manager.clone(),
             base_node_service_config,
         ))
         .add_initializer(MempoolServiceInitializer::new(
             mempool_service_config,
             mempool.clone(),
             subscription_factory,
+            consensus_manager,
         ))
         .add_initializer(mock_state_machine.get_initializer())
         .add_initializer(ChainMetadataServiceInitializer)
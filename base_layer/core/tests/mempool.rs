@@ -21,7 +21,12 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 // use crate::helpers::database::create_store;
-use std::{ops::Deref, sync::Arc, time::Duration};
+use std::{
+    collections::HashSet,
+    ops::Deref,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use tari_crypto::{keys::PublicKey as PublicKeyTrait, script};
 use tempfile::tempdir;
@@ -40,7 +45,7 @@ use helpers::{
 };
 use randomx_rs::RandomXFlag;
 use tari_common::configuration::Network;
-use tari_common_types::types::{Commitment, PrivateKey, PublicKey, Signature};
+use tari_common_types::types::{Commitment, FixedHash, PrivateKey, PublicKey, Signature};
 use tari_comms_dht::domain_message::OutboundDomainMessage;
 use tari_core::{
     base_node::{
@@ -49,7 +54,21 @@ use tari_core::{
         state_machine_service::states::{ListeningInfo, StateInfo, StatusInfo},
     },
     consensus::{ConsensusConstantsBuilder, ConsensusManager, NetworkConsensus},
-    mempool::{Mempool, MempoolConfig, MempoolServiceConfig, MempoolServiceError, TxStorageResponse},
+    mempool::{
+        BlockProcessSummary,
+        Mempool,
+        MempoolBuilder,
+        MempoolConfig,
+        MempoolError,
+        MempoolServiceConfig,
+        MempoolServiceError,
+        OrphanPromotionMode,
+        OrphanStore,
+        RejectReason,
+        TransactionWeightPolicy,
+        TxStorageResponse,
+        ValidationOutcome,
+    },
     proof_of_work::Difficulty,
     proto,
     transactions::{
@@ -62,7 +81,10 @@ use tari_core::{
     },
     tx,
     txn_schema,
-    validation::transaction_validators::{TxConsensusValidator, TxInputAndMaturityValidator},
+    validation::{
+        mocks::MockValidator,
+        transaction_validators::{TxConsensusValidator, TxInputAndMaturityValidator},
+    },
 };
 use tari_p2p::{services::liveness::LivenessConfig, tari_message::TariMessageType};
 use tari_test_utils::async_assert_eventually;
@@ -246,6 +268,403 @@ async fn test_time_locked() {
     assert_eq!(mempool.insert(tx2).unwrap(), TxStorageResponse::UnconfirmedPool);
 }
 
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_duplicate_output_commitment() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![2 * T, 2 * T], fee: 25*uT, lock: 0, features: OutputFeatures::default()
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    let txs = vec![
+        txn_schema!(from: vec![outputs[1][0].clone()], to: vec![1*T], fee: 20*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][1].clone()], to: vec![1*T], fee: 20*uT, lock: 0, features: OutputFeatures::default()),
+    ];
+    let (txs, _) = schema_to_transaction(&txs);
+    let tx1 = txs[0].clone();
+    // The second transaction creates an output with the same commitment as an output of the first
+    let mut tx2 = (*txs[1]).clone();
+    tx2.body.add_output(tx1.body.outputs()[0].clone());
+    let tx2 = Arc::new(tx2);
+
+    assert_eq!(mempool.insert(tx1.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+    assert!(matches!(
+        mempool.validate_tx(&tx2).unwrap(),
+        ValidationOutcome::Invalid(_)
+    ));
+    assert_eq!(
+        mempool.insert(tx2.clone()).unwrap(),
+        TxStorageResponse::NotStoredDuplicateOutput
+    );
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 1);
+    assert_eq!(
+        mempool
+            .has_tx_with_excess_sig(tx2.first_kernel_excess_sig().unwrap().clone())
+            .unwrap(),
+        TxStorageResponse::NotStored
+    );
+    // Re-submitting the stored transaction is not treated as a collision with itself
+    assert_eq!(mempool.insert(tx1).unwrap(), TxStorageResponse::UnconfirmedPool);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_validate_tx_matches_insert() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![2 * T, 2 * T, 2 * T, 2 * T], fee: 25*uT, lock: 0, features: OutputFeatures::default()
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+    let mut tx2 = txn_schema!(from: vec![outputs[1][0].clone()], to: vec![1*T], fee: 20*uT, lock: 0, features: OutputFeatures::default());
+    tx2.lock_height = 3;
+    let (tx2, tx2_outputs, _) = spend_utxos(tx2);
+    let tx2 = Arc::new(tx2);
+
+    let mut tx3 = txn_schema!(
+        from: vec![outputs[1][1].clone()],
+        to: vec![1*T],
+        fee: 20*uT,
+        lock: 4,
+        features: OutputFeatures::with_maturity(1)
+    );
+    tx3.lock_height = 2;
+    let tx3 = Arc::new(spend_utxos(tx3).0);
+
+    // Spends an output of tx2, which is unknown until tx2 is in the mempool
+    let child = txn_schema!(from: vec![tx2_outputs[0].clone()], to: vec![500_000*uT], fee: 20*uT, lock: 0, features: OutputFeatures::default());
+    let child = spend_utxos(child).0;
+
+    // A dry run does not store anything
+    assert_eq!(mempool.validate_tx(&tx2).unwrap(), ValidationOutcome::TimeLocked);
+    assert_eq!(mempool.validate_tx(&tx3).unwrap(), ValidationOutcome::Valid);
+    assert_eq!(mempool.validate_tx(&child).unwrap(), ValidationOutcome::Orphan);
+    assert_eq!(mempool.stats().unwrap().total_txs, 0);
+    assert_eq!(mempool.orphan_count().unwrap(), 0);
+
+    assert_eq!(
+        mempool.insert(tx2.clone()).unwrap(),
+        TxStorageResponse::NotStoredTimeLocked
+    );
+    assert_eq!(mempool.insert(tx3.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+
+    generate_block(&store, &mut blocks, vec![tx3.deref().clone()], &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+
+    // Block height increased, so tx2 is now valid. The outputs of tx3 have been mined, so spending them again is
+    // invalid.
+    assert_eq!(mempool.validate_tx(&tx2).unwrap(), ValidationOutcome::Valid);
+    assert_eq!(mempool.insert(tx2).unwrap(), TxStorageResponse::UnconfirmedPool);
+    assert_eq!(mempool.validate_tx(&child).unwrap(), ValidationOutcome::Valid);
+    assert!(matches!(
+        mempool.validate_tx(&tx3).unwrap(),
+        ValidationOutcome::Invalid(_)
+    ));
+    assert_eq!(mempool.insert(tx3).unwrap(), TxStorageResponse::NotStoredAlreadySpent);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_validate_tx_applies_admission_checks() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mut unconfirmed_pool = MempoolConfig::default().unconfirmed_pool;
+    unconfirmed_pool.storage_capacity = 1;
+    let config = MempoolConfig {
+        unconfirmed_pool,
+        ..Default::default()
+    };
+    let mempool = Mempool::new(config, Arc::new(TxInputAndMaturityValidator::new(store.clone())));
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![2 * T, 2 * T, 2 * T, 2 * T], fee: 25*uT, lock: 0, features: OutputFeatures::default()
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+    let tx1 = txn_schema!(from: vec![outputs[1][0].clone()], to: vec![1*T], fee: 50*uT, lock: 0, features: OutputFeatures::default());
+    let tx1 = Arc::new(spend_utxos(tx1).0);
+    let tx2 = txn_schema!(from: vec![outputs[1][1].clone()], to: vec![1*T], fee: 20*uT, lock: 0, features: OutputFeatures::default());
+    let tx2 = Arc::new(spend_utxos(tx2).0);
+
+    assert_eq!(mempool.insert(tx1.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+    // The pool is full and the fee of tx2 is too low to evict tx1
+    assert!(matches!(
+        mempool.validate_tx(&tx2).unwrap(),
+        ValidationOutcome::Invalid(_)
+    ));
+    assert!(matches!(
+        mempool.insert(tx2).unwrap(),
+        TxStorageResponse::NotStoredFeeTooLow(_)
+    ));
+    // A stored transaction is accepted again even though the pool is full
+    assert_eq!(mempool.validate_tx(&tx1).unwrap(), ValidationOutcome::Valid);
+    assert_eq!(mempool.insert(tx1.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+
+    // Nothing is watched, so nothing is accepted
+    mempool
+        .set_config(MempoolConfig {
+            watch_only: true,
+            ..Default::default()
+        })
+        .unwrap();
+    let tx3 = txn_schema!(from: vec![outputs[1][2].clone()], to: vec![1*T], fee: 100*uT, lock: 0, features: OutputFeatures::default());
+    let tx3 = Arc::new(spend_utxos(tx3).0);
+    assert!(matches!(
+        mempool.validate_tx(&tx3).unwrap(),
+        ValidationOutcome::Invalid(_)
+    ));
+    assert_eq!(mempool.insert(tx3).unwrap(), TxStorageResponse::NotStored);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_coinbase_spend_promoted_on_maturity() {
+    let network = Network::LocalNet;
+    let consensus_constants = ConsensusConstantsBuilder::new(network)
+        .with_emission_amounts(100_000_000.into(), &EMISSION, 100.into())
+        .with_coinbase_lockheight(3)
+        .build();
+    let (store, mut blocks, outputs, consensus_manager) =
+        create_new_blockchain_with_constants(network, consensus_constants);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+
+    // The genesis coinbase has not matured yet
+    let (tx, _, _) = spend_utxos(txn_schema!(from: vec![outputs[0][0].clone()], to: vec![2 * T, 2 * T]));
+    let tx = Arc::new(tx);
+    assert_eq!(
+        mempool.insert(tx.clone()).unwrap(),
+        TxStorageResponse::NotStoredTimeLocked
+    );
+    assert_eq!(mempool.pending_coinbase_maturity().unwrap().len(), 1);
+
+    generate_block(&store, &mut blocks, vec![], &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 0);
+    assert_eq!(mempool.pending_coinbase_maturity().unwrap().len(), 1);
+
+    // Mine past the coinbase maturity, the transaction is promoted without being re-submitted
+    for height in 2..=3 {
+        generate_block(&store, &mut blocks, vec![], &consensus_manager).unwrap();
+        mempool.process_published_block(blocks[height].to_arc_block()).unwrap();
+    }
+    assert!(mempool.pending_coinbase_maturity().unwrap().is_empty());
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 1);
+    assert_eq!(
+        mempool
+            .has_tx_with_excess_sig(tx.first_kernel_excess_sig().unwrap().clone())
+            .unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_transactions_for_block_template() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![1 * T, 1 * T, 1 * T, 1 * T]
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    let txs = vec![
+        txn_schema!(from: vec![outputs[1][0].clone()], to: vec![], fee: 30*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][1].clone()], to: vec![], fee: 20*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][2].clone()], to: vec![], fee: 40*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][3].clone()], to: vec![], fee: 50*uT, lock: 0, features: OutputFeatures::default()),
+    ];
+    let (tx, _) = schema_to_transaction(&txs);
+    for t in &tx {
+        assert_eq!(mempool.insert(t.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+    }
+
+    // The coinbase weight is reserved, so only the three highest priority transactions fit
+    let coinbase_weight = 1_000;
+    let max_weight = tx[0].calculate_weight() + tx[2].calculate_weight() + tx[3].calculate_weight() + coinbase_weight;
+    let template_txs = mempool
+        .transactions_for_block_template(max_weight, coinbase_weight)
+        .unwrap();
+    assert_eq!(
+        template_txs.transactions,
+        mempool.retrieve(max_weight - coinbase_weight).unwrap()
+    );
+    assert_eq!(template_txs.transactions.len(), 3);
+    assert!(!template_txs.transactions.contains(&tx[1]));
+
+    let selected = &template_txs.transactions;
+    assert_eq!(
+        template_txs.total_fees,
+        selected.iter().map(|t| t.body.get_total_fee()).sum::<MicroTari>()
+    );
+    assert_eq!(
+        template_txs.total_weight,
+        selected.iter().map(|t| t.calculate_weight()).sum::<u64>()
+    );
+    assert!(template_txs.total_weight <= max_weight - coinbase_weight);
+    let kernel_offset = selected.iter().fold(PrivateKey::default(), |sum, t| &sum + &t.offset);
+    assert_eq!(template_txs.total_kernel_offset, kernel_offset);
+    let script_offset = selected
+        .iter()
+        .fold(PrivateKey::default(), |sum, t| &sum + &t.script_offset);
+    assert_eq!(template_txs.total_script_offset, script_offset);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_reorg_pool_evicts_oldest_mined_txs() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mut config = MempoolConfig::default();
+    config.reorg_pool.storage_capacity = 2;
+    let mempool = Mempool::new(config, Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![2 * T, 2 * T, 2 * T, 2 * T], fee: 25*uT, lock: 0, features: OutputFeatures::default()
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    let mut mined_txs = Vec::new();
+    for (i, output) in outputs[1].iter().take(4).enumerate() {
+        let schema = txn_schema!(from: vec![output.clone()], to: vec![1*T], fee: 20*uT, lock: 0, features: OutputFeatures::default());
+        let tx = Arc::new(spend_utxos(schema).0);
+        assert_eq!(mempool.insert(tx.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+        generate_block(&store, &mut blocks, vec![tx.deref().clone()], &consensus_manager).unwrap();
+        mempool.process_published_block(blocks[i + 2].to_arc_block()).unwrap();
+        assert!(mempool.stats().unwrap().reorg_txs <= 2);
+        mined_txs.push(tx);
+    }
+
+    assert_eq!(mempool.stats().unwrap().reorg_txs, 2);
+    let responses = mined_txs
+        .iter()
+        .map(|tx| {
+            mempool
+                .has_tx_with_excess_sig(tx.first_kernel_excess_sig().unwrap().clone())
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(responses, vec![
+        TxStorageResponse::NotStored,
+        TxStorageResponse::NotStored,
+        TxStorageResponse::ReorgPool,
+        TxStorageResponse::ReorgPool,
+    ]);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_pending_tx_evicted_when_input_spent_on_chain() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![1 * T, 1 * T])];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    // Block 2 creates an output that only matures at height 10
+    let txs = vec![txn_schema!(
+        from: vec![outputs[1][0].clone()],
+        to: vec![500_000*uT],
+        fee: 20*uT,
+        lock: 0,
+        features: OutputFeatures::with_maturity(10)
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+
+    // The pending transaction spends the immature output along with a mature one
+    let (pending_tx, _, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[2][0].clone(), outputs[1][1].clone()],
+        to: vec![],
+        fee: 20*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let pending_tx = Arc::new(pending_tx);
+    assert_eq!(
+        mempool.insert(pending_tx.clone()).unwrap(),
+        TxStorageResponse::NotStoredTimeLocked
+    );
+    assert_eq!(mempool.pending_coinbase_maturity().unwrap().len(), 1);
+
+    // A conflicting transaction spending the mature output is mined
+    let (conflicting_tx, _, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[1][1].clone()],
+        to: vec![],
+        fee: 20*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    generate_block(&store, &mut blocks, vec![conflicting_tx], &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[3].to_arc_block()).unwrap();
+
+    // The pending transaction can never become valid, so it is evicted rather than held until height 10
+    assert!(mempool.pending_coinbase_maturity().unwrap().is_empty());
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 0);
+    assert_eq!(
+        mempool
+            .has_tx_with_excess_sig(pending_tx.first_kernel_excess_sig().unwrap().clone())
+            .unwrap(),
+        TxStorageResponse::NotStored
+    );
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_recheck_timelocked_without_block_event() {
+    let network = Network::LocalNet;
+    let consensus_constants = ConsensusConstantsBuilder::new(network)
+        .with_emission_amounts(100_000_000.into(), &EMISSION, 100.into())
+        .with_coinbase_lockheight(3)
+        .build();
+    let (store, mut blocks, outputs, consensus_manager) =
+        create_new_blockchain_with_constants(network, consensus_constants);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+
+    let (tx, _, _) = spend_utxos(txn_schema!(from: vec![outputs[0][0].clone()], to: vec![2 * T, 2 * T]));
+    let tx = Arc::new(tx);
+    assert_eq!(
+        mempool.insert(tx.clone()).unwrap(),
+        TxStorageResponse::NotStoredTimeLocked
+    );
+    assert_eq!(mempool.recheck_timelocked(0).unwrap(), 0);
+
+    // The chain advances past the coinbase maturity, e.g. by a block sync, without the mempool processing the blocks
+    for _ in 1..=3 {
+        generate_block(&store, &mut blocks, vec![], &consensus_manager).unwrap();
+    }
+    assert_eq!(mempool.pending_coinbase_maturity().unwrap().len(), 1);
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 0);
+
+    assert_eq!(mempool.recheck_timelocked(3).unwrap(), 1);
+    assert!(mempool.pending_coinbase_maturity().unwrap().is_empty());
+    assert_eq!(
+        mempool
+            .has_tx_with_excess_sig(tx.first_kernel_excess_sig().unwrap().clone())
+            .unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+}
+
 #[tokio::test]
 #[allow(clippy::identity_op)]
 async fn test_retrieve() {
@@ -334,28 +753,503 @@ async fn test_retrieve() {
 
 #[tokio::test]
 #[allow(clippy::identity_op)]
-async fn test_zero_conf() {
+async fn test_fee_range_queries() {
     let network = Network::LocalNet;
     let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
     let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
     let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
     let txs = vec![txn_schema!(
         from: vec![outputs[0][0].clone()],
-        to: vec![21 * T, 11 * T, 11 * T, 16 * T]
+        to: vec![1 * T, 1 * T, 1 * T, 1 * T, 1 * T, 1 * T, 1 * T]
     )];
-    // "Mine" Block 1
     generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
     mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+    // The same pool as test_retrieve. tx[5] is time-locked and is not in the unconfirmed pool.
+    let txs = vec![
+        txn_schema!(from: vec![outputs[1][0].clone()], to: vec![], fee: 30*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][1].clone()], to: vec![], fee: 20*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][2].clone()], to: vec![], fee: 40*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][3].clone()], to: vec![], fee: 50*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][4].clone()], to: vec![], fee: 20*uT, lock: 2, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][5].clone()], to: vec![], fee: 20*uT, lock: 3, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][6].clone()], to: vec![800_000*uT], fee: 60*uT, lock: 0,
+        features: OutputFeatures::with_maturity(4)),
+        txn_schema!(from: vec![outputs[1][7].clone()], to: vec![800_000*uT], fee: 25*uT, lock: 0,
+        features: OutputFeatures::with_maturity(3)),
+    ];
+    let (tx, _) = schema_to_transaction(&txs);
+    tx.iter().for_each(|t| {
+        mempool.insert(t.clone()).unwrap();
+    });
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 7);
 
-    // This transaction graph will be created, containing 3 levels of zero-conf transactions, inheriting dependent
-    // outputs from multiple parents
-    //
-    // tx01   tx02   tx03   tx04    Basis transactions using mined inputs (lowest fees, increases left to right)
-    //   | \    |      |      |
-    //   |  \   |      |      |
-    //   |   \  |      |      |
-    // tx11   tx12   tx13   tx14    Zero-conf level 1 transactions (fees up from previous, increases left to right)
-    //   | \    | \    |   /  |
+    // Fees per gram of the unconfirmed txs: 60, 50, 40, 30, 25, 20, 20
+    assert_eq!(mempool.count_in_fee_range(20 * uT, 40 * uT).unwrap(), 4);
+    assert_eq!(mempool.count_in_fee_range(40 * uT, 60 * uT).unwrap(), 2);
+    assert_eq!(mempool.count_in_fee_range(60 * uT, 61 * uT).unwrap(), 1);
+    assert_eq!(mempool.count_in_fee_range(MicroTari::from(0), 20 * uT).unwrap(), 0);
+    assert_eq!(mempool.count_in_fee_range(MicroTari::from(0), 1_000 * uT).unwrap(), 7);
+
+    let txs_in_range = mempool.txs_in_fee_range(40 * uT, 60 * uT).unwrap();
+    assert_eq!(txs_in_range, vec![tx[3].clone(), tx[2].clone()]);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_next_block_preview() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![1 * T, 1 * T, 1 * T, 1 * T]
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    let preview = mempool.next_block_preview(u64::MAX).unwrap();
+    assert!(preview.transactions.is_empty());
+    assert_eq!(preview.total_weight, 0);
+    assert_eq!(preview.total_fees, MicroTari::from(0));
+
+    let txs = vec![
+        txn_schema!(from: vec![outputs[1][0].clone()], to: vec![], fee: 30*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][1].clone()], to: vec![], fee: 20*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][2].clone()], to: vec![], fee: 40*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][3].clone()], to: vec![], fee: 50*uT, lock: 0, features: OutputFeatures::default()),
+    ];
+    let (tx, _) = schema_to_transaction(&txs);
+    for t in &tx {
+        assert_eq!(mempool.insert(t.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+    }
+
+    // Only the two highest priority transactions fit
+    let weight = tx[2].calculate_weight() + tx[3].calculate_weight();
+    let preview = mempool.next_block_preview(weight).unwrap();
+    let retrieved_txs = mempool.retrieve(weight).unwrap();
+    assert_eq!(preview.transactions, retrieved_txs);
+    assert_eq!(preview.transactions.len(), 2);
+    assert!(preview.transactions.contains(&tx[2]));
+    assert!(preview.transactions.contains(&tx[3]));
+    assert_eq!(preview.total_weight, weight);
+    assert_eq!(
+        preview.total_fees,
+        tx[2].body.get_total_fee() + tx[3].body.get_total_fee()
+    );
+    // Previewing does not remove the transactions from the mempool
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 4);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_content_hash() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool1 = Mempool::new(
+        MempoolConfig::default(),
+        Arc::new(TxInputAndMaturityValidator::new(store.clone())),
+    );
+    let mempool2 = Mempool::new(
+        MempoolConfig::default(),
+        Arc::new(TxInputAndMaturityValidator::new(store.clone())),
+    );
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![1 * T, 1 * T, 1 * T]
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool1.process_published_block(blocks[1].to_arc_block()).unwrap();
+    mempool2.process_published_block(blocks[1].to_arc_block()).unwrap();
+    assert_eq!(mempool1.content_hash().unwrap(), FixedHash::zero());
+    assert_eq!(mempool1.content_hash().unwrap(), mempool2.content_hash().unwrap());
+
+    let txs = vec![
+        txn_schema!(from: vec![outputs[1][0].clone()], to: vec![], fee: 30*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][1].clone()], to: vec![], fee: 20*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][2].clone()], to: vec![], fee: 40*uT, lock: 0, features: OutputFeatures::default()),
+    ];
+    let (tx, _) = schema_to_transaction(&txs);
+    for t in &tx {
+        mempool1.insert(t.clone()).unwrap();
+    }
+    // Insert all but the last transaction in reverse order
+    for t in tx.iter().take(2).rev() {
+        mempool2.insert(t.clone()).unwrap();
+    }
+    assert_ne!(mempool1.content_hash().unwrap(), mempool2.content_hash().unwrap());
+
+    mempool2.insert(tx[2].clone()).unwrap();
+    assert_eq!(mempool1.content_hash().unwrap(), mempool2.content_hash().unwrap());
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_reject_reasons_stat() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![2 * T, 2 * T, 2 * T, 2 * T], fee: 25*uT, lock: 0, features: OutputFeatures::default()
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+    assert!(mempool.stats().unwrap().reject_reasons.is_empty());
+
+    let (orphan1, _, _) = tx!(1*T, fee: 100*uT);
+    let (orphan2, _, _) = tx!(1*T, fee: 100*uT);
+    let mut time_locked = txn_schema!(from: vec![outputs[1][0].clone()], to: vec![1*T], fee: 20*uT, lock: 0, features: OutputFeatures::default());
+    time_locked.lock_height = 3;
+    let time_locked = Arc::new(spend_utxos(time_locked).0);
+    let tx = txn_schema!(from: vec![outputs[1][1].clone()], to: vec![1*T], fee: 20*uT, lock: 0, features: OutputFeatures::default());
+    let tx = Arc::new(spend_utxos(tx).0);
+
+    assert_eq!(
+        mempool.insert(Arc::new(orphan1)).unwrap(),
+        TxStorageResponse::NotStoredOrphan
+    );
+    assert_eq!(
+        mempool.insert(Arc::new(orphan2)).unwrap(),
+        TxStorageResponse::NotStoredOrphan
+    );
+    assert_eq!(
+        mempool.insert(time_locked).unwrap(),
+        TxStorageResponse::NotStoredTimeLocked
+    );
+    assert_eq!(mempool.insert(tx.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+
+    generate_block(&store, &mut blocks, vec![tx.deref().clone()], &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+    assert_eq!(mempool.insert(tx).unwrap(), TxStorageResponse::NotStoredAlreadySpent);
+
+    let reject_reasons = mempool.stats().unwrap().reject_reasons;
+    assert_eq!(reject_reasons.len(), 3);
+    assert_eq!(reject_reasons[&RejectReason::Orphan], 2);
+    assert_eq!(reject_reasons[&RejectReason::TimeLocked], 1);
+    assert_eq!(reject_reasons[&RejectReason::DoubleSpend], 1);
+}
+
+struct DoubleWeightPolicy;
+
+impl TransactionWeightPolicy for DoubleWeightPolicy {
+    fn calculate_weight(&self, tx: &Transaction) -> u64 {
+        tx.calculate_weight() * 2
+    }
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_custom_weight_policy() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = MempoolBuilder::new(MempoolConfig::default(), Arc::new(mempool_validator))
+        .with_weight_policy(Arc::new(DoubleWeightPolicy))
+        .build();
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![1 * T, 1 * T, 1 * T, 1 * T]
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    let txs = vec![
+        txn_schema!(from: vec![outputs[1][0].clone()], to: vec![], fee: 30*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][1].clone()], to: vec![], fee: 20*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][2].clone()], to: vec![], fee: 40*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][3].clone()], to: vec![], fee: 50*uT, lock: 0, features: OutputFeatures::default()),
+    ];
+    let (tx, _) = schema_to_transaction(&txs);
+    for t in &tx {
+        assert_eq!(mempool.insert(t.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+    }
+    let consensus_weight = tx.iter().map(|t| t.calculate_weight()).sum::<u64>();
+    assert_eq!(mempool.stats().unwrap().total_weight, consensus_weight * 2);
+
+    // The two highest priority transactions would fit by consensus weight, but only one fits with doubled weights
+    let weight = tx[2].calculate_weight() + tx[3].calculate_weight();
+    let retrieved_txs = mempool.retrieve(weight).unwrap();
+    assert_eq!(retrieved_txs.len(), 1);
+    assert!(retrieved_txs.contains(&tx[3]));
+
+    let retrieved_txs = mempool.retrieve(weight * 2).unwrap();
+    assert_eq!(retrieved_txs.len(), 2);
+    assert!(retrieved_txs.contains(&tx[2]));
+    assert!(retrieved_txs.contains(&tx[3]));
+    assert_eq!(mempool.next_block_preview(weight * 2).unwrap().total_weight, weight * 2);
+}
+
+struct PenaltyWeightPolicy(Signature);
+
+impl TransactionWeightPolicy for PenaltyWeightPolicy {
+    fn calculate_weight(&self, tx: &Transaction) -> u64 {
+        if tx.first_kernel_excess_sig() == Some(&self.0) {
+            tx.calculate_weight() * 10
+        } else {
+            tx.calculate_weight()
+        }
+    }
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_weight_policy_determines_priority() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![1 * T, 1 * T, 1 * T, 1 * T]
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    let txs = vec![
+        txn_schema!(from: vec![outputs[1][0].clone()], to: vec![], fee: 30*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][1].clone()], to: vec![], fee: 20*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][2].clone()], to: vec![], fee: 40*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][3].clone()], to: vec![], fee: 50*uT, lock: 0, features: OutputFeatures::default()),
+    ];
+    let (tx, _) = schema_to_transaction(&txs);
+
+    // The highest fee transaction pays the lowest fee per gram of its penalized weight
+    let mut unconfirmed_pool = MempoolConfig::default().unconfirmed_pool;
+    unconfirmed_pool.storage_capacity = 3;
+    let mempool_config = MempoolConfig {
+        unconfirmed_pool,
+        ..Default::default()
+    };
+    let mempool = MempoolBuilder::new(
+        mempool_config,
+        Arc::new(TxInputAndMaturityValidator::new(store.clone())),
+    )
+    .with_weight_policy(Arc::new(PenaltyWeightPolicy(
+        tx[3].first_kernel_excess_sig().unwrap().clone(),
+    )))
+    .build();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+    for t in &tx[..3] {
+        assert_eq!(mempool.insert(t.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+    }
+    let min_fee_per_gram = 20 / tx[1].calculate_weight() + 1;
+    assert_eq!(
+        mempool.insert(tx[3].clone()).unwrap(),
+        TxStorageResponse::NotStoredFeeTooLow(min_fee_per_gram.into())
+    );
+    let snapshot = mempool.snapshot().unwrap();
+    assert_eq!(snapshot.len(), 3);
+    assert!(!snapshot.contains(&tx[3]));
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_retrieve_omits_transactions_invalidated_by_tip() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool_config = MempoolConfig {
+        revalidate_on_retrieve: true,
+        ..Default::default()
+    };
+    let mempool = Mempool::new(mempool_config, Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![1 * T, 1 * T])];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    let txs = vec![
+        txn_schema!(from: vec![outputs[1][0].clone()], to: vec![], fee: 30*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][1].clone()], to: vec![], fee: 20*uT, lock: 0, features: OutputFeatures::default()),
+    ];
+    let (tx, _) = schema_to_transaction(&txs);
+    tx.iter().for_each(|t| {
+        mempool.insert(t.clone()).unwrap();
+    });
+
+    // Advance the chain with a block that spends the input of tx[0], without the mempool processing the block
+    let double_spend = txn_schema!(from: vec![outputs[1][0].clone()], to: vec![], fee: 40*uT, lock: 0, features: OutputFeatures::default());
+    let (double_spend, _) = schema_to_transaction(&[double_spend]);
+    generate_block(
+        &store,
+        &mut blocks,
+        vec![double_spend[0].deref().clone()],
+        &consensus_manager,
+    )
+    .unwrap();
+
+    let retrieved_txs = mempool.retrieve(mempool.stats().unwrap().total_weight).unwrap();
+    assert_eq!(retrieved_txs.len(), 1);
+    assert!(retrieved_txs.contains(&tx[1]));
+    assert!(!retrieved_txs.contains(&tx[0]));
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_retrieve_page() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![1 * T, 1 * T, 1 * T, 1 * T, 1 * T, 1 * T, 1 * T]
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+    // Two pairs of transactions share a fee, so that the cursor has to break ties on more than the fee
+    let txs = vec![
+        txn_schema!(from: vec![outputs[1][0].clone()], to: vec![], fee: 30*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][1].clone()], to: vec![], fee: 20*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][2].clone()], to: vec![], fee: 40*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][3].clone()], to: vec![], fee: 50*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][4].clone()], to: vec![], fee: 20*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][5].clone()], to: vec![], fee: 30*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][6].clone()], to: vec![], fee: 60*uT, lock: 0, features: OutputFeatures::default()),
+    ];
+    let (tx, _) = schema_to_transaction(&txs);
+    tx.iter().for_each(|t| {
+        mempool.insert(t.clone()).unwrap();
+    });
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 7);
+
+    let mut cursor = None;
+    let mut paged = Vec::new();
+    loop {
+        let (page, next) = mempool.retrieve_page(cursor, 3).unwrap();
+        assert!(page.len() <= 3);
+        paged.extend(page);
+        if next.is_none() {
+            break;
+        }
+        cursor = next;
+    }
+
+    // Every transaction is returned exactly once
+    assert_eq!(paged.len(), 7);
+    let sigs = paged
+        .iter()
+        .map(|info| info.transaction.first_kernel_excess_sig().unwrap().clone())
+        .collect::<Vec<_>>();
+    for t in &tx {
+        let sig = t.first_kernel_excess_sig().unwrap();
+        assert_eq!(sigs.iter().filter(|s| *s == sig).count(), 1);
+    }
+    // In descending fee order
+    assert!(paged
+        .windows(2)
+        .all(|pair| pair[0].fee_per_gram() >= pair[1].fee_per_gram()));
+
+    // A cursor remains valid if the pool changes between pages
+    let (first_page, cursor) = mempool.retrieve_page(None, 2).unwrap();
+    assert_eq!(first_page.len(), 2);
+    let mined = vec![first_page[0].transaction.deref().clone()];
+    generate_block(&store, &mut blocks, mined, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+    let (rest, next) = mempool.retrieve_page(cursor, 10).unwrap();
+    assert!(next.is_none());
+    assert_eq!(rest.len(), 5);
+    assert!(rest.iter().all(|info| info.transaction != first_page[1].transaction));
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_txs_creating_commitment() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![1 * T, 1 * T])];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    let (tx, _, _) = spend_utxos(txn_schema!(from: vec![outputs[1][0].clone()], to: vec![500_000 * uT]));
+    let tx = Arc::new(tx);
+    assert_eq!(mempool.insert(tx.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+
+    let commitment = tx.body.outputs()[0].commitment.clone();
+    let found = mempool.txs_creating_commitment(&commitment).unwrap();
+    assert_eq!(found, vec![tx.clone()]);
+
+    // The input of the transaction is spent, not created, by it, so is unknown to the index
+    let input_commitment = tx.body.inputs()[0].commitment.clone();
+    assert!(mempool.txs_creating_commitment(&input_commitment).unwrap().is_empty());
+
+    // The index is cleaned up once the transaction is mined
+    generate_block(&store, &mut blocks, vec![tx.deref().clone()], &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+    assert!(mempool.txs_creating_commitment(&commitment).unwrap().is_empty());
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_spent_commitments() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![1 * T, 1 * T, 1 * T]
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+    assert!(mempool.spent_commitments().unwrap().is_empty());
+
+    let (tx1, _, _) = spend_utxos(txn_schema!(from: vec![outputs[1][0].clone()], to: vec![500_000 * uT]));
+    let (tx2, _, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[1][1].clone(), outputs[1][2].clone()],
+        to: vec![500_000 * uT]
+    ));
+    let tx1 = Arc::new(tx1);
+    let tx2 = Arc::new(tx2);
+    assert_eq!(mempool.insert(tx1.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+    assert_eq!(mempool.insert(tx2.clone()).unwrap(), TxStorageResponse::UnconfirmedPool);
+
+    let spent = mempool.spent_commitments().unwrap();
+    let expected = tx1
+        .body
+        .inputs()
+        .iter()
+        .chain(tx2.body.inputs())
+        .map(|input| input.commitment.clone())
+        .collect::<HashSet<_>>();
+    assert_eq!(spent.len(), 3);
+    assert_eq!(spent, expected);
+    // Outputs created by the transactions are not spent
+    assert!(!spent.contains(&tx1.body.outputs()[0].commitment));
+
+    // The inputs are no longer reported once the transaction is mined
+    generate_block(&store, &mut blocks, vec![tx1.deref().clone()], &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+    let spent = mempool.spent_commitments().unwrap();
+    assert_eq!(
+        spent,
+        tx2.body.inputs().iter().map(|input| input.commitment.clone()).collect()
+    );
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_zero_conf() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![21 * T, 11 * T, 11 * T, 16 * T]
+    )];
+    // "Mine" Block 1
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    // This transaction graph will be created, containing 3 levels of zero-conf transactions, inheriting dependent
+    // outputs from multiple parents
+    //
+    // tx01   tx02   tx03   tx04    Basis transactions using mined inputs (lowest fees, increases left to right)
+    //   | \    |      |      |
+    //   |  \   |      |      |
+    //   |   \  |      |      |
+    // tx11   tx12   tx13   tx14    Zero-conf level 1 transactions (fees up from previous, increases left to right)
+    //   | \    | \    |   /  |
     //   |  |   |  \   |  |   |
     //   |  |   |   \  |  |   |
     // tx21 | tx22   tx23 | tx24    Zero-conf level 2 transactions (fees up from previous, increases left to right)
@@ -510,126 +1404,664 @@ async fn test_zero_conf() {
         lock: 0,
         features: OutputFeatures::default()
     ));
-    let (tx33, _, _) = spend_utxos(txn_schema!(
-        from: vec![tx14_out[1].clone(), tx23_out[0].clone(), tx23_out[1].clone()],
-        to: vec![2 * T, 2 * T],
-        fee: 150*uT,
+    let (tx33, _, _) = spend_utxos(txn_schema!(
+        from: vec![tx14_out[1].clone(), tx23_out[0].clone(), tx23_out[1].clone()],
+        to: vec![2 * T, 2 * T],
+        fee: 150*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx34, _, _) = spend_utxos(txn_schema!(
+        from: tx24_out,
+        to: vec![2 * T, 2 * T],
+        fee: 160*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    assert_eq!(
+        mempool.insert(Arc::new(tx31.clone())).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+    assert_eq!(
+        mempool.insert(Arc::new(tx32.clone())).unwrap(),
+        TxStorageResponse::NotStoredOrphan
+    );
+    assert_eq!(
+        mempool.insert(Arc::new(tx33.clone())).unwrap(),
+        TxStorageResponse::NotStoredOrphan
+    );
+    assert_eq!(
+        mempool.insert(Arc::new(tx34.clone())).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+
+    // Try to retrieve all transactions in the mempool (a couple of our transactions should be missing from retrieved)
+    let retrieved_txs = mempool.retrieve(mempool.stats().unwrap().total_weight).unwrap();
+    assert_eq!(retrieved_txs.len(), 10);
+    assert!(retrieved_txs.contains(&Arc::new(tx01.clone())));
+    assert!(!retrieved_txs.contains(&Arc::new(tx02.clone()))); // Missing
+    assert!(retrieved_txs.contains(&Arc::new(tx03.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx04.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx11.clone())));
+    assert!(!retrieved_txs.contains(&Arc::new(tx12.clone()))); // Missing
+    assert!(retrieved_txs.contains(&Arc::new(tx13.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx14.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx21.clone())));
+    assert!(!retrieved_txs.contains(&Arc::new(tx22.clone()))); // Missing
+    assert!(!retrieved_txs.contains(&Arc::new(tx23.clone()))); // Missing
+    assert!(retrieved_txs.contains(&Arc::new(tx24.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx31.clone())));
+    assert!(!retrieved_txs.contains(&Arc::new(tx32.clone()))); // Missing
+    assert!(!retrieved_txs.contains(&Arc::new(tx33.clone()))); // Missing
+    assert!(retrieved_txs.contains(&Arc::new(tx34.clone())));
+
+    // Submit the missing original transactions
+    assert_eq!(
+        mempool.insert(Arc::new(tx02.clone())).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+    // Re-submit failed zero-conf level 1 transactions
+    assert_eq!(
+        mempool.insert(Arc::new(tx12.clone())).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+    // Re-submit failed zero-conf level 2 transactions
+    assert_eq!(
+        mempool.insert(Arc::new(tx22.clone())).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+    assert_eq!(
+        mempool.insert(Arc::new(tx23.clone())).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+    // Re-submit failed zero-conf level 3 transactions
+    assert_eq!(
+        mempool.insert(Arc::new(tx32.clone())).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+    assert_eq!(
+        mempool.insert(Arc::new(tx33.clone())).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+
+    // Try to retrieve all transactions in the mempool (all transactions should be retrieved)
+    let retrieved_txs = mempool.retrieve(mempool.stats().unwrap().total_weight).unwrap();
+    assert_eq!(retrieved_txs.len(), 16);
+    assert!(retrieved_txs.contains(&Arc::new(tx01.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx02.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx03.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx04.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx11.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx12.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx13.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx14.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx21.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx22.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx23.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx24.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx31.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx32.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx33.clone())));
+    assert!(retrieved_txs.contains(&Arc::new(tx34.clone())));
+
+    // Verify that a higher priority transaction is not retrieved due to its zero-conf dependency instead of the lowest
+    // priority transaction
+    let retrieved_txs = mempool.retrieve(mempool.stats().unwrap().total_weight - 1).unwrap();
+    assert_eq!(retrieved_txs.len(), 15);
+    assert!(retrieved_txs.contains(&Arc::new(tx01)));
+    assert!(retrieved_txs.contains(&Arc::new(tx02)));
+    assert!(retrieved_txs.contains(&Arc::new(tx03)));
+    assert!(retrieved_txs.contains(&Arc::new(tx04)));
+    assert!(retrieved_txs.contains(&Arc::new(tx11)));
+    assert!(retrieved_txs.contains(&Arc::new(tx12)));
+    assert!(retrieved_txs.contains(&Arc::new(tx13)));
+    assert!(retrieved_txs.contains(&Arc::new(tx14)));
+    assert!(retrieved_txs.contains(&Arc::new(tx21)));
+    assert!(retrieved_txs.contains(&Arc::new(tx22)));
+    assert!(retrieved_txs.contains(&Arc::new(tx23)));
+    assert!(retrieved_txs.contains(&Arc::new(tx24)));
+    assert!(!retrieved_txs.contains(&Arc::new(tx31))); // Missing
+    assert!(retrieved_txs.contains(&Arc::new(tx32)));
+    assert!(retrieved_txs.contains(&Arc::new(tx33)));
+    assert!(retrieved_txs.contains(&Arc::new(tx34)));
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_zero_conf_chain_length_cap() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool_config = MempoolConfig {
+        max_zero_conf_chain_length: 2,
+        ..Default::default()
+    };
+    let mempool = Mempool::new(mempool_config, Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![21 * T])];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    // tx0 spends a mined output, tx1, tx2 and tx3 each spend the output of the previous transaction
+    let (tx0, tx0_out, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[1][0].clone()],
+        to: vec![15 * T],
+        fee: 10*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx1, tx1_out, _) = spend_utxos(txn_schema!(
+        from: tx0_out,
+        to: vec![11 * T],
+        fee: 50*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx2, tx2_out, _) = spend_utxos(txn_schema!(
+        from: tx1_out,
+        to: vec![7 * T],
+        fee: 90*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    // A high fee must not allow the chain to be extended
+    let (tx3, _, _) = spend_utxos(txn_schema!(
+        from: tx2_out,
+        to: vec![3 * T],
+        fee: 10_000*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+
+    for tx in vec![tx0, tx1, tx2.clone()] {
+        assert_eq!(
+            mempool.insert(Arc::new(tx)).unwrap(),
+            TxStorageResponse::UnconfirmedPool
+        );
+    }
+    assert!(matches!(
+        mempool.validate_tx(&tx3).unwrap(),
+        ValidationOutcome::Invalid(_)
+    ));
+    assert_eq!(
+        mempool.insert(Arc::new(tx3.clone())).unwrap(),
+        TxStorageResponse::NotStoredZeroConfChainTooLong
+    );
+    assert_eq!(
+        mempool
+            .has_tx_with_excess_sig(tx3.body.kernels()[0].excess_sig.clone())
+            .unwrap(),
+        TxStorageResponse::NotStored
+    );
+    assert_eq!(
+        mempool.dependency_depth(&tx2.body.kernels()[0].excess_sig).unwrap(),
+        Some(2)
+    );
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_block_process_summary() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![5 * T, 5 * T, 5 * T, 5 * T]
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    let (tx1, _, _) = spend_utxos(txn_schema!(from: vec![outputs[1][0].clone()], to: vec![2 * T], fee: 20*uT));
+    let (tx2, _, _) = spend_utxos(txn_schema!(from: vec![outputs[1][1].clone()], to: vec![2 * T], fee: 30*uT));
+    let (tx3, _, _) = spend_utxos(txn_schema!(from: vec![outputs[1][2].clone()], to: vec![2 * T], fee: 40*uT));
+    for tx in vec![tx1.clone(), tx2.clone(), tx3] {
+        assert_eq!(
+            mempool.insert(Arc::new(tx)).unwrap(),
+            TxStorageResponse::UnconfirmedPool
+        );
+    }
+    // A transaction that was never in the pool is not counted as mined from it
+    let (other_tx, _, _) = spend_utxos(txn_schema!(from: vec![outputs[1][3].clone()], to: vec![2 * T], fee: 20*uT));
+
+    generate_block(&store, &mut blocks, vec![tx1, tx2, other_tx], &consensus_manager).unwrap();
+    let summary = mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+    assert_eq!(summary.mined_from_pool, 2);
+    assert_eq!(summary.newly_timelocked_promoted, 0);
+    assert_eq!(summary.orphans_promoted, 0);
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 1);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_replay_blocks() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(
+        from: vec![outputs[0][0].clone()],
+        to: vec![5 * T, 5 * T, 5 * T]
+    )];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    let (tx1, _, _) = spend_utxos(txn_schema!(from: vec![outputs[1][0].clone()], to: vec![2 * T], fee: 20*uT));
+    let (tx2, _, _) = spend_utxos(txn_schema!(from: vec![outputs[1][1].clone()], to: vec![2 * T], fee: 30*uT));
+    let (tx3, _, _) = spend_utxos(txn_schema!(from: vec![outputs[1][2].clone()], to: vec![2 * T], fee: 40*uT));
+    for tx in vec![tx1.clone(), tx2.clone(), tx3] {
+        assert_eq!(
+            mempool.insert(Arc::new(tx)).unwrap(),
+            TxStorageResponse::UnconfirmedPool
+        );
+    }
+    generate_block(&store, &mut blocks, vec![tx1], &consensus_manager).unwrap();
+    generate_block(&store, &mut blocks, vec![tx2], &consensus_manager).unwrap();
+    let new_blocks = vec![blocks[2].to_arc_block(), blocks[3].to_arc_block()];
+
+    let stats_before = mempool.stats().unwrap();
+    let replay_stats = mempool.replay_blocks(new_blocks.clone()).unwrap();
+    // Replaying does not modify the mempool
+    assert_eq!(mempool.stats().unwrap(), stats_before);
+    assert_eq!(replay_stats.unconfirmed_txs, 1);
+    assert_eq!(replay_stats.reorg_txs, 2);
+
+    for block in new_blocks {
+        mempool.process_published_block(block).unwrap();
+    }
+    assert_eq!(mempool.stats().unwrap(), replay_stats);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_dependency_depth() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![21 * T, 11 * T])];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    // tx01 -> tx11 -> tx21 -> tx31, as in test_zero_conf, and an unrelated tx02 that spends a mined output
+    let (tx01, tx01_out, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[1][0].clone()],
+        to: vec![15 * T, 5 * T],
+        fee: 10*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx02, _, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[1][1].clone()],
+        to: vec![5 * T, 5 * T],
+        fee: 20*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx11, tx11_out, _) = spend_utxos(txn_schema!(
+        from: vec![tx01_out[0].clone()],
+        to: vec![7 * T, 4 * T],
+        fee: 50*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx21, tx21_out, _) = spend_utxos(txn_schema!(
+        from: vec![tx11_out[0].clone()],
+        to: vec![3 * T, 3 * T],
+        fee: 90*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx31, _, _) = spend_utxos(txn_schema!(
+        from: tx21_out,
+        to: vec![2 * T, 2 * T],
+        fee: 130*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let txs = vec![tx01, tx02, tx11, tx21, tx31];
+    for tx in &txs {
+        assert_eq!(
+            mempool.insert(Arc::new(tx.clone())).unwrap(),
+            TxStorageResponse::UnconfirmedPool
+        );
+    }
+
+    let depths = txs
+        .iter()
+        .map(|tx| mempool.dependency_depth(tx.first_kernel_excess_sig().unwrap()).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(depths, vec![Some(0), Some(0), Some(1), Some(2), Some(3)]);
+
+    let (unknown_tx, _, _) = tx!(1*T, fee: 100*uT);
+    assert_eq!(
+        mempool
+            .dependency_depth(unknown_tx.first_kernel_excess_sig().unwrap())
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_max_dependency_depth_stat() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![21 * T, 11 * T])];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+    assert_eq!(mempool.stats().unwrap().max_dependency_depth, 0);
+
+    // tx01 -> tx11 -> tx21 -> tx31, as in test_zero_conf
+    let (tx01, tx01_out, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[1][0].clone()],
+        to: vec![15 * T, 5 * T],
+        fee: 10*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx11, tx11_out, _) = spend_utxos(txn_schema!(
+        from: vec![tx01_out[0].clone()],
+        to: vec![7 * T, 4 * T],
+        fee: 50*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx21, tx21_out, _) = spend_utxos(txn_schema!(
+        from: vec![tx11_out[0].clone()],
+        to: vec![3 * T, 3 * T],
+        fee: 90*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx31, _, _) = spend_utxos(txn_schema!(
+        from: tx21_out,
+        to: vec![2 * T, 2 * T],
+        fee: 130*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    for tx in vec![tx01.clone(), tx11.clone(), tx21, tx31] {
+        assert_eq!(
+            mempool.insert(Arc::new(tx)).unwrap(),
+            TxStorageResponse::UnconfirmedPool
+        );
+    }
+    assert_eq!(mempool.stats().unwrap().max_dependency_depth, 3);
+
+    // Mining the roots of the chain shortens it
+    generate_block(&store, &mut blocks, vec![tx01, tx11], &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+    let stats = mempool.stats().unwrap();
+    assert_eq!(stats.unconfirmed_txs, 2);
+    assert_eq!(stats.max_dependency_depth, 1);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_batched_orphan_promotion() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let config = MempoolConfig {
+        hold_orphans: true,
+        orphan_promotion_mode: OrphanPromotionMode::Batched,
+        ..Default::default()
+    };
+    let mempool = Mempool::new(config, Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![21 * T, 11 * T])];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    // tx01 <- tx11 <- tx21
+    let (tx01, tx01_out, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[1][0].clone()],
+        to: vec![15 * T, 5 * T],
+        fee: 10*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx11, tx11_out, _) = spend_utxos(txn_schema!(
+        from: tx01_out,
+        to: vec![5 * T, 5 * T],
+        fee: 20*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx21, _, _) = spend_utxos(txn_schema!(
+        from: tx11_out,
+        to: vec![3 * T, 3 * T],
+        fee: 30*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (unresolvable, _, _) = tx!(1*T, fee: 100*uT);
+
+    let responses = mempool
+        .insert_many(vec![
+            Arc::new(tx21.clone()),
+            Arc::new(tx11.clone()),
+            Arc::new(unresolvable),
+        ])
+        .unwrap();
+    assert!(responses.iter().all(|r| *r == TxStorageResponse::NotStoredOrphan));
+    assert_eq!(mempool.orphan_count().unwrap(), 3);
+
+    // The parent arrives, but the orphans are not promoted until promote_orphans is called
+    assert_eq!(
+        mempool.insert(Arc::new(tx01.clone())).unwrap(),
+        TxStorageResponse::UnconfirmedPool
+    );
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 1);
+    assert_eq!(mempool.orphan_count().unwrap(), 3);
+
+    // All resolvable orphans are promoted at once
+    assert_eq!(mempool.promote_orphans().unwrap(), 2);
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 3);
+    assert_eq!(mempool.orphan_count().unwrap(), 1);
+    let snapshot_txs = mempool.snapshot().unwrap();
+    assert!(snapshot_txs.contains(&Arc::new(tx11)));
+    assert!(snapshot_txs.contains(&Arc::new(tx21)));
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_orphans_promoted_by_published_block() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let config = MempoolConfig {
+        hold_orphans: true,
+        ..Default::default()
+    };
+    let mempool = Mempool::new(config, Arc::new(mempool_validator));
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![21 * T, 11 * T])];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    // The parent is never seen by the mempool, it is only mined
+    let (tx01, tx01_out, _) =
+        spend_utxos(txn_schema!(from: vec![outputs[1][0].clone()], to: vec![15 * T, 5 * T], fee: 10*uT));
+    let (tx11, _, _) = spend_utxos(txn_schema!(from: tx01_out, to: vec![5 * T, 5 * T], fee: 20*uT));
+    let (unresolvable, _, _) = tx!(1*T, fee: 100*uT);
+    for tx in vec![tx11.clone(), unresolvable] {
+        assert_eq!(
+            mempool.insert(Arc::new(tx)).unwrap(),
+            TxStorageResponse::NotStoredOrphan
+        );
+    }
+    assert_eq!(mempool.orphan_count().unwrap(), 2);
+
+    generate_block(&store, &mut blocks, vec![tx01], &consensus_manager).unwrap();
+    let summary = mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+    assert_eq!(summary.mined_from_pool, 0);
+    assert_eq!(summary.orphans_promoted, 1);
+    assert_eq!(mempool.orphan_count().unwrap(), 1);
+    assert_eq!(mempool.snapshot().unwrap(), vec![Arc::new(tx11)]);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_orphans_held_only_when_enabled() {
+    let network = Network::LocalNet;
+    let (store, _, _, _) = create_new_blockchain(network);
+    let mempool = Mempool::new(
+        MempoolConfig::default(),
+        Arc::new(TxInputAndMaturityValidator::new(store.clone())),
+    );
+    let (orphan, _, _) = tx!(1*T, fee: 100*uT);
+    assert_eq!(
+        mempool.insert(Arc::new(orphan)).unwrap(),
+        TxStorageResponse::NotStoredOrphan
+    );
+    assert_eq!(mempool.orphan_count().unwrap(), 0);
+
+    // Held orphans are dropped once they outlive the TTL
+    let config = MempoolConfig {
+        hold_orphans: true,
+        orphan_tx_ttl: Duration::from_secs(0),
+        ..Default::default()
+    };
+    let mempool = Mempool::new(config, Arc::new(TxInputAndMaturityValidator::new(store)));
+    let (orphan1, _, _) = tx!(1*T, fee: 100*uT);
+    let (orphan2, _, _) = tx!(1*T, fee: 100*uT);
+    assert_eq!(
+        mempool.insert(Arc::new(orphan1)).unwrap(),
+        TxStorageResponse::NotStoredOrphan
+    );
+    assert_eq!(mempool.orphan_count().unwrap(), 1);
+    assert_eq!(
+        mempool.insert(Arc::new(orphan2)).unwrap(),
+        TxStorageResponse::NotStoredOrphan
+    );
+    assert_eq!(mempool.orphan_count().unwrap(), 1);
+    // Held orphans have not been rejected
+    assert!(mempool.stats().unwrap().reject_reasons.is_empty());
+    assert_eq!(mempool.promote_orphans().unwrap(), 0);
+    assert_eq!(mempool.orphan_count().unwrap(), 0);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_orphans_persisted_across_restart() {
+    let network = Network::LocalNet;
+    let (mut store, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![21 * T, 11 * T])];
+    generate_new_block(&mut store, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    let config = MempoolConfig {
+        hold_orphans: true,
+        persist_orphans: true,
+        ..Default::default()
+    };
+    let create_mempool = || {
+        MempoolBuilder::new(config, Arc::new(TxInputAndMaturityValidator::new(store.clone())))
+            .with_reorg_marker_store(Arc::new(store.clone()))
+            .with_orphan_store(Arc::new(store.clone()))
+            .build()
+    };
+    let mempool = create_mempool();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    // tx01 <- tx11 <- tx21
+    let (tx01, tx01_out, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[1][0].clone()],
+        to: vec![15 * T, 5 * T],
+        fee: 10*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx11, tx11_out, _) = spend_utxos(txn_schema!(
+        from: tx01_out,
+        to: vec![5 * T, 5 * T],
+        fee: 20*uT,
         lock: 0,
         features: OutputFeatures::default()
     ));
-    let (tx34, _, _) = spend_utxos(txn_schema!(
-        from: tx24_out,
-        to: vec![2 * T, 2 * T],
-        fee: 160*uT,
+    let (tx21, _, _) = spend_utxos(txn_schema!(
+        from: tx11_out,
+        to: vec![3 * T, 3 * T],
+        fee: 30*uT,
         lock: 0,
         features: OutputFeatures::default()
     ));
+    let tx11 = Arc::new(tx11);
+    let tx21 = Arc::new(tx21);
     assert_eq!(
-        mempool.insert(Arc::new(tx31.clone())).unwrap(),
-        TxStorageResponse::UnconfirmedPool
-    );
-    assert_eq!(
-        mempool.insert(Arc::new(tx32.clone())).unwrap(),
+        mempool.insert(tx21.clone()).unwrap(),
         TxStorageResponse::NotStoredOrphan
     );
     assert_eq!(
-        mempool.insert(Arc::new(tx33.clone())).unwrap(),
+        mempool.insert(tx11.clone()).unwrap(),
         TxStorageResponse::NotStoredOrphan
     );
-    assert_eq!(
-        mempool.insert(Arc::new(tx34.clone())).unwrap(),
-        TxStorageResponse::UnconfirmedPool
-    );
+    assert_eq!(mempool.orphan_count().unwrap(), 2);
 
-    // Try to retrieve all transactions in the mempool (a couple of our transactions should be missing from retrieved)
-    let retrieved_txs = mempool.retrieve(mempool.stats().unwrap().total_weight).unwrap();
-    assert_eq!(retrieved_txs.len(), 10);
-    assert!(retrieved_txs.contains(&Arc::new(tx01.clone())));
-    assert!(!retrieved_txs.contains(&Arc::new(tx02.clone()))); // Missing
-    assert!(retrieved_txs.contains(&Arc::new(tx03.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx04.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx11.clone())));
-    assert!(!retrieved_txs.contains(&Arc::new(tx12.clone()))); // Missing
-    assert!(retrieved_txs.contains(&Arc::new(tx13.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx14.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx21.clone())));
-    assert!(!retrieved_txs.contains(&Arc::new(tx22.clone()))); // Missing
-    assert!(!retrieved_txs.contains(&Arc::new(tx23.clone()))); // Missing
-    assert!(retrieved_txs.contains(&Arc::new(tx24.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx31.clone())));
-    assert!(!retrieved_txs.contains(&Arc::new(tx32.clone()))); // Missing
-    assert!(!retrieved_txs.contains(&Arc::new(tx33.clone()))); // Missing
-    assert!(retrieved_txs.contains(&Arc::new(tx34.clone())));
+    // Restart, the orphans are reloaded
+    drop(mempool);
+    let mempool = create_mempool();
+    assert_eq!(mempool.orphan_count().unwrap(), 2);
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 0);
 
-    // Submit the missing original transactions
-    assert_eq!(
-        mempool.insert(Arc::new(tx02.clone())).unwrap(),
-        TxStorageResponse::UnconfirmedPool
-    );
-    // Re-submit failed zero-conf level 1 transactions
-    assert_eq!(
-        mempool.insert(Arc::new(tx12.clone())).unwrap(),
-        TxStorageResponse::UnconfirmedPool
-    );
-    // Re-submit failed zero-conf level 2 transactions
-    assert_eq!(
-        mempool.insert(Arc::new(tx22.clone())).unwrap(),
-        TxStorageResponse::UnconfirmedPool
-    );
-    assert_eq!(
-        mempool.insert(Arc::new(tx23.clone())).unwrap(),
-        TxStorageResponse::UnconfirmedPool
-    );
-    // Re-submit failed zero-conf level 3 transactions
-    assert_eq!(
-        mempool.insert(Arc::new(tx32.clone())).unwrap(),
-        TxStorageResponse::UnconfirmedPool
-    );
+    // The reloaded orphans are promoted once their parent arrives
     assert_eq!(
-        mempool.insert(Arc::new(tx33.clone())).unwrap(),
+        mempool.insert(Arc::new(tx01)).unwrap(),
         TxStorageResponse::UnconfirmedPool
     );
+    assert_eq!(mempool.orphan_count().unwrap(), 0);
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 3);
+    let snapshot_txs = mempool.snapshot().unwrap();
+    assert!(snapshot_txs.contains(&tx11));
+    assert!(snapshot_txs.contains(&tx21));
 
-    // Try to retrieve all transactions in the mempool (all transactions should be retrieved)
-    let retrieved_txs = mempool.retrieve(mempool.stats().unwrap().total_weight).unwrap();
-    assert_eq!(retrieved_txs.len(), 16);
-    assert!(retrieved_txs.contains(&Arc::new(tx01.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx02.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx03.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx04.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx11.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx12.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx13.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx14.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx21.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx22.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx23.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx24.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx31.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx32.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx33.clone())));
-    assert!(retrieved_txs.contains(&Arc::new(tx34.clone())));
+    // Promoted orphans are removed from the store
+    drop(mempool);
+    assert_eq!(create_mempool().orphan_count().unwrap(), 0);
+}
 
-    // Verify that a higher priority transaction is not retrieved due to its zero-conf dependency instead of the lowest
-    // priority transaction
-    let retrieved_txs = mempool.retrieve(mempool.stats().unwrap().total_weight - 1).unwrap();
-    assert_eq!(retrieved_txs.len(), 15);
-    assert!(retrieved_txs.contains(&Arc::new(tx01)));
-    assert!(retrieved_txs.contains(&Arc::new(tx02)));
-    assert!(retrieved_txs.contains(&Arc::new(tx03)));
-    assert!(retrieved_txs.contains(&Arc::new(tx04)));
-    assert!(retrieved_txs.contains(&Arc::new(tx11)));
-    assert!(retrieved_txs.contains(&Arc::new(tx12)));
-    assert!(retrieved_txs.contains(&Arc::new(tx13)));
-    assert!(retrieved_txs.contains(&Arc::new(tx14)));
-    assert!(retrieved_txs.contains(&Arc::new(tx21)));
-    assert!(retrieved_txs.contains(&Arc::new(tx22)));
-    assert!(retrieved_txs.contains(&Arc::new(tx23)));
-    assert!(retrieved_txs.contains(&Arc::new(tx24)));
-    assert!(!retrieved_txs.contains(&Arc::new(tx31))); // Missing
-    assert!(retrieved_txs.contains(&Arc::new(tx32)));
-    assert!(retrieved_txs.contains(&Arc::new(tx33)));
-    assert!(retrieved_txs.contains(&Arc::new(tx34)));
+#[derive(Default)]
+struct RecordingOrphanStore {
+    updates: Mutex<Vec<(usize, usize)>>,
+}
+
+impl OrphanStore for RecordingOrphanStore {
+    fn fetch_orphans(&self) -> Result<Vec<Transaction>, MempoolError> {
+        Ok(Vec::new())
+    }
+
+    fn update_orphans(&self, inserted: Vec<Transaction>, removed: Vec<Signature>) -> Result<(), MempoolError> {
+        self.updates.lock().unwrap().push((inserted.len(), removed.len()));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_orphans_persisted_incrementally() {
+    let network = Network::LocalNet;
+    let (store, _, _, _) = create_new_blockchain(network);
+    let config = MempoolConfig {
+        hold_orphans: true,
+        persist_orphans: true,
+        orphan_pool_capacity: 2,
+        ..Default::default()
+    };
+    let orphan_store = Arc::new(RecordingOrphanStore::default());
+    let mempool = MempoolBuilder::new(config, Arc::new(TxInputAndMaturityValidator::new(store.clone())))
+        .with_reorg_marker_store(Arc::new(store))
+        .with_orphan_store(orphan_store.clone())
+        .build();
+    for _ in 0..3 {
+        let (orphan, _, _) = tx!(1*T, fee: 100*uT);
+        assert_eq!(
+            mempool.insert(Arc::new(orphan)).unwrap(),
+            TxStorageResponse::NotStoredOrphan
+        );
+    }
+    assert_eq!(mempool.orphan_count().unwrap(), 2);
+    // Only the newly held orphan is written, and the orphan it displaced is deleted
+    assert_eq!(*orphan_store.updates.lock().unwrap(), vec![(1, 0), (1, 0), (1, 1)]);
 }
 
 #[tokio::test]
@@ -713,6 +2145,183 @@ async fn test_reorg() {
     mempool.process_reorg(vec![], vec![reorg_block4.into()]).unwrap();
 }
 
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_evict_by_missing_inputs() {
+    let network = Network::LocalNet;
+    let (mut db, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(db.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+
+    // "Mine" blocks 1 and 2
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![10 * T, 10 * T])];
+    generate_new_block(&mut db, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+    let txs = vec![txn_schema!(from: vec![outputs[1][0].clone()], to: vec![5 * T, 4 * T])];
+    generate_new_block(&mut db, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+
+    // tx_a spends an output of block 2, tx_b spends an output of tx_a and tx_c spends an output of block 1
+    let (tx_a, tx_a_out, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[2][0].clone()],
+        to: vec![2 * T, 2 * T],
+        fee: 20*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx_b, _, _) = spend_utxos(txn_schema!(
+        from: vec![tx_a_out[0].clone()],
+        to: vec![1 * T],
+        fee: 20*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    let (tx_c, _, _) = spend_utxos(txn_schema!(
+        from: vec![outputs[1][1].clone()],
+        to: vec![5 * T],
+        fee: 20*uT,
+        lock: 0,
+        features: OutputFeatures::default()
+    ));
+    for tx in &[&tx_a, &tx_b, &tx_c] {
+        assert_eq!(
+            mempool.insert(Arc::new((*tx).clone())).unwrap(),
+            TxStorageResponse::UnconfirmedPool
+        );
+    }
+    assert_eq!(mempool.evict_by_missing_inputs().unwrap(), 0);
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 3);
+
+    // The outputs of block 2 no longer exist after the rewind, tx_a and its descendant tx_b are evicted
+    db.rewind_to_height(1).unwrap();
+    assert_eq!(mempool.evict_by_missing_inputs().unwrap(), 2);
+    let unconfirmed_txs = mempool.snapshot().unwrap();
+    assert_eq!(unconfirmed_txs.len(), 1);
+    assert_eq!(*unconfirmed_txs[0], tx_c);
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_reorg_is_not_applied_twice_after_restart() {
+    let network = Network::LocalNet;
+    let (mut db, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let reorg_marker_store = Arc::new(db.clone());
+    let mempool = MempoolBuilder::new(MempoolConfig::default(), Arc::new(MockValidator::new(true)))
+        .with_reorg_marker_store(reorg_marker_store.clone())
+        .build();
+
+    // "Mine" Block 1
+    let txs = vec![
+        txn_schema!(from: vec![outputs[0][0].clone()], to: vec![1 * T, 1 * T], fee: 25*uT, lock: 0, features: OutputFeatures::default()),
+    ];
+    generate_new_block(&mut db, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    // "Mine" block 2 and deliver it as a reorg
+    let schemas = vec![
+        txn_schema!(from: vec![outputs[1][0].clone()], to: vec![], fee: 25*uT, lock: 0, features: OutputFeatures::default()),
+        txn_schema!(from: vec![outputs[1][1].clone()], to: vec![], fee: 25*uT, lock: 0, features: OutputFeatures::default()),
+    ];
+    let (txns2, _) = schema_to_transaction(&schemas);
+    txns2.iter().for_each(|tx| {
+        mempool.insert(tx.clone()).unwrap();
+    });
+    generate_block(
+        &db,
+        &mut blocks,
+        txns2.iter().map(|t| t.deref().clone()).collect(),
+        &consensus_manager,
+    )
+    .unwrap();
+    mempool.process_reorg(vec![], vec![blocks[2].to_arc_block()]).unwrap();
+    let stats = mempool.stats().unwrap();
+    assert_eq!(stats.unconfirmed_txs, 0);
+    assert_eq!(stats.reorg_txs, 2);
+
+    // Simulate a restart: a new mempool sharing the persisted marker receives the same transactions and reorg again
+    let mempool = MempoolBuilder::new(MempoolConfig::default(), Arc::new(MockValidator::new(true)))
+        .with_reorg_marker_store(reorg_marker_store)
+        .build();
+    txns2.iter().for_each(|tx| {
+        mempool.insert(tx.clone()).unwrap();
+    });
+    mempool.process_reorg(vec![], vec![blocks[2].to_arc_block()]).unwrap();
+    let stats = mempool.stats().unwrap();
+    assert_eq!(stats.unconfirmed_txs, 2);
+    assert_eq!(stats.reorg_txs, 0);
+}
+
+#[tokio::test]
+async fn test_out_of_order_block_is_rejected() {
+    let network = Network::LocalNet;
+    let (store, mut blocks, _, consensus_manager) = create_new_blockchain(network);
+    let mempool_validator = TxInputAndMaturityValidator::new(store.clone());
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(mempool_validator));
+    for _ in 0..3 {
+        generate_block(&store, &mut blocks, vec![], &consensus_manager).unwrap();
+    }
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    // Block 3 arrives before block 2
+    let err = mempool.process_published_block(blocks[3].to_arc_block()).unwrap_err();
+    match err {
+        MempoolError::UnexpectedBlock {
+            expected_prev,
+            got_prev,
+        } => {
+            assert_eq!(expected_prev, *blocks[1].hash());
+            assert_eq!(got_prev, *blocks[2].hash());
+        },
+        err => panic!("Unexpected error: {}", err),
+    }
+
+    // The rejected block is not recorded as the last processed block
+    mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+    mempool.process_published_block(blocks[3].to_arc_block()).unwrap();
+}
+
+#[tokio::test]
+#[allow(clippy::identity_op)]
+async fn test_known_blocks_do_not_revalidate_pool() {
+    let network = Network::LocalNet;
+    let (mut db, mut blocks, mut outputs, consensus_manager) = create_new_blockchain(network);
+    let validator = MockValidator::new(true);
+    let is_valid = validator.shared_flag();
+    let mempool = Mempool::new(MempoolConfig::default(), Arc::new(validator));
+    let txs = vec![txn_schema!(from: vec![outputs[0][0].clone()], to: vec![1 * T, 1 * T])];
+    generate_new_block(&mut db, &mut blocks, &mut outputs, txs, &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[1].to_arc_block()).unwrap();
+
+    let (tx1, _, _) = spend_utxos(txn_schema!(from: vec![outputs[1][0].clone()], to: vec![], fee: 25*uT));
+    let (tx2, _, _) = spend_utxos(txn_schema!(from: vec![outputs[1][1].clone()], to: vec![], fee: 25*uT));
+    for tx in vec![tx1.clone(), tx2] {
+        assert_eq!(
+            mempool.insert(Arc::new(tx)).unwrap(),
+            TxStorageResponse::UnconfirmedPool
+        );
+    }
+    generate_block(&db, &mut blocks, vec![tx1], &consensus_manager).unwrap();
+    mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 1);
+
+    // Revalidating the pool would now drop the remaining transaction
+    is_valid.set(false);
+
+    // A duplicate block is skipped
+    let summary = mempool.process_published_block(blocks[2].to_arc_block()).unwrap();
+    assert_eq!(summary, BlockProcessSummary::default());
+    let stats = mempool.stats().unwrap();
+    assert_eq!(stats.unconfirmed_txs, 1);
+    assert_eq!(stats.reorg_txs, 1);
+
+    // A block that builds on an earlier processed block is processed without draining the pool
+    db.rewind_to_height(1).unwrap();
+    let template = chain_block(blocks[1].block(), vec![], &consensus_manager);
+    let fork_block2 = db.prepare_new_block(template).unwrap();
+    mempool.process_published_block(Arc::new(fork_block2)).unwrap();
+    assert_eq!(mempool.stats().unwrap().unconfirmed_txs, 1);
+}
+
 // TODO: This test returns 0 in the unconfirmed pool, so might not catch errors. It should be updated to return better
 // data
 #[allow(clippy::identity_op)]
@@ -1040,6 +2649,40 @@ async fn consensus_validation_large_tx() {
     assert!(matches!(response, TxStorageResponse::NotStored));
 }
 
+#[tokio::test]
+async fn request_response_with_unresponsive_peer() {
+    let network = Network::LocalNet;
+    let consensus_manager = ConsensusManager::builder(network).build();
+    let mempool_service_config = MempoolServiceConfig {
+        outbound_request_num_peers: 2,
+        ..Default::default()
+    };
+    let temp_dir = tempdir().unwrap();
+    let (mut alice_node, bob_node, carol_node, _consensus_manager) = create_network_with_3_base_nodes_with_config(
+        BaseNodeServiceConfig::default(),
+        mempool_service_config,
+        LivenessConfig::default(),
+        consensus_manager,
+        temp_dir.path().to_str().unwrap(),
+    )
+    .await;
+
+    // Carol is unresponsive, but Bob can still answer
+    carol_node.shutdown().await;
+
+    let (tx, _, _) = tx!(1*T, fee: 100*uT);
+    let excess_sig = tx.body.kernels()[0].excess_sig.clone();
+    let response = alice_node
+        .outbound_mp_interface
+        .get_tx_state_by_excess_sig(excess_sig)
+        .await
+        .unwrap();
+    assert_eq!(response, TxStorageResponse::NotStored);
+
+    let stats = alice_node.outbound_mp_interface.get_stats().await.unwrap();
+    assert_eq!(stats, bob_node.mempool.stats().unwrap());
+}
+
 #[tokio::test]
 async fn service_request_timeout() {
     let network = Network::LocalNet;
@@ -47,6 +47,7 @@ pub mod transport;
 mod dns;
 
 // Re-export
+pub use dns::DnsClientError;
 pub use tari_common::configuration::Network;
 
 /// Default DNS resolver set to cloudflare's private 1.1.1.1 resolver
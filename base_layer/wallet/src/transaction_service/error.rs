@@ -30,7 +30,11 @@ use futures::channel::oneshot::Canceled;
 use serde_json::Error as SerdeJsonError;
 use tari_comms::{peer_manager::node_id::NodeIdError, protocol::rpc::RpcError};
 use tari_comms_dht::outbound::DhtOutboundError;
-use tari_core::transactions::{transaction::TransactionError, transaction_protocol::TransactionProtocolError};
+use tari_core::transactions::{
+    tari_amount::MicroTari,
+    transaction::TransactionError,
+    transaction_protocol::TransactionProtocolError,
+};
 use tari_p2p::services::liveness::error::LivenessError;
 use tari_service_framework::reply_channel::TransportChannelError;
 use thiserror::Error;
@@ -131,6 +135,8 @@ pub enum TransactionServiceError {
     MempoolRejectionDoubleSpend,
     #[error("Transaction detected as rejected by mempool due to invalid transaction")]
     MempoolRejectionInvalidTransaction,
+    #[error("Transaction detected as rejected by mempool due to a low fee (minimum fee per gram: {0})")]
+    MempoolRejectionFeeTooLow(MicroTari),
     #[error("Transaction is malformed")]
     InvalidTransaction,
     #[error("RpcError: `{0}`")]
@@ -397,6 +397,9 @@ where TBackend: TransactionBackend + 'static
                 TxSubmissionRejectionReason::DoubleSpend => TransactionServiceError::MempoolRejectionDoubleSpend,
                 TxSubmissionRejectionReason::Orphan => TransactionServiceError::MempoolRejectionOrphan,
                 TxSubmissionRejectionReason::TimeLocked => TransactionServiceError::MempoolRejectionTimeLocked,
+                TxSubmissionRejectionReason::FeeTooLow(min_fee_per_gram) => {
+                    TransactionServiceError::MempoolRejectionFeeTooLow(min_fee_per_gram)
+                },
                 _ => TransactionServiceError::UnexpectedBaseNodeResponse,
             };
             return Err(TransactionServiceProtocolError::new(self.tx_id, reason));
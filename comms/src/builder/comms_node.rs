@@ -140,11 +140,15 @@ impl UnspawnedCommsNode {
         let CommsBuilder {
             dial_backoff,
             hidden_service_ctl,
-            connection_manager_config,
+            mut connection_manager_config,
             connectivity_config,
             ..
         } = builder;
 
+        // The dialer skips the addresses that the connectivity manager does not permit
+        connection_manager_config.address_denylist = connectivity_config.address_denylist.clone();
+        connection_manager_config.address_allowlist = connectivity_config.address_allowlist.clone();
+
         //---------------------------------- Connectivity Manager --------------------------------------------//
         let connectivity_manager = ConnectivityManager {
             config: connectivity_config,
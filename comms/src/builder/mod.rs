@@ -41,10 +41,12 @@ mod placeholder;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "rpc")]
+use crate::protocol::rpc::RpcSessionLimit;
 use crate::{
     backoff::{Backoff, BoxedBackoff, ExponentialBackoff},
     connection_manager::{ConnectionManagerConfig, ConnectionManagerRequester},
-    connectivity::{ConnectivityConfig, ConnectivityRequester},
+    connectivity::{ConnectivityConfig, ConnectivityRequester, MinConnectivity},
     multiaddr::Multiaddr,
     peer_manager::{NodeIdentity, PeerManager},
     protocol::{NodeNetworkInfo, ProtocolExtensions},
@@ -172,9 +174,25 @@ impl CommsBuilder {
         self
     }
 
-    /// Sets the minimum required connectivity as a percentage of peers added to the connectivity manager peer set.
-    pub fn with_min_connectivity(mut self, min_connectivity: usize) -> Self {
-        self.connectivity_config.min_connectivity = min_connectivity;
+    /// The maximum number of RPC client sessions that this node may hold at the same time over all of its peer
+    /// connections. Establishing a session fails with `RpcError::SessionLimitReached` once the limit is reached.
+    #[cfg(feature = "rpc")]
+    pub fn with_max_rpc_client_sessions(mut self, max_sessions: usize) -> Self {
+        self.connection_manager_config.rpc_client_session_limit = Some(RpcSessionLimit::new(max_sessions));
+        self
+    }
+
+    /// Removes the limit on the number of RPC client sessions that this node may hold at the same time
+    #[cfg(feature = "rpc")]
+    pub fn with_unlimited_rpc_client_sessions(mut self) -> Self {
+        self.connection_manager_config.rpc_client_session_limit = None;
+        self
+    }
+
+    /// Sets the minimum required connectivity, either as an absolute number of connected nodes or as a fraction of the
+    /// peers in the connectivity manager's connection pool.
+    pub fn with_min_connectivity<T: Into<MinConnectivity>>(mut self, min_connectivity: T) -> Self {
+        self.connectivity_config.min_connectivity = min_connectivity.into();
         self
     }
 
@@ -20,7 +20,11 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::{error::ConnectionManagerError, peer_connection::PeerConnection, types::ConnectionDirection};
+use super::{
+    error::{AddressDialFailure, ConnectionManagerError, DialFailure},
+    peer_connection::{PeerConnection, PeerConnectionOptions},
+    types::ConnectionDirection,
+};
 use crate::{
     backoff::Backoff,
     connection_manager::{
@@ -46,7 +50,7 @@ use futures::{
     FutureExt,
 };
 use log::*;
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{collections::HashMap, mem, sync::Arc, time::Duration};
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use tokio::{
     io::{AsyncRead, AsyncWrite, AsyncWriteExt},
@@ -65,9 +69,12 @@ type DialFuturesUnordered =
 
 #[derive(Debug)]
 pub(crate) enum DialerRequest {
+    /// Dial a peer. The correlation id of the dial request is recorded in the dial span and carried by the resulting
+    /// connection.
     Dial(
         Box<Peer>,
         Option<oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>>,
+        Option<u64>,
     ),
     CancelPendingDial(NodeId),
 }
@@ -161,8 +168,8 @@ where
         use DialerRequest::*;
         trace!(target: LOG_TARGET, "Connection dialer got request: {:?}", request);
         match request {
-            Dial(peer, reply_tx) => {
-                self.handle_dial_peer_request(pending_dials, peer, reply_tx);
+            Dial(peer, reply_tx, correlation_id) => {
+                self.handle_dial_peer_request(pending_dials, peer, reply_tx, correlation_id);
             },
             CancelPendingDial(peer_id) => {
                 if let Some(mut s) = self.cancel_signals.remove(&peer_id) {
@@ -259,6 +266,7 @@ where
         pending_dials: &mut DialFuturesUnordered,
         peer: Box<Peer>,
         reply_tx: Option<oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>>,
+        correlation_id: Option<u64>,
     ) {
         if self.is_pending_dial(&peer.node_id) {
             if let Some(reply_tx) = reply_tx {
@@ -283,7 +291,14 @@ where
         let noise_config = self.noise_config.clone();
         let config = self.config.clone();
 
-        let span = span!(Level::TRACE, "handle_dial_peer_request_inner1");
+        let span = span!(
+            Level::TRACE,
+            "handle_dial_peer_request_inner1",
+            correlation_id = tracing::field::Empty
+        );
+        if let Some(correlation_id) = correlation_id {
+            span.record("correlation_id", &correlation_id);
+        }
         let dial_fut = async move {
             let (dial_state, dial_result) =
                 Self::dial_peer_with_retry(dial_state, noise_config, transport, backoff, &config).await;
@@ -310,6 +325,7 @@ where
                         supported_protocols,
                         &config,
                         cancel_signal,
+                        correlation_id,
                     )
                     .await;
 
@@ -350,6 +366,7 @@ where
         our_supported_protocols: Vec<ProtocolId>,
         config: &ConnectionManagerConfig,
         cancel_signal: ShutdownSignal,
+        correlation_id: Option<u64>,
     ) -> Result<PeerConnection, ConnectionManagerError> {
         static CONNECTION_DIRECTION: ConnectionDirection = ConnectionDirection::Outbound;
         let mut muxer = Yamux::upgrade_connection(socket, CONNECTION_DIRECTION)
@@ -420,6 +437,14 @@ where
             conn_man_notifier,
             our_supported_protocols,
             their_supported_protocols,
+            PeerConnectionOptions {
+                substream_write_timeout: config.substream_write_timeout,
+                correlation_id,
+                keepalive: config.keepalive,
+                #[cfg(feature = "rpc")]
+                rpc_session_limit: config.rpc_client_session_limit.clone(),
+                ..Default::default()
+            },
         )
     }
 
@@ -452,16 +477,23 @@ where
             tokio::select! {
                 _ = delay => {
                     debug!(target: LOG_TARGET, "[Attempt {}] Connecting to peer '{}'", current_state.num_attempts(), current_state.peer().node_id.short_str());
-                    match Self::dial_peer(current_state, &noise_config, &current_transport, config.network_info.network_byte).await {
+                    match Self::dial_peer(current_state, &noise_config, &current_transport, config).await {
                         (state, Ok((socket, addr))) => {
                             debug!(target: LOG_TARGET, "Dial succeeded for peer '{}' after {} attempt(s)", state.peer().node_id.short_str(), state.num_attempts());
                             break (state, Ok((socket, addr)));
                         },
                         // Inflight dial was cancelled
                         (state, Err(ConnectionManagerError::DialCancelled)) => break (state, Err(ConnectionManagerError::DialCancelled)),
-                        (state, Err(_err)) => {
+                        // Retrying will not change which addresses are permitted
+                        (state, Err(ConnectionManagerError::NoPermittedAddresses)) => break (state, Err(ConnectionManagerError::NoPermittedAddresses)),
+                        (state, Err(err)) => {
                             if state.num_attempts() >= config.max_dial_attempts {
-                                break (state, Err(ConnectionManagerError::ConnectFailedMaximumAttemptsReached));
+                                // Report the per-address failures of the last attempt
+                                let err = match err {
+                                    ConnectionManagerError::DialFailed(failure) => ConnectionManagerError::ConnectFailedMaximumAttemptsReached(failure),
+                                    err => err,
+                                };
+                                break (state, Err(err));
                             }
 
                             // Put the dial state and transport back for the retry
@@ -479,20 +511,36 @@ where
         }
     }
 
-    /// Attempts to dial a peer sequentially on all addresses.
+    /// Attempts to dial a peer sequentially on all permitted addresses.
     /// Returns ownership of the given `DialState` and a success or failure result for the dial,
     /// or None if the dial was cancelled inflight
     async fn dial_peer(
         dial_state: DialState,
         noise_config: &NoiseConfig,
         transport: &TTransport,
-        network_byte: u8,
+        config: &ConnectionManagerConfig,
     ) -> (
         DialState,
         Result<(NoiseSocket<TTransport::Output>, Multiaddr), ConnectionManagerError>,
     ) {
-        let mut addr_iter = dial_state.peer().addresses.iter();
+        let network_byte = config.network_info.network_byte;
+        let mut addr_iter = dial_state
+            .peer()
+            .addresses
+            .iter()
+            .filter(|address| config.is_address_permitted(address))
+            .peekable();
+        if addr_iter.peek().is_none() && !dial_state.peer().addresses.is_empty() {
+            debug!(
+                target: LOG_TARGET,
+                "None of the addresses for peer '{}' are permitted",
+                dial_state.peer().node_id.short_str()
+            );
+            drop(addr_iter);
+            return (dial_state, Err(ConnectionManagerError::NoPermittedAddresses));
+        }
         let cancel_signal = dial_state.get_cancel_signal();
+        let mut failures = Vec::new();
         loop {
             let result = match addr_iter.next() {
                 Some(address) => {
@@ -540,6 +588,10 @@ where
                                 dial_state.peer().node_id.short_str(),
                                 err,
                             );
+                            failures.push(AddressDialFailure {
+                                address: address.clone(),
+                                error: err,
+                            });
                             // Try the next address
                             continue;
                         },
@@ -555,7 +607,10 @@ where
                     }
                 },
                 // No more addresses to try - returning failure
-                None => Err(ConnectionManagerError::DialConnectFailedAllAddresses),
+                None if failures.is_empty() => Err(ConnectionManagerError::DialFailed(DialFailure::NoAddresses)),
+                None => Err(ConnectionManagerError::DialFailed(DialFailure::AllAddressesFailed(
+                    mem::take(&mut failures),
+                ))),
             };
 
             drop(addr_iter);
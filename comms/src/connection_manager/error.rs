@@ -22,10 +22,12 @@
 
 use crate::{
     connection_manager::PeerConnectionRequest,
+    multiaddr::Multiaddr,
     noise,
     peer_manager::PeerManagerError,
     protocol::{IdentityProtocolError, ProtocolError},
 };
+use std::fmt;
 use thiserror::Error;
 use tokio::{sync::mpsc, time::error::Elapsed};
 
@@ -43,10 +45,10 @@ pub enum ConnectionManagerError {
     ActorRequestCanceled,
     #[error("The dial reply channel was closed when sending a reply")]
     DialReplyChannelClosed,
-    #[error("Failed to connect on all addresses for peer")]
-    DialConnectFailedAllAddresses,
-    #[error("Failed to connect to peer within the maximum number of attempts")]
-    ConnectFailedMaximumAttemptsReached,
+    #[error("Failed to connect to peer: {0}")]
+    DialFailed(DialFailure),
+    #[error("Failed to connect to peer within the maximum number of attempts: {0}")]
+    ConnectFailedMaximumAttemptsReached(DialFailure),
     #[error("Yamux connection error: {0}")]
     YamuxConnectionError(String),
     #[error("Failed to perform yamux upgrade on socket: {0}")]
@@ -55,6 +57,8 @@ pub enum ConnectionManagerError {
     EstablisherChannelError,
     #[error("Transport error: {0}")]
     TransportError(String),
+    #[error("Failed to bind the listener: {0}")]
+    ListenerBindFailed(String),
     #[error("The peer authenticated to a public key which did not match the dialed peer's public key")]
     DialedPublicKeyMismatch,
     #[error("The noise transport failed to provide a valid static public key for the peer")]
@@ -81,6 +85,45 @@ pub enum ConnectionManagerError {
     NoiseProtocolTimeout,
     #[error("Listener oneshot cancelled")]
     ListenerOneshotCancelled,
+    #[error("None of the peer's addresses are permitted by the connectivity address allow and deny lists")]
+    NoPermittedAddresses,
+}
+
+/// The reason a dial to a peer failed, broken down by the addresses that were attempted
+#[derive(Debug, Clone)]
+pub enum DialFailure {
+    /// The peer has no addresses to dial
+    NoAddresses,
+    /// Every address of the peer was attempted and failed
+    AllAddressesFailed(Vec<AddressDialFailure>),
+}
+
+impl fmt::Display for DialFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DialFailure::NoAddresses => write!(f, "peer has no addresses"),
+            DialFailure::AllAddressesFailed(failures) => {
+                write!(f, "tried {} address(es)", failures.len())?;
+                for failure in failures {
+                    write!(f, ", {}", failure)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+/// A failed dial attempt on a single address
+#[derive(Debug, Clone)]
+pub struct AddressDialFailure {
+    pub address: Multiaddr,
+    pub error: ConnectionManagerError,
+}
+
+impl fmt::Display for AddressDialFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}' failed because '{}'", self.address, self.error)
+    }
 }
 
 impl From<yamux::ConnectionError> for ConnectionManagerError {
@@ -113,6 +156,10 @@ pub enum PeerConnectionError {
     ProtocolError(#[from] ProtocolError),
     #[error("Protocol negotiation timeout")]
     ProtocolNegotiationTimeout,
+    #[error("Timed out writing to substream")]
+    WriteTimeout,
+    #[error("The substream was closed locally")]
+    SubstreamClosed,
 }
 
 impl From<Elapsed> for PeerConnectionError {
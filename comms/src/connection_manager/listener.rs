@@ -23,7 +23,7 @@
 use super::{
     common,
     error::ConnectionManagerError,
-    peer_connection::{self, PeerConnection},
+    peer_connection::{self, PeerConnection, PeerConnectionOptions},
     types::ConnectionDirection,
     ConnectionManagerConfig,
     ConnectionManagerEvent,
@@ -445,6 +445,13 @@ where
             conn_man_notifier,
             our_supported_protocols,
             their_supported_protocols,
+            PeerConnectionOptions {
+                substream_write_timeout: config.substream_write_timeout,
+                keepalive: config.keepalive,
+                #[cfg(feature = "rpc")]
+                rpc_session_limit: config.rpc_client_session_limit.clone(),
+                ..Default::default()
+            },
         )
     }
 
@@ -454,6 +461,6 @@ where
         self.transport
             .listen(bind_address)
             .await
-            .map_err(|err| ConnectionManagerError::TransportError(err.to_string()))
+            .map_err(|err| ConnectionManagerError::ListenerBindFailed(err.to_string()))
     }
 }
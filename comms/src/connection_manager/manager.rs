@@ -24,11 +24,14 @@ use super::{
     dialer::{Dialer, DialerRequest},
     error::ConnectionManagerError,
     listener::PeerListener,
-    peer_connection::PeerConnection,
+    peer_connection::{KeepaliveConfig, PeerConnection},
     requester::ConnectionManagerRequest,
 };
+#[cfg(feature = "rpc")]
+use crate::protocol::rpc::RpcSessionLimit;
 use crate::{
     backoff::Backoff,
+    connectivity::{self, AddressPattern},
     multiplexing::Substream,
     noise::NoiseConfig,
     peer_manager::{NodeId, NodeIdentity},
@@ -59,6 +62,9 @@ pub enum ConnectionManagerEvent {
     // Peer connection
     PeerConnected(PeerConnection),
     PeerDisconnected(NodeId),
+    /// The peer closed the connection. This is published instead of `PeerDisconnected` when the close was not
+    /// requested by this node.
+    PeerDisconnectedByPeer(NodeId),
     PeerConnectFailed(NodeId, ConnectionManagerError),
     PeerInboundConnectFailed(ConnectionManagerError),
 
@@ -72,6 +78,7 @@ impl fmt::Display for ConnectionManagerEvent {
         match self {
             PeerConnected(conn) => write!(f, "PeerConnected({})", conn),
             PeerDisconnected(node_id) => write!(f, "PeerDisconnected({})", node_id.short_str()),
+            PeerDisconnectedByPeer(node_id) => write!(f, "PeerDisconnectedByPeer({})", node_id.short_str()),
             PeerConnectFailed(node_id, err) => write!(f, "PeerConnectFailed({}, {:?})", node_id.short_str(), err),
             PeerInboundConnectFailed(err) => write!(f, "PeerInboundConnectFailed({:?})", err),
             NewInboundSubstream(node_id, protocol, _) => write!(
@@ -108,6 +115,30 @@ pub struct ConnectionManagerConfig {
     /// If set, an additional TCP-only p2p listener will be started. This is useful for local wallet connections.
     /// Default: None (disabled)
     pub auxilary_tcp_listener_address: Option<Multiaddr>,
+    /// The maximum time that a write to a negotiated substream may stall before failing with
+    /// `PeerConnectionError::WriteTimeout`. Default: None (no timeout)
+    pub substream_write_timeout: Option<Duration>,
+    /// If set, keepalive pings are sent on idle connections and connections to peers that do not respond are closed.
+    /// Default: None (disabled)
+    pub keepalive: Option<KeepaliveConfig>,
+    /// Addresses matching any of these patterns are not dialed. This is set from
+    /// `ConnectivityConfig::address_denylist` when the comms node is spawned. Default: empty
+    pub address_denylist: Vec<AddressPattern>,
+    /// If not empty, only addresses matching one of these patterns are dialed. This is set from
+    /// `ConnectivityConfig::address_allowlist` when the comms node is spawned. Default: empty
+    pub address_allowlist: Vec<AddressPattern>,
+    /// Limits the number of RPC client sessions that may be live at the same time over all peer connections. Clones of
+    /// the config share the same limit. Sessions using an `RpcClientBuilder` that has its own limit are counted
+    /// against that limit instead. Default: 1000 sessions
+    #[cfg(feature = "rpc")]
+    pub rpc_client_session_limit: Option<RpcSessionLimit>,
+}
+
+impl ConnectionManagerConfig {
+    /// Returns true if the address may be dialed according to the address allow and deny lists
+    pub fn is_address_permitted(&self, address: &Multiaddr) -> bool {
+        connectivity::is_address_permitted(&self.address_denylist, &self.address_allowlist, address)
+    }
 }
 
 impl Default for ConnectionManagerConfig {
@@ -131,6 +162,12 @@ impl Default for ConnectionManagerConfig {
             time_to_first_byte: Duration::from_secs(45),
             liveness_cidr_allowlist: vec![cidr::AnyIpCidr::V4("127.0.0.1/32".parse().unwrap())],
             auxilary_tcp_listener_address: None,
+            substream_write_timeout: None,
+            keepalive: None,
+            address_denylist: Vec::new(),
+            address_allowlist: Vec::new(),
+            #[cfg(feature = "rpc")]
+            rpc_client_session_limit: Some(RpcSessionLimit::new(1000)),
         }
     }
 }
@@ -353,12 +390,15 @@ where
             DialPeer {
                 node_id,
                 reply_tx,
-                tracing_id: _tracing,
+                tracing_id,
             } => {
                 let span = span!(Level::TRACE, "connection_manager::handle_request");
                 // This causes a panic for some reason?
                 // span.follows_from(tracing_id);
-                self.dial_peer(node_id, reply_tx).instrument(span).await
+                // The requesting span may have closed by the time the connection is established, so its id is carried
+                // as a correlation id rather than followed
+                let correlation_id = tracing_id.map(|id| id.into_u64());
+                self.dial_peer(node_id, reply_tx, correlation_id).instrument(span).await
             },
             CancelDial(node_id) => {
                 if let Err(err) = self.dialer_tx.send(DialerRequest::CancelPendingDial(node_id)).await {
@@ -446,10 +486,11 @@ where
         &mut self,
         node_id: NodeId,
         reply: Option<oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>>,
+        correlation_id: Option<u64>,
     ) {
         match self.peer_manager.find_by_node_id(&node_id).await {
             Ok(peer) => {
-                self.send_dialer_request(DialerRequest::Dial(Box::new(peer), reply))
+                self.send_dialer_request(DialerRequest::Dial(Box::new(peer), reply, correlation_id))
                     .await;
             },
             Err(err) => {
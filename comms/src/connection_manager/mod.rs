@@ -37,10 +37,10 @@ mod manager;
 pub use manager::{ConnectionManager, ConnectionManagerConfig, ConnectionManagerEvent, ListenerInfo};
 
 mod error;
-pub use error::{ConnectionManagerError, PeerConnectionError};
+pub use error::{AddressDialFailure, ConnectionManagerError, DialFailure, PeerConnectionError};
 
 mod peer_connection;
-pub use peer_connection::{ConnectionId, NegotiatedSubstream, PeerConnection, PeerConnectionRequest};
+pub use peer_connection::{ConnectionId, KeepaliveConfig, NegotiatedSubstream, PeerConnection, PeerConnectionRequest};
 
 mod liveness;
 mod wire_mode;
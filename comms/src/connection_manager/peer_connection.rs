@@ -28,6 +28,7 @@ use crate::protocol::rpc::{
     RpcClientPool,
     RpcError,
     RpcPoolClient,
+    RpcSessionLimit,
     RPC_MAX_FRAME_SIZE,
 };
 
@@ -39,33 +40,81 @@ use super::{
 use crate::{
     framing,
     framing::CanonicalFraming,
-    multiplexing::{Control, IncomingSubstreams, Substream, Yamux},
+    multiplexing::{ByteCounts, Control, IncomingSubstreams, Substream, Yamux},
     peer_manager::{NodeId, PeerFeatures},
-    protocol::{ProtocolId, ProtocolNegotiation},
+    protocol::{ProtocolError, ProtocolId, ProtocolNegotiation},
     runtime,
     utils::atomic_ref_counter::AtomicRefCounter,
 };
+use futures::{future, future::BoxFuture};
 use log::*;
 use multiaddr::Multiaddr;
 use std::{
+    collections::{HashMap, HashSet},
     fmt,
+    hash::{Hash, Hasher},
+    iter,
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc,
     },
     time::{Duration, Instant},
 };
+use tari_shutdown::Shutdown;
 use tokio::{
     sync::{mpsc, oneshot},
     time,
+    time::MissedTickBehavior,
 };
 use tokio_stream::StreamExt;
 use tracing::{self, span, Instrument, Level, Span};
 
 const LOG_TARGET: &str = "comms::connection_manager::peer_connection";
 
+/// A reserved protocol that is used to check that a peer is responsive. It is accepted on every connection and its
+/// substreams are closed immediately, so it is never passed on to a protocol handler.
+const LIVENESS_CHECK_PROTOCOL: &[u8] = b"t/liveness-check/1";
+const PROTOCOL_NEGOTIATION_TIMEOUT: Duration = Duration::from_secs(10);
+
 static ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
+/// Configuration for the keepalive pings that are sent on idle connections to keep NAT mappings alive and detect dead
+/// peers. A ping is a liveness check, see `PeerConnection::check_liveness`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    /// The time that a connection must be idle before a keepalive ping is sent. This is also the time that the peer
+    /// has to respond to the ping, up to the protocol negotiation timeout. Default: 30s
+    pub interval: Duration,
+    /// The number of consecutive keepalive pings that may go unanswered before the connection is considered dead and
+    /// is disconnected. Default: 3
+    pub max_missed: usize,
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+            max_missed: 3,
+        }
+    }
+}
+
+/// Optional settings for a peer connection, see `create`
+#[derive(Debug, Clone, Default)]
+pub struct PeerConnectionOptions {
+    /// The maximum time that a write to a framed substream opened by the connection may stall
+    pub substream_write_timeout: Option<Duration>,
+    /// The purpose for which the connection was established, e.g. sync, wallet or dht
+    pub purpose: Option<String>,
+    /// The correlation id of the dial that established the connection
+    pub correlation_id: Option<u64>,
+    /// Keepalive pings are sent on the connection when it is idle, if set
+    pub keepalive: Option<KeepaliveConfig>,
+    /// The limit that RPC client sessions opened on the connection are counted against, if set
+    #[cfg(feature = "rpc")]
+    pub rpc_session_limit: Option<RpcSessionLimit>,
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn create(
     connection: Yamux,
@@ -76,6 +125,7 @@ pub fn create(
     event_notifier: mpsc::Sender<ConnectionManagerEvent>,
     our_supported_protocols: Vec<ProtocolId>,
     their_supported_protocols: Vec<ProtocolId>,
+    options: PeerConnectionOptions,
 ) -> Result<PeerConnection, ConnectionManagerError> {
     trace!(
         target: LOG_TARGET,
@@ -94,7 +144,12 @@ pub fn create(
         peer_addr,
         direction,
         substream_counter,
-    );
+    )
+    .with_substream_write_timeout(options.substream_write_timeout)
+    .with_purpose(options.purpose)
+    .with_correlation_id(options.correlation_id);
+    #[cfg(feature = "rpc")]
+    let peer_conn = peer_conn.with_rpc_session_limit(options.rpc_session_limit);
     let peer_actor = PeerConnectionActor::new(
         id,
         peer_node_id,
@@ -104,6 +159,8 @@ pub fn create(
         event_notifier,
         our_supported_protocols,
         their_supported_protocols,
+        options.correlation_id,
+        options.keepalive,
     );
     runtime::current().spawn(peer_actor.run());
 
@@ -117,9 +174,21 @@ pub enum PeerConnectionRequest {
         protocol_id: ProtocolId,
         reply_tx: oneshot::Sender<Result<NegotiatedSubstream<Substream>, PeerConnectionError>>,
         tracing_id: Option<tracing::span::Id>,
+        /// Overrides the protocol negotiation timeout if it is less than the default timeout
+        negotiation_timeout: Option<Duration>,
     },
     /// Disconnect all substreams and close the transport connection
     Disconnect(bool, oneshot::Sender<Result<(), PeerConnectionError>>),
+    /// Get the protocols supported by both this node and the peer
+    GetProtocolsInCommon(oneshot::Sender<Vec<ProtocolId>>),
+    /// Get the protocols the peer advertised during connection establishment
+    GetTheirProtocols(oneshot::Sender<Vec<ProtocolId>>),
+    /// Get the number of bytes sent and received over all substreams of this connection
+    GetByteCounters(oneshot::Sender<ByteCounts>),
+    /// Get the protocols that have been successfully negotiated over this connection
+    GetNegotiatedProtocolsObserved(oneshot::Sender<Vec<ProtocolId>>),
+    /// Close the open substreams that negotiated the given protocol, leaving the connection open
+    CloseSubstreamsForProtocol(ProtocolId, oneshot::Sender<()>),
 }
 
 pub type ConnectionId = usize;
@@ -136,6 +205,11 @@ pub struct PeerConnection {
     started_at: Instant,
     substream_counter: AtomicRefCounter,
     handle_counter: Arc<()>,
+    substream_write_timeout: Option<Duration>,
+    purpose: Option<Arc<String>>,
+    correlation_id: Option<u64>,
+    #[cfg(feature = "rpc")]
+    rpc_session_limit: Option<RpcSessionLimit>,
 }
 
 impl PeerConnection {
@@ -158,9 +232,40 @@ impl PeerConnection {
             started_at: Instant::now(),
             substream_counter,
             handle_counter: Arc::new(()),
+            substream_write_timeout: None,
+            purpose: None,
+            correlation_id: None,
+            #[cfg(feature = "rpc")]
+            rpc_session_limit: None,
         }
     }
 
+    /// Sets the maximum time that a write to a framed substream opened by this connection may stall before failing
+    /// with `PeerConnectionError::WriteTimeout`.
+    pub(crate) fn with_substream_write_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.substream_write_timeout = timeout;
+        self
+    }
+
+    /// Labels the connection with the purpose for which it was established, e.g. sync, wallet or dht.
+    pub(crate) fn with_purpose(mut self, purpose: Option<String>) -> Self {
+        self.purpose = purpose.map(Arc::new);
+        self
+    }
+
+    /// Sets the correlation id of the dial that established the connection
+    pub(crate) fn with_correlation_id(mut self, correlation_id: Option<u64>) -> Self {
+        self.correlation_id = correlation_id;
+        self
+    }
+
+    /// Sets the limit that RPC client sessions are counted against when the `RpcClientBuilder` has no limit of its own
+    #[cfg(feature = "rpc")]
+    pub(crate) fn with_rpc_session_limit(mut self, session_limit: Option<RpcSessionLimit>) -> Self {
+        self.rpc_session_limit = session_limit;
+        self
+    }
+
     pub fn peer_node_id(&self) -> &NodeId {
         &self.peer_node_id
     }
@@ -177,14 +282,38 @@ impl PeerConnection {
         &self.address
     }
 
+    /// Returns the purpose for which this connection was established, if one was given.
+    pub fn purpose(&self) -> Option<&str> {
+        self.purpose.as_deref().map(String::as_str)
+    }
+
     pub fn id(&self) -> ConnectionId {
         self.id
     }
 
+    /// Returns the id of the tracing span that requested the dial which established this connection, if any. The same
+    /// id is recorded as the `correlation_id` field of the dial and substream negotiation spans, so that a single dial
+    /// can be traced through to the substreams opened on the connection.
+    pub fn correlation_id(&self) -> Option<u64> {
+        self.correlation_id
+    }
+
     pub fn is_connected(&self) -> bool {
         !self.request_tx.is_closed()
     }
 
+    /// Resolves once the connection has been closed and the peer connection actor has terminated. This can be used,
+    /// for example, to wait for a connection to be fully torn down before dialing the same peer again.
+    pub async fn wait_disconnected(&self) {
+        self.request_tx.closed().await
+    }
+
+    /// Returns true if both connections are to the same peer in the same direction. Unlike `PartialEq`, which compares
+    /// the unique connection id, this is true for connections to the same peer made at different times.
+    pub fn same_peer(&self, other: &Self) -> bool {
+        self.peer_node_id == other.peer_node_id && self.direction == other.direction
+    }
+
     pub fn age(&self) -> Duration {
         self.started_at.elapsed()
     }
@@ -197,10 +326,20 @@ impl PeerConnection {
         Arc::strong_count(&self.handle_counter)
     }
 
-    #[tracing::instrument("peer_connection::open_substream", skip(self))]
     pub async fn open_substream(
         &mut self,
         protocol_id: &ProtocolId,
+    ) -> Result<NegotiatedSubstream<Substream>, PeerConnectionError> {
+        self.open_substream_with_timeout(protocol_id, None).await
+    }
+
+    /// Open a substream and negotiate the given protocol. Negotiation will time out after the smaller of
+    /// `negotiation_timeout` and the default protocol negotiation timeout.
+    #[tracing::instrument("peer_connection::open_substream", skip(self))]
+    pub async fn open_substream_with_timeout(
+        &mut self,
+        protocol_id: &ProtocolId,
+        negotiation_timeout: Option<Duration>,
     ) -> Result<NegotiatedSubstream<Substream>, PeerConnectionError> {
         let (reply_tx, reply_rx) = oneshot::channel();
         self.request_tx
@@ -208,6 +347,7 @@ impl PeerConnection {
                 protocol_id: protocol_id.clone(),
                 reply_tx,
                 tracing_id: Span::current().id(),
+                negotiation_timeout,
             })
             .await?;
         reply_rx
@@ -215,13 +355,38 @@ impl PeerConnection {
             .map_err(|_| PeerConnectionError::InternalReplyCancelled)?
     }
 
+    /// Checks that the peer is still responsive by negotiating the reserved liveness check protocol. Any reply from the
+    /// peer, including a rejection of the protocol by a node that does not know it, shows that the connection is alive.
+    /// An error is returned if the peer does not reply within `timeout` or the connection has failed.
+    pub async fn check_liveness(&mut self, timeout: Duration) -> Result<(), PeerConnectionError> {
+        let protocol = ProtocolId::from_static(LIVENESS_CHECK_PROTOCOL);
+        let result = self.open_substream_with_timeout(&protocol, Some(timeout)).await;
+        if is_liveness_response(&result) {
+            return Ok(());
+        }
+        result.map(|_| ())
+    }
+
     #[tracing::instrument("peer_connection::open_framed_substream", skip(self))]
     pub async fn open_framed_substream(
         &mut self,
         protocol_id: &ProtocolId,
         max_frame_size: usize,
     ) -> Result<CanonicalFraming<Substream>, PeerConnectionError> {
-        let substream = self.open_substream(protocol_id).await?;
+        self.open_framed_substream_with_timeout(protocol_id, max_frame_size, None)
+            .await
+    }
+
+    async fn open_framed_substream_with_timeout(
+        &mut self,
+        protocol_id: &ProtocolId,
+        max_frame_size: usize,
+        negotiation_timeout: Option<Duration>,
+    ) -> Result<CanonicalFraming<Substream>, PeerConnectionError> {
+        let mut substream = self
+            .open_substream_with_timeout(protocol_id, negotiation_timeout)
+            .await?;
+        substream.stream.set_write_timeout(self.substream_write_timeout);
         Ok(framing::canonical(substream.stream, max_frame_size))
     }
 
@@ -236,15 +401,45 @@ impl PeerConnection {
     #[tracing::instrument("peer_connection::connect_rpc_with_builder", skip(self, builder))]
     pub async fn connect_rpc_using_builder<T>(&mut self, builder: RpcClientBuilder<T>) -> Result<T, RpcError>
     where T: From<RpcClient> + NamedProtocolService {
-        let protocol = ProtocolId::from_static(T::PROTOCOL_NAME);
+        let protocol = self.select_rpc_protocol::<T>().await?;
         debug!(
             target: LOG_TARGET,
             "Attempting to establish RPC protocol `{}` to peer `{}`",
             String::from_utf8_lossy(&protocol),
             self.peer_node_id
         );
-        let framed = self.open_framed_substream(&protocol, RPC_MAX_FRAME_SIZE).await?;
-        builder.with_protocol_id(protocol).connect(framed).await
+        let builder = builder.with_default_session_limit(self.rpc_session_limit.clone());
+        // Fail before opening a substream if the session limit has been reached
+        let session_permit = builder.acquire_session_permit()?;
+        let negotiation_timeout = builder.config().negotiation_timeout;
+        let framed = self
+            .open_framed_substream_with_timeout(&protocol, RPC_MAX_FRAME_SIZE, Some(negotiation_timeout))
+            .await?;
+        builder
+            .with_protocol_id(protocol)
+            .connect_with_session_permit(framed, session_permit)
+            .await
+    }
+
+    /// Selects the newest version of the RPC protocol for `T` that the peer advertised. If the peer advertised none of
+    /// them, the newest version is selected and negotiated as usual.
+    #[cfg(feature = "rpc")]
+    pub async fn select_rpc_protocol<T: NamedProtocolService>(&mut self) -> Result<ProtocolId, PeerConnectionError> {
+        let their_supported_protocols = self.their_supported_protocols().await?;
+        let selected = std::iter::once(T::PROTOCOL_NAME)
+            .chain(T::COMPATIBLE_PROTOCOL_NAMES.iter().copied())
+            .find(|name| their_supported_protocols.iter().any(|p| p.as_ref() == *name))
+            .unwrap_or(T::PROTOCOL_NAME);
+        if selected != T::PROTOCOL_NAME {
+            debug!(
+                target: LOG_TARGET,
+                "Peer `{}` does not support RPC protocol `{}`. Downgrading to `{}`",
+                self.peer_node_id,
+                String::from_utf8_lossy(T::PROTOCOL_NAME),
+                String::from_utf8_lossy(selected)
+            );
+        }
+        Ok(ProtocolId::from_static(selected))
     }
 
     /// Creates a new RpcClientPool that can be shared between tasks. The client pool will lazily establish up to
@@ -273,6 +468,59 @@ impl PeerConnection {
             .map_err(|_| PeerConnectionError::InternalReplyCancelled)?
     }
 
+    /// Returns the protocols that are supported by both this node and the peer, as exchanged during connection
+    /// establishment.
+    pub async fn protocols_in_common(&mut self) -> Result<Vec<ProtocolId>, PeerConnectionError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.request_tx
+            .send(PeerConnectionRequest::GetProtocolsInCommon(reply_tx))
+            .await?;
+        reply_rx.await.map_err(|_| PeerConnectionError::InternalReplyCancelled)
+    }
+
+    /// Returns the protocols that the peer advertised during connection establishment.
+    pub async fn their_supported_protocols(&mut self) -> Result<Vec<ProtocolId>, PeerConnectionError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.request_tx
+            .send(PeerConnectionRequest::GetTheirProtocols(reply_tx))
+            .await?;
+        reply_rx.await.map_err(|_| PeerConnectionError::InternalReplyCancelled)
+    }
+
+    /// Returns the number of bytes sent and received over all substreams of this connection.
+    pub async fn get_byte_counters(&mut self) -> Result<ByteCounts, PeerConnectionError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.request_tx
+            .send(PeerConnectionRequest::GetByteCounters(reply_tx))
+            .await?;
+        reply_rx.await.map_err(|_| PeerConnectionError::InternalReplyCancelled)
+    }
+
+    /// Returns the protocols that have been successfully negotiated outbound over this connection. These are negotiated
+    /// optimistically when subsequent substreams are opened, even if the peer did not advertise them. Protocols that
+    /// the peer opened to us are not included, because that does not show that the peer accepts them.
+    pub async fn negotiated_protocols_observed(&mut self) -> Result<Vec<ProtocolId>, PeerConnectionError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.request_tx
+            .send(PeerConnectionRequest::GetNegotiatedProtocolsObserved(reply_tx))
+            .await?;
+        reply_rx.await.map_err(|_| PeerConnectionError::InternalReplyCancelled)
+    }
+
+    /// Closes the open substreams, both inbound and outbound, that negotiated `protocol` without closing the connection
+    /// or the substreams of other protocols, e.g. when a peer misbehaves on a single protocol. Once closed, reads
+    /// return EOF and writes fail. Substreams opened for the protocol afterwards are not affected.
+    pub async fn close_substreams_for_protocol(&mut self, protocol: &ProtocolId) -> Result<(), PeerConnectionError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.request_tx
+            .send(PeerConnectionRequest::CloseSubstreamsForProtocol(
+                protocol.clone(),
+                reply_tx,
+            ))
+            .await?;
+        reply_rx.await.map_err(|_| PeerConnectionError::InternalReplyCancelled)
+    }
+
     pub(crate) async fn disconnect_silent(&mut self) -> Result<(), PeerConnectionError> {
         let (reply_tx, reply_rx) = oneshot::channel();
         self.request_tx
@@ -296,7 +544,11 @@ impl fmt::Display for PeerConnection {
             self.age(),
             self.substream_count(),
             self.handle_count()
-        )
+        )?;
+        if let Some(purpose) = self.purpose() {
+            write!(f, ", Purpose: {}", purpose)?;
+        }
+        Ok(())
     }
 }
 
@@ -306,6 +558,17 @@ impl PartialEq for PeerConnection {
     }
 }
 
+impl Eq for PeerConnection {}
+
+/// Hashes by peer and direction, which is consistent with `PartialEq` because equal connection ids always refer to the
+/// same peer and direction.
+impl Hash for PeerConnection {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.peer_node_id.hash(state);
+        self.direction.hash(state);
+    }
+}
+
 /// Actor for an active connection to a peer.
 struct PeerConnectionActor {
     id: ConnectionId,
@@ -317,6 +580,13 @@ struct PeerConnectionActor {
     event_notifier: mpsc::Sender<ConnectionManagerEvent>,
     our_supported_protocols: Vec<ProtocolId>,
     their_supported_protocols: Vec<ProtocolId>,
+    negotiated_protocols_observed: HashSet<ProtocolId>,
+    substream_close_triggers: HashMap<ProtocolId, Shutdown>,
+    correlation_id: Option<u64>,
+    keepalive: Option<KeepaliveConfig>,
+    keepalive_byte_counts: ByteCounts,
+    missed_keepalives: usize,
+    is_closing: bool,
 }
 
 impl PeerConnectionActor {
@@ -330,6 +600,8 @@ impl PeerConnectionActor {
         event_notifier: mpsc::Sender<ConnectionManagerEvent>,
         our_supported_protocols: Vec<ProtocolId>,
         their_supported_protocols: Vec<ProtocolId>,
+        correlation_id: Option<u64>,
+        keepalive: Option<KeepaliveConfig>,
     ) -> Self {
         Self {
             id,
@@ -339,12 +611,29 @@ impl PeerConnectionActor {
             incoming_substreams: connection.into_incoming(),
             request_rx,
             event_notifier,
-            our_supported_protocols,
+            our_supported_protocols: our_supported_protocols
+                .into_iter()
+                .chain(iter::once(ProtocolId::from_static(LIVENESS_CHECK_PROTOCOL)))
+                .collect(),
             their_supported_protocols,
+            negotiated_protocols_observed: HashSet::new(),
+            substream_close_triggers: HashMap::new(),
+            correlation_id,
+            keepalive,
+            keepalive_byte_counts: ByteCounts::default(),
+            missed_keepalives: 0,
+            is_closing: false,
         }
     }
 
     pub async fn run(mut self) {
+        let mut keepalive_interval = self.keepalive.map(|keepalive| {
+            let mut interval = time::interval_at(time::Instant::now() + keepalive.interval, keepalive.interval);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        let mut keepalive_ping = None;
+        let mut closed_by_peer = false;
         loop {
             tokio::select! {
                 maybe_request = self.request_rx.recv() => {
@@ -372,14 +661,35 @@ impl PeerConnectionActor {
                         },
                         None => {
                             debug!(target: LOG_TARGET, "[{}] Peer '{}' closed the connection", self, self.peer_node_id.short_str());
+                            closed_by_peer = !self.is_closing;
                             break;
                         },
                     }
+                },
+
+                _ = next_keepalive_tick(&mut keepalive_interval) => {
+                    // A tick is skipped if the previous ping has not completed
+                    if keepalive_ping.is_none() {
+                        keepalive_ping = self.handle_keepalive_tick();
+                    }
+                },
+
+                is_alive = next_keepalive_response(&mut keepalive_ping) => {
+                    keepalive_ping = None;
+                    if !self.handle_keepalive_response(is_alive) {
+                        break;
+                    }
                 }
             }
         }
 
-        if let Err(err) = self.disconnect(false).await {
+        if closed_by_peer {
+            self.notify_event(ConnectionManagerEvent::PeerDisconnectedByPeer(
+                self.peer_node_id.clone(),
+            ))
+            .await;
+        }
+        if let Err(err) = self.disconnect(closed_by_peer).await {
             warn!(
                 target: LOG_TARGET,
                 "[{}] Failed to politely close connection to peer '{}' because '{}'",
@@ -398,10 +708,21 @@ impl PeerConnectionActor {
                 protocol_id,
                 reply_tx,
                 tracing_id,
+                negotiation_timeout,
             } => {
-                let span = span!(Level::TRACE, "handle_request");
+                let span = span!(
+                    Level::TRACE,
+                    "peer_connection::open_substream",
+                    correlation_id = tracing::field::Empty
+                );
                 span.follows_from(tracing_id);
-                let result = self.open_negotiated_protocol_stream(protocol_id).instrument(span).await;
+                if let Some(correlation_id) = self.correlation_id {
+                    span.record("correlation_id", &correlation_id);
+                }
+                let result = self
+                    .open_negotiated_protocol_stream(protocol_id, negotiation_timeout)
+                    .instrument(span)
+                    .await;
                 log_if_error_fmt!(
                     target: LOG_TARGET,
                     reply_tx.send(result),
@@ -419,7 +740,118 @@ impl PeerConnectionActor {
                 );
                 let _ = reply_tx.send(self.disconnect(silent).await);
             },
+            GetProtocolsInCommon(reply_tx) => {
+                let _ = reply_tx.send(self.protocols_in_common());
+            },
+            GetTheirProtocols(reply_tx) => {
+                let _ = reply_tx.send(self.their_supported_protocols.clone());
+            },
+            GetByteCounters(reply_tx) => {
+                let _ = reply_tx.send(self.control.byte_counts());
+            },
+            GetNegotiatedProtocolsObserved(reply_tx) => {
+                let _ = reply_tx.send(self.negotiated_protocols_observed.iter().cloned().collect());
+            },
+            CloseSubstreamsForProtocol(protocol, reply_tx) => {
+                if let Some(mut trigger) = self.substream_close_triggers.remove(&protocol) {
+                    debug!(
+                        target: LOG_TARGET,
+                        "[{}] Closing '{}' substreams for peer '{}'",
+                        self,
+                        String::from_utf8_lossy(&protocol),
+                        self.peer_node_id.short_str()
+                    );
+                    trigger.trigger();
+                }
+                let _ = reply_tx.send(());
+            },
+        }
+    }
+
+    /// Returns a keepalive ping if there has been no traffic on the connection since the last tick. The ping is polled
+    /// by `run` so that requests and inbound substreams are handled while waiting for the peer to respond.
+    fn handle_keepalive_tick(&mut self) -> Option<BoxFuture<'static, bool>> {
+        let keepalive = self.keepalive?;
+        let byte_counts = self.control.byte_counts();
+        if byte_counts != self.keepalive_byte_counts {
+            self.keepalive_byte_counts = byte_counts;
+            self.missed_keepalives = 0;
+            return None;
+        }
+
+        trace!(
+            target: LOG_TARGET,
+            "[{}] Connection is idle, sending keepalive ping to peer '{}'",
+            self,
+            self.peer_node_id.short_str()
+        );
+        let mut control = self.control.clone();
+        let negotiation_timeout = keepalive.interval.min(PROTOCOL_NEGOTIATION_TIMEOUT);
+        let ping = async move {
+            let result = async {
+                let mut stream = control.open_stream().await?;
+                let protocols = [ProtocolId::from_static(LIVENESS_CHECK_PROTOCOL)];
+                let fut = ProtocolNegotiation::new(&mut stream).negotiate_protocol_outbound(&protocols);
+                time::timeout(negotiation_timeout, fut).await??;
+                Ok::<_, PeerConnectionError>(())
+            }
+            .await;
+            is_liveness_response(&result)
+        };
+        Some(Box::pin(ping))
+    }
+
+    /// Records the response to a keepalive ping. Returns false if too many consecutive pings have gone unanswered, in
+    /// which case the connection should be closed.
+    fn handle_keepalive_response(&mut self, is_alive: bool) -> bool {
+        let keepalive = match self.keepalive {
+            Some(keepalive) => keepalive,
+            None => return true,
+        };
+        if is_alive {
+            self.missed_keepalives = 0;
+        } else {
+            self.missed_keepalives += 1;
+            debug!(
+                target: LOG_TARGET,
+                "[{}] Peer '{}' did not respond to keepalive ping ({}/{} missed)",
+                self,
+                self.peer_node_id.short_str(),
+                self.missed_keepalives,
+                keepalive.max_missed
+            );
         }
+        // Traffic caused by the ping is not counted as activity
+        self.keepalive_byte_counts = self.control.byte_counts();
+
+        if self.missed_keepalives >= keepalive.max_missed.max(1) {
+            warn!(
+                target: LOG_TARGET,
+                "[{}] Peer '{}' missed {} keepalive ping(s). Closing the connection.",
+                self,
+                self.peer_node_id.short_str(),
+                self.missed_keepalives
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Allows the substream to be closed by `CloseSubstreamsForProtocol`
+    fn track_substream(&mut self, protocol: &ProtocolId, stream: &mut Substream) {
+        let trigger = self
+            .substream_close_triggers
+            .entry(protocol.clone())
+            .or_insert_with(Shutdown::new);
+        stream.set_close_signal(trigger.to_signal());
+    }
+
+    fn protocols_in_common(&self) -> Vec<ProtocolId> {
+        self.our_supported_protocols
+            .iter()
+            .filter(|p| self.their_supported_protocols.contains(p))
+            .cloned()
+            .collect()
     }
 
     #[tracing::instrument(skip(self, stream),fields(comms.direction="inbound"))]
@@ -427,6 +859,16 @@ impl PeerConnectionActor {
         let selected_protocol = ProtocolNegotiation::new(&mut stream)
             .negotiate_protocol_inbound(&self.our_supported_protocols)
             .await?;
+        if selected_protocol == LIVENESS_CHECK_PROTOCOL {
+            trace!(
+                target: LOG_TARGET,
+                "[{}] Responded to liveness check from peer '{}'",
+                self,
+                self.peer_node_id.short_str()
+            );
+            return Ok(());
+        }
+        self.track_substream(&selected_protocol, &mut stream);
 
         self.notify_event(ConnectionManagerEvent::NewInboundSubstream(
             self.peer_node_id.clone(),
@@ -442,8 +884,11 @@ impl PeerConnectionActor {
     async fn open_negotiated_protocol_stream(
         &mut self,
         protocol: ProtocolId,
+        negotiation_timeout: Option<Duration>,
     ) -> Result<NegotiatedSubstream<Substream>, PeerConnectionError> {
-        const PROTOCOL_NEGOTIATION_TIMEOUT: Duration = Duration::from_secs(10);
+        let negotiation_timeout = negotiation_timeout
+            .map(|t| t.min(PROTOCOL_NEGOTIATION_TIMEOUT))
+            .unwrap_or(PROTOCOL_NEGOTIATION_TIMEOUT);
         debug!(
             target: LOG_TARGET,
             "[{}] Negotiating protocol '{}' on new substream for peer '{}'",
@@ -455,14 +900,20 @@ impl PeerConnectionActor {
 
         let mut negotiation = ProtocolNegotiation::new(&mut stream);
 
-        let selected_protocol = if self.their_supported_protocols.contains(&protocol) {
+        // Protocols that the peer advertised or that have previously been negotiated are negotiated optimistically
+        let selected_protocol = if self.their_supported_protocols.contains(&protocol) ||
+            self.negotiated_protocols_observed.contains(&protocol)
+        {
             let fut = negotiation.negotiate_protocol_outbound_optimistic(&protocol);
-            time::timeout(PROTOCOL_NEGOTIATION_TIMEOUT, fut).await??
+            time::timeout(negotiation_timeout, fut).await??
         } else {
             let selected_protocols = [protocol];
             let fut = negotiation.negotiate_protocol_outbound(&selected_protocols);
-            time::timeout(PROTOCOL_NEGOTIATION_TIMEOUT, fut).await??
+            let selected_protocol = time::timeout(negotiation_timeout, fut).await??;
+            self.negotiated_protocols_observed.insert(selected_protocol.clone());
+            selected_protocol
         };
+        self.track_substream(&selected_protocol, &mut stream);
 
         Ok(NegotiatedSubstream::new(selected_protocol, stream))
     }
@@ -481,6 +932,7 @@ impl PeerConnectionActor {
     ///
     /// silent - true to suppress the PeerDisconnected event, false to publish the event
     async fn disconnect(&mut self, silent: bool) -> Result<(), PeerConnectionError> {
+        self.is_closing = true;
         if !silent {
             self.notify_event(ConnectionManagerEvent::PeerDisconnected(self.peer_node_id.clone()))
                 .await;
@@ -498,6 +950,37 @@ impl PeerConnectionActor {
     }
 }
 
+async fn next_keepalive_tick(interval: &mut Option<time::Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        },
+        None => future::pending().await,
+    }
+}
+
+async fn next_keepalive_response(ping: &mut Option<BoxFuture<'static, bool>>) -> bool {
+    match ping {
+        Some(ping) => ping.await,
+        None => future::pending().await,
+    }
+}
+
+/// Returns true if the result of negotiating the liveness check protocol shows that the peer is responsive. The
+/// protocol is never registered, so a rejection is the expected response.
+fn is_liveness_response<T>(result: &Result<T, PeerConnectionError>) -> bool {
+    matches!(
+        result,
+        Ok(_) |
+            Err(PeerConnectionError::ProtocolError(
+                ProtocolError::ProtocolOutboundNegotiationFailed(_)
+            )) |
+            Err(PeerConnectionError::ProtocolError(
+                ProtocolError::ProtocolNegotiationTerminatedByPeer
+            ))
+    )
+}
+
 impl fmt::Display for PeerConnectionActor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -529,3 +1012,549 @@ impl<TSubstream> fmt::Debug for NegotiatedSubstream<TSubstream> {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{node_id, transport::build_multiplexed_connections};
+    use bytes::Bytes;
+    use futures::SinkExt;
+    use std::{collections::HashSet, io};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[cfg(feature = "rpc")]
+    struct TestRpcClient;
+
+    #[cfg(feature = "rpc")]
+    impl NamedProtocolService for TestRpcClient {
+        const PROTOCOL_NAME: &'static [u8] = b"t/test-rpc/1";
+    }
+
+    #[cfg(feature = "rpc")]
+    impl From<RpcClient> for TestRpcClient {
+        fn from(_: RpcClient) -> Self {
+            Self
+        }
+    }
+
+    async fn create_connection(
+        our_supported_protocols: Vec<ProtocolId>,
+        their_supported_protocols: Vec<ProtocolId>,
+    ) -> (PeerConnection, Yamux) {
+        let (_, muxer_out, muxer_in) = build_multiplexed_connections().await;
+        let (event_tx, _event_rx) = mpsc::channel(1);
+        let conn = create(
+            muxer_out,
+            Multiaddr::empty(),
+            node_id::random(),
+            PeerFeatures::COMMUNICATION_NODE,
+            ConnectionDirection::Outbound,
+            event_tx,
+            our_supported_protocols,
+            their_supported_protocols,
+            Default::default(),
+        )
+        .unwrap();
+        (conn, muxer_in)
+    }
+
+    #[runtime::test]
+    async fn protocols_in_common() {
+        let proto_a = ProtocolId::from_static(b"/test/a");
+        let proto_b = ProtocolId::from_static(b"/test/b");
+        let proto_c = ProtocolId::from_static(b"/test/c");
+
+        let (mut conn, _muxer_in) = create_connection(vec![proto_a.clone(), proto_b.clone()], vec![
+            proto_b.clone(),
+            proto_c.clone(),
+        ])
+        .await;
+        assert_eq!(conn.protocols_in_common().await.unwrap(), vec![proto_b.clone()]);
+
+        let (mut conn, _muxer_in) = create_connection(vec![proto_a], vec![proto_b, proto_c]).await;
+        assert!(conn.protocols_in_common().await.unwrap().is_empty());
+    }
+
+    #[runtime::test]
+    async fn their_supported_protocols() {
+        let proto_a = ProtocolId::from_static(b"/test/a/1");
+        let proto_b = ProtocolId::from_static(b"/test/b/2");
+
+        let (mut conn, _muxer_in) =
+            create_connection(vec![proto_a.clone()], vec![proto_a.clone(), proto_b.clone()]).await;
+        let protocols = conn.their_supported_protocols().await.unwrap();
+        assert_eq!(protocols, vec![proto_a, proto_b]);
+    }
+
+    #[runtime::test]
+    async fn liveness_checks_are_accepted_without_notifying() {
+        let (_, muxer_out, muxer_in) = build_multiplexed_connections().await;
+        let (event_tx, _event_rx) = mpsc::channel(1);
+        let mut conn = create(
+            muxer_out,
+            Multiaddr::empty(),
+            node_id::random(),
+            PeerFeatures::COMMUNICATION_NODE,
+            ConnectionDirection::Outbound,
+            event_tx,
+            vec![],
+            vec![],
+            Default::default(),
+        )
+        .unwrap();
+        let (event_tx, mut event_rx) = mpsc::channel(1);
+        let _remote_conn = create(
+            muxer_in,
+            Multiaddr::empty(),
+            node_id::random(),
+            PeerFeatures::COMMUNICATION_NODE,
+            ConnectionDirection::Inbound,
+            event_tx,
+            vec![],
+            vec![],
+            Default::default(),
+        )
+        .unwrap();
+
+        conn.check_liveness(Duration::from_secs(5)).await.unwrap();
+        // The remote accepts the protocol rather than failing the negotiation
+        let protocol = ProtocolId::from_static(LIVENESS_CHECK_PROTOCOL);
+        let negotiated = conn.open_substream(&protocol).await.unwrap();
+        assert_eq!(negotiated.protocol, protocol);
+        assert!(event_rx.try_recv().is_err());
+    }
+
+    #[runtime::test]
+    async fn wait_disconnected() {
+        let (mut conn, _muxer_in) = create_connection(vec![], vec![]).await;
+        assert!(conn.is_connected());
+
+        let waiter = runtime::task::spawn({
+            let conn = conn.clone();
+            async move { conn.wait_disconnected().await }
+        });
+        conn.disconnect().await.unwrap();
+        time::timeout(Duration::from_secs(5), waiter).await.unwrap().unwrap();
+        assert!(!conn.is_connected());
+    }
+
+    #[runtime::test]
+    async fn framed_substream_write_times_out_if_peer_does_not_read() {
+        let protocol = ProtocolId::from_static(b"/test/write-timeout");
+        let (_, muxer_out, mut muxer_in) = build_multiplexed_connections().await;
+        let (event_tx, _event_rx) = mpsc::channel(1);
+        let mut conn = create(
+            muxer_out,
+            Multiaddr::empty(),
+            node_id::random(),
+            PeerFeatures::COMMUNICATION_NODE,
+            ConnectionDirection::Outbound,
+            event_tx,
+            vec![],
+            vec![],
+            PeerConnectionOptions {
+                substream_write_timeout: Some(Duration::from_millis(100)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let server = runtime::task::spawn({
+            let protocol = protocol.clone();
+            async move {
+                let mut stream = muxer_in.incoming_mut().next().await.unwrap();
+                ProtocolNegotiation::new(&mut stream)
+                    .negotiate_protocol_inbound(&[protocol])
+                    .await
+                    .unwrap();
+                // Keep the substream open without ever reading from it
+                (stream, muxer_in)
+            }
+        });
+
+        let mut framed = conn.open_framed_substream(&protocol, 1024 * 1024).await.unwrap();
+        let _server = server.await.unwrap();
+
+        let frame = Bytes::from(vec![0u8; 512 * 1024]);
+        let err = time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let Err(err) = framed.send(frame.clone()).await {
+                    break err;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        let err = err.get_ref().unwrap().downcast_ref::<PeerConnectionError>().unwrap();
+        assert!(matches!(err, PeerConnectionError::WriteTimeout));
+    }
+
+    #[runtime::test]
+    async fn byte_counters() {
+        let protocol = ProtocolId::from_static(b"/test/byte-counters");
+        let (mut conn, mut muxer_in) = create_connection(vec![], vec![]).await;
+        assert_eq!(conn.get_byte_counters().await.unwrap(), ByteCounts::default());
+
+        let server = runtime::task::spawn({
+            let protocol = protocol.clone();
+            async move {
+                let mut stream = muxer_in.incoming_mut().next().await.unwrap();
+                ProtocolNegotiation::new(&mut stream)
+                    .negotiate_protocol_inbound(&[protocol])
+                    .await
+                    .unwrap();
+                let mut buf = [0u8; 100];
+                stream.read_exact(&mut buf).await.unwrap();
+                stream.write_all(&[1u8; 30]).await.unwrap();
+                stream.flush().await.unwrap();
+                (stream, muxer_in)
+            }
+        });
+
+        let mut substream = conn.open_substream(&protocol).await.unwrap().stream;
+        // Protocol negotiation is counted too
+        let after_negotiation = conn.get_byte_counters().await.unwrap();
+        assert!(after_negotiation.bytes_sent > 0);
+        assert!(after_negotiation.bytes_received > 0);
+
+        substream.write_all(&[0u8; 100]).await.unwrap();
+        substream.flush().await.unwrap();
+        let mut buf = [0u8; 30];
+        substream.read_exact(&mut buf).await.unwrap();
+        let _server = server.await.unwrap();
+
+        let counts = conn.get_byte_counters().await.unwrap();
+        assert_eq!(counts.bytes_sent - after_negotiation.bytes_sent, 100);
+        assert_eq!(counts.bytes_received - after_negotiation.bytes_received, 30);
+    }
+
+    #[runtime::test]
+    async fn negotiated_protocols_are_negotiated_optimistically() {
+        let protocol = ProtocolId::from_static(b"/test/observed");
+        // The peer did not advertise the protocol
+        let (mut conn, mut muxer_in) = create_connection(vec![], vec![]).await;
+        assert!(conn.negotiated_protocols_observed().await.unwrap().is_empty());
+
+        let server = runtime::task::spawn({
+            let protocol = protocol.clone();
+            async move {
+                let mut stream = muxer_in.incoming_mut().next().await.unwrap();
+                ProtocolNegotiation::new(&mut stream)
+                    .negotiate_protocol_inbound(&[protocol])
+                    .await
+                    .unwrap();
+                (stream, muxer_in)
+            }
+        });
+        conn.open_substream(&protocol).await.unwrap();
+        let (_stream, mut muxer_in) = server.await.unwrap();
+        assert_eq!(conn.negotiated_protocols_observed().await.unwrap(), vec![
+            protocol.clone()
+        ]);
+
+        conn.open_substream(&protocol).await.unwrap();
+        let mut stream = muxer_in.incoming_mut().next().await.unwrap();
+        let mut header = [0u8; 2];
+        stream.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[0] as usize, protocol.len());
+        // The OPTIMISTIC and TERMINATE flags are set
+        assert_eq!(header[1], 0x03);
+    }
+
+    #[runtime::test]
+    async fn inbound_protocols_are_not_negotiated_optimistically() {
+        let protocol = ProtocolId::from_static(b"/test/inbound");
+        let (mut conn, muxer_in) = create_connection(vec![protocol.clone()], vec![]).await;
+
+        // The peer opens a substream to us
+        let mut stream = muxer_in.get_yamux_control().open_stream().await.unwrap();
+        ProtocolNegotiation::new(&mut stream)
+            .negotiate_protocol_outbound(&[protocol])
+            .await
+            .unwrap();
+        assert!(conn.negotiated_protocols_observed().await.unwrap().is_empty());
+    }
+
+    #[runtime::test]
+    async fn close_substreams_for_protocol() {
+        let proto_a = ProtocolId::from_static(b"/test/a");
+        let proto_b = ProtocolId::from_static(b"/test/b");
+        // The peer advertised both protocols, so they are negotiated optimistically
+        let (mut conn, mut muxer_in) = create_connection(vec![], vec![proto_a.clone(), proto_b.clone()]).await;
+
+        let mut stream_a1 = conn.open_substream(&proto_a).await.unwrap().stream;
+        let mut stream_a2 = conn.open_substream(&proto_a).await.unwrap().stream;
+        let mut stream_b = conn.open_substream(&proto_b).await.unwrap().stream;
+
+        conn.close_substreams_for_protocol(&proto_a).await.unwrap();
+
+        let err = stream_a1.write_all(b"hello").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionAborted);
+        let mut buf = [0u8; 8];
+        assert_eq!(stream_a2.read(&mut buf).await.unwrap(), 0);
+
+        // The remote sees EOF on the closed substreams
+        for _ in 0..2 {
+            let mut remote_stream = muxer_in.incoming_mut().next().await.unwrap();
+            let mut buf = Vec::new();
+            time::timeout(Duration::from_secs(5), remote_stream.read_to_end(&mut buf))
+                .await
+                .unwrap()
+                .unwrap();
+        }
+
+        // The other protocol's substream and the connection are unaffected
+        stream_b.write_all(b"hello").await.unwrap();
+        stream_b.flush().await.unwrap();
+        assert!(conn.is_connected());
+
+        // New substreams for the closed protocol can still be opened
+        let mut stream_a3 = conn.open_substream(&proto_a).await.unwrap().stream;
+        stream_a3.write_all(b"hello").await.unwrap();
+    }
+
+    fn create_keepalive_connection(muxer: Yamux, interval: Duration, max_missed: usize) -> PeerConnection {
+        let (event_tx, _event_rx) = mpsc::channel(1);
+        create(
+            muxer,
+            Multiaddr::empty(),
+            node_id::random(),
+            PeerFeatures::COMMUNICATION_NODE,
+            ConnectionDirection::Outbound,
+            event_tx,
+            vec![],
+            vec![],
+            PeerConnectionOptions {
+                keepalive: Some(KeepaliveConfig { interval, max_missed }),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[runtime::test]
+    async fn keepalive_pings_are_sent_on_idle_connections() {
+        let (_, muxer_out, mut muxer_in) = build_multiplexed_connections().await;
+        let conn = create_keepalive_connection(muxer_out, Duration::from_millis(50), 2);
+
+        // The peer responds to every ping by rejecting the liveness check protocol
+        let server = runtime::task::spawn(async move {
+            for _ in 0..5 {
+                let mut stream = muxer_in.incoming_mut().next().await.unwrap();
+                let _ = ProtocolNegotiation::new(&mut stream)
+                    .negotiate_protocol_inbound(&[])
+                    .await;
+            }
+            muxer_in
+        });
+        let _muxer_in = time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+        assert!(conn.is_connected());
+    }
+
+    #[runtime::test]
+    async fn keepalive_disconnects_after_missed_pings() {
+        let (_, muxer_out, mut muxer_in) = build_multiplexed_connections().await;
+        let conn = create_keepalive_connection(muxer_out, Duration::from_millis(50), 2);
+
+        // The peer accepts the ping substreams but never responds to them
+        let mut pings = Vec::new();
+        for _ in 0..2 {
+            let stream = time::timeout(Duration::from_secs(5), muxer_in.incoming_mut().next())
+                .await
+                .unwrap()
+                .unwrap();
+            pings.push(stream);
+        }
+        time::timeout(Duration::from_secs(5), conn.wait_disconnected())
+            .await
+            .unwrap();
+        assert!(!conn.is_connected());
+    }
+
+    #[runtime::test]
+    async fn keepalive_ping_does_not_block_requests() {
+        let protocol = ProtocolId::from_static(b"/test/keepalive");
+        let (_, muxer_out, mut muxer_in) = build_multiplexed_connections().await;
+        let mut conn = create_keepalive_connection(muxer_out, Duration::from_millis(500), 10);
+
+        // The peer accepts the ping substream but never responds to it
+        let _ping = time::timeout(Duration::from_secs(5), muxer_in.incoming_mut().next())
+            .await
+            .unwrap()
+            .unwrap();
+        let server = runtime::task::spawn({
+            let protocol = protocol.clone();
+            async move {
+                let mut stream = muxer_in.incoming_mut().next().await.unwrap();
+                ProtocolNegotiation::new(&mut stream)
+                    .negotiate_protocol_inbound(&[protocol])
+                    .await
+                    .unwrap();
+                muxer_in
+            }
+        });
+
+        // The substream is opened while the ping is waiting for a response, well before the ping times out
+        let negotiated = time::timeout(Duration::from_millis(250), conn.open_substream(&protocol))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(negotiated.protocol, protocol);
+        let _muxer_in = server.await.unwrap();
+    }
+
+    #[test]
+    fn same_peer() {
+        let (request_tx, _request_rx) = mpsc::channel(1);
+        let peer_node_id = node_id::random();
+        let create_conn = |id, node_id, direction| {
+            PeerConnection::new(
+                id,
+                request_tx.clone(),
+                node_id,
+                PeerFeatures::COMMUNICATION_NODE,
+                Multiaddr::empty(),
+                direction,
+                AtomicRefCounter::new(),
+            )
+        };
+        let conn1 = create_conn(1, peer_node_id.clone(), ConnectionDirection::Outbound);
+        // Reconnect to the same peer
+        let conn2 = create_conn(2, peer_node_id.clone(), ConnectionDirection::Outbound);
+        let conn3 = create_conn(3, peer_node_id, ConnectionDirection::Inbound);
+        let conn4 = create_conn(4, node_id::random(), ConnectionDirection::Outbound);
+
+        assert!(conn1.same_peer(&conn2));
+        assert_ne!(conn1, conn2);
+        assert!(!conn1.same_peer(&conn3));
+        assert!(!conn1.same_peer(&conn4));
+
+        let set = vec![conn1.clone(), conn1, conn2, conn3, conn4]
+            .into_iter()
+            .collect::<HashSet<_>>();
+        assert_eq!(set.len(), 4);
+    }
+
+    #[test]
+    fn purpose_is_displayed() {
+        let (request_tx, _request_rx) = mpsc::channel(1);
+        let conn = PeerConnection::new(
+            1,
+            request_tx,
+            node_id::random(),
+            PeerFeatures::COMMUNICATION_NODE,
+            Multiaddr::empty(),
+            ConnectionDirection::Outbound,
+            AtomicRefCounter::new(),
+        );
+        assert!(conn.purpose().is_none());
+        assert!(!conn.to_string().contains("Purpose"));
+
+        let conn = conn.with_purpose(Some("sync".to_string()));
+        assert_eq!(conn.purpose(), Some("sync"));
+        assert!(conn.to_string().ends_with(", Purpose: sync"));
+    }
+
+    #[cfg(feature = "rpc")]
+    #[runtime::test]
+    async fn connect_rpc_negotiation_respects_client_negotiation_timeout() {
+        // The remote side never responds to protocol negotiation
+        let (mut conn, _muxer_in) = create_connection(vec![], vec![]).await;
+
+        let negotiation_timeout = Duration::from_millis(200);
+        let timer = Instant::now();
+        let err = conn
+            .connect_rpc_using_builder::<TestRpcClient>(
+                RpcClientBuilder::new()
+                    .with_deadline(Duration::from_secs(60))
+                    .with_negotiation_timeout(negotiation_timeout),
+            )
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            RpcError::PeerConnectionError(PeerConnectionError::ProtocolNegotiationTimeout)
+        ));
+        // The connect fails once the negotiation timeout elapses, well before the default negotiation timeout
+        let elapsed = timer.elapsed();
+        assert!(elapsed >= negotiation_timeout);
+        assert!(elapsed < PROTOCOL_NEGOTIATION_TIMEOUT / 2);
+    }
+
+    #[cfg(feature = "rpc")]
+    struct TestRpcClientV2;
+
+    #[cfg(feature = "rpc")]
+    impl NamedProtocolService for TestRpcClientV2 {
+        const COMPATIBLE_PROTOCOL_NAMES: &'static [&'static [u8]] = &[b"t/test-rpc/1"];
+        const PROTOCOL_NAME: &'static [u8] = b"t/test-rpc/2";
+    }
+
+    #[cfg(feature = "rpc")]
+    impl From<RpcClient> for TestRpcClientV2 {
+        fn from(_: RpcClient) -> Self {
+            Self
+        }
+    }
+
+    #[cfg(feature = "rpc")]
+    #[runtime::test]
+    async fn connect_rpc_downgrades_to_protocol_supported_by_peer() {
+        use crate::protocol::rpc::Handshake;
+
+        let old_protocol = ProtocolId::from_static(b"t/test-rpc/1");
+        let (mut conn, mut muxer_in) = create_connection(vec![], vec![old_protocol.clone()]).await;
+
+        let server = runtime::task::spawn({
+            let old_protocol = old_protocol.clone();
+            async move {
+                let mut stream = muxer_in.incoming_mut().next().await.unwrap();
+                let selected = ProtocolNegotiation::new(&mut stream)
+                    .negotiate_protocol_inbound(&[old_protocol])
+                    .await
+                    .unwrap();
+                let mut framed = framing::canonical(stream, RPC_MAX_FRAME_SIZE);
+                Handshake::new(&mut framed).perform_server_handshake().await.unwrap();
+                (selected, framed, muxer_in)
+            }
+        });
+
+        conn.connect_rpc_using_builder::<TestRpcClientV2>(
+            RpcClientBuilder::new().with_deadline(Duration::from_secs(5)),
+        )
+        .await
+        .unwrap();
+        let (selected, _framed, _muxer_in) = server.await.unwrap();
+        assert_eq!(selected, old_protocol);
+    }
+
+    #[cfg(feature = "rpc")]
+    #[runtime::test]
+    async fn connect_rpc_is_counted_against_connection_session_limit() {
+        let (_, muxer_out, mut muxer_in) = build_multiplexed_connections().await;
+        let (event_tx, _event_rx) = mpsc::channel(1);
+        let mut conn = create(
+            muxer_out,
+            Multiaddr::empty(),
+            node_id::random(),
+            PeerFeatures::COMMUNICATION_NODE,
+            ConnectionDirection::Outbound,
+            event_tx,
+            vec![],
+            vec![],
+            PeerConnectionOptions {
+                rpc_session_limit: Some(RpcSessionLimit::new(0)),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let err = conn.connect_rpc::<TestRpcClient>().await.unwrap_err();
+        assert!(matches!(err, RpcError::SessionLimitReached { max_sessions: 0 }));
+        // The session failed before a substream was opened
+        let result = time::timeout(Duration::from_millis(100), muxer_in.incoming_mut().next()).await;
+        assert!(result.is_err());
+    }
+}
@@ -29,6 +29,7 @@ use crate::{
         ConnectionManagerConfig,
         ConnectionManagerError,
     },
+    connectivity::AddressPattern,
     noise::NoiseConfig,
     peer_manager::PeerFeatures,
     protocol::ProtocolId,
@@ -74,6 +75,31 @@ async fn listen() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[runtime::test]
+async fn listen_fails_if_address_in_use() {
+    let (event_tx, _) = mpsc::channel(1);
+    let shutdown = Shutdown::new();
+    let peer_manager = build_peer_manager();
+    let node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let noise_config = NoiseConfig::new(node_identity.clone());
+    let create_listener = |address| {
+        PeerListener::new(
+            Default::default(),
+            address,
+            MemoryTransport,
+            noise_config.clone(),
+            event_tx.clone(),
+            peer_manager.clone(),
+            node_identity.clone(),
+            shutdown.to_signal(),
+        )
+    };
+
+    let address = create_listener("/memory/0".parse().unwrap()).listen().await.unwrap();
+    let err = create_listener(address).listen().await.unwrap_err();
+    unpack_enum!(ConnectionManagerError::ListenerBindFailed(_err) = err);
+}
+
 #[runtime::test]
 async fn smoke() {
     let rt_handle = runtime::current();
@@ -128,7 +154,7 @@ async fn smoke() {
 
     let (reply_tx, reply_rx) = oneshot::channel();
     request_tx
-        .send(DialerRequest::Dial(Box::new(peer), Some(reply_tx)))
+        .send(DialerRequest::Dial(Box::new(peer), Some(reply_tx), None))
         .await
         .unwrap();
 
@@ -229,7 +255,7 @@ async fn banned() {
 
     let (reply_tx, reply_rx) = oneshot::channel();
     request_tx
-        .send(DialerRequest::Dial(Box::new(peer), Some(reply_tx)))
+        .send(DialerRequest::Dial(Box::new(peer), Some(reply_tx), None))
         .await
         .unwrap();
 
@@ -245,3 +271,61 @@ async fn banned() {
 
     timeout(Duration::from_secs(5), dialer_fut).await.unwrap().unwrap();
 }
+
+#[runtime::test]
+async fn denied_addresses_are_not_dialed() {
+    let rt_handle = runtime::current();
+    let (event_tx, _event_rx) = mpsc::channel(10);
+    let mut shutdown = Shutdown::new();
+
+    let node_identity1 = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let noise_config1 = NoiseConfig::new(node_identity1.clone());
+    let peer_manager1 = build_peer_manager();
+    let mut listener = PeerListener::new(
+        Default::default(),
+        "/memory/0".parse().unwrap(),
+        MemoryTransport,
+        noise_config1,
+        event_tx.clone(),
+        peer_manager1,
+        node_identity1.clone(),
+        shutdown.to_signal(),
+    );
+    let address = listener.listen().await.unwrap();
+
+    let node_identity2 = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let noise_config2 = NoiseConfig::new(node_identity2.clone());
+    let (request_tx, request_rx) = mpsc::channel(1);
+    let dialer = Dialer::new(
+        ConnectionManagerConfig {
+            address_denylist: vec![AddressPattern::Multiaddr(address.clone())],
+            ..Default::default()
+        },
+        node_identity2,
+        build_peer_manager(),
+        MemoryTransport,
+        noise_config2,
+        ConstantBackoff::new(Duration::from_millis(100)),
+        request_rx,
+        event_tx,
+        shutdown.to_signal(),
+    );
+    let dialer_fut = rt_handle.spawn(dialer.run());
+
+    let mut peer = node_identity1.to_peer();
+    peer.addresses = vec![address].into();
+    peer.set_id_for_test(1);
+
+    let (reply_tx, reply_rx) = oneshot::channel();
+    request_tx
+        .send(DialerRequest::Dial(Box::new(peer), Some(reply_tx), None))
+        .await
+        .unwrap();
+
+    let err = reply_rx.await.unwrap().unwrap_err();
+    unpack_enum!(ConnectionManagerError::NoPermittedAddresses = err);
+
+    shutdown.trigger();
+
+    timeout(Duration::from_secs(5), dialer_fut).await.unwrap().unwrap();
+}
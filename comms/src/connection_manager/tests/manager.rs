@@ -27,8 +27,10 @@ use crate::{
         manager::ConnectionManagerEvent,
         ConnectionManager,
         ConnectionManagerRequester,
+        DialFailure,
         PeerConnectionError,
     },
+    multiaddr::Multiaddr,
     noise::NoiseConfig,
     peer_manager::{NodeId, Peer, PeerFeatures, PeerFlags, PeerManagerError},
     protocol::{ProtocolEvent, ProtocolId, Protocols, IDENTITY_PROTOCOL},
@@ -42,7 +44,10 @@ use crate::{
     transports::{MemoryTransport, TcpTransport},
 };
 use futures::future;
-use std::time::Duration;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use tari_shutdown::Shutdown;
 use tari_test_utils::{collect_try_recv, unpack_enum};
 use tokio::{
@@ -50,6 +55,13 @@ use tokio::{
     runtime::Handle,
     sync::{broadcast, mpsc, oneshot},
 };
+use tracing::{
+    field::{Field, Visit},
+    span,
+    Instrument,
+    Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
 
 #[runtime::test]
 async fn connect_to_nonexistent_peer() {
@@ -84,6 +96,47 @@ async fn connect_to_nonexistent_peer() {
     shutdown.trigger();
 }
 
+#[runtime::test]
+async fn dial_failure_reports_each_address() {
+    let shutdown = Shutdown::new();
+    let node_identity1 = build_node_identity(PeerFeatures::empty());
+    let node_identity2 = build_node_identity(PeerFeatures::empty());
+
+    let peer_manager1 = build_peer_manager();
+    let mut conn_man1 = build_connection_manager(
+        TestNodeConfig {
+            node_identity: node_identity1.clone(),
+            ..Default::default()
+        },
+        MemoryTransport,
+        peer_manager1.clone(),
+        Default::default(),
+        shutdown.to_signal(),
+    );
+    conn_man1.wait_until_listening().await.unwrap();
+
+    // Nothing is listening on any of the peer's addresses
+    let addresses = (0..3)
+        .map(|_| {
+            format!("/memory/{}", MemoryTransport::acquire_next_memsocket_port())
+                .parse::<Multiaddr>()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let mut peer = node_identity2.to_peer();
+    peer.addresses = addresses.clone().into();
+    peer_manager1.add_peer(peer).await.unwrap();
+
+    let err = conn_man1.dial_peer(node_identity2.node_id().clone()).await.unwrap_err();
+    unpack_enum!(ConnectionManagerError::ConnectFailedMaximumAttemptsReached(failure) = err);
+    unpack_enum!(DialFailure::AllAddressesFailed(failures) = failure);
+    assert_eq!(failures.len(), 3);
+    for (failure, address) in failures.iter().zip(&addresses) {
+        assert_eq!(failure.address, *address);
+        unpack_enum!(ConnectionManagerError::TransportError(_err) = &failure.error);
+    }
+}
+
 #[runtime::test]
 async fn dial_success() {
     static TEST_PROTO: ProtocolId = ProtocolId::from_static(b"/test/valid");
@@ -435,3 +488,116 @@ async fn dial_cancelled() {
     assert_eq!(&*node_id, node_identity2.node_id());
     unpack_enum!(ConnectionManagerError::DialCancelled = err);
 }
+
+/// Records the `correlation_id` field of every span, along with the span name
+#[derive(Clone, Default)]
+struct CorrelationIdLayer {
+    recorded: Arc<Mutex<Vec<(&'static str, u64)>>>,
+}
+
+impl CorrelationIdLayer {
+    fn span_names_with(&self, correlation_id: u64) -> Vec<&'static str> {
+        self.recorded
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, id)| *id == correlation_id)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+struct CorrelationIdVisitor(Option<u64>);
+
+impl Visit for CorrelationIdVisitor {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == "correlation_id" {
+            self.0 = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+}
+
+impl<S> Layer<S> for CorrelationIdLayer
+where S: Subscriber + for<'a> LookupSpan<'a>
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, _: &span::Id, _: Context<'_, S>) {
+        let mut visitor = CorrelationIdVisitor(None);
+        attrs.record(&mut visitor);
+        if let Some(correlation_id) = visitor.0 {
+            self.recorded
+                .lock()
+                .unwrap()
+                .push((attrs.metadata().name(), correlation_id));
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let mut visitor = CorrelationIdVisitor(None);
+        values.record(&mut visitor);
+        if let (Some(correlation_id), Some(span)) = (visitor.0, ctx.span(id)) {
+            self.recorded.lock().unwrap().push((span.name(), correlation_id));
+        }
+    }
+}
+
+#[runtime::test]
+async fn dial_correlation_id_propagates_to_substreams() {
+    static TEST_PROTO: ProtocolId = ProtocolId::from_static(b"/test/valid");
+    let layer = CorrelationIdLayer::default();
+    // The test runtime is single-threaded, so the connection manager tasks all use this subscriber
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(layer.clone()));
+    let shutdown = Shutdown::new();
+
+    let node_identity1 = build_node_identity(PeerFeatures::empty());
+    let node_identity2 = build_node_identity(PeerFeatures::empty());
+    let (proto_tx1, _) = mpsc::channel(1);
+    let (proto_tx2, _proto_rx2) = mpsc::channel(1);
+
+    let peer_manager1 = build_peer_manager();
+    let mut protocols = Protocols::new();
+    protocols.add([TEST_PROTO.clone()], proto_tx1);
+    let mut conn_man1 = build_connection_manager(
+        TestNodeConfig {
+            node_identity: node_identity1.clone(),
+            ..Default::default()
+        },
+        MemoryTransport,
+        peer_manager1.clone(),
+        protocols,
+        shutdown.to_signal(),
+    );
+    conn_man1.wait_until_listening().await.unwrap();
+
+    let mut protocols = Protocols::new();
+    protocols.add([TEST_PROTO.clone()], proto_tx2);
+    let mut conn_man2 = build_connection_manager(
+        TestNodeConfig {
+            node_identity: node_identity2.clone(),
+            ..Default::default()
+        },
+        MemoryTransport,
+        build_peer_manager(),
+        protocols,
+        shutdown.to_signal(),
+    );
+    let listener_info = conn_man2.wait_until_listening().await.unwrap();
+    let mut peer2 = node_identity2.to_peer();
+    peer2.addresses = vec![listener_info.bind_address().clone()].into();
+    peer_manager1.add_peer(peer2).await.unwrap();
+
+    let dial_span = tracing::trace_span!("test_dial");
+    let correlation_id = dial_span.id().unwrap().into_u64();
+    let mut conn = conn_man1
+        .dial_peer(node_identity2.node_id().clone())
+        .instrument(dial_span)
+        .await
+        .unwrap();
+    assert_eq!(conn.correlation_id(), Some(correlation_id));
+
+    conn.open_substream(&TEST_PROTO).await.unwrap();
+    let span_names = layer.span_names_with(correlation_id);
+    assert!(span_names.contains(&"handle_dial_peer_request_inner1"));
+    assert!(span_names.contains(&"peer_connection::open_substream"));
+}
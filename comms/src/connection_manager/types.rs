@@ -23,7 +23,7 @@
 use std::fmt;
 
 /// Direction of the connection relative to this node
-#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
 pub enum ConnectionDirection {
     /// Connection listens for incoming connections
     Inbound,
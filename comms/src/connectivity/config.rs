@@ -20,17 +20,22 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use std::time::Duration;
+use crate::utils::multiaddr::multiaddr_to_socketaddr;
+use multiaddr::Multiaddr;
+use std::{cmp, str::FromStr, time::Duration};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct ConnectivityConfig {
     /// The minimum number of connected nodes before connectivity is transitioned to ONLINE
     /// Default: 1
-    pub min_connectivity: usize,
+    pub min_connectivity: MinConnectivity,
     /// Interval to check the connection pool, including reaping inactive connections and retrying failed managed peer
     /// connections. Default: 60s
     pub connection_pool_refresh_interval: Duration,
-    /// True if connection reaping is enabled, otherwise false (default: true)
+    /// True if connection reaping is enabled, otherwise false. When disabled, inactive connections are never closed by
+    /// this node and remain open until the peer disconnects or the connection fails. Failed and disconnected
+    /// connection states are still cleared from the connection pool on each refresh.
+    /// Default: true
     pub is_connection_reaping_enabled: bool,
     /// The minimum age of the connection before it can be reaped. This prevents a connection that has just been
     /// established from being reaped due to inactivity. Default: 20 minutes
@@ -38,20 +43,200 @@ pub struct ConnectivityConfig {
     /// The number of connection failures before a peer is considered offline
     /// Default: 1
     pub max_failures_mark_offline: usize,
+    /// The number of connection failures before a peer is considered offline while this node's own connectivity is
+    /// degraded. Failures during a degraded state are more likely to be caused by this node than the peer, so this
+    /// should be higher than `max_failures_mark_offline`.
+    /// Default: 4
+    pub max_failures_mark_offline_degraded: usize,
+    /// The window of time in which `max_failures_mark_offline` failures must occur for a peer to be marked offline.
+    /// Failures older than this window are discarded.
+    /// Default: 1 hour
+    pub offline_failure_window: Duration,
     /// The length of time to wait before disconnecting a connection that failed tie breaking.
     /// Default: 1s
     pub connection_tie_break_linger: Duration,
+    /// If true, peers that were marked offline while this node's own connectivity was degraded are marked online again
+    /// once connectivity recovers, so that they are promptly retried. Explicit bans are not affected.
+    /// Default: false
+    pub clear_outage_offline_marks: bool,
+    /// The maximum number of dials that may be in progress at the same time. Further dials are queued until an
+    /// in-progress dial completes. This prevents a spike in file descriptors and CPU when many peers are dialed at
+    /// once. Default: None (unlimited)
+    pub max_concurrent_dials: Option<usize>,
+    /// Connections to and from addresses matching any of these patterns are not permitted. New connections on a
+    /// matching address are closed, matching addresses are not dialed and peers whose addresses all match are not
+    /// dialed at all. Default: empty
+    pub address_denylist: Vec<AddressPattern>,
+    /// If not empty, only connections to and from addresses matching one of these patterns are permitted. The deny
+    /// list takes precedence. Default: empty (all addresses are permitted)
+    pub address_allowlist: Vec<AddressPattern>,
+    /// Limits the rate at which new inbound connections are accepted, both in total and from each peer. Excess inbound
+    /// connections are closed. Connections from managed peers, i.e. peers that this node has dialed, are exempt.
+    /// Default: None (unlimited)
+    pub inbound_connection_rate_limit: Option<InboundConnectionRateLimit>,
+    /// The maximum number of connected peers. New connections beyond this are closed. Default: None (unlimited)
+    pub max_connections: Option<usize>,
+    /// The number of `max_connections` slots that are kept free for managed peers, i.e. peers that this node has
+    /// dialed. Connections from other peers may only use the remaining slots. Once all slots are used, a connection
+    /// from a managed peer closes a connection to a peer that is not managed to make room.
+    /// Default: 0
+    pub reserved_managed_slots: usize,
+    /// If true, a new connection to the same canonical host and port as an existing connection to a different peer is
+    /// treated as a duplicate and closed, for e.g. when a peer has rotated its keys. This may merge legitimately
+    /// distinct peers that share an address, e.g. behind a proxy, so it is opt-in.
+    /// Default: false
+    pub dedup_connections_by_address: bool,
+    /// Repeated connection failures for the same peer are logged at most once within this interval, followed by a
+    /// summary of the number of failures once it has elapsed. A zero interval logs every failure.
+    /// Default: 5 minutes
+    pub connect_failure_log_interval: Duration,
+    /// The time that disconnecting all peers is expected to take once the shutdown signal is triggered. A warning is
+    /// logged if it takes longer, as this indicates that some connections are stuck.
+    /// Default: 10s
+    pub shutdown_grace_period: Duration,
+    /// The number of connectivity status transitions that are kept and returned by
+    /// `ConnectivityRequester::get_status_history`. The oldest transitions are discarded first.
+    /// Default: 50
+    pub status_history_size: usize,
+}
+
+impl ConnectivityConfig {
+    /// Returns true if connections to and from the address are permitted by the address allow and deny lists
+    pub fn is_address_permitted(&self, address: &Multiaddr) -> bool {
+        is_address_permitted(&self.address_denylist, &self.address_allowlist, address)
+    }
+
+    /// Returns true if an address allow or deny list is set
+    pub fn has_address_filter(&self) -> bool {
+        !self.address_denylist.is_empty() || !self.address_allowlist.is_empty()
+    }
 }
 
 impl Default for ConnectivityConfig {
     fn default() -> Self {
         Self {
-            min_connectivity: 1,
+            min_connectivity: MinConnectivity::Absolute(1),
             connection_pool_refresh_interval: Duration::from_secs(60),
             reaper_min_inactive_age: Duration::from_secs(20 * 60),
             is_connection_reaping_enabled: true,
             max_failures_mark_offline: 2,
+            max_failures_mark_offline_degraded: 4,
+            offline_failure_window: Duration::from_secs(60 * 60),
             connection_tie_break_linger: Duration::from_secs(2),
+            clear_outage_offline_marks: false,
+            max_concurrent_dials: None,
+            address_denylist: Vec::new(),
+            address_allowlist: Vec::new(),
+            inbound_connection_rate_limit: None,
+            max_connections: None,
+            reserved_managed_slots: 0,
+            dedup_connections_by_address: false,
+            connect_failure_log_interval: Duration::from_secs(5 * 60),
+            shutdown_grace_period: Duration::from_secs(10),
+            status_history_size: 50,
+        }
+    }
+}
+
+/// Token bucket parameters for the inbound connection rate limit. All inbound connections share one token bucket, so
+/// that a flood from many identities is limited. Each peer also has a smaller token bucket of its own, so that a single
+/// peer cannot use up the shared bucket and can be banned.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InboundConnectionRateLimit {
+    /// The sustained number of new inbound connections accepted per second from all peers
+    pub connections_per_second: f32,
+    /// The number of new inbound connections that may be accepted from all peers at once before the sustained rate
+    /// applies
+    pub burst: usize,
+    /// The sustained number of new inbound connections accepted per second from a single peer
+    pub peer_connections_per_second: f32,
+    /// The number of new inbound connections that may be accepted from a single peer at once before the sustained
+    /// rate applies
+    pub peer_burst: usize,
+    /// If set, peers whose inbound connections exceed their own rate limit are banned for this duration
+    pub ban_duration: Option<Duration>,
+}
+
+/// A pattern that addresses are matched against in the connectivity address allow and deny lists
+#[derive(Debug, Clone, PartialEq)]
+pub enum AddressPattern {
+    /// Matches TCP/IP addresses within the IP range, e.g. `10.0.0.0/8`
+    Cidr(cidr::AnyIpCidr),
+    /// Matches addresses that begin with the multiaddr, e.g. `/ip4/10.0.0.1` matches `/ip4/10.0.0.1/tcp/18141`
+    Multiaddr(Multiaddr),
+}
+
+impl AddressPattern {
+    pub fn matches(&self, address: &Multiaddr) -> bool {
+        match self {
+            AddressPattern::Cidr(cidr) => multiaddr_to_socketaddr(address)
+                .map(|socket_addr| cidr.contains(&socket_addr.ip()))
+                .unwrap_or(false),
+            AddressPattern::Multiaddr(prefix) => {
+                let mut components = address.iter();
+                prefix
+                    .iter()
+                    .all(|protocol| components.next().map(|c| c == protocol).unwrap_or(false))
+            },
+        }
+    }
+}
+
+impl FromStr for AddressPattern {
+    type Err = String;
+
+    /// Parses a multiaddr if the string starts with `/`, otherwise a CIDR
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with('/') {
+            s.parse()
+                .map(AddressPattern::Multiaddr)
+                .map_err(|err| format!("Invalid multiaddr pattern '{}': {}", s, err))
+        } else {
+            cidr::AnyIpCidr::from_str(s)
+                .map(AddressPattern::Cidr)
+                .map_err(|err| format!("Invalid CIDR pattern '{}': {}", s, err))
         }
     }
 }
+
+/// Returns true if the address matches none of the `denylist` patterns and, if the `allowlist` is not empty, matches
+/// one of the `allowlist` patterns
+pub(crate) fn is_address_permitted(
+    denylist: &[AddressPattern],
+    allowlist: &[AddressPattern],
+    address: &Multiaddr,
+) -> bool {
+    if denylist.iter().any(|pattern| pattern.matches(address)) {
+        return false;
+    }
+    allowlist.is_empty() || allowlist.iter().any(|pattern| pattern.matches(address))
+}
+
+/// The number of connected nodes required for connectivity to be ONLINE
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MinConnectivity {
+    /// A fixed number of connected nodes
+    Absolute(usize),
+    /// A fraction (0.0 to 1.0) of the managed peers, i.e. the peers that this node has dialed and that have not since
+    /// disconnected or been marked offline, banned or reaped. At least one connected node is always required.
+    Fraction(f32),
+}
+
+impl MinConnectivity {
+    /// Returns the number of connected nodes required, given the number of managed peers
+    pub fn threshold(&self, num_managed_peers: usize) -> usize {
+        match *self {
+            MinConnectivity::Absolute(n) => n,
+            MinConnectivity::Fraction(fraction) => {
+                let n = (f64::from(fraction.max(0.0).min(1.0)) * num_managed_peers as f64).ceil() as usize;
+                cmp::max(n, 1)
+            },
+        }
+    }
+}
+
+impl From<usize> for MinConnectivity {
+    fn from(n: usize) -> Self {
+        MinConnectivity::Absolute(n)
+    }
+}
@@ -20,11 +20,11 @@
 // WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use crate::utils::datetime::format_duration;
+use crate::{peer_manager::NodeId, utils::datetime::format_duration};
 use std::{
     fmt,
     fmt::{Display, Formatter},
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 #[derive(Debug, Clone, Default, PartialEq)]
@@ -34,6 +34,12 @@ pub struct PeerConnectionStats {
     pub last_connected_at: Option<Instant>,
     /// Represents the last connection attempt
     pub last_connection_attempt: LastConnectionAttempt,
+    /// Timestamps of the failed connection attempts since the last successful connection
+    pub failed_attempts_at: Vec<Instant>,
+    /// The last time a failure (e.g. an RPC or application-level failure) was reported for a connected peer
+    pub last_failure_reported_at: Option<Instant>,
+    /// Timestamps of the failures reported for the peer while connected
+    pub failures_reported_at: Vec<Instant>,
 }
 
 impl PeerConnectionStats {
@@ -45,6 +51,7 @@ impl PeerConnectionStats {
     pub fn set_connection_success(&mut self) {
         self.last_connected_at = Some(Instant::now());
         self.last_connection_attempt = LastConnectionAttempt::Succeeded(Instant::now());
+        self.failed_attempts_at.clear();
     }
 
     /// Sets the last connection as a failure
@@ -53,6 +60,60 @@ impl PeerConnectionStats {
             failed_at: Instant::now(),
             num_attempts: self.failed_attempts() + 1,
         };
+        self.failed_attempts_at.push(Instant::now());
+    }
+
+    /// Records a failure that occurred while the peer was connected
+    pub fn set_failure_reported(&mut self) {
+        let now = Instant::now();
+        self.last_failure_reported_at = Some(now);
+        self.failures_reported_at.push(now);
+    }
+
+    /// Returns true if a connection attempt failed or a failure was reported within `window`
+    pub fn has_failed_within(&self, window: Duration) -> bool {
+        self.last_failed_at()
+            .into_iter()
+            .chain(self.last_failure_reported_at)
+            .any(|failed_at| failed_at.elapsed() <= window)
+    }
+
+    /// Discards failed attempt timestamps that are older than `window`
+    pub fn expire_failed_attempts(&mut self, window: Duration) {
+        self.failed_attempts_at
+            .retain(|failed_at| failed_at.elapsed() <= window);
+        self.failures_reported_at
+            .retain(|failed_at| failed_at.elapsed() <= window);
+    }
+
+    /// Returns the number of failed attempts that occurred within `window`
+    pub fn failed_attempts_within(&self, window: Duration) -> usize {
+        self.failed_attempts_at
+            .iter()
+            .filter(|failed_at| failed_at.elapsed() <= window)
+            .count()
+    }
+
+    /// Returns the number of failed connection attempts and reported failures that occurred within `window`
+    pub fn failures_within(&self, window: Duration) -> usize {
+        self.failed_attempts_within(window) +
+            self.failures_reported_at
+                .iter()
+                .filter(|failed_at| failed_at.elapsed() <= window)
+                .count()
+    }
+
+    /// Returns a score between 0.0 and 1.0 for the quality of a connection to this peer, higher is better.
+    ///
+    /// `score = 0.5 * min(uptime / 1 hour, 1) + 0.5 / (1 + failures)`
+    ///
+    /// where `uptime` is the age of the current connection and `failures` is the number of failed connection attempts
+    /// and reported failures within `failure_window`.
+    pub fn quality_score(&self, uptime: Duration, failure_window: Duration) -> f32 {
+        const UPTIME_SATURATION: Duration = Duration::from_secs(60 * 60);
+        let uptime_score = (uptime.as_secs_f32() / UPTIME_SATURATION.as_secs_f32()).min(1.0);
+        let failure_score = 1.0 / (1.0 + self.failures_within(failure_window) as f32);
+        0.5 * uptime_score + 0.5 * failure_score
     }
 
     /// Returns the number of failed attempts. 0 is returned if the `last_connection_attempt` is not `Failed`
@@ -93,6 +154,37 @@ impl fmt::Display for PeerConnectionStats {
     }
 }
 
+/// Statistics for a connected peer, used to rank peers by connection quality
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerStats {
+    pub node_id: NodeId,
+    /// The age of the current connection to the peer
+    pub uptime: Duration,
+    /// The number of failed connection attempts and reported failures within the offline failure window
+    pub failures: usize,
+    /// See [PeerConnectionStats::quality_score]
+    pub quality_score: f32,
+}
+
+/// Counts of the tie breaks that were needed because a second connection to an already connected peer was established.
+/// Frequent tie breaks indicate that this node and its peers are dialing each other simultaneously, for e.g. because of
+/// NAT issues.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TieBreakStats {
+    /// The number of tie breaks between two live connections that were resolved by comparing node ids
+    pub num_tie_breaks: usize,
+    /// The number of those tie breaks that kept the new connection
+    pub num_new_kept: usize,
+    /// The number of those tie breaks that kept the existing connection
+    pub num_existing_kept: usize,
+    /// The number of times an existing connection that was at least 60s old was replaced because the peer connected
+    /// again
+    pub num_stale_replaced: usize,
+    /// The number of connections that the peer closed shortly after they were established, most likely because the
+    /// peer resolved a tie break in favour of another connection
+    pub num_lost_to_peer: usize,
+}
+
 /// Peer connection statistics
 #[derive(Debug, Clone, PartialOrd, PartialEq)]
 pub enum LastConnectionAttempt {
@@ -159,9 +251,11 @@ mod test {
         state.set_connection_failed();
         assert!(state.last_failed_at().is_some());
         assert_eq!(state.failed_attempts(), 3);
+        assert_eq!(state.failed_attempts_within(Duration::from_secs(60)), 3);
 
         state.set_connection_success();
         assert_eq!(state.failed_attempts(), 0);
+        assert_eq!(state.failed_attempts_within(Duration::from_secs(60)), 0);
         assert!(state.last_failed_at().is_none());
     }
 }
@@ -0,0 +1,160 @@
+// Copyright 2021, The Tari Project
+//
+// Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+// following conditions are met:
+//
+// 1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+// disclaimer.
+//
+// 2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+// following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+// 3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+// products derived from this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+// INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+// DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+// SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+// SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+// WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+// USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+use crate::peer_manager::NodeId;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Collapses repeated connection failures for the same peer, so that at most one line is logged per peer within each
+/// `interval`. Failures that occur within the interval are counted and reported as a summary once it has elapsed.
+pub(super) struct ConnectFailureLog {
+    interval: Duration,
+    entries: HashMap<NodeId, FailureLogEntry>,
+}
+
+struct FailureLogEntry {
+    window_start: Instant,
+    suppressed: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum FailureLogAction {
+    /// Log the failure
+    Log,
+    /// Log a summary of the `failures` (including this one) that occurred within `period`
+    Summarize { failures: usize, period: Duration },
+    /// The failure has been counted and should not be logged
+    Suppress,
+}
+
+impl ConnectFailureLog {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Records a connection failure for `node_id` at `now` and returns how it should be logged
+    pub fn record(&mut self, node_id: &NodeId, now: Instant) -> FailureLogAction {
+        match self.entries.get_mut(node_id) {
+            Some(entry) => {
+                let period = now.saturating_duration_since(entry.window_start);
+                if period < self.interval {
+                    entry.suppressed += 1;
+                    return FailureLogAction::Suppress;
+                }
+                let failures = entry.suppressed + 1;
+                entry.window_start = now;
+                entry.suppressed = 0;
+                if failures > 1 {
+                    FailureLogAction::Summarize { failures, period }
+                } else {
+                    FailureLogAction::Log
+                }
+            },
+            None => {
+                self.entries.insert(node_id.clone(), FailureLogEntry {
+                    window_start: now,
+                    suppressed: 0,
+                });
+                FailureLogAction::Log
+            },
+        }
+    }
+
+    /// Forgets the failures of `node_id`, e.g. after a successful connection
+    pub fn clear(&mut self, node_id: &NodeId) {
+        self.entries.remove(node_id);
+    }
+
+    /// Removes the peers that have not failed for more than an interval, returning the number of failures that were
+    /// suppressed and will not be reported.
+    pub fn prune(&mut self, now: Instant) -> usize {
+        let interval = self.interval;
+        let mut dropped = 0;
+        self.entries.retain(|_, entry| {
+            let is_expired = now.saturating_duration_since(entry.window_start) >= interval * 2;
+            if is_expired {
+                dropped += entry.suppressed;
+            }
+            !is_expired
+        });
+        dropped
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::node_id;
+
+    #[test]
+    fn repeated_failures_are_collapsed() {
+        let interval = Duration::from_secs(5 * 60);
+        let mut log = ConnectFailureLog::new(interval);
+        let node_id = node_id::random();
+        let start = Instant::now();
+
+        assert_eq!(log.record(&node_id, start), FailureLogAction::Log);
+        let actions = (1..=13)
+            .map(|i| log.record(&node_id, start + Duration::from_secs(i * 20)))
+            .collect::<Vec<_>>();
+        assert!(actions.iter().all(|a| *a == FailureLogAction::Suppress));
+
+        assert_eq!(log.record(&node_id, start + interval), FailureLogAction::Summarize {
+            failures: 14,
+            period: interval
+        });
+        // A single failure in the next interval is logged as is
+        assert_eq!(log.record(&node_id, start + interval * 2), FailureLogAction::Log);
+
+        let other_node_id = node_id::random();
+        assert_eq!(log.record(&other_node_id, start), FailureLogAction::Log);
+        assert_eq!(log.len(), 2);
+
+        log.clear(&node_id);
+        assert_eq!(log.record(&node_id, start + interval * 2), FailureLogAction::Log);
+    }
+
+    #[test]
+    fn prune_drops_quiet_peers() {
+        let interval = Duration::from_secs(60);
+        let mut log = ConnectFailureLog::new(interval);
+        let node_id = node_id::random();
+        let start = Instant::now();
+        log.record(&node_id, start);
+        log.record(&node_id, start + Duration::from_secs(1));
+
+        assert_eq!(log.prune(start + interval), 0);
+        assert_eq!(log.len(), 1);
+        assert_eq!(log.prune(start + interval * 2), 1);
+        assert_eq!(log.len(), 0);
+    }
+}
@@ -22,9 +22,10 @@
 use super::{
     config::ConnectivityConfig,
     connection_pool::{ConnectionPool, ConnectionStatus},
-    connection_stats::PeerConnectionStats,
+    connection_stats::{PeerConnectionStats, PeerStats, TieBreakStats},
     error::ConnectivityError,
-    requester::{ConnectivityEvent, ConnectivityRequest},
+    failure_log::{ConnectFailureLog, FailureLogAction},
+    requester::{ConnectivityEvent, ConnectivityRequest, ShutdownReport},
     selection::ConnectivitySelection,
 };
 use crate::{
@@ -33,29 +34,59 @@ use crate::{
         ConnectionManagerError,
         ConnectionManagerEvent,
         ConnectionManagerRequester,
+        PeerConnectionError,
     },
     connectivity::ConnectivityEventTx,
-    peer_manager::NodeId,
+    multiaddr::Multiaddr,
+    peer_manager::{NodeId, PeerQuery},
     runtime::task,
-    utils::datetime::format_duration,
+    utils::{datetime::format_duration, multiaddr::canonicalize_multiaddr},
     NodeIdentity,
     PeerConnection,
     PeerManager,
 };
+use chrono::{DateTime, Utc};
+use futures::{future, future::BoxFuture, stream::FuturesUnordered, StreamExt};
 use log::*;
 use nom::lib::std::collections::hash_map::Entry;
 use std::{
-    collections::HashMap,
+    cmp,
+    collections::{HashMap, HashSet, VecDeque},
     fmt,
+    mem,
     sync::Arc,
     time::{Duration, Instant},
 };
 use tari_shutdown::ShutdownSignal;
-use tokio::{sync::mpsc, task::JoinHandle, time, time::MissedTickBehavior};
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+    time,
+    time::MissedTickBehavior,
+};
 use tracing::{span, Instrument, Level};
 
 const LOG_TARGET: &str = "comms::connectivity::manager";
 
+type DialReplyTx = oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>;
+
+/// The reply of the connection manager to a dial, which is resolved by the actor before it is passed on to the dialer
+struct DialReply {
+    node_id: NodeId,
+    /// None if the connection manager dropped the reply without sending it
+    result: Option<Result<PeerConnection, ConnectionManagerError>>,
+    reply_tx: Option<DialReplyTx>,
+    is_retry: bool,
+}
+
+type LivenessCheck = BoxFuture<'static, (PeerConnection, Result<(), PeerConnectionError>)>;
+
+/// The time a peer has to respond to a liveness check after the local network has changed
+const NETWORK_CHANGED_LIVENESS_TIMEOUT: Duration = Duration::from_secs(10);
+/// The maximum number of peers whose inbound connection rate is tracked at once. Idle peers are evicted first, and
+/// new peers are only limited by the shared rate limit while the maximum is reached.
+const MAX_INBOUND_RATE_LIMITED_PEERS: usize = 1000;
+
 /// # Connectivity Manager
 ///
 /// The ConnectivityManager actor is responsible for tracking the state of all peer
@@ -79,7 +110,9 @@ pub struct ConnectivityManager {
 }
 
 impl ConnectivityManager {
-    pub fn spawn(self) -> JoinHandle<()> {
+    pub fn spawn(self) -> JoinHandle<ShutdownReport> {
+        let connect_failure_log = ConnectFailureLog::new(self.config.connect_failure_log_interval);
+        let (dial_reply_tx, dial_reply_rx) = mpsc::unbounded_channel();
         ConnectivityManagerActor {
             config: self.config,
             status: ConnectivityStatus::Initializing,
@@ -88,9 +121,26 @@ impl ConnectivityManager {
             peer_manager: self.peer_manager.clone(),
             event_tx: self.event_tx,
             connection_stats: HashMap::new(),
+            managed_peers: HashSet::new(),
+            dialed_primary_addresses: HashMap::new(),
             node_identity: self.node_identity,
             pool: ConnectionPool::new(),
             shutdown_signal: self.shutdown_signal,
+            status_override: None,
+            accept_inbound: true,
+            shutdown_report: None,
+            outage_offline_marks: HashSet::new(),
+            tie_break_stats: TieBreakStats::default(),
+            dials_in_flight: HashSet::new(),
+            dial_queue: VecDeque::new(),
+            dial_reply_tx,
+            dial_reply_rx,
+            cancelled_dials: HashSet::new(),
+            liveness_checks: FuturesUnordered::new(),
+            inbound_rate_limiter: None,
+            peer_inbound_rate_limiters: HashMap::new(),
+            connect_failure_log,
+            status_history: VecDeque::new(),
         }
         .spawn()
     }
@@ -134,6 +184,23 @@ impl fmt::Display for ConnectivityStatus {
     }
 }
 
+/// A change of the connectivity status, e.g. from Online to Degraded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectivityStatusTransition {
+    pub timestamp: DateTime<Utc>,
+    pub from: ConnectivityStatus,
+    pub to: ConnectivityStatus,
+}
+
+/// A peer that is currently banned, see `ConnectivityRequester::get_banned_peers`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BannedPeer {
+    pub node_id: NodeId,
+    /// The time left until the ban expires
+    pub remaining: Duration,
+    pub reason: String,
+}
+
 struct ConnectivityManagerActor {
     config: ConnectivityConfig,
     status: ConnectivityStatus,
@@ -143,18 +210,50 @@ struct ConnectivityManagerActor {
     peer_manager: Arc<PeerManager>,
     event_tx: ConnectivityEventTx,
     connection_stats: HashMap<NodeId, PeerConnectionStats>,
+    /// Peers that this node has dialed, until they disconnect or are marked offline, banned or reaped. Unlike the
+    /// pool, this retains peers whose dials have failed.
+    managed_peers: HashSet<NodeId>,
+    /// The primary address of each peer being dialed, as it was before the dial
+    dialed_primary_addresses: HashMap<NodeId, Multiaddr>,
     pool: ConnectionPool,
     shutdown_signal: ShutdownSignal,
+    status_override: Option<ConnectivityStatus>,
+    accept_inbound: bool,
+    /// Set once the actor has been shut down by a `Shutdown` request
+    shutdown_report: Option<ShutdownReport>,
+    outage_offline_marks: HashSet<NodeId>,
+    tie_break_stats: TieBreakStats,
+    dials_in_flight: HashSet<NodeId>,
+    dial_queue: VecDeque<(NodeId, Option<DialReplyTx>)>,
+    dial_reply_tx: mpsc::UnboundedSender<DialReply>,
+    dial_reply_rx: mpsc::UnboundedReceiver<DialReply>,
+    /// Peers whose dials were cancelled by a `CancelDial` request since they were last dialed
+    cancelled_dials: HashSet<NodeId>,
+    /// Liveness checks of the active connections that were started because the local network changed
+    liveness_checks: FuturesUnordered<LivenessCheck>,
+    /// The inbound connection rate limit shared by all peers
+    inbound_rate_limiter: Option<TokenBucket>,
+    /// The inbound connection rate limit of each peer that recently connected to us
+    peer_inbound_rate_limiters: HashMap<NodeId, TokenBucket>,
+    connect_failure_log: ConnectFailureLog,
+    status_history: VecDeque<ConnectivityStatusTransition>,
 }
 
 impl ConnectivityManagerActor {
-    pub fn spawn(self) -> JoinHandle<()> {
+    pub fn spawn(self) -> JoinHandle<ShutdownReport> {
         task::spawn(Self::run(self))
     }
 
+    /// Runs the actor until it is shut down, returning the summary of the disconnected connections
     #[tracing::instrument(name = "connectivity_manager_actor::run", skip(self))]
-    pub async fn run(mut self) {
+    pub async fn run(mut self) -> ShutdownReport {
         info!(target: LOG_TARGET, "ConnectivityManager started");
+        if !self.config.is_connection_reaping_enabled {
+            info!(
+                target: LOG_TARGET,
+                "Connection reaping is disabled. Inactive connections will remain open until closed by the peer"
+            );
+        }
 
         let mut connection_manager_events = self.connection_manager.get_event_subscription();
 
@@ -174,6 +273,9 @@ impl ConnectivityManagerActor {
             tokio::select! {
                 Some(req) = self.request_rx.recv() => {
                     self.handle_request(req).await;
+                    if let Some(report) = self.shutdown_report {
+                        break report;
+                    }
                 },
 
                 event = connection_manager_events.recv() => {
@@ -181,9 +283,18 @@ impl ConnectivityManagerActor {
                         if let Err(err) = self.handle_connection_manager_event(&event).await {
                             error!(target:LOG_TARGET, "Error handling connection manager event: {:?}", err);
                         }
+                        self.complete_dial(&event).await;
                     }
                 },
 
+                Some(reply) = self.dial_reply_rx.recv() => {
+                    self.handle_dial_reply(reply).await;
+                },
+
+                Some((conn, result)) = self.liveness_checks.next() => {
+                    self.handle_liveness_check_result(conn, result).await;
+                },
+
                 _ = ticker.tick() => {
                     if let Err(err) = self.refresh_connection_pool().await {
                         error!(target: LOG_TARGET, "Error when refreshing connection pools: {:?}", err);
@@ -192,8 +303,7 @@ impl ConnectivityManagerActor {
 
                 _ = self.shutdown_signal.wait() => {
                     info!(target: LOG_TARGET, "ConnectivityManager is shutting down because it received the shutdown signal");
-                    self.disconnect_all().await;
-                    break;
+                    break self.disconnect_all().await;
                 }
             }
         }
@@ -207,7 +317,7 @@ impl ConnectivityManagerActor {
                 let _ = reply.send(());
             },
             GetConnectivityStatus(reply) => {
-                let _ = reply.send(self.status);
+                let _ = reply.send(self.status_override.unwrap_or(self.status));
             },
             DialPeer {
                 node_id,
@@ -218,6 +328,7 @@ impl ConnectivityManagerActor {
                 // let _e = span.enter();
                 span.follows_from(tracing_id);
                 async move {
+                    self.cancelled_dials.remove(&node_id);
                     match self.pool.get(&node_id) {
                         Some(state) if state.is_connected() => {
                             debug!(
@@ -235,21 +346,41 @@ impl ConnectivityManagerActor {
                                 "No existing connection found for peer `{}`. Dialing...",
                                 node_id.short_str()
                             );
-                            if let Err(err) = self.connection_manager.send_dial_peer(node_id, reply_tx).await {
-                                error!(
-                                    target: LOG_TARGET,
-                                    "Failed to send dial request to connection manager: {:?}", err
-                                );
-                            }
+                            self.dispatch_dial(node_id, reply_tx).await;
                         },
                     }
                 }
                 .instrument(span)
                 .await
             },
+            CancelDial(node_id) => {
+                debug!(target: LOG_TARGET, "Cancelling dial to peer `{}`", node_id.short_str());
+                self.cancelled_dials.insert(node_id.clone());
+                let (cancelled, queued) = mem::take(&mut self.dial_queue)
+                    .into_iter()
+                    .partition::<VecDeque<_>, _>(|(queued_node_id, _)| *queued_node_id == node_id);
+                self.dial_queue = queued;
+                for reply_tx in cancelled.into_iter().filter_map(|(_, reply_tx)| reply_tx) {
+                    let _ = reply_tx.send(Err(ConnectionManagerError::DialCancelled));
+                }
+                if let Err(err) = self.connection_manager.cancel_dial(node_id).await {
+                    error!(
+                        target: LOG_TARGET,
+                        "Failed to send cancel dial request to connection manager: {:?}", err
+                    );
+                }
+            },
             SelectConnections(selection, reply) => {
                 let _ = reply.send(self.select_connections(selection).await);
             },
+            GetSelectionCandidates(selection, reply) => {
+                let candidates = selection
+                    .select(&self.pool, &self.connection_stats)
+                    .into_iter()
+                    .map(|conn| conn.peer_node_id().clone())
+                    .collect();
+                let _ = reply.send(candidates);
+            },
             GetConnection(node_id, reply) => {
                 let _ = reply.send(
                     self.pool
@@ -260,6 +391,15 @@ impl ConnectivityManagerActor {
                         .cloned(),
                 );
             },
+            GetConnectionByAddress(address, reply) => {
+                let _ = reply.send(
+                    self.pool
+                        .filter_connection_states(|c| c.status() == ConnectionStatus::Connected)
+                        .into_iter()
+                        .find(|conn| conn.is_connected() && *conn.address() == address)
+                        .cloned(),
+                );
+            },
             GetAllConnectionStates(reply) => {
                 let states = self.pool.all().into_iter().cloned().collect();
                 let _ = reply.send(states);
@@ -269,6 +409,51 @@ impl ConnectivityManagerActor {
                     error!(target: LOG_TARGET, "Error when banning peer: {:?}", err);
                 }
             },
+            SetStatusOverride(status_override) => {
+                self.set_status_override(status_override);
+            },
+            SetAcceptInbound(accept_inbound) => {
+                if accept_inbound {
+                    info!(target: LOG_TARGET, "Accepting new inbound connections");
+                } else {
+                    info!(
+                        target: LOG_TARGET,
+                        "No longer accepting new inbound connections. Existing connections are unaffected."
+                    );
+                }
+                self.accept_inbound = accept_inbound;
+            },
+            ReportPeerFailure(node_id) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Failure reported for peer `{}`",
+                    node_id.short_str()
+                );
+                self.get_connection_stat_mut(node_id).set_failure_reported();
+            },
+            GetPeerStats(reply) => {
+                let _ = reply.send(self.get_peer_stats());
+            },
+            GetTieBreakStats(reply) => {
+                let _ = reply.send(self.tie_break_stats);
+            },
+            GetStatusHistory(reply) => {
+                let _ = reply.send(self.status_history.iter().copied().collect());
+            },
+            GetBannedPeers(reply) => {
+                let _ = reply.send(self.get_banned_peers().await);
+            },
+            NetworkChanged => {
+                info!(
+                    target: LOG_TARGET,
+                    "Network change reported. Checking existing connections and redialing disconnected peers"
+                );
+                self.handle_network_changed().await;
+            },
+            Shutdown { timeout, reply } => {
+                let report = self.shutdown_gracefully(timeout).await;
+                let _ = reply.send(report);
+            },
             GetActiveConnections(reply) => {
                 let _ = reply.send(
                     self.pool
@@ -281,15 +466,241 @@ impl ConnectivityManagerActor {
         }
     }
 
-    async fn disconnect_all(&mut self) {
+    /// Checks the liveness of every active connection, disconnecting and redialing peers that do not respond, and
+    /// redials peers in the pool that are not connected.
+    async fn handle_network_changed(&mut self) {
+        for conn in self.pool.filter_connection_states(|s| s.is_connected()) {
+            let mut conn = conn.clone();
+            self.liveness_checks.push(Box::pin(async move {
+                let result = conn.check_liveness(NETWORK_CHANGED_LIVENESS_TIMEOUT).await;
+                (conn, result)
+            }));
+        }
+
+        let node_ids = self
+            .pool
+            .all()
+            .into_iter()
+            .filter(|state| {
+                matches!(
+                    state.status(),
+                    ConnectionStatus::Disconnected | ConnectionStatus::Failed
+                )
+            })
+            .filter(|state| state.connection().map(|c| c.peer_features().is_node()).unwrap_or(true))
+            .map(|state| state.node_id().clone())
+            .collect::<Vec<_>>();
+        for node_id in node_ids {
+            self.dispatch_dial(node_id, None).await;
+        }
+    }
+
+    /// Disconnects and redials a peer that did not respond to a liveness check after a network change
+    async fn handle_liveness_check_result(
+        &mut self,
+        mut conn: PeerConnection,
+        result: Result<(), PeerConnectionError>,
+    ) {
+        let err = match result {
+            Ok(_) => return,
+            Err(err) => err,
+        };
+        debug!(
+            target: LOG_TARGET,
+            "Peer `{}` failed liveness check after network change: {}. Reconnecting.",
+            conn.peer_node_id().short_str(),
+            err
+        );
+        let _ = conn.disconnect().await;
+        self.dispatch_dial(conn.peer_node_id().clone(), None).await;
+    }
+
+    /// Sends a dial request to the connection manager, or queues it if `max_concurrent_dials` dials are in progress.
+    async fn dispatch_dial(&mut self, node_id: NodeId, reply_tx: Option<DialReplyTx>) {
+        if !self.is_dial_permitted(&node_id).await {
+            debug!(
+                target: LOG_TARGET,
+                "Not dialing peer `{}` because none of its addresses are permitted",
+                node_id.short_str()
+            );
+            if let Some(reply_tx) = reply_tx {
+                let _ = reply_tx.send(Err(ConnectionManagerError::NoPermittedAddresses));
+            }
+            return;
+        }
+        let is_at_limit = self
+            .config
+            .max_concurrent_dials
+            .map(|max| self.dials_in_flight.len() >= max)
+            .unwrap_or(false);
+        // A dial to a peer that is already being dialed is merged with the in-progress dial by the connection manager
+        if is_at_limit && !self.dials_in_flight.contains(&node_id) {
+            debug!(
+                target: LOG_TARGET,
+                "{} dial(s) in progress. Queuing dial to peer `{}`",
+                self.dials_in_flight.len(),
+                node_id.short_str()
+            );
+            self.dial_queue.push_back((node_id, reply_tx));
+            return;
+        }
+        self.send_dial(node_id, reply_tx, false).await;
+    }
+
+    /// Returns false if the peer has addresses and none of them are permitted by the address allow and deny lists
+    async fn is_dial_permitted(&self, node_id: &NodeId) -> bool {
+        if !self.config.has_address_filter() {
+            return true;
+        }
+        match self.peer_manager.find_by_node_id(node_id).await {
+            Ok(peer) => {
+                peer.addresses.is_empty() || peer.addresses.iter().any(|addr| self.config.is_address_permitted(addr))
+            },
+            // Let the connection manager reply with the failure
+            Err(_) => true,
+        }
+    }
+
+    async fn send_dial(&mut self, node_id: NodeId, reply_tx: Option<DialReplyTx>, is_retry: bool) {
+        self.managed_peers.insert(node_id.clone());
+        if let Ok(peer) = self.peer_manager.find_by_node_id(&node_id).await {
+            if let Some(addr) = peer.addresses.first() {
+                self.dialed_primary_addresses
+                    .insert(node_id.clone(), addr.address.clone());
+            }
+        }
+        if self.config.max_concurrent_dials.is_some() {
+            self.dials_in_flight.insert(node_id.clone());
+        }
+        let reply_tx = self.track_dial_reply(node_id.clone(), reply_tx, is_retry);
+        if let Err(err) = self
+            .connection_manager
+            .send_dial_peer(node_id.clone(), Some(reply_tx))
+            .await
+        {
+            error!(
+                target: LOG_TARGET,
+                "Failed to send dial request to connection manager: {:?}", err
+            );
+            self.dials_in_flight.remove(&node_id);
+        }
+    }
+
+    /// Returns a reply sender that passes the outcome of the dial back to the actor, which resolves it and replies to
+    /// `reply_tx`. Not every dial outcome is published as an event, e.g. a dial to a peer that is not in the peer
+    /// manager is only replied to.
+    fn track_dial_reply(&self, node_id: NodeId, reply_tx: Option<DialReplyTx>, is_retry: bool) -> DialReplyTx {
+        let (tracked_tx, tracked_rx) = oneshot::channel();
+        let dial_reply_tx = self.dial_reply_tx.clone();
+        task::spawn(async move {
+            // The reply is dropped without being sent if the connection manager shuts down
+            let result = tracked_rx.await.ok();
+            let _ = dial_reply_tx.send(DialReply {
+                node_id,
+                result,
+                reply_tx,
+                is_retry,
+            });
+        });
+        tracked_tx
+    }
+
+    /// Releases the dial slot of the peer and replies to the dialer. A dial that was cancelled without a `CancelDial`
+    /// request is resolved to the peer's connection, or retried once.
+    async fn handle_dial_reply(&mut self, reply: DialReply) {
+        let DialReply {
+            node_id,
+            result,
+            reply_tx,
+            is_retry,
+        } = reply;
+        let result = match result {
+            Some(Err(ConnectionManagerError::DialCancelled)) if !self.cancelled_dials.contains(&node_id) => {
+                // Due to simultaneous dialing, the dial may be cancelled because the peer connected to us
+                let existing_conn = self
+                    .pool
+                    .get_connection(&node_id)
+                    .filter(|conn| conn.is_connected())
+                    .cloned();
+                match existing_conn {
+                    Some(conn) => Some(Ok(conn)),
+                    // The connection may not have been reported yet, in which case dialing again typically returns it
+                    None if !is_retry && reply_tx.is_some() => {
+                        debug!(
+                            target: LOG_TARGET,
+                            "Dial to peer `{}` was cancelled without a request. Retrying...",
+                            node_id.short_str()
+                        );
+                        self.send_dial(node_id, reply_tx, true).await;
+                        return;
+                    },
+                    None => Some(Err(ConnectionManagerError::DialCancelled)),
+                }
+            },
+            result => result,
+        };
+        self.release_dial_slot(&node_id).await;
+        if let (Some(result), Some(reply_tx)) = (result, reply_tx) {
+            let _ = reply_tx.send(result);
+        }
+    }
+
+    /// Marks the dial to the peer as complete once the connection manager reports its outcome
+    async fn complete_dial(&mut self, event: &ConnectionManagerEvent) {
+        use ConnectionManagerEvent::*;
+        let node_id = match event {
+            PeerConnected(conn) => conn.peer_node_id(),
+            PeerConnectFailed(node_id, _) => node_id,
+            _ => return,
+        };
+        self.release_dial_slot(node_id).await;
+    }
+
+    /// Releases the dial slot of the peer, if it holds one, and sends queued dials while fewer than
+    /// `max_concurrent_dials` dials are in progress.
+    async fn release_dial_slot(&mut self, node_id: &NodeId) {
+        if !self.dials_in_flight.remove(node_id) {
+            return;
+        }
+
+        let max_concurrent_dials = self.config.max_concurrent_dials.unwrap_or(usize::MAX);
+        while self.dials_in_flight.len() < max_concurrent_dials {
+            let (node_id, reply_tx) = match self.dial_queue.pop_front() {
+                Some(dial) => dial,
+                None => break,
+            };
+            // The peer may have connected while the dial was queued
+            let existing_conn = self
+                .pool
+                .get_connection(&node_id)
+                .filter(|conn| conn.is_connected())
+                .cloned();
+            match existing_conn {
+                Some(conn) => {
+                    if let Some(reply_tx) = reply_tx {
+                        let _ = reply_tx.send(Ok(conn));
+                    }
+                },
+                None => {
+                    self.send_dial(node_id, reply_tx, false).await;
+                },
+            }
+        }
+    }
+
+    async fn disconnect_all(&mut self) -> ShutdownReport {
+        let timer = Instant::now();
+        let mut report = ShutdownReport::default();
         let mut node_ids = Vec::with_capacity(self.pool.count_connected());
         for mut state in self.pool.filter_drain(|_| true) {
             if let Some(conn) = state.connection_mut() {
                 match conn.disconnect_silent().await {
                     Ok(_) => {
+                        report.num_closed_cleanly += 1;
                         node_ids.push(conn.peer_node_id().clone());
                     },
                     Err(err) => {
+                        report.num_failed += 1;
                         debug!(
                             target: LOG_TARGET,
                             "In disconnect_all: Error when disconnecting peer '{}' because '{:?}'",
@@ -304,6 +715,83 @@ impl ConnectivityManagerActor {
         for node_id in node_ids {
             self.publish_event(ConnectivityEvent::PeerDisconnected(node_id));
         }
+
+        let elapsed = timer.elapsed();
+        info!(
+            target: LOG_TARGET,
+            "Disconnected {} peer(s) in {:.2?} ({} failed to disconnect)",
+            report.num_closed_cleanly,
+            elapsed,
+            report.num_failed
+        );
+        if elapsed > self.config.shutdown_grace_period {
+            warn!(
+                target: LOG_TARGET,
+                "Disconnecting all peers took {:.2?} which exceeds the shutdown grace period of {}. Some connections \
+                 may be stuck.",
+                elapsed,
+                format_duration(self.config.shutdown_grace_period)
+            );
+        }
+        report
+    }
+
+    async fn shutdown_gracefully(&mut self, timeout: Duration) -> ShutdownReport {
+        info!(
+            target: LOG_TARGET,
+            "ConnectivityManager is shutting down. Disconnecting {} connection(s) within {}",
+            self.pool.count_connected(),
+            format_duration(timeout)
+        );
+        self.accept_inbound = false;
+        self.request_rx.close();
+
+        let mut conns = self
+            .pool
+            .filter_drain(|_| true)
+            .into_iter()
+            .filter_map(|state| state.into_connection())
+            .collect::<Vec<_>>();
+        let results = future::join_all(conns.iter_mut().map(|conn| time::timeout(timeout, conn.disconnect()))).await;
+
+        let mut report = ShutdownReport::default();
+        for (conn, result) in conns.iter().zip(results) {
+            match result {
+                Ok(Ok(_)) => {
+                    report.num_closed_cleanly += 1;
+                },
+                Ok(Err(err)) => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Error when disconnecting peer '{}' because '{:?}'",
+                        conn.peer_node_id().short_str(),
+                        err
+                    );
+                    report.num_failed += 1;
+                },
+                Err(_) => {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Peer '{}' did not disconnect within {}",
+                        conn.peer_node_id().short_str(),
+                        format_duration(timeout)
+                    );
+                    report.num_closed_forcibly += 1;
+                },
+            }
+            self.publish_event(ConnectivityEvent::PeerDisconnected(conn.peer_node_id().clone()));
+        }
+
+        info!(
+            target: LOG_TARGET,
+            "ConnectivityManager shut down. {} connection(s) closed cleanly, {} closed forcibly, {} failed to \
+             disconnect",
+            report.num_closed_cleanly,
+            report.num_closed_forcibly,
+            report.num_failed
+        );
+        self.shutdown_report = Some(report);
+        report
     }
 
     async fn refresh_connection_pool(&mut self) -> Result<(), ConnectivityError> {
@@ -318,14 +806,58 @@ impl ConnectivityManagerActor {
             self.pool.count_connected_clients()
         );
 
+        // Failed and disconnected states are always cleared, regardless of whether reaping is enabled
         self.clean_connection_pool();
+        self.prune_inbound_rate_limiters();
+        let unreported_failures = self.connect_failure_log.prune(Instant::now());
+        if unreported_failures > 0 {
+            debug!(
+                target: LOG_TARGET,
+                "{} repeated connection failure(s) to peers that have since stopped failing were not logged",
+                unreported_failures
+            );
+        }
         if self.config.is_connection_reaping_enabled {
             self.reap_inactive_connections().await;
+        } else {
+            trace!(
+                target: LOG_TARGET,
+                "Connection reaping is disabled, inactive connections are kept open"
+            );
         }
         self.update_connectivity_status();
+        self.clear_outage_offline_marks().await?;
+        if let Some(status_override) = self.status_override {
+            warn!(
+                target: LOG_TARGET,
+                "Connectivity status override is active. Reporting {} instead of {}", status_override, self.status
+            );
+        }
         Ok(())
     }
 
+    fn set_status_override(&mut self, status_override: Option<ConnectivityStatus>) {
+        self.status_override = status_override;
+        match status_override {
+            Some(status) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Connectivity status override set. Reporting {} until the override is cleared (actual status: {})",
+                    status,
+                    self.status
+                );
+                self.publish_event(status_event(status));
+            },
+            None => {
+                info!(
+                    target: LOG_TARGET,
+                    "Connectivity status override cleared. Reporting actual status {}", self.status
+                );
+                self.publish_event(status_event(self.status));
+            },
+        }
+    }
+
     async fn reap_inactive_connections(&mut self) {
         let connections = self
             .pool
@@ -340,6 +872,7 @@ impl ConnectivityManagerActor {
                 "Disconnecting '{}' because connection was inactive",
                 conn.peer_node_id().short_str()
             );
+            self.managed_peers.remove(conn.peer_node_id());
             if let Err(err) = conn.disconnect().await {
                 // Already disconnected
                 debug!(
@@ -381,12 +914,40 @@ impl ConnectivityManagerActor {
             self.pool.count_connected_nodes()
         );
 
-        let conns = selection.select(&self.pool);
+        let conns = selection.select(&self.pool, &self.connection_stats);
         debug!(target: LOG_TARGET, "Selected {} connections(s)", conns.len());
 
         Ok(conns.into_iter().cloned().collect())
     }
 
+    fn get_peer_stats(&self) -> Vec<PeerStats> {
+        let window = self.config.offline_failure_window;
+        let mut peer_stats = self
+            .pool
+            .filter_connection_states(|s| s.is_connected())
+            .into_iter()
+            .map(|conn| {
+                let uptime = conn.age();
+                let (failures, quality_score) = match self.connection_stats.get(conn.peer_node_id()) {
+                    Some(stats) => (stats.failures_within(window), stats.quality_score(uptime, window)),
+                    None => (0, PeerConnectionStats::new().quality_score(uptime, window)),
+                };
+                PeerStats {
+                    node_id: conn.peer_node_id().clone(),
+                    uptime,
+                    failures,
+                    quality_score,
+                }
+            })
+            .collect::<Vec<_>>();
+        peer_stats.sort_by(|a, b| {
+            b.quality_score
+                .partial_cmp(&a.quality_score)
+                .unwrap_or(cmp::Ordering::Equal)
+        });
+        peer_stats
+    }
+
     fn get_connection_stat_mut(&mut self, node_id: NodeId) -> &mut PeerConnectionStats {
         match self.connection_stats.entry(node_id) {
             Entry::Occupied(entry) => entry.into_mut(),
@@ -394,15 +955,42 @@ impl ConnectivityManagerActor {
         }
     }
 
+    fn log_peer_connect_failure(&mut self, node_id: &NodeId, err: &ConnectionManagerError) {
+        match self.connect_failure_log.record(node_id, Instant::now()) {
+            FailureLogAction::Log => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Connection to peer '{}' failed because '{:?}'", node_id, err
+                );
+            },
+            FailureLogAction::Summarize { failures, period } => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Connection to peer '{}' failed {} times in the last {} (last error: '{:?}')",
+                    node_id,
+                    failures,
+                    format_duration(period),
+                    err
+                );
+            },
+            FailureLogAction::Suppress => {},
+        }
+    }
+
     fn mark_peer_succeeded(&mut self, node_id: NodeId) {
+        self.connect_failure_log.clear(&node_id);
         let entry = self.get_connection_stat_mut(node_id);
         entry.set_connection_success();
     }
 
+    /// Marks the connection attempt as failed and returns the number of failures within the
+    /// `offline_failure_window`.
     fn mark_peer_failed(&mut self, node_id: NodeId) -> usize {
+        let window = self.config.offline_failure_window;
         let entry = self.get_connection_stat_mut(node_id);
         entry.set_connection_failed();
-        entry.failed_attempts()
+        entry.expire_failed_attempts(window);
+        entry.failed_attempts_within(window)
     }
 
     async fn handle_peer_connection_failure(&mut self, node_id: &NodeId) -> Result<(), ConnectivityError> {
@@ -415,13 +1003,19 @@ impl ConnectivityManagerActor {
         }
 
         let num_failed = self.mark_peer_failed(node_id.clone());
+        let max_failures = if self.status.is_degraded() {
+            self.config.max_failures_mark_offline_degraded
+        } else {
+            self.config.max_failures_mark_offline
+        };
 
-        if num_failed >= self.config.max_failures_mark_offline {
+        if num_failed >= max_failures {
             debug!(
                 target: LOG_TARGET,
-                "Marking peer '{}' as offline because this node failed to connect to them {} times",
+                "Marking peer '{}' as offline because this node failed to connect to them {} times within {}",
                 node_id.short_str(),
-                num_failed
+                num_failed,
+                format_duration(self.config.offline_failure_window)
             );
             if self.peer_manager.set_offline(node_id, true).await? {
                 debug!(
@@ -431,8 +1025,13 @@ impl ConnectivityManagerActor {
             } else {
                 // Only publish the `PeerOffline` event if we changed the offline state from online to offline
                 self.publish_event(ConnectivityEvent::PeerOffline(node_id.clone()));
+                // The failure may be caused by our own network being down rather than the peer
+                if self.config.clear_outage_offline_marks && self.status.is_degraded() {
+                    self.outage_offline_marks.insert(node_id.clone());
+                }
             }
             self.connection_stats.remove(node_id);
+            self.managed_peers.remove(node_id);
         }
 
         Ok(())
@@ -445,6 +1044,91 @@ impl ConnectivityManagerActor {
         use ConnectionManagerEvent::*;
         #[allow(clippy::single_match)]
         match event {
+            PeerConnected(new_conn) if !self.accept_inbound && new_conn.direction().is_inbound() => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Closing new inbound connection from peer `{}` because inbound connections are not being accepted",
+                    new_conn.peer_node_id().short_str()
+                );
+                delayed_close(new_conn.clone(), Duration::from_secs(0));
+                return Ok(());
+            },
+            PeerConnected(new_conn) if !self.config.is_address_permitted(new_conn.address()) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Closing new {} connection to peer `{}` because the address `{}` is not permitted",
+                    new_conn.direction(),
+                    new_conn.peer_node_id().short_str(),
+                    new_conn.address()
+                );
+                delayed_close(new_conn.clone(), Duration::from_secs(0));
+                return Ok(());
+            },
+            PeerConnected(new_conn) if new_conn.direction().is_inbound() => {
+                match self.check_inbound_rate_limit(new_conn) {
+                    InboundRateLimitCheck::Accepted => {},
+                    InboundRateLimitCheck::RateLimited => {
+                        debug!(
+                            target: LOG_TARGET,
+                            "Closing new inbound connection from peer `{}` because the inbound connection rate limit \
+                             was exceeded",
+                            new_conn.peer_node_id().short_str()
+                        );
+                        delayed_close(new_conn.clone(), Duration::from_secs(0));
+                        return Ok(());
+                    },
+                    InboundRateLimitCheck::PeerRateLimited => {
+                        debug!(
+                            target: LOG_TARGET,
+                            "Closing new inbound connection from peer `{}` because the peer exceeded its inbound \
+                             connection rate limit",
+                            new_conn.peer_node_id().short_str()
+                        );
+                        delayed_close(new_conn.clone(), Duration::from_secs(0));
+                        let ban_duration = self
+                            .config
+                            .inbound_connection_rate_limit
+                            .and_then(|limit| limit.ban_duration);
+                        if let Some(duration) = ban_duration {
+                            self.ban_peer(
+                                new_conn.peer_node_id(),
+                                duration,
+                                "Exceeded the inbound connection rate limit".to_string(),
+                            )
+                            .await?;
+                        }
+                        return Ok(());
+                    },
+                }
+            },
+            _ => {},
+        }
+
+        match event {
+            PeerConnected(new_conn) if !self.try_reserve_connection_slot(new_conn) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Closing new {} connection to peer `{}` because there is no free connection slot",
+                    new_conn.direction(),
+                    new_conn.peer_node_id().short_str()
+                );
+                delayed_close(new_conn.clone(), Duration::from_secs(0));
+                return Ok(());
+            },
+            PeerConnected(new_conn) if self.has_connection_to_same_address(new_conn) => {
+                self.tie_break_stats.num_tie_breaks += 1;
+                self.tie_break_stats.num_existing_kept += 1;
+                debug!(
+                    target: LOG_TARGET,
+                    "Tie break: (Peer = {}) Disconnecting new {} connection because another peer is already connected \
+                     at the same address `{}`",
+                    new_conn.peer_node_id().short_str(),
+                    new_conn.direction(),
+                    new_conn.address()
+                );
+                delayed_close(new_conn.clone(), self.config.connection_tie_break_linger);
+                return Ok(());
+            },
             PeerConnected(new_conn) => {
                 self.connection_manager
                     .cancel_dial(new_conn.peer_node_id().clone())
@@ -469,12 +1153,15 @@ impl ConnectivityManagerActor {
                             new_conn,
                             existing_conn,
                         );
+                        self.tie_break_stats.num_stale_replaced += 1;
                         let node_id = existing_conn.peer_node_id().clone();
                         let direction = existing_conn.direction();
                         delayed_close(existing_conn.clone(), self.config.connection_tie_break_linger);
                         self.publish_event(ConnectivityEvent::PeerConnectionWillClose(node_id, direction));
                     },
                     Some(existing_conn) if self.tie_break_existing_connection(existing_conn, new_conn) => {
+                        self.tie_break_stats.num_tie_breaks += 1;
+                        self.tie_break_stats.num_new_kept += 1;
                         debug!(
                             target: LOG_TARGET,
                             "Tie break: (Peer = {}) Keep new {} connection, Disconnect existing {} connection",
@@ -489,6 +1176,8 @@ impl ConnectivityManagerActor {
                         self.publish_event(ConnectivityEvent::PeerConnectionWillClose(node_id, direction));
                     },
                     Some(existing_conn) => {
+                        self.tie_break_stats.num_tie_breaks += 1;
+                        self.tie_break_stats.num_existing_kept += 1;
                         debug!(
                             target: LOG_TARGET,
                             "Tie break: (Peer = {}) Keeping existing {} connection, Disconnecting new {} connection",
@@ -510,11 +1199,27 @@ impl ConnectivityManagerActor {
         let (node_id, mut new_status, connection) = match event {
             PeerDisconnected(node_id) => {
                 self.connection_stats.remove(node_id);
+                self.managed_peers.remove(node_id);
+                (&*node_id, ConnectionStatus::Disconnected, None)
+            },
+            PeerDisconnectedByPeer(node_id) => {
+                if self.is_tie_break_lost(node_id) {
+                    self.tie_break_stats.num_lost_to_peer += 1;
+                    debug!(
+                        target: LOG_TARGET,
+                        "Tie break: (Peer = {}) Peer closed our connection shortly after it was established",
+                        node_id.short_str()
+                    );
+                    self.publish_event(ConnectivityEvent::TieBreakLost(node_id.clone()));
+                }
+                self.connection_stats.remove(node_id);
+                self.managed_peers.remove(node_id);
                 (&*node_id, ConnectionStatus::Disconnected, None)
             },
             PeerConnected(conn) => (conn.peer_node_id(), ConnectionStatus::Connected, Some(conn.clone())),
 
             PeerConnectFailed(node_id, ConnectionManagerError::DialCancelled) => {
+                self.dialed_primary_addresses.remove(node_id);
                 debug!(
                     target: LOG_TARGET,
                     "Dial was cancelled before connection completed to peer '{}'", node_id
@@ -522,10 +1227,8 @@ impl ConnectivityManagerActor {
                 (&*node_id, ConnectionStatus::Failed, None)
             },
             PeerConnectFailed(node_id, err) => {
-                debug!(
-                    target: LOG_TARGET,
-                    "Connection to peer '{}' failed because '{:?}'", node_id, err
-                );
+                self.dialed_primary_addresses.remove(node_id);
+                self.log_peer_connect_failure(node_id, err);
                 self.handle_peer_connection_failure(node_id).await?;
                 (&*node_id, ConnectionStatus::Failed, None)
             },
@@ -551,7 +1254,17 @@ impl ConnectivityManagerActor {
                 self.mark_peer_succeeded(node_id.clone());
                 match self.pool.get_connection(&node_id).cloned() {
                     Some(conn) => {
-                        self.publish_event(ConnectivityEvent::PeerConnected(conn));
+                        self.publish_event(ConnectivityEvent::PeerConnected(conn.clone()));
+                        if conn.direction().is_outbound() {
+                            if let Err(err) = self.update_peer_address(&conn).await {
+                                warn!(
+                                    target: LOG_TARGET,
+                                    "Failed to update address for peer '{}': {}",
+                                    node_id.short_str(),
+                                    err
+                                );
+                            }
+                        }
                     },
                     None => unreachable!(
                         "Connection transitioning to CONNECTED state must always have a connection set i.e. \
@@ -576,9 +1289,87 @@ impl ConnectivityManagerActor {
         }
 
         self.update_connectivity_status();
+        self.clear_outage_offline_marks().await?;
+        Ok(())
+    }
+
+    /// Marks the peers that were marked offline during a local outage as online again once this node is back online.
+    async fn clear_outage_offline_marks(&mut self) -> Result<(), ConnectivityError> {
+        if !self.status.is_online() || self.outage_offline_marks.is_empty() {
+            return Ok(());
+        }
+        let node_ids = mem::take(&mut self.outage_offline_marks);
+        debug!(
+            target: LOG_TARGET,
+            "Connectivity recovered. Clearing {} offline mark(s) set during the outage",
+            node_ids.len()
+        );
+        for node_id in node_ids {
+            self.peer_manager.set_offline(&node_id, false).await?;
+        }
+        Ok(())
+    }
+
+    /// Updates the peer manager if an outbound connection was established on an address that differs from the peer's
+    /// primary address, and publishes a `ConnectivityEvent::AddressResolved` event. The dialer marks the dialed
+    /// address as primary before the connection is reported, so the primary address recorded when the dial was sent is
+    /// used if there is one.
+    async fn update_peer_address(&mut self, conn: &PeerConnection) -> Result<(), ConnectivityError> {
+        let address = conn.address();
+        let mut peer = self.peer_manager.find_by_node_id(conn.peer_node_id()).await?;
+        let previous_address = self
+            .dialed_primary_addresses
+            .remove(conn.peer_node_id())
+            .or_else(|| peer.addresses.first().map(|addr| addr.address.clone()));
+        if previous_address.as_ref() == Some(address) {
+            return Ok(());
+        }
+
+        debug!(
+            target: LOG_TARGET,
+            "Peer '{}' connected on new address '{}'",
+            conn.peer_node_id().short_str(),
+            address
+        );
+        peer.addresses.add_net_address(address);
+        peer.addresses.mark_successful_connection_attempt(address);
+        self.peer_manager.add_peer(peer).await?;
+        self.publish_event(ConnectivityEvent::AddressResolved(
+            conn.peer_node_id().clone(),
+            address.clone(),
+        ));
         Ok(())
     }
 
+    /// Returns true if address deduplication is enabled and another peer is connected at the connection's address
+    fn has_connection_to_same_address(&self, conn: &PeerConnection) -> bool {
+        if !self.config.dedup_connections_by_address {
+            return false;
+        }
+        let address = canonicalize_multiaddr(conn.address());
+        self.pool.all().into_iter().any(|state| {
+            state.node_id() != conn.peer_node_id() &&
+                state
+                    .connection()
+                    .filter(|existing_conn| existing_conn.is_connected())
+                    .map(|existing_conn| canonicalize_multiaddr(existing_conn.address()) == address)
+                    .unwrap_or(false)
+        })
+    }
+
+    /// Returns true if the connection to the peer was closed soon enough after being established that the peer most
+    /// likely closed it because of a tie break. The peer closes the losing connection after its own linger period, so
+    /// closes within twice the linger period are counted.
+    fn is_tie_break_lost(&self, node_id: &NodeId) -> bool {
+        let max_age = self.config.connection_tie_break_linger * 2;
+        self.pool
+            .get(node_id)
+            .filter(|state| state.status() == ConnectionStatus::Connected)
+            .and_then(|state| state.connection())
+            .map(|conn| conn.age() <= max_age)
+            .unwrap_or(false)
+    }
+
     /// Two connections to the same peer have been created. This function deterministically determines which peer
     /// connection to close. It does this by comparing our NodeId to that of the peer. This rule enables both sides to
     /// agree which connection to disconnect
@@ -614,9 +1405,10 @@ impl ConnectivityManagerActor {
         // - Clients SHOULD tolerate entering a DEGRADED/OFFLINE status
         // - If a number of peers disconnect or the local system's network goes down, the status MAY transition to
         //   DEGRADED
-        let min_peers = self.config.min_connectivity;
         let num_connected_nodes = self.pool.count_connected_nodes();
         let num_connected_clients = self.pool.count_connected_clients();
+        // Failed peers are cleared from the pool on refresh, so the managed peer set is used instead
+        let min_peers = self.config.min_connectivity.threshold(self.managed_peers.len());
         debug!(
             target: LOG_TARGET,
             "#min_peers = {}, #nodes = {}, #clients = {}", min_peers, num_connected_nodes, num_connected_clients
@@ -647,6 +1439,8 @@ impl ConnectivityManagerActor {
             );
         }
 
+        let is_recovering = self.status.is_offline() && matches!(next_status, Online(_) | Degraded(_));
+
         match (self.status, next_status) {
             (Online(_), Online(_)) => {},
             (_, Online(n)) => {
@@ -654,7 +1448,7 @@ impl ConnectivityManagerActor {
                     target: LOG_TARGET,
                     "Connectivity is ONLINE ({}/{} connections)", n, required_num_peers
                 );
-                self.publish_event(ConnectivityEvent::ConnectivityStateOnline(n));
+                self.publish_status_event(ConnectivityEvent::ConnectivityStateOnline(n));
             },
             (Degraded(m), Degraded(n)) => {
                 info!(
@@ -662,7 +1456,7 @@ impl ConnectivityManagerActor {
                     "Connectivity is DEGRADED ({}/{} connections)", n, required_num_peers
                 );
                 if m != n {
-                    self.publish_event(ConnectivityEvent::ConnectivityStateDegraded(n));
+                    self.publish_status_event(ConnectivityEvent::ConnectivityStateDegraded(n));
                 }
             },
             (_, Degraded(n)) => {
@@ -670,7 +1464,7 @@ impl ConnectivityManagerActor {
                     target: LOG_TARGET,
                     "Connectivity is DEGRADED ({}/{} connections)", n, required_num_peers
                 );
-                self.publish_event(ConnectivityEvent::ConnectivityStateDegraded(n));
+                self.publish_status_event(ConnectivityEvent::ConnectivityStateDegraded(n));
             },
             (Offline, Offline) => {},
             (_, Offline) => {
@@ -678,18 +1472,169 @@ impl ConnectivityManagerActor {
                     target: LOG_TARGET,
                     "Connectivity is OFFLINE (0/{} connections)", required_num_peers
                 );
-                self.publish_event(ConnectivityEvent::ConnectivityStateOffline);
+                self.publish_status_event(ConnectivityEvent::ConnectivityStateOffline);
             },
             (status, next_status) => unreachable!("Unexpected status transition ({} to {})", status, next_status),
         }
+        if is_recovering {
+            info!(target: LOG_TARGET, "Connectivity has RECOVERED from being offline");
+            self.publish_status_event(ConnectivityEvent::ConnectivityStateRecovered);
+        }
+        if mem::discriminant(&self.status) != mem::discriminant(&next_status) {
+            self.record_status_transition(next_status);
+        }
         self.status = next_status;
     }
 
+    fn record_status_transition(&mut self, next_status: ConnectivityStatus) {
+        let capacity = self.config.status_history_size;
+        if capacity == 0 {
+            return;
+        }
+        if self.status_history.len() >= capacity {
+            self.status_history.pop_front();
+        }
+        self.status_history.push_back(ConnectivityStatusTransition {
+            timestamp: Utc::now(),
+            from: self.status,
+            to: next_status,
+        });
+    }
+
     fn publish_event(&mut self, event: ConnectivityEvent) {
         // A send operation can only fail if there are no subscribers, so it is safe to ignore the error
         let _ = self.event_tx.send(event);
     }
 
+    /// Publishes a connectivity state event, unless the status is currently overridden
+    fn publish_status_event(&mut self, event: ConnectivityEvent) {
+        if self.status_override.is_some() {
+            debug!(
+                target: LOG_TARGET,
+                "Not publishing {} because the connectivity status is overridden", event
+            );
+            return;
+        }
+        self.publish_event(event);
+    }
+
+    /// Checks the new inbound connection against the peer's own inbound connection rate limit and then the rate limit
+    /// shared by all peers. Connections from managed peers are always accepted.
+    fn check_inbound_rate_limit(&mut self, conn: &PeerConnection) -> InboundRateLimitCheck {
+        let limit = match self.config.inbound_connection_rate_limit {
+            Some(limit) => limit,
+            None => return InboundRateLimitCheck::Accepted,
+        };
+        let node_id = conn.peer_node_id();
+        if self.managed_peers.contains(node_id) {
+            return InboundRateLimitCheck::Accepted;
+        }
+
+        let now = Instant::now();
+        if self.peer_inbound_rate_limiters.len() >= MAX_INBOUND_RATE_LIMITED_PEERS &&
+            !self.peer_inbound_rate_limiters.contains_key(node_id)
+        {
+            self.prune_inbound_rate_limiters();
+        }
+        if self.peer_inbound_rate_limiters.len() < MAX_INBOUND_RATE_LIMITED_PEERS ||
+            self.peer_inbound_rate_limiters.contains_key(node_id)
+        {
+            let is_within_peer_limit = self
+                .peer_inbound_rate_limiters
+                .entry(node_id.clone())
+                .or_insert_with(|| TokenBucket::new(limit.peer_burst, limit.peer_connections_per_second))
+                .try_acquire(now);
+            if !is_within_peer_limit {
+                return InboundRateLimitCheck::PeerRateLimited;
+            }
+        }
+
+        let is_within_limit = self
+            .inbound_rate_limiter
+            .get_or_insert_with(|| TokenBucket::new(limit.burst, limit.connections_per_second))
+            .try_acquire(now);
+        if is_within_limit {
+            InboundRateLimitCheck::Accepted
+        } else {
+            InboundRateLimitCheck::RateLimited
+        }
+    }
+
+    /// Returns true if there is a free connection slot for the new connection, see
+    /// `ConnectivityConfig::reserved_managed_slots`. If all slots are used, a connection to a peer that is not managed
+    /// is closed to make room for a managed peer.
+    fn try_reserve_connection_slot(&mut self, conn: &PeerConnection) -> bool {
+        let max_connections = match self.config.max_connections {
+            Some(max) => max,
+            None => return true,
+        };
+        let node_id = conn.peer_node_id();
+        // A new connection to a connected peer replaces the existing connection in its slot
+        if self
+            .pool
+            .get(node_id)
+            .map(|state| state.is_connected())
+            .unwrap_or(false)
+        {
+            return true;
+        }
+        let num_connected = self.pool.count_connected_nodes() + self.pool.count_connected_clients();
+        if !self.managed_peers.contains(node_id) {
+            return num_connected < max_connections.saturating_sub(self.config.reserved_managed_slots);
+        }
+        if num_connected < max_connections {
+            return true;
+        }
+
+        let unmanaged_conn = self
+            .pool
+            .filter_connection_states(|state| state.is_connected() && !self.managed_peers.contains(state.node_id()))
+            .into_iter()
+            .next()
+            .cloned();
+        match unmanaged_conn {
+            Some(mut unmanaged_conn) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Closing connection to peer `{}` to make room for managed peer `{}`",
+                    unmanaged_conn.peer_node_id().short_str(),
+                    node_id.short_str()
+                );
+                // Not silent, so that the peer is removed from the pool once it has disconnected
+                task::spawn(async move {
+                    let _ = unmanaged_conn.disconnect().await;
+                });
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Removes the rate limits of peers that have not made any inbound connections for long enough to be back at their
+    /// full burst
+    fn prune_inbound_rate_limiters(&mut self) {
+        let now = Instant::now();
+        self.peer_inbound_rate_limiters
+            .retain(|_, limiter| !limiter.is_full(now));
+    }
+
+    async fn get_banned_peers(&self) -> Result<Vec<BannedPeer>, ConnectivityError> {
+        let query = PeerQuery::new().select_where(|peer| peer.is_banned());
+        let peers = self.peer_manager.perform_query(query).await?;
+        let now = Utc::now().naive_utc();
+        Ok(peers
+            .into_iter()
+            .filter_map(|peer| {
+                let remaining = peer.banned_until()?.signed_duration_since(now).to_std().ok()?;
+                Some(BannedPeer {
+                    node_id: peer.node_id,
+                    remaining,
+                    reason: peer.banned_reason,
+                })
+            })
+            .collect())
+    }
+
     async fn ban_peer(
         &mut self,
         node_id: &NodeId,
@@ -705,6 +1650,9 @@ impl ConnectivityManagerActor {
         );
 
         self.peer_manager.ban_peer_by_node_id(node_id, duration, reason).await?;
+        // An explicit ban supersedes the offline mark, so it is left alone when connectivity recovers
+        self.outage_offline_marks.remove(node_id);
+        self.managed_peers.remove(node_id);
 
         self.publish_event(ConnectivityEvent::PeerBanned(node_id.clone()));
 
@@ -720,6 +1668,67 @@ impl ConnectivityManagerActor {
     }
 }
 
+fn status_event(status: ConnectivityStatus) -> ConnectivityEvent {
+    use ConnectivityStatus::*;
+    match status {
+        Initializing => ConnectivityEvent::ConnectivityStateInitialized,
+        Online(n) => ConnectivityEvent::ConnectivityStateOnline(n),
+        Degraded(n) => ConnectivityEvent::ConnectivityStateDegraded(n),
+        Offline => ConnectivityEvent::ConnectivityStateOffline,
+    }
+}
+
+/// The outcome of checking a new inbound connection against the inbound connection rate limit
+enum InboundRateLimitCheck {
+    Accepted,
+    /// The rate limit shared by all peers was exceeded
+    RateLimited,
+    /// The peer exceeded its own rate limit
+    PeerRateLimited,
+}
+
+/// A token bucket that holds up to `capacity` tokens and is restocked at `refill_per_sec` tokens per second
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: usize, refill_per_sec: f32) -> Self {
+        Self {
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            refill_per_sec: f64::from(refill_per_sec.max(0.0)),
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Returns true if the bucket has been restocked to capacity
+    fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.capacity
+    }
+
+    /// Takes a token from the bucket, returning false if there are none available
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
 fn delayed_close(conn: PeerConnection, delay: Duration) {
     task::spawn(async move {
         time::sleep(delay).await;
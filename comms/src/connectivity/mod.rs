@@ -21,22 +21,32 @@
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 mod connection_stats;
+pub use connection_stats::{PeerStats, TieBreakStats};
 
 mod config;
-pub use config::ConnectivityConfig;
+pub(crate) use config::is_address_permitted;
+pub use config::{AddressPattern, ConnectivityConfig, InboundConnectionRateLimit, MinConnectivity};
 
 mod connection_pool;
 
+mod failure_log;
+
 mod error;
 pub use error::ConnectivityError;
 
 mod manager;
 pub(crate) use manager::ConnectivityManager;
-pub use manager::ConnectivityStatus;
+pub use manager::{BannedPeer, ConnectivityStatus, ConnectivityStatusTransition};
 
 mod requester;
 pub(crate) use requester::ConnectivityRequest;
-pub use requester::{ConnectivityEvent, ConnectivityEventRx, ConnectivityEventTx, ConnectivityRequester};
+pub use requester::{
+    ConnectivityEvent,
+    ConnectivityEventRx,
+    ConnectivityEventTx,
+    ConnectivityRequester,
+    ShutdownReport,
+};
 
 mod selection;
 pub use selection::ConnectivitySelection;
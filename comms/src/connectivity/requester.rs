@@ -22,8 +22,9 @@
 
 use super::{
     connection_pool::PeerConnectionState,
+    connection_stats::{PeerStats, TieBreakStats},
     error::ConnectivityError,
-    manager::ConnectivityStatus,
+    manager::{BannedPeer, ConnectivityStatus, ConnectivityStatusTransition},
     ConnectivitySelection,
 };
 use crate::{
@@ -33,6 +34,7 @@ use crate::{
 };
 use futures::{future, stream::FuturesUnordered, Stream};
 use log::*;
+use multiaddr::Multiaddr;
 use std::{
     fmt,
     time::{Duration, Instant},
@@ -56,11 +58,21 @@ pub enum ConnectivityEvent {
     PeerBanned(NodeId),
     PeerOffline(NodeId),
     PeerConnectionWillClose(NodeId, ConnectionDirection),
+    /// An outbound connection was established to the peer on an address that differs from the primary address in the
+    /// peer manager. The peer record has been updated to prefer this address.
+    AddressResolved(NodeId, Multiaddr),
+    /// The peer closed the connection within the tie break linger period of it being established, most likely because
+    /// the peer resolved a tie break in favour of another connection. This is published before `PeerDisconnected` and
+    /// is not counted as a connection failure.
+    TieBreakLost(NodeId),
 
     ConnectivityStateInitialized,
     ConnectivityStateOnline(usize),
     ConnectivityStateDegraded(usize),
     ConnectivityStateOffline,
+    /// Connectivity transitioned from offline to online or degraded. This is published in addition to the
+    /// `ConnectivityStateOnline` or `ConnectivityStateDegraded` event.
+    ConnectivityStateRecovered,
 }
 
 impl fmt::Display for ConnectivityEvent {
@@ -75,10 +87,13 @@ impl fmt::Display for ConnectivityEvent {
             PeerConnectionWillClose(node_id, direction) => {
                 write!(f, "PeerConnectionWillClose({}, {})", node_id, direction)
             },
+            AddressResolved(node_id, address) => write!(f, "AddressResolved({}, {})", node_id, address),
+            TieBreakLost(node_id) => write!(f, "TieBreakLost({})", node_id),
             ConnectivityStateInitialized => write!(f, "ConnectivityStateInitialized"),
             ConnectivityStateOnline(n) => write!(f, "ConnectivityStateOnline({})", n),
             ConnectivityStateDegraded(n) => write!(f, "ConnectivityStateDegraded({})", n),
             ConnectivityStateOffline => write!(f, "ConnectivityStateOffline"),
+            ConnectivityStateRecovered => write!(f, "ConnectivityStateRecovered"),
         }
     }
 }
@@ -91,15 +106,43 @@ pub enum ConnectivityRequest {
         reply_tx: Option<oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>>,
         tracing_id: Option<tracing::span::Id>,
     },
+    CancelDial(NodeId),
     GetConnectivityStatus(oneshot::Sender<ConnectivityStatus>),
     SelectConnections(
         ConnectivitySelection,
         oneshot::Sender<Result<Vec<PeerConnection>, ConnectivityError>>,
     ),
+    GetSelectionCandidates(ConnectivitySelection, oneshot::Sender<Vec<NodeId>>),
     GetConnection(NodeId, oneshot::Sender<Option<PeerConnection>>),
+    GetConnectionByAddress(Multiaddr, oneshot::Sender<Option<PeerConnection>>),
     GetAllConnectionStates(oneshot::Sender<Vec<PeerConnectionState>>),
     GetActiveConnections(oneshot::Sender<Vec<PeerConnection>>),
+    GetPeerStats(oneshot::Sender<Vec<PeerStats>>),
+    GetTieBreakStats(oneshot::Sender<TieBreakStats>),
+    GetStatusHistory(oneshot::Sender<Vec<ConnectivityStatusTransition>>),
+    GetBannedPeers(oneshot::Sender<Result<Vec<BannedPeer>, ConnectivityError>>),
     BanPeer(NodeId, Duration, String),
+    SetStatusOverride(Option<ConnectivityStatus>),
+    ReportPeerFailure(NodeId),
+    SetAcceptInbound(bool),
+    NetworkChanged,
+    Shutdown {
+        timeout: Duration,
+        reply: oneshot::Sender<ShutdownReport>,
+    },
+}
+
+/// The outcome of a connectivity shutdown, either requested with `ConnectivityRequester::shutdown` or caused by the
+/// shutdown signal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// The number of connections that were disconnected within the timeout
+    pub num_closed_cleanly: usize,
+    /// The number of connections that did not disconnect within the timeout. These are dropped without waiting for
+    /// them to close.
+    pub num_closed_forcibly: usize,
+    /// The number of connections that failed to disconnect, e.g. because the connection actor had already stopped
+    pub num_failed: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -124,32 +167,32 @@ impl ConnectivityRequester {
     /// Dial a single peer
     #[tracing::instrument(skip(self))]
     pub async fn dial_peer(&self, peer: NodeId) -> Result<PeerConnection, ConnectivityError> {
-        let mut num_cancels = 0;
-        loop {
-            let (reply_tx, reply_rx) = oneshot::channel();
-            self.sender
-                .send(ConnectivityRequest::DialPeer {
-                    node_id: peer.clone(),
-                    reply_tx: Some(reply_tx),
-                    tracing_id: tracing::Span::current().id(),
-                })
-                .await
-                .map_err(|_| ConnectivityError::ActorDisconnected)?;
-
-            match reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)? {
-                Ok(c) => return Ok(c),
-                Err(err @ ConnectionManagerError::DialCancelled) => {
-                    num_cancels += 1;
-                    // Due to simultaneous dialing, it's possible for the dial to be cancelled. However, typically if
-                    // dial is called again right after, the resolved connection will be returned.
-                    if num_cancels == 1 {
-                        continue;
-                    }
-                    return Err(err.into());
-                },
-                Err(err) => return Err(err.into()),
-            }
-        }
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ConnectivityRequest::DialPeer {
+                node_id: peer,
+                reply_tx: Some(reply_tx),
+                tracing_id: tracing::Span::current().id(),
+            })
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+
+        // A dial that is cancelled because of simultaneous dialing is resolved by the connectivity manager, so
+        // `ConnectivityError::DialCancelled` is only returned if the dial was cancelled by `cancel_dial`
+        let conn = reply_rx
+            .await
+            .map_err(|_| ConnectivityError::ActorResponseCancelled)??;
+        Ok(conn)
+    }
+
+    /// Cancel a pending dial to the given peer. Any pending `dial_peer` calls for the peer will resolve with
+    /// `ConnectivityError::DialCancelled`.
+    pub async fn cancel_dial(&self, peer: NodeId) -> Result<(), ConnectivityError> {
+        self.sender
+            .send(ConnectivityRequest::CancelDial(peer))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        Ok(())
     }
 
     /// Dial many peers, returning a Stream that emits the dial Result as each dial completes.
@@ -191,8 +234,66 @@ impl ConnectivityRequester {
         reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)?
     }
 
+    /// Returns the node ids of the peers that the given selection would choose from the current connection pool. This
+    /// has no side effects and is intended to help debug selection logic before calling `select_connections`.
+    pub async fn get_selection_candidates(
+        &mut self,
+        selection: ConnectivitySelection,
+    ) -> Result<Vec<NodeId>, ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ConnectivityRequest::GetSelectionCandidates(selection, reply_tx))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)
+    }
+
+    /// Returns connection statistics, including a quality score, for each connected peer. Peers are returned in
+    /// descending order of quality score.
+    pub async fn get_peer_stats(&mut self) -> Result<Vec<PeerStats>, ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ConnectivityRequest::GetPeerStats(reply_tx))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)
+    }
+
+    /// Returns the number of connection tie breaks that have occurred, which helps to diagnose connection churn caused
+    /// by peers dialing each other simultaneously.
+    pub async fn get_tie_break_stats(&mut self) -> Result<TieBreakStats, ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ConnectivityRequest::GetTieBreakStats(reply_tx))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)
+    }
+
+    /// Returns the most recent connectivity status transitions, oldest first. Only changes between the Initializing,
+    /// Online, Degraded and Offline states are recorded, not changes in the number of connections. This helps to
+    /// correlate connectivity flaps with other events.
+    pub async fn get_status_history(&mut self) -> Result<Vec<ConnectivityStatusTransition>, ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ConnectivityRequest::GetStatusHistory(reply_tx))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)
+    }
+
+    /// Returns all peers that are currently banned, along with the time left on each ban and the reason for it.
+    pub async fn get_banned_peers(&mut self) -> Result<Vec<BannedPeer>, ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ConnectivityRequest::GetBannedPeers(reply_tx))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)?
+    }
+
     /// Get an active connection to the given node id if one exists. This will return None if the peer is not connected.
-    pub async fn get_connection(&mut self, node_id: NodeId) -> Result<Option<PeerConnection>, ConnectivityError> {
+    pub async fn get_connection(&self, node_id: NodeId) -> Result<Option<PeerConnection>, ConnectivityError> {
         let (reply_tx, reply_rx) = oneshot::channel();
         self.sender
             .send(ConnectivityRequest::GetConnection(node_id, reply_tx))
@@ -201,6 +302,20 @@ impl ConnectivityRequester {
         reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)
     }
 
+    /// Get an active connection that uses the given address if one exists. This is useful when only the address of a
+    /// peer is known, for e.g. from logs or netstat output.
+    pub async fn get_connection_by_address(
+        &self,
+        address: Multiaddr,
+    ) -> Result<Option<PeerConnection>, ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ConnectivityRequest::GetConnectionByAddress(address, reply_tx))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)
+    }
+
     pub async fn get_connectivity_status(&mut self) -> Result<ConnectivityStatus, ConnectivityError> {
         let (reply_tx, reply_rx) = oneshot::channel();
         self.sender
@@ -210,6 +325,64 @@ impl ConnectivityRequester {
         reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)
     }
 
+    /// Pins the reported connectivity status to the given status, regardless of the actual state of the connection
+    /// pool. The override applies to `get_connectivity_status` and published connectivity state events until it is
+    /// cleared by passing `None`. This is intended for testing and maintenance.
+    pub async fn set_status_override(&mut self, status: Option<ConnectivityStatus>) -> Result<(), ConnectivityError> {
+        self.sender
+            .send(ConnectivityRequest::SetStatusOverride(status))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        Ok(())
+    }
+
+    /// Set whether new inbound connections are accepted. When false, new inbound connections are closed as soon as
+    /// they are established, e.g. to drain the node before a restart. Existing connections are not affected.
+    pub async fn set_accept_inbound(&mut self, accept_inbound: bool) -> Result<(), ConnectivityError> {
+        self.sender
+            .send(ConnectivityRequest::SetAcceptInbound(accept_inbound))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        Ok(())
+    }
+
+    /// Notifies the connectivity manager that the local network interface has changed (e.g. switching from wifi to
+    /// cellular). Existing connections are checked for liveness, dead connections are disconnected and peers that are
+    /// not connected are redialed, rather than waiting for TCP timeouts to detect the change.
+    pub async fn network_changed(&mut self) -> Result<(), ConnectivityError> {
+        self.sender
+            .send(ConnectivityRequest::NetworkChanged)
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        Ok(())
+    }
+
+    /// Gracefully shuts down the connectivity manager. New requests are no longer accepted and all connections are
+    /// disconnected, waiting up to `timeout` for them to close. Any connection that has not closed by then is dropped.
+    /// This allows an application to control the order in which it shuts down, e.g. draining RPC sessions before
+    /// triggering the shutdown signal.
+    pub async fn shutdown(&mut self, timeout: Duration) -> Result<ShutdownReport, ConnectivityError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.sender
+            .send(ConnectivityRequest::Shutdown {
+                timeout,
+                reply: reply_tx,
+            })
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        reply_rx.await.map_err(|_| ConnectivityError::ActorResponseCancelled)
+    }
+
+    /// Reports a failure (e.g. an RPC or application-level failure) with a connected peer. Peers with recent failures
+    /// are excluded from `ConnectivitySelection::healthy_nodes` selections.
+    pub async fn report_peer_failure(&mut self, node_id: NodeId) -> Result<(), ConnectivityError> {
+        self.sender
+            .send(ConnectivityRequest::ReportPeerFailure(node_id))
+            .await
+            .map_err(|_| ConnectivityError::ActorDisconnected)?;
+        Ok(())
+    }
+
     pub async fn get_all_connection_states(&mut self) -> Result<Vec<PeerConnectionState>, ConnectivityError> {
         let (reply_tx, reply_rx) = oneshot::channel();
         self.sender
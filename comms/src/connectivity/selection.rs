@@ -20,15 +20,21 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use super::connection_pool::ConnectionPool;
-use crate::{connectivity::connection_pool::ConnectionStatus, peer_manager::NodeId, PeerConnection};
+use super::{connection_pool::ConnectionPool, connection_stats::PeerConnectionStats};
+use crate::{
+    connection_manager::ConnectionDirection,
+    connectivity::connection_pool::ConnectionStatus,
+    peer_manager::NodeId,
+    PeerConnection,
+};
 use rand::{rngs::OsRng, seq::SliceRandom};
-use std::{fmt, fmt::Display};
+use std::{collections::HashMap, fmt, fmt::Display, time::Duration};
 
 #[derive(Debug, Clone)]
 pub struct ConnectivitySelection {
     selection_mode: SelectionMode,
     excluded_peers: Vec<NodeId>,
+    outbound_only: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -36,13 +42,17 @@ enum SelectionMode {
     AllNodes,
     RandomNodes(usize),
     ClosestTo(Box<NodeId>, usize),
+    HealthyNodes(usize, Duration),
 }
 
 impl ConnectivitySelection {
+    /// Select every connected node, excluding client connections. This is intended for broadcast-style operations that
+    /// should reach all connected nodes.
     pub fn all_nodes(exclude: Vec<NodeId>) -> Self {
         Self {
             selection_mode: SelectionMode::AllNodes,
             excluded_peers: exclude,
+            outbound_only: false,
         }
     }
 
@@ -50,6 +60,7 @@ impl ConnectivitySelection {
         Self {
             selection_mode: SelectionMode::RandomNodes(n),
             excluded_peers: exclude,
+            outbound_only: false,
         }
     }
 
@@ -58,17 +69,69 @@ impl ConnectivitySelection {
         Self {
             selection_mode: SelectionMode::ClosestTo(Box::new(node_id), n),
             excluded_peers: exclude,
+            outbound_only: false,
         }
     }
 
+    /// Select `n` random peer connections, excluding peers for which a connection or application failure occurred
+    /// within `failure_window`. This prevents a peer that is connected but unreliable from being selected repeatedly.
+    pub fn healthy_nodes(n: usize, failure_window: Duration, exclude: Vec<NodeId>) -> Self {
+        Self {
+            selection_mode: SelectionMode::HealthyNodes(n, failure_window),
+            excluded_peers: exclude,
+            outbound_only: false,
+        }
+    }
+
+    /// Additionally exclude the given peers from the selection, for e.g. peers that have already been tried. Peers are
+    /// excluded before selecting, so a `random_nodes` selection still returns up to `n` peers chosen from the
+    /// remaining peers.
+    pub fn exclude_peers<I: IntoIterator<Item = NodeId>>(mut self, peers: I) -> Self {
+        self.excluded_peers.extend(peers);
+        self
+    }
+
+    /// Only select connections that we initiated, i.e. exclude inbound connections, which could have been initiated by
+    /// an attacker. Like excluded peers, inbound connections are excluded before selecting.
+    pub fn outbound_only(mut self) -> Self {
+        self.outbound_only = true;
+        self
+    }
+
     /// Select peers from the pool according to the ConnectivitySelection
-    pub fn select<'a>(&self, pool: &'a ConnectionPool) -> Vec<&'a PeerConnection> {
+    pub fn select<'a>(
+        &self,
+        pool: &'a ConnectionPool,
+        connection_stats: &HashMap<NodeId, PeerConnectionStats>,
+    ) -> Vec<&'a PeerConnection> {
         use SelectionMode::*;
+        let mut exclude = self.excluded_peers.clone();
+        if self.outbound_only {
+            exclude.extend(
+                pool.filter_connection_states(|state| {
+                    state
+                        .connection()
+                        .map(|conn| conn.direction() != ConnectionDirection::Outbound)
+                        .unwrap_or(false)
+                })
+                .into_iter()
+                .map(|conn| conn.peer_node_id().clone()),
+            );
+        }
         match &self.selection_mode {
-            AllNodes => select_connected_nodes(pool, &self.excluded_peers),
-            RandomNodes(n) => select_random_nodes(pool, *n, &self.excluded_peers),
+            AllNodes => select_connected_nodes(pool, &exclude),
+            RandomNodes(n) => select_random_nodes(pool, *n, &exclude),
+            HealthyNodes(n, failure_window) => {
+                exclude.extend(
+                    connection_stats
+                        .iter()
+                        .filter(|(_, stats)| stats.has_failed_within(*failure_window))
+                        .map(|(node_id, _)| node_id.clone()),
+                );
+                select_random_nodes(pool, *n, &exclude)
+            },
             ClosestTo(dest_node_id, n) => {
-                let mut connections = select_closest(pool, dest_node_id, &self.excluded_peers);
+                let mut connections = select_closest(pool, dest_node_id, &exclude);
                 connections.truncate(*n);
                 connections.to_vec()
             },
@@ -109,9 +172,10 @@ impl Display for ConnectivitySelection {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "ConnectivitySelection(mode = {}, excluded {} peer(s))",
+            "ConnectivitySelection(mode = {}, excluded {} peer(s){})",
             self.selection_mode,
-            self.excluded_peers.len()
+            self.excluded_peers.len(),
+            if self.outbound_only { ", outbound only" } else { "" }
         )
     }
 }
@@ -123,6 +187,7 @@ impl Display for SelectionMode {
             AllNodes => write!(f, "AllNodes"),
             RandomNodes(n) => write!(f, "RandomNodes({})", n),
             ClosestTo(node_id, n) => write!(f, "ClosestTo({}, {})", node_id, n),
+            HealthyNodes(n, failure_window) => write!(f, "HealthyNodes({}, {:.0?})", n, failure_window),
         }
     }
 }
@@ -131,9 +196,11 @@ impl Display for SelectionMode {
 mod test {
     use super::*;
     use crate::{
-        connection_manager::PeerConnectionRequest,
-        peer_manager::node_id::NodeDistance,
+        connection_manager::{ConnectionDirection, PeerConnectionRequest},
+        multiaddr::Multiaddr,
+        peer_manager::{node_id::NodeDistance, PeerFeatures},
         test_utils::{mocks::create_dummy_peer_connection, node_id, node_identity::build_node_identity},
+        utils::atomic_ref_counter::AtomicRefCounter,
     };
     use std::iter::repeat_with;
     use tokio::sync::mpsc;
@@ -163,6 +230,99 @@ mod test {
         assert!(conns.iter().all(|c| c.peer_node_id() != &first_node));
     }
 
+    #[test]
+    fn select_random_excluding_peers() {
+        let (pool, _receivers) = create_pool_with_connections(10);
+        let excluded = select_random_nodes(&pool, 3, &[])
+            .into_iter()
+            .map(|c| c.peer_node_id().clone())
+            .collect::<Vec<_>>();
+        let selection = ConnectivitySelection::random_nodes(7, vec![]).exclude_peers(excluded.clone());
+        for _ in 0..10 {
+            let conns = selection.select(&pool, &HashMap::new());
+            assert_eq!(conns.len(), 7);
+            assert!(conns.iter().all(|c| !excluded.contains(c.peer_node_id())));
+        }
+    }
+
+    #[test]
+    fn select_all_nodes_excludes_clients() {
+        let (mut pool, mut receivers) = create_pool_with_connections(3);
+        let node_ids = pool
+            .filter_connection_states(|_| true)
+            .into_iter()
+            .map(|conn| conn.peer_node_id().clone())
+            .collect::<Vec<_>>();
+        for id in 100..102 {
+            let (tx, rx) = mpsc::channel(1);
+            receivers.push(rx);
+            pool.insert_connection(PeerConnection::new(
+                id,
+                tx,
+                node_id::random(),
+                PeerFeatures::COMMUNICATION_CLIENT,
+                Multiaddr::empty(),
+                ConnectionDirection::Inbound,
+                AtomicRefCounter::new(),
+            ));
+        }
+        assert_eq!(pool.count_connected(), 5);
+
+        let selection = ConnectivitySelection::all_nodes(vec![]);
+        let conns = selection.select(&pool, &HashMap::new());
+        assert_eq!(conns.len(), 3);
+        assert!(conns.iter().all(|c| c.peer_features().is_node()));
+        assert!(conns.iter().all(|c| node_ids.contains(c.peer_node_id())));
+
+        let selection = ConnectivitySelection::all_nodes(vec![node_ids[0].clone()]);
+        let conns = selection.select(&pool, &HashMap::new());
+        assert_eq!(conns.len(), 2);
+        assert!(conns.iter().all(|c| c.peer_node_id() != &node_ids[0]));
+    }
+
+    #[test]
+    fn select_outbound_only() {
+        let mut pool = ConnectionPool::new();
+        let mut receivers = Vec::new();
+        let mut outbound_node_ids = Vec::new();
+        for (id, direction) in (0..6).zip(
+            [ConnectionDirection::Inbound, ConnectionDirection::Outbound]
+                .iter()
+                .cycle(),
+        ) {
+            let (tx, rx) = mpsc::channel(1);
+            receivers.push(rx);
+            let node_id = node_id::random();
+            if *direction == ConnectionDirection::Outbound {
+                outbound_node_ids.push(node_id.clone());
+            }
+            pool.insert_connection(PeerConnection::new(
+                id,
+                tx,
+                node_id,
+                PeerFeatures::COMMUNICATION_NODE,
+                Multiaddr::empty(),
+                *direction,
+                AtomicRefCounter::new(),
+            ));
+        }
+        assert_eq!(pool.count_connected(), 6);
+
+        let conns = ConnectivitySelection::all_nodes(vec![])
+            .outbound_only()
+            .select(&pool, &HashMap::new());
+        assert_eq!(conns.len(), 3);
+        assert!(conns.iter().all(|c| c.direction() == ConnectionDirection::Outbound));
+        assert!(conns.iter().all(|c| outbound_node_ids.contains(c.peer_node_id())));
+
+        let conns = ConnectivitySelection::random_nodes(6, vec![outbound_node_ids[0].clone()])
+            .outbound_only()
+            .select(&pool, &HashMap::new());
+        assert_eq!(conns.len(), 2);
+        assert!(conns.iter().all(|c| c.direction() == ConnectionDirection::Outbound));
+        assert!(conns.iter().all(|c| c.peer_node_id() != &outbound_node_ids[0]));
+    }
+
     #[test]
     fn select_closest_ordering() {
         let (pool, _receivers) = create_pool_with_connections(10);
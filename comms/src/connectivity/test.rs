@@ -20,14 +20,16 @@
 //  WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //  USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 use super::{
-    config::ConnectivityConfig,
+    config::{AddressPattern, ConnectivityConfig, InboundConnectionRateLimit, MinConnectivity},
     connection_pool::ConnectionStatus,
-    manager::ConnectivityManager,
-    requester::{ConnectivityEvent, ConnectivityRequester},
+    connection_stats::TieBreakStats,
+    error::ConnectivityError,
+    manager::{ConnectivityManager, ConnectivityStatus},
+    requester::{ConnectivityEvent, ConnectivityRequester, ShutdownReport},
     selection::ConnectivitySelection,
 };
 use crate::{
-    connection_manager::{ConnectionManagerError, ConnectionManagerEvent},
+    connection_manager::{ConnectionManagerError, ConnectionManagerEvent, DialFailure},
     connectivity::ConnectivityEventRx,
     peer_manager::{Peer, PeerFeatures},
     runtime,
@@ -41,10 +43,15 @@ use crate::{
     PeerManager,
 };
 use futures::{future, StreamExt};
+use multiaddr::Multiaddr;
+use rand::rngs::OsRng;
 use std::{sync::Arc, time::Duration};
 use tari_shutdown::Shutdown;
-use tari_test_utils::{collect_try_recv, streams, unpack_enum};
-use tokio::sync::{broadcast, mpsc};
+use tari_test_utils::{async_assert_eventually, collect_try_recv, streams, unpack_enum};
+use tokio::{
+    sync::{broadcast, mpsc},
+    time,
+};
 
 #[allow(clippy::type_complexity)]
 fn setup_connectivity_manager(
@@ -56,6 +63,30 @@ fn setup_connectivity_manager(
     Arc<PeerManager>,
     ConnectionManagerMockState,
     Shutdown,
+) {
+    let (requester, event_rx, node_identity, peer_manager, cm_mock_state, shutdown, _) =
+        spawn_connectivity_manager(config);
+    (
+        requester,
+        event_rx,
+        node_identity,
+        peer_manager,
+        cm_mock_state,
+        shutdown,
+    )
+}
+
+#[allow(clippy::type_complexity)]
+fn spawn_connectivity_manager(
+    config: ConnectivityConfig,
+) -> (
+    ConnectivityRequester,
+    ConnectivityEventRx,
+    Arc<NodeIdentity>,
+    Arc<PeerManager>,
+    ConnectionManagerMockState,
+    Shutdown,
+    task::JoinHandle<ShutdownReport>,
 ) {
     let peer_manager = build_peer_manager();
     let node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
@@ -67,7 +98,7 @@ fn setup_connectivity_manager(
     let (request_tx, request_rx) = mpsc::channel(1);
     let (event_tx, event_rx) = broadcast::channel(10);
     let requester = ConnectivityRequester::new(request_tx, event_tx.clone());
-    ConnectivityManager {
+    let handle = ConnectivityManager {
         config,
         event_tx,
         request_rx,
@@ -85,6 +116,7 @@ fn setup_connectivity_manager(
         peer_manager,
         cm_mock_state,
         shutdown,
+        handle,
     )
 }
 
@@ -139,7 +171,7 @@ async fn connecting_peers() {
 async fn online_then_offline() {
     let (mut connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
         setup_connectivity_manager(ConnectivityConfig {
-            min_connectivity: 2,
+            min_connectivity: MinConnectivity::Absolute(2),
             ..Default::default()
         });
     let peers = add_test_peers(&peer_manager, 8).await;
@@ -233,11 +265,214 @@ async fn online_then_offline() {
     assert!(is_offline);
 }
 
+#[runtime::test]
+async fn online_at_fraction_of_managed_peers() {
+    let (mut connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            min_connectivity: MinConnectivity::Fraction(0.6),
+            ..Default::default()
+        });
+    let peers = add_test_peers(&peer_manager, 10).await;
+    for peer in &peers {
+        cm_mock_state.set_pending_connection(peer.node_id.clone()).await;
+    }
+    // The 10 dialed peers are managed
+    connectivity
+        .request_many_dials(peers.iter().map(|peer| peer.node_id.clone()))
+        .await
+        .unwrap();
+    let connections = future::join_all(
+        peers
+            .iter()
+            .map(|peer| create_peer_connection_mock_pair(node_identity.to_peer(), peer.clone())),
+    )
+    .await
+    .into_iter()
+    .map(|(conn, _, _, _)| conn)
+    .collect::<Vec<_>>();
+
+    for conn in connections.iter().take(5) {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    }
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Degraded(5),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(connections[5].clone()));
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Online(6),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+}
+
+#[runtime::test]
+async fn disconnected_and_inbound_peers_are_not_managed() {
+    let (mut connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            min_connectivity: MinConnectivity::Fraction(0.6),
+            ..Default::default()
+        });
+    let peers = add_test_peers(&peer_manager, 12).await;
+    let (dialed, inbound) = peers.split_at(2);
+    for peer in dialed {
+        cm_mock_state.set_pending_connection(peer.node_id.clone()).await;
+    }
+    connectivity
+        .request_many_dials(dialed.iter().map(|peer| peer.node_id.clone()))
+        .await
+        .unwrap();
+    for peer in dialed {
+        let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peer.clone()).await;
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn));
+    }
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Online(2),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    // Nodes that connect to us and then disconnect do not raise the threshold
+    for peer in inbound {
+        let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peer.clone()).await;
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn));
+    }
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Online(12),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    for peer in inbound {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerDisconnected(peer.node_id.clone()));
+    }
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Online(2),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    // A managed peer that disconnects is no longer managed, so the remaining peer is enough to be online
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerDisconnected(dialed[0].node_id.clone()));
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Online(1),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+}
+
+#[runtime::test]
+async fn connection_slots_are_reserved_for_managed_peers() {
+    let (connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            max_connections: Some(3),
+            reserved_managed_slots: 1,
+            ..Default::default()
+        });
+    let peers = add_test_peers(&peer_manager, 5).await;
+    let (unmanaged, managed) = peers.split_at(3);
+    for peer in managed {
+        cm_mock_state.set_pending_connection(peer.node_id.clone()).await;
+    }
+    connectivity
+        .request_many_dials(managed.iter().map(|peer| peer.node_id.clone()))
+        .await
+        .unwrap();
+
+    // Inbound connections fill the unreserved slots
+    let mut unmanaged_conns = Vec::new();
+    for peer in unmanaged {
+        let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peer.clone()).await;
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+        unmanaged_conns.push(conn);
+    }
+    async_assert_eventually!(
+        unmanaged_conns[2].is_connected(),
+        expect = false,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    assert!(unmanaged_conns[0].is_connected());
+    assert!(unmanaged_conns[1].is_connected());
+
+    // A managed peer can still connect using the reserved slot
+    let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), managed[0].clone()).await;
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    async_assert_eventually!(
+        connectivity.get_active_connections().await.unwrap().len(),
+        expect = 3,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    assert!(conn.is_connected());
+
+    // Once all slots are used, a managed peer makes room by closing a connection to an unmanaged peer
+    let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), managed[1].clone()).await;
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    async_assert_eventually!(
+        unmanaged_conns[..2].iter().filter(|conn| conn.is_connected()).count(),
+        expect = 1,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    assert!(conn.is_connected());
+    let active_conn = connectivity.get_connection(managed[1].node_id.clone()).await.unwrap();
+    assert!(active_conn.is_some());
+}
+
+#[runtime::test]
+async fn status_transitions_are_recorded_in_history() {
+    let (mut connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            min_connectivity: MinConnectivity::Absolute(2),
+            status_history_size: 3,
+            ..Default::default()
+        });
+    let peers = add_test_peers(&peer_manager, 2).await;
+    let mut connections = Vec::new();
+    for peer in peers {
+        let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peer).await;
+        connections.push(conn);
+    }
+    let mut events = collect_try_recv!(event_stream, take = 1, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::ConnectivityStateInitialized = events.remove(0));
+
+    // Initializing -> Degraded(1) -> Online(2)
+    for conn in &connections {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    }
+    let events = collect_try_recv!(event_stream, take = 4, timeout = Duration::from_secs(10));
+    assert!(matches!(events[3], ConnectivityEvent::ConnectivityStateOnline(2)));
+
+    // Online(2) -> Degraded(1) -> Offline
+    for conn in &connections {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerDisconnected(conn.peer_node_id().clone()));
+    }
+    let events = collect_try_recv!(event_stream, take = 4, timeout = Duration::from_secs(10));
+    assert!(matches!(events[3], ConnectivityEvent::ConnectivityStateOffline));
+
+    // Only the 3 most recent transitions are kept
+    let history = connectivity.get_status_history().await.unwrap();
+    assert_eq!(history.iter().map(|t| (t.from, t.to)).collect::<Vec<_>>(), vec![
+        (ConnectivityStatus::Degraded(1), ConnectivityStatus::Online(2)),
+        (ConnectivityStatus::Online(2), ConnectivityStatus::Degraded(1)),
+        (ConnectivityStatus::Degraded(1), ConnectivityStatus::Offline),
+    ]);
+    assert!(history.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+}
+
 #[runtime::test]
 async fn ban_peer() {
     let (mut connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
         setup_connectivity_manager(ConnectivityConfig {
-            min_connectivity: 1,
+            min_connectivity: MinConnectivity::Absolute(1),
             ..Default::default()
         });
     let peer = add_test_peers(&peer_manager, 1).await.pop().unwrap();
@@ -276,10 +511,46 @@ async fn ban_peer() {
     assert!(conn.is_none());
 }
 
+#[runtime::test]
+async fn get_banned_peers() {
+    let (mut connectivity, _event_stream, _node_identity, peer_manager, _cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let peers = add_test_peers(&peer_manager, 3).await;
+
+    assert!(connectivity.get_banned_peers().await.unwrap().is_empty());
+
+    connectivity
+        .ban_peer_until(peers[0].node_id.clone(), Duration::from_secs(3600), "spam".to_string())
+        .await
+        .unwrap();
+    connectivity
+        .ban_peer_until(
+            peers[1].node_id.clone(),
+            Duration::from_secs(60),
+            "bad block".to_string(),
+        )
+        .await
+        .unwrap();
+
+    let mut banned = connectivity.get_banned_peers().await.unwrap();
+    banned.sort_by_key(|b| b.remaining);
+    assert_eq!(banned.len(), 2);
+
+    assert_eq!(banned[0].node_id, peers[1].node_id);
+    assert_eq!(banned[0].reason, "bad block");
+    assert!(banned[0].remaining <= Duration::from_secs(60));
+    assert!(banned[0].remaining > Duration::from_secs(50));
+
+    assert_eq!(banned[1].node_id, peers[0].node_id);
+    assert_eq!(banned[1].reason, "spam");
+    assert!(banned[1].remaining <= Duration::from_secs(3600));
+    assert!(banned[1].remaining > Duration::from_secs(3590));
+}
+
 #[runtime::test]
 async fn peer_selection() {
     let config = ConnectivityConfig {
-        min_connectivity: 1,
+        min_connectivity: MinConnectivity::Absolute(1),
         ..Default::default()
     };
     let (mut connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
@@ -336,10 +607,65 @@ async fn peer_selection() {
     }
 }
 
+#[runtime::test]
+async fn reaping_disabled_keeps_idle_connections() {
+    let config = ConnectivityConfig {
+        connection_pool_refresh_interval: Duration::from_millis(50),
+        reaper_min_inactive_age: Duration::from_millis(0),
+        is_connection_reaping_enabled: false,
+        ..Default::default()
+    };
+    let (mut connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(config);
+    let peers = add_test_peers(&peer_manager, 3).await;
+
+    let connections = future::join_all(
+        peers
+            .iter()
+            .take(2)
+            .cloned()
+            .map(|peer| create_peer_connection_mock_pair(node_identity.to_peer(), peer)),
+    )
+    .await
+    .into_iter()
+    .map(|(conn, _, _, _)| conn)
+    .collect::<Vec<_>>();
+
+    for conn in &connections {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    }
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnectFailed(
+        peers[2].node_id.clone(),
+        ConnectionManagerError::InvalidStaticPublicKey,
+    ));
+    streams::assert_in_broadcast(
+        &mut event_stream,
+        |item| match item {
+            ConnectivityEvent::PeerConnectFailed(node_id) if node_id == peers[2].node_id => Some(()),
+            _ => None,
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+    // Drop our handles so that the connections are inactive
+    drop(connections);
+
+    // Allow several pool refreshes
+    time::sleep(Duration::from_millis(300)).await;
+
+    let states = connectivity.get_all_connection_states().await.unwrap();
+    assert_eq!(states.len(), 2);
+    for state in states {
+        assert_ne!(state.node_id(), &peers[2].node_id);
+        assert_eq!(state.status(), ConnectionStatus::Connected);
+        assert!(state.connection().unwrap().is_connected());
+    }
+}
+
 #[runtime::test]
 async fn pool_management() {
     let config = ConnectivityConfig {
-        min_connectivity: 1,
+        min_connectivity: MinConnectivity::Absolute(1),
         connection_pool_refresh_interval: Duration::from_secs(10),
         reaper_min_inactive_age: Duration::from_secs(10),
         is_connection_reaping_enabled: true,
@@ -414,3 +740,1255 @@ async fn pool_management() {
     let conns = connectivity.get_active_connections().await.unwrap();
     assert!(conns.is_empty());
 }
+
+#[runtime::test]
+async fn failures_outside_window_do_not_mark_peer_offline() {
+    let (_connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            min_connectivity: MinConnectivity::Absolute(1),
+            max_failures_mark_offline: 2,
+            offline_failure_window: Duration::from_millis(100),
+            ..Default::default()
+        });
+    let mut peers = add_test_peers(&peer_manager, 2).await;
+    let failing_peer = peers.pop().unwrap();
+    let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers.pop().unwrap()).await;
+
+    let mut events = collect_try_recv!(event_stream, take = 1, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::ConnectivityStateInitialized = events.remove(0));
+
+    // Keep the node online so that connection failures are not ignored
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn));
+    let mut events = collect_try_recv!(event_stream, take = 2, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::PeerConnected(_conn) = events.remove(0));
+    unpack_enum!(ConnectivityEvent::ConnectivityStateOnline(_n) = events.remove(0));
+
+    let publish_failure = || {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnectFailed(
+            failing_peer.node_id.clone(),
+            ConnectionManagerError::InvalidStaticPublicKey,
+        ));
+    };
+
+    publish_failure();
+    let event = collect_try_recv!(event_stream, take = 1, timeout = Duration::from_secs(10))
+        .pop()
+        .unwrap();
+    unpack_enum!(ConnectivityEvent::PeerConnectFailed(_node_id) = event);
+
+    // The first failure falls outside of the window by the time the second failure occurs
+    time::sleep(Duration::from_millis(200)).await;
+    publish_failure();
+    let event = collect_try_recv!(event_stream, take = 1, timeout = Duration::from_secs(10))
+        .pop()
+        .unwrap();
+    unpack_enum!(ConnectivityEvent::PeerConnectFailed(_node_id) = event);
+
+    let peer = peer_manager.find_by_node_id(&failing_peer.node_id).await.unwrap();
+    assert!(!peer.is_offline());
+
+    // Two failures within the window marks the peer as offline
+    publish_failure();
+    let mut events = collect_try_recv!(event_stream, take = 2, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::PeerOffline(node_id) = events.remove(0));
+    assert_eq!(node_id, failing_peer.node_id);
+
+    let peer = peer_manager.find_by_node_id(&failing_peer.node_id).await.unwrap();
+    assert!(peer.is_offline());
+}
+
+#[runtime::test]
+async fn degraded_connectivity_raises_offline_threshold() {
+    let (_connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            min_connectivity: MinConnectivity::Absolute(2),
+            max_failures_mark_offline: 2,
+            max_failures_mark_offline_degraded: 4,
+            ..Default::default()
+        });
+    let mut peers = add_test_peers(&peer_manager, 2).await;
+    let failing_peer = peers.pop().unwrap();
+    let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers.pop().unwrap()).await;
+
+    let mut events = collect_try_recv!(event_stream, take = 1, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::ConnectivityStateInitialized = events.remove(0));
+
+    // One of the two required connections is up, so the node is degraded
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn));
+    let mut events = collect_try_recv!(event_stream, take = 2, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::PeerConnected(_conn) = events.remove(0));
+    unpack_enum!(ConnectivityEvent::ConnectivityStateDegraded(_n) = events.remove(0));
+
+    let publish_failure = || {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnectFailed(
+            failing_peer.node_id.clone(),
+            ConnectionManagerError::InvalidStaticPublicKey,
+        ));
+    };
+
+    // The normal threshold is reached, but the peer is not marked offline while degraded
+    for _ in 0..2 {
+        publish_failure();
+        let event = collect_try_recv!(event_stream, take = 1, timeout = Duration::from_secs(10))
+            .pop()
+            .unwrap();
+        unpack_enum!(ConnectivityEvent::PeerConnectFailed(_node_id) = event);
+    }
+    let peer = peer_manager.find_by_node_id(&failing_peer.node_id).await.unwrap();
+    assert!(!peer.is_offline());
+
+    // The degraded threshold is reached
+    publish_failure();
+    let event = collect_try_recv!(event_stream, take = 1, timeout = Duration::from_secs(10))
+        .pop()
+        .unwrap();
+    unpack_enum!(ConnectivityEvent::PeerConnectFailed(_node_id) = event);
+    publish_failure();
+    let mut events = collect_try_recv!(event_stream, take = 2, timeout = Duration::from_secs(10));
+    unpack_enum!(ConnectivityEvent::PeerOffline(node_id) = events.remove(0));
+    assert_eq!(node_id, failing_peer.node_id);
+
+    let peer = peer_manager.find_by_node_id(&failing_peer.node_id).await.unwrap();
+    assert!(peer.is_offline());
+}
+
+#[runtime::test]
+async fn offline_marks_set_during_outage_are_cleared_on_recovery() {
+    let (mut connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            min_connectivity: MinConnectivity::Absolute(2),
+            max_failures_mark_offline: 1,
+            max_failures_mark_offline_degraded: 1,
+            clear_outage_offline_marks: true,
+            ..Default::default()
+        });
+    let peers = add_test_peers(&peer_manager, 4).await;
+    let (conn1, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers[0].clone()).await;
+    let (mut conn2, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers[1].clone()).await;
+    let failed_peer = &peers[2];
+    let banned_peer = &peers[3];
+
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn1));
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn2.clone()));
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Online(2),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    // Our own network starts failing
+    conn2.disconnect().await.unwrap();
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerDisconnected(conn2.peer_node_id().clone()));
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Degraded(1),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    for peer in &[failed_peer, banned_peer] {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnectFailed(
+            peer.node_id.clone(),
+            ConnectionManagerError::InvalidStaticPublicKey,
+        ));
+    }
+    async_assert_eventually!(
+        peer_manager
+            .find_by_node_id(&failed_peer.node_id)
+            .await
+            .unwrap()
+            .is_offline(),
+        expect = true,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    async_assert_eventually!(
+        peer_manager
+            .find_by_node_id(&banned_peer.node_id)
+            .await
+            .unwrap()
+            .is_offline(),
+        expect = true,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    connectivity
+        .ban_peer_until(banned_peer.node_id.clone(), Duration::from_secs(3600), "".to_string())
+        .await
+        .unwrap();
+
+    // Connectivity recovers
+    let (conn2, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers[1].clone()).await;
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn2));
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Online(2),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    async_assert_eventually!(
+        peer_manager
+            .find_by_node_id(&failed_peer.node_id)
+            .await
+            .unwrap()
+            .is_offline(),
+        expect = false,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    let peer = peer_manager.find_by_node_id(&banned_peer.node_id).await.unwrap();
+    assert!(peer.is_banned());
+    assert!(peer.is_offline());
+}
+
+#[runtime::test]
+async fn cancel_dial() {
+    let (connectivity, _event_stream, _node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let peer = add_test_peers(&peer_manager, 1).await.pop().unwrap();
+    cm_mock_state.set_pending_connection(peer.node_id.clone()).await;
+
+    let dial_task = task::spawn({
+        let connectivity = connectivity.clone();
+        let node_id = peer.node_id.clone();
+        async move { connectivity.dial_peer(node_id).await }
+    });
+
+    // Wait for the dial to reach the connection manager
+    async_assert_eventually!(
+        cm_mock_state.call_count(),
+        expect = 1,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    connectivity.cancel_dial(peer.node_id.clone()).await.unwrap();
+
+    let err = dial_task.await.unwrap().unwrap_err();
+    unpack_enum!(ConnectivityError::DialCancelled = err);
+}
+
+#[runtime::test]
+async fn dial_cancelled_by_simultaneous_connection_resolves_to_connection() {
+    let (connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let peer = add_test_peers(&peer_manager, 1).await.pop().unwrap();
+    cm_mock_state.set_pending_connection(peer.node_id.clone()).await;
+
+    let dial_task = task::spawn({
+        let connectivity = connectivity.clone();
+        let node_id = peer.node_id.clone();
+        async move { connectivity.dial_peer(node_id).await }
+    });
+    async_assert_eventually!(
+        cm_mock_state.call_count(),
+        expect = 1,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    // The peer connects to us, which cancels the pending dial
+    let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peer.clone()).await;
+    assert!(conn.direction().is_inbound());
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+
+    let dialed_conn = dial_task.await.unwrap().unwrap();
+    assert_eq!(dialed_conn.peer_node_id(), &peer.node_id);
+    assert_eq!(dialed_conn.id(), conn.id());
+}
+
+#[runtime::test]
+async fn status_override() {
+    let (mut connectivity, mut event_stream, _node_identity, _peer_manager, _cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let status = connectivity.get_connectivity_status().await.unwrap();
+    assert!(status.is_initializing());
+
+    connectivity
+        .set_status_override(Some(ConnectivityStatus::Online(5)))
+        .await
+        .unwrap();
+    let status = connectivity.get_connectivity_status().await.unwrap();
+    assert_eq!(status, ConnectivityStatus::Online(5));
+    streams::assert_in_broadcast(
+        &mut event_stream,
+        |item| match item {
+            ConnectivityEvent::ConnectivityStateOnline(5) => Some(()),
+            _ => None,
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+
+    connectivity.set_status_override(None).await.unwrap();
+    let status = connectivity.get_connectivity_status().await.unwrap();
+    assert!(status.is_initializing());
+}
+
+#[runtime::test]
+async fn address_resolved() {
+    let (_connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let peer = add_test_peers(&peer_manager, 1).await.pop().unwrap();
+    let new_address = "/ip4/127.0.0.1/tcp/18189".parse::<Multiaddr>().unwrap();
+    assert_ne!(peer.addresses.first().unwrap().address, new_address);
+
+    // The peer is dialed on an address that is different to the address in the peer manager
+    let mut dialed_peer = peer.clone();
+    dialed_peer.addresses = new_address.clone().into();
+    let (_, _, conn, _) = create_peer_connection_mock_pair(dialed_peer, node_identity.to_peer()).await;
+    assert_eq!(*conn.address(), new_address);
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn));
+
+    let (node_id, address) = streams::assert_in_broadcast(
+        &mut event_stream,
+        |item| match item {
+            ConnectivityEvent::AddressResolved(node_id, address) => Some((node_id, address)),
+            _ => None,
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+    assert_eq!(node_id, peer.node_id);
+    assert_eq!(address, new_address);
+
+    let peer = peer_manager.find_by_node_id(&peer.node_id).await.unwrap();
+    assert_eq!(peer.addresses.first().unwrap().address, new_address);
+}
+
+#[runtime::test]
+async fn address_resolved_when_dialed_on_non_primary_address() {
+    let (connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let mut peer = add_test_peers(&peer_manager, 1).await.pop().unwrap();
+    let other_address = "/ip4/127.0.0.1/tcp/18189".parse::<Multiaddr>().unwrap();
+    let primary_address = peer.addresses.first().unwrap().address.clone();
+    peer.addresses.add_net_address(&other_address);
+    peer.addresses.mark_successful_connection_attempt(&primary_address);
+    peer_manager.add_peer(peer.clone()).await.unwrap();
+    assert_ne!(peer.addresses.first().unwrap().address, other_address);
+
+    cm_mock_state.set_pending_connection(peer.node_id.clone()).await;
+    task::spawn({
+        let mut connectivity = connectivity.clone();
+        let node_id = peer.node_id.clone();
+        async move {
+            let _ = connectivity.dial_peer(node_id).await;
+        }
+    });
+    async_assert_eventually!(
+        cm_mock_state.call_count(),
+        expect = 1,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    // The dialer marks the address that it connected on as the primary address before reporting the connection
+    let mut updated_peer = peer.clone();
+    updated_peer
+        .addresses
+        .mark_successful_connection_attempt(&other_address);
+    assert_eq!(updated_peer.addresses.first().unwrap().address, other_address);
+    peer_manager.add_peer(updated_peer).await.unwrap();
+    let mut dialed_peer = peer.clone();
+    dialed_peer.addresses = other_address.clone().into();
+    let (_, _, conn, _) = create_peer_connection_mock_pair(dialed_peer, node_identity.to_peer()).await;
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn));
+
+    let (node_id, address) = streams::assert_in_broadcast(
+        &mut event_stream,
+        |item| match item {
+            ConnectivityEvent::AddressResolved(node_id, address) => Some((node_id, address)),
+            _ => None,
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+    assert_eq!(node_id, peer.node_id);
+    assert_eq!(address, other_address);
+}
+
+#[runtime::test]
+async fn select_healthy_nodes() {
+    let (mut connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let peers = add_test_peers(&peer_manager, 3).await;
+
+    let connections = future::join_all(
+        peers
+            .iter()
+            .cloned()
+            .map(|peer| create_peer_connection_mock_pair(peer, node_identity.to_peer())),
+    )
+    .await
+    .into_iter()
+    .map(|(_, _, conn, _)| conn)
+    .collect::<Vec<_>>();
+
+    for conn in &connections {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    }
+    async_assert_eventually!(
+        connectivity.get_active_connections().await.unwrap().len(),
+        expect = 3,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    let flaky_peer = connections[0].peer_node_id().clone();
+    connectivity.report_peer_failure(flaky_peer.clone()).await.unwrap();
+
+    let conns = connectivity
+        .select_connections(ConnectivitySelection::healthy_nodes(
+            10,
+            Duration::from_secs(60),
+            vec![],
+        ))
+        .await
+        .unwrap();
+    assert_eq!(conns.len(), 2);
+    assert!(conns.iter().all(|c| *c.peer_node_id() != flaky_peer));
+
+    // The flaky peer is still connected and is included in other selections
+    let conns = connectivity
+        .select_connections(ConnectivitySelection::random_nodes(10, vec![]))
+        .await
+        .unwrap();
+    assert_eq!(conns.len(), 3);
+}
+
+#[runtime::test]
+async fn selection_candidates_match_selected_connections() {
+    let (mut connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let peers = add_test_peers(&peer_manager, 5).await;
+
+    let connections = future::join_all(
+        peers
+            .iter()
+            .cloned()
+            .map(|peer| create_peer_connection_mock_pair(peer, node_identity.to_peer())),
+    )
+    .await
+    .into_iter()
+    .map(|(_, _, conn, _)| conn)
+    .collect::<Vec<_>>();
+
+    for conn in &connections {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    }
+    async_assert_eventually!(
+        connectivity.get_active_connections().await.unwrap().len(),
+        expect = 5,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    let selection = ConnectivitySelection::closest_to(connections[0].peer_node_id().clone(), 3, vec![connections[1]
+        .peer_node_id()
+        .clone()]);
+    let call_count = cm_mock_state.call_count();
+    let candidates = connectivity.get_selection_candidates(selection.clone()).await.unwrap();
+    assert_eq!(candidates.len(), 3);
+    assert!(!candidates.contains(connections[1].peer_node_id()));
+    // Previewing the selection does not make any connection manager requests or change the pool
+    assert_eq!(cm_mock_state.call_count(), call_count);
+    assert_eq!(connectivity.get_active_connections().await.unwrap().len(), 5);
+
+    let conns = connectivity.select_connections(selection).await.unwrap();
+    let selected = conns.iter().map(|c| c.peer_node_id().clone()).collect::<Vec<_>>();
+    assert_eq!(candidates, selected);
+}
+
+#[runtime::test]
+async fn peer_stats_rank_flaky_peers_lower() {
+    let (mut connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let peers = add_test_peers(&peer_manager, 2).await;
+
+    let connections = future::join_all(
+        peers
+            .iter()
+            .cloned()
+            .map(|peer| create_peer_connection_mock_pair(peer, node_identity.to_peer())),
+    )
+    .await
+    .into_iter()
+    .map(|(_, _, conn, _)| conn)
+    .collect::<Vec<_>>();
+
+    for conn in &connections {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    }
+    async_assert_eventually!(
+        connectivity.get_active_connections().await.unwrap().len(),
+        expect = 2,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    let stable_peer = connections[0].peer_node_id().clone();
+    let flaky_peer = connections[1].peer_node_id().clone();
+    for _ in 0..3 {
+        connectivity.report_peer_failure(flaky_peer.clone()).await.unwrap();
+    }
+
+    let stats = connectivity.get_peer_stats().await.unwrap();
+    assert_eq!(stats.len(), 2);
+    assert!(stats.iter().all(|s| (0.0..=1.0).contains(&s.quality_score)));
+    assert_eq!(stats[0].node_id, stable_peer);
+    assert_eq!(stats[0].failures, 0);
+    assert_eq!(stats[1].node_id, flaky_peer);
+    assert_eq!(stats[1].failures, 3);
+    assert!(stats[0].quality_score > stats[1].quality_score);
+}
+
+#[runtime::test]
+async fn set_accept_inbound() {
+    let (mut connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let peers = add_test_peers(&peer_manager, 2).await;
+    let (existing_conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers[0].clone()).await;
+    let (new_conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers[1].clone()).await;
+    assert!(existing_conn.direction().is_inbound());
+    assert!(new_conn.direction().is_inbound());
+
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(existing_conn.clone()));
+    async_assert_eventually!(
+        connectivity.get_active_connections().await.unwrap().len(),
+        expect = 1,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    connectivity.set_accept_inbound(false).await.unwrap();
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(new_conn.clone()));
+    async_assert_eventually!(
+        new_conn.is_connected(),
+        expect = false,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    assert!(existing_conn.is_connected());
+    let conns = connectivity.get_active_connections().await.unwrap();
+    assert_eq!(conns.len(), 1);
+    assert_eq!(conns[0].peer_node_id(), existing_conn.peer_node_id());
+
+    // Inbound connections are accepted again
+    connectivity.set_accept_inbound(true).await.unwrap();
+    let (new_conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers[1].clone()).await;
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(new_conn.clone()));
+    async_assert_eventually!(
+        connectivity.get_active_connections().await.unwrap().len(),
+        expect = 2,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    assert!(new_conn.is_connected());
+}
+
+#[runtime::test]
+async fn shutdown_disconnects_all_connections() {
+    let (mut connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let peers = add_test_peers(&peer_manager, 3).await;
+    let connections = future::join_all(
+        peers
+            .iter()
+            .map(|peer| create_peer_connection_mock_pair(node_identity.to_peer(), peer.clone())),
+    )
+    .await
+    .into_iter()
+    .map(|(conn, _, _, _)| conn)
+    .collect::<Vec<_>>();
+    for conn in &connections {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    }
+    async_assert_eventually!(
+        connectivity.get_active_connections().await.unwrap().len(),
+        expect = 3,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    let report = connectivity.shutdown(Duration::from_millis(500)).await.unwrap();
+    assert_eq!(report, ShutdownReport {
+        num_closed_cleanly: 3,
+        num_closed_forcibly: 0,
+        num_failed: 0,
+    });
+    assert!(connections.iter().all(|conn| !conn.is_connected()));
+
+    // No further requests are accepted
+    let err = connectivity.get_connectivity_status().await.unwrap_err();
+    unpack_enum!(ConnectivityError::ActorDisconnected = err);
+}
+
+#[runtime::test]
+async fn shutdown_signal_disconnects_all_connections() {
+    let (_connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, mut shutdown, handle) =
+        spawn_connectivity_manager(Default::default());
+    let peers = add_test_peers(&peer_manager, 3).await;
+    let mut connections = Vec::new();
+    for peer in peers {
+        let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peer).await;
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+        connections.push(conn);
+    }
+    let events = collect_try_recv!(event_stream, take = 5, timeout = Duration::from_secs(10));
+    assert_eq!(
+        events
+            .iter()
+            .filter(|e| matches!(e, ConnectivityEvent::PeerConnected(_)))
+            .count(),
+        3
+    );
+
+    shutdown.trigger();
+    let events = collect_try_recv!(event_stream, take = 3, timeout = Duration::from_secs(10));
+    let mut disconnected = events
+        .into_iter()
+        .map(|e| match e {
+            ConnectivityEvent::PeerDisconnected(node_id) => node_id,
+            e => panic!("Unexpected event {:?}", e),
+        })
+        .collect::<Vec<_>>();
+    disconnected.sort();
+    let mut expected = connections
+        .iter()
+        .map(|conn| conn.peer_node_id().clone())
+        .collect::<Vec<_>>();
+    expected.sort();
+    assert_eq!(disconnected, expected);
+    assert!(connections.iter().all(|conn| !conn.is_connected()));
+
+    let report = handle.await.unwrap();
+    assert_eq!(report, ShutdownReport {
+        num_closed_cleanly: 3,
+        num_closed_forcibly: 0,
+        num_failed: 0,
+    });
+}
+
+#[runtime::test]
+async fn network_changed_checks_connections_and_redials() {
+    let (mut connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let peers = add_test_peers(&peer_manager, 3).await;
+    let mut mock_states = Vec::new();
+    for peer in peers.iter().take(2) {
+        let (conn, mock_state, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peer.clone()).await;
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn));
+        mock_states.push(mock_state);
+    }
+    // The third peer was connected before the network changed
+    let (mut conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers[2].clone()).await;
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Online(3),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    conn.disconnect().await.unwrap();
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerDisconnected(peers[2].node_id.clone()));
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Online(2),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    let _ = cm_mock_state.take_calls().await;
+    let cm_call_count = cm_mock_state.call_count();
+    let call_counts = mock_states.iter().map(|s| s.call_count()).collect::<Vec<_>>();
+
+    connectivity.network_changed().await.unwrap();
+
+    // Each active connection is checked for liveness
+    for (mock_state, call_count) in mock_states.iter().zip(call_counts) {
+        async_assert_eventually!(
+            mock_state.call_count(),
+            expect = call_count + 1,
+            max_attempts = 20,
+            interval = Duration::from_millis(50)
+        );
+    }
+    // The responsive peers are not redialed, the disconnected peer is
+    async_assert_eventually!(
+        cm_mock_state.call_count(),
+        expect = cm_call_count + 1,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    let calls = cm_mock_state.take_calls().await;
+    let dials = calls
+        .iter()
+        .filter(|call| call.starts_with("DialPeer"))
+        .collect::<Vec<_>>();
+    assert_eq!(dials.len(), 1);
+    assert!(dials[0].contains(&peers[2].node_id.to_string()));
+    let status = connectivity.get_connectivity_status().await.unwrap();
+    assert_eq!(status, ConnectivityStatus::Online(2));
+}
+
+#[runtime::test]
+async fn tie_breaks_are_counted() {
+    let (mut connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let peers = add_test_peers(&peer_manager, 1).await;
+    assert_eq!(
+        connectivity.get_tie_break_stats().await.unwrap(),
+        TieBreakStats::default()
+    );
+
+    // The peer connects to us at the same time as we connect to them
+    let (inbound_conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers[0].clone()).await;
+    let (_, _, outbound_conn, _) = create_peer_connection_mock_pair(peers[0].clone(), node_identity.to_peer()).await;
+    assert!(inbound_conn.direction().is_inbound());
+    assert!(outbound_conn.direction().is_outbound());
+
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(inbound_conn.clone()));
+    async_assert_eventually!(
+        connectivity.get_active_connections().await.unwrap().len(),
+        expect = 1,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    assert_eq!(
+        connectivity.get_tie_break_stats().await.unwrap(),
+        TieBreakStats::default()
+    );
+
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(outbound_conn.clone()));
+    async_assert_eventually!(
+        connectivity.get_tie_break_stats().await.unwrap().num_tie_breaks,
+        expect = 1,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    let stats = connectivity.get_tie_break_stats().await.unwrap();
+    // The existing inbound connection is closed if the peer's node id is greater than ours
+    if peers[0].node_id > *node_identity.node_id() {
+        assert_eq!(stats.num_new_kept, 1);
+        assert_eq!(stats.num_existing_kept, 0);
+    } else {
+        assert_eq!(stats.num_new_kept, 0);
+        assert_eq!(stats.num_existing_kept, 1);
+    }
+    assert_eq!(stats.num_stale_replaced, 0);
+}
+
+#[runtime::test]
+async fn tie_break_lost_is_not_a_failure() {
+    let (mut connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            max_failures_mark_offline: 1,
+            ..Default::default()
+        });
+    let peers = add_test_peers(&peer_manager, 1).await;
+    let node_id = peers[0].node_id.clone();
+
+    let (_, _, mut conn, _) = create_peer_connection_mock_pair(peers[0].clone(), node_identity.to_peer()).await;
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    async_assert_eventually!(
+        connectivity.get_active_connections().await.unwrap().len(),
+        expect = 1,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    // The peer closes the connection straight away, as it would if it had kept another connection to us
+    conn.disconnect().await.unwrap();
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerDisconnectedByPeer(node_id.clone()));
+    streams::assert_in_broadcast(
+        &mut event_stream,
+        |item| match item {
+            ConnectivityEvent::TieBreakLost(id) if id == node_id => Some(()),
+            ConnectivityEvent::PeerConnectFailed(_) | ConnectivityEvent::PeerOffline(_) => {
+                panic!("Unexpected connection failure event: {}", item)
+            },
+            _ => None,
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+    streams::assert_in_broadcast(
+        &mut event_stream,
+        |item| match item {
+            ConnectivityEvent::PeerDisconnected(id) if id == node_id => Some(()),
+            _ => None,
+        },
+        Duration::from_secs(10),
+    )
+    .await;
+    assert!(connectivity.get_active_connections().await.unwrap().is_empty());
+    assert_eq!(connectivity.get_tie_break_stats().await.unwrap().num_lost_to_peer, 1);
+    assert!(!peer_manager.find_by_node_id(&node_id).await.unwrap().is_offline());
+
+    // The failure count is unaffected once the peer reconnects
+    let (_, _, conn, _) = create_peer_connection_mock_pair(peers[0].clone(), node_identity.to_peer()).await;
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn));
+    async_assert_eventually!(
+        connectivity.get_active_connections().await.unwrap().len(),
+        expect = 1,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    let stats = connectivity.get_peer_stats().await.unwrap();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].failures, 0);
+}
+
+#[runtime::test]
+async fn concurrent_dials_are_limited() {
+    let (mut connectivity, _event_stream, _node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            max_concurrent_dials: Some(2),
+            ..Default::default()
+        });
+    let peers = add_test_peers(&peer_manager, 5).await;
+    for peer in &peers {
+        cm_mock_state.set_pending_connection(peer.node_id.clone()).await;
+    }
+
+    connectivity
+        .request_many_dials(peers.iter().map(|peer| peer.node_id.clone()))
+        .await
+        .unwrap();
+    async_assert_eventually!(
+        cm_mock_state.call_count(),
+        expect = 2,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    // Ensure that all dial requests have been handled by the actor
+    connectivity.get_connectivity_status().await.unwrap();
+    let calls = cm_mock_state.take_calls().await;
+    let dials = calls
+        .iter()
+        .filter(|call| call.starts_with("DialPeer"))
+        .collect::<Vec<_>>();
+    assert_eq!(dials.len(), 2);
+
+    // Once a dial completes, the next queued dial is sent
+    let dialed_peer = peers
+        .iter()
+        .find(|peer| dials.iter().any(|call| call.contains(&peer.node_id.to_string())))
+        .unwrap();
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnectFailed(
+        dialed_peer.node_id.clone(),
+        ConnectionManagerError::DialFailed(DialFailure::NoAddresses),
+    ));
+    async_assert_eventually!(
+        cm_mock_state.call_count(),
+        expect = 3,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    connectivity.get_connectivity_status().await.unwrap();
+    assert_eq!(cm_mock_state.call_count(), 3);
+    let calls = cm_mock_state.take_calls().await;
+    assert_eq!(calls.len(), 1);
+    assert!(calls[0].starts_with("DialPeer"));
+    assert!(!calls[0].contains(&dialed_peer.node_id.to_string()));
+}
+
+#[runtime::test]
+async fn dial_slot_is_released_when_dial_is_replied_to() {
+    let (connectivity, _event_stream, _node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            max_concurrent_dials: Some(1),
+            ..Default::default()
+        });
+    let peers = add_test_peers(&peer_manager, 1).await;
+    cm_mock_state.set_pending_connection(peers[0].node_id.clone()).await;
+
+    // The mock replies with a failure without publishing an event, like a dial to a peer that is not in the peer
+    // manager
+    let unknown_node_id = build_node_identity(PeerFeatures::COMMUNICATION_NODE).node_id().clone();
+    let err = connectivity.dial_peer(unknown_node_id).await.unwrap_err();
+    unpack_enum!(ConnectivityError::ConnectionFailed(_err) = err);
+
+    connectivity
+        .request_many_dials(vec![peers[0].node_id.clone()])
+        .await
+        .unwrap();
+    async_assert_eventually!(
+        cm_mock_state.call_count(),
+        expect = 2,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    let calls = cm_mock_state.take_calls().await;
+    assert!(calls[1].starts_with("DialPeer"));
+    assert!(calls[1].contains(&peers[0].node_id.to_string()));
+}
+
+#[runtime::test]
+async fn get_connection_by_address() {
+    let (connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(Default::default());
+    let peer = add_test_peers(&peer_manager, 1).await.pop().unwrap();
+    let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peer.clone()).await;
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    async_assert_eventually!(
+        connectivity
+            .get_connection(peer.node_id.clone())
+            .await
+            .unwrap()
+            .is_some(),
+        expect = true,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    let found = connectivity
+        .get_connection_by_address(conn.address().clone())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.peer_node_id(), &peer.node_id);
+
+    let unknown_address = "/ip4/127.0.0.1/tcp/1".parse::<Multiaddr>().unwrap();
+    assert_ne!(*conn.address(), unknown_address);
+    let found = connectivity.get_connection_by_address(unknown_address).await.unwrap();
+    assert!(found.is_none());
+}
+
+#[runtime::test]
+async fn recovered_event_is_published_once_after_offline() {
+    let (mut connectivity, mut event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            min_connectivity: MinConnectivity::Absolute(1),
+            ..Default::default()
+        });
+    let peers = add_test_peers(&peer_manager, 1).await;
+
+    let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers[0].clone()).await;
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Online(1),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerDisconnected(conn.peer_node_id().clone()));
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Offline,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers[0].clone()).await;
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn));
+    async_assert_eventually!(
+        connectivity.get_connectivity_status().await.unwrap(),
+        expect = ConnectivityStatus::Online(1),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    let mut events = Vec::new();
+    while let Ok(event) = event_stream.try_recv() {
+        events.push(event);
+    }
+    // Initializing -> Online is not a recovery, only the Offline -> Online transition is
+    let num_recovered = events
+        .iter()
+        .filter(|event| matches!(event, ConnectivityEvent::ConnectivityStateRecovered))
+        .count();
+    assert_eq!(num_recovered, 1);
+    let pos = events
+        .iter()
+        .position(|event| matches!(event, ConnectivityEvent::ConnectivityStateOffline))
+        .unwrap();
+    assert!(events[pos..]
+        .iter()
+        .any(|event| matches!(event, ConnectivityEvent::ConnectivityStateRecovered)));
+}
+
+#[runtime::test]
+async fn denied_inbound_connection_is_closed() {
+    let node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let peers = build_many_node_identities(2, PeerFeatures::COMMUNICATION_NODE);
+    let (denied_conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers[0].to_peer()).await;
+    let (allowed_conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peers[1].to_peer()).await;
+    assert!(denied_conn.direction().is_inbound());
+    assert!(allowed_conn.direction().is_inbound());
+
+    let (connectivity, _event_stream, _node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            address_denylist: vec![AddressPattern::Multiaddr(denied_conn.address().clone())],
+            ..Default::default()
+        });
+    for peer in &peers {
+        peer_manager.add_peer(peer.to_peer()).await.unwrap();
+    }
+
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(denied_conn.clone()));
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(allowed_conn.clone()));
+    async_assert_eventually!(
+        denied_conn.is_connected(),
+        expect = false,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    async_assert_eventually!(
+        connectivity.get_active_connections().await.unwrap().len(),
+        expect = 1,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    let conns = connectivity.get_active_connections().await.unwrap();
+    assert_eq!(conns[0].peer_node_id(), allowed_conn.peer_node_id());
+    assert!(allowed_conn.is_connected());
+}
+
+#[runtime::test]
+async fn denied_outbound_connection_is_closed() {
+    let node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let peer = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let (_, _, denied_conn, _) = create_peer_connection_mock_pair(peer.to_peer(), node_identity.to_peer()).await;
+    assert!(denied_conn.direction().is_outbound());
+
+    let (connectivity, _event_stream, _node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            address_denylist: vec![AddressPattern::Multiaddr(denied_conn.address().clone())],
+            ..Default::default()
+        });
+    peer_manager.add_peer(peer.to_peer()).await.unwrap();
+
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(denied_conn.clone()));
+    async_assert_eventually!(
+        denied_conn.is_connected(),
+        expect = false,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    assert!(connectivity.get_active_connections().await.unwrap().is_empty());
+}
+
+#[runtime::test]
+async fn address_allowlist_restricts_dials() {
+    let (connectivity, _event_stream, _node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            address_allowlist: vec!["10.0.0.0/8".parse().unwrap()],
+            ..Default::default()
+        });
+    let allowed_peer = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/10.0.0.1/tcp/18141".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    )
+    .to_peer();
+    peer_manager.add_peer(allowed_peer.clone()).await.unwrap();
+    // Test peers have memory addresses, which are not in the allowed range
+    let peers = add_test_peers(&peer_manager, 1).await;
+
+    let err = connectivity.dial_peer(peers[0].node_id.clone()).await.unwrap_err();
+    assert!(matches!(
+        err,
+        ConnectivityError::ConnectionFailed(ConnectionManagerError::NoPermittedAddresses)
+    ));
+    assert_eq!(cm_mock_state.call_count(), 0);
+
+    // The mock connection manager fails the dial, but the dial is attempted
+    connectivity.dial_peer(allowed_peer.node_id.clone()).await.unwrap_err();
+    let calls = cm_mock_state.take_calls().await;
+    assert_eq!(calls.len(), 1);
+    assert!(calls[0].starts_with("DialPeer"));
+    assert!(calls[0].contains(&allowed_peer.node_id.to_string()));
+}
+
+#[runtime::test]
+async fn inbound_connection_flood_is_rate_limited() {
+    let node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let flooder = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let honest_peer = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    let mut flood_conns = Vec::new();
+    for _ in 0..4 {
+        let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), flooder.to_peer()).await;
+        assert!(conn.direction().is_inbound());
+        flood_conns.push(conn);
+    }
+    let (honest_conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), honest_peer.to_peer()).await;
+
+    let (_connectivity, _event_stream, _node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            inbound_connection_rate_limit: Some(InboundConnectionRateLimit {
+                // Slow enough that no tokens are restocked during the test
+                connections_per_second: 0.001,
+                burst: 10,
+                peer_connections_per_second: 0.001,
+                peer_burst: 2,
+                ban_duration: Some(Duration::from_secs(60)),
+            }),
+            ..Default::default()
+        });
+    peer_manager.add_peer(flooder.to_peer()).await.unwrap();
+    peer_manager.add_peer(honest_peer.to_peer()).await.unwrap();
+
+    // The flooder uses up its burst, which does not affect other peers
+    for conn in &flood_conns[..2] {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    }
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(honest_conn.clone()));
+    // Peers with a pool entry are limited too
+    for conn in &flood_conns[2..] {
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+    }
+
+    for conn in &flood_conns[2..] {
+        async_assert_eventually!(
+            conn.is_connected(),
+            expect = false,
+            max_attempts = 20,
+            interval = Duration::from_millis(50)
+        );
+    }
+    async_assert_eventually!(
+        peer_manager
+            .find_by_node_id(flooder.node_id())
+            .await
+            .unwrap()
+            .is_banned(),
+        expect = true,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    let peer = peer_manager.find_by_node_id(honest_peer.node_id()).await.unwrap();
+    assert!(!peer.is_banned());
+    assert!(honest_conn.is_connected());
+}
+
+#[runtime::test]
+async fn inbound_connection_flood_from_many_identities_is_rate_limited() {
+    let (mut connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            inbound_connection_rate_limit: Some(InboundConnectionRateLimit {
+                // Slow enough that no tokens are restocked during the test
+                connections_per_second: 0.001,
+                burst: 3,
+                peer_connections_per_second: 0.001,
+                peer_burst: 2,
+                ban_duration: Some(Duration::from_secs(60)),
+            }),
+            ..Default::default()
+        });
+    // Each connection uses a fresh identity, so no peer exceeds its own rate limit
+    let peers = add_test_peers(&peer_manager, 6).await;
+    let mut conns = Vec::new();
+    for peer in &peers {
+        let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peer.clone()).await;
+        assert!(conn.direction().is_inbound());
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn.clone()));
+        conns.push(conn);
+    }
+
+    for conn in &conns[3..] {
+        async_assert_eventually!(
+            conn.is_connected(),
+            expect = false,
+            max_attempts = 20,
+            interval = Duration::from_millis(50)
+        );
+    }
+    assert_eq!(connectivity.get_active_connections().await.unwrap().len(), 3);
+    for peer in &peers {
+        let peer = peer_manager.find_by_node_id(&peer.node_id).await.unwrap();
+        assert!(!peer.is_banned());
+    }
+}
+
+#[runtime::test]
+async fn managed_peers_are_exempt_from_inbound_rate_limit() {
+    let (connectivity, _event_stream, node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            inbound_connection_rate_limit: Some(InboundConnectionRateLimit {
+                connections_per_second: 0.001,
+                burst: 1,
+                peer_connections_per_second: 0.001,
+                peer_burst: 1,
+                ban_duration: Some(Duration::from_secs(60)),
+            }),
+            ..Default::default()
+        });
+    let peer = add_test_peers(&peer_manager, 1).await.pop().unwrap();
+    // The peer is managed because we dialed it
+    cm_mock_state.set_pending_connection(peer.node_id.clone()).await;
+    connectivity
+        .request_many_dials(vec![peer.node_id.clone()])
+        .await
+        .unwrap();
+
+    for _ in 0..3 {
+        let (conn, _, _, _) = create_peer_connection_mock_pair(node_identity.to_peer(), peer.clone()).await;
+        assert!(conn.direction().is_inbound());
+        cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn));
+    }
+    async_assert_eventually!(
+        connectivity
+            .get_connection(peer.node_id.clone())
+            .await
+            .unwrap()
+            .map(|conn| conn.is_connected()),
+        expect = Some(true),
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    // Give the actor time to handle every connection
+    time::sleep(Duration::from_millis(100)).await;
+    let peer = peer_manager.find_by_node_id(&peer.node_id).await.unwrap();
+    assert!(!peer.is_banned());
+}
+
+#[runtime::test]
+async fn connections_to_the_same_canonical_address_are_deduplicated() {
+    let node_identity = build_node_identity(PeerFeatures::COMMUNICATION_NODE);
+    // The same host and port, e.g. a peer that has rotated its keys
+    let peer1 = NodeIdentity::random(
+        &mut OsRng,
+        "/ip4/10.0.0.1/tcp/18141".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    );
+    let peer2 = NodeIdentity::random(
+        &mut OsRng,
+        "/ip6/::ffff:10.0.0.1/tcp/18141".parse().unwrap(),
+        PeerFeatures::COMMUNICATION_NODE,
+    );
+    let (_, _, conn1, _) = create_peer_connection_mock_pair(peer1.to_peer(), node_identity.to_peer()).await;
+    let (_, _, conn2, _) = create_peer_connection_mock_pair(peer2.to_peer(), node_identity.to_peer()).await;
+    assert!(conn1.direction().is_outbound());
+    assert_ne!(conn1.address(), conn2.address());
+
+    let (connectivity, _event_stream, _node_identity, peer_manager, cm_mock_state, _shutdown) =
+        setup_connectivity_manager(ConnectivityConfig {
+            dedup_connections_by_address: true,
+            connection_tie_break_linger: Duration::from_millis(1),
+            ..Default::default()
+        });
+    peer_manager.add_peer(peer1.to_peer()).await.unwrap();
+    peer_manager.add_peer(peer2.to_peer()).await.unwrap();
+
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn1.clone()));
+    async_assert_eventually!(
+        connectivity.get_active_connections().await.unwrap().len(),
+        expect = 1,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+    cm_mock_state.publish_event(ConnectionManagerEvent::PeerConnected(conn2.clone()));
+    async_assert_eventually!(
+        conn2.is_connected(),
+        expect = false,
+        max_attempts = 20,
+        interval = Duration::from_millis(50)
+    );
+
+    let conns = connectivity.get_active_connections().await.unwrap();
+    assert_eq!(conns.len(), 1);
+    assert_eq!(conns[0].peer_node_id(), conn1.peer_node_id());
+    assert!(conn1.is_connected());
+}
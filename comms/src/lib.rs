@@ -35,7 +35,7 @@ pub mod framing;
 pub mod rate_limit;
 
 mod multiplexing;
-pub use multiplexing::Substream;
+pub use multiplexing::{ByteCounts, Substream};
 
 mod noise;
 mod proto;
@@ -21,18 +21,29 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    connection_manager::ConnectionDirection,
+    connection_manager::{ConnectionDirection, PeerConnectionError},
     runtime,
     stream_id,
     stream_id::StreamId,
     utils::atomic_ref_counter::{AtomicRefCounter, AtomicRefCounterGuard},
 };
 use futures::{task::Context, Stream};
-use std::{future::Future, io, pin::Pin, task::Poll};
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::Poll,
+    time::Duration,
+};
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use tokio::{
     io::{AsyncRead, AsyncWrite, ReadBuf},
     sync::mpsc,
+    time,
 };
 use tokio_util::compat::{Compat, FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
 use tracing::{self, debug, error, event, Level};
@@ -70,9 +81,10 @@ impl Yamux {
         config.set_receive_window(RECEIVE_WINDOW);
 
         let substream_counter = AtomicRefCounter::new();
+        let byte_counters = ByteCounters::default();
         let connection = yamux::Connection::new(socket.compat(), config, mode);
-        let control = Control::new(connection.control(), substream_counter.clone());
-        let incoming = Self::spawn_incoming_stream_worker(connection, substream_counter.clone());
+        let control = Control::new(connection.control(), substream_counter.clone(), byte_counters.clone());
+        let incoming = Self::spawn_incoming_stream_worker(connection, substream_counter.clone(), byte_counters);
 
         Ok(Self {
             control,
@@ -86,6 +98,7 @@ impl Yamux {
     fn spawn_incoming_stream_worker<TSocket>(
         connection: yamux::Connection<TSocket>,
         counter: AtomicRefCounter,
+        byte_counters: ByteCounters,
     ) -> IncomingSubstreams
     where
         TSocket: futures::AsyncRead + futures::AsyncWrite + Unpin + Send + 'static,
@@ -94,7 +107,7 @@ impl Yamux {
         let (incoming_tx, incoming_rx) = mpsc::channel(10);
         let incoming = IncomingWorker::new(connection, incoming_tx, shutdown.to_signal());
         runtime::task::spawn(incoming.run());
-        IncomingSubstreams::new(incoming_rx, counter, shutdown)
+        IncomingSubstreams::new(incoming_rx, counter, byte_counters, shutdown)
     }
 
     /// Get the yamux control struct
@@ -123,17 +136,50 @@ impl Yamux {
     }
 }
 
+/// The number of bytes sent and received over the substreams of a connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ByteCounts {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+/// Counts the bytes sent and received over all substreams of a connection. Clones share the same counters.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ByteCounters {
+    sent: Arc<AtomicU64>,
+    received: Arc<AtomicU64>,
+}
+
+impl ByteCounters {
+    pub fn get(&self) -> ByteCounts {
+        ByteCounts {
+            bytes_sent: self.sent.load(Ordering::Relaxed),
+            bytes_received: self.received.load(Ordering::Relaxed),
+        }
+    }
+
+    fn add_sent(&self, n: usize) {
+        self.sent.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    fn add_received(&self, n: usize) {
+        self.received.fetch_add(n as u64, Ordering::Relaxed);
+    }
+}
+
 #[derive(Clone)]
 pub struct Control {
     inner: yamux::Control,
     substream_counter: AtomicRefCounter,
+    byte_counters: ByteCounters,
 }
 
 impl Control {
-    pub fn new(inner: yamux::Control, substream_counter: AtomicRefCounter) -> Self {
+    pub(crate) fn new(inner: yamux::Control, substream_counter: AtomicRefCounter, byte_counters: ByteCounters) -> Self {
         Self {
             inner,
             substream_counter,
+            byte_counters,
         }
     }
 
@@ -145,6 +191,11 @@ impl Control {
         Ok(Substream {
             stream: stream.compat(),
             counter_guard,
+            byte_counters: self.byte_counters.clone(),
+            write_timeout: None,
+            write_deadline: None,
+            close_signal: None,
+            is_closed: false,
         })
     }
 
@@ -160,11 +211,17 @@ impl Control {
     pub(crate) fn substream_counter(&self) -> AtomicRefCounter {
         self.substream_counter.clone()
     }
+
+    /// Return the number of bytes sent and received over all substreams of this connection
+    pub fn byte_counts(&self) -> ByteCounts {
+        self.byte_counters.get()
+    }
 }
 
 pub struct IncomingSubstreams {
     inner: mpsc::Receiver<yamux::Stream>,
     substream_counter: AtomicRefCounter,
+    byte_counters: ByteCounters,
     shutdown: Shutdown,
 }
 
@@ -172,11 +229,13 @@ impl IncomingSubstreams {
     pub(self) fn new(
         inner: mpsc::Receiver<yamux::Stream>,
         substream_counter: AtomicRefCounter,
+        byte_counters: ByteCounters,
         shutdown: Shutdown,
     ) -> Self {
         Self {
             inner,
             substream_counter,
+            byte_counters,
             shutdown,
         }
     }
@@ -194,6 +253,11 @@ impl Stream for IncomingSubstreams {
             Some(stream) => Poll::Ready(Some(Substream {
                 stream: stream.compat(),
                 counter_guard: self.substream_counter.new_guard(),
+                byte_counters: self.byte_counters.clone(),
+                write_timeout: None,
+                write_deadline: None,
+                close_signal: None,
+                is_closed: false,
             })),
             None => Poll::Ready(None),
         }
@@ -210,6 +274,77 @@ impl Drop for IncomingSubstreams {
 pub struct Substream {
     stream: Compat<yamux::Stream>,
     counter_guard: AtomicRefCounterGuard,
+    byte_counters: ByteCounters,
+    write_timeout: Option<Duration>,
+    write_deadline: Option<Pin<Box<time::Sleep>>>,
+    close_signal: Option<ShutdownSignal>,
+    is_closed: bool,
+}
+
+impl Substream {
+    /// Sets the maximum time that a write or flush may stall, for e.g. because the peer is not reading and the send
+    /// window is exhausted, before it fails with an IO error of kind `TimedOut` carrying
+    /// `PeerConnectionError::WriteTimeout`. A timeout of None (the default) waits indefinitely.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) {
+        self.write_timeout = timeout;
+        self.write_deadline = None;
+    }
+
+    /// Closes the substream when `signal` is triggered. The close is sent to the remote on the next read or write,
+    /// after which reads return EOF and writes fail with an IO error of kind `ConnectionAborted` carrying
+    /// `PeerConnectionError::SubstreamClosed`.
+    pub(crate) fn set_close_signal(&mut self, signal: ShutdownSignal) {
+        self.close_signal = Some(signal);
+    }
+
+    /// Returns true once the close signal has been triggered and the yamux stream has been closed, so that the remote
+    /// sees EOF
+    fn poll_is_closed(&mut self, cx: &mut Context<'_>) -> Poll<bool> {
+        if !self.is_closed {
+            let is_triggered = match self.close_signal.as_mut() {
+                Some(signal) => Pin::new(signal).poll(cx).is_ready(),
+                None => false,
+            };
+            if !is_triggered {
+                return Poll::Ready(false);
+            }
+            if let Err(err) = futures::ready!(Pin::new(&mut self.stream).poll_shutdown(cx)) {
+                debug!(
+                    target: LOG_TARGET,
+                    "Failed to close substream {:?}: {}",
+                    self.stream.get_ref().id(),
+                    err
+                );
+            }
+            self.is_closed = true;
+            self.close_signal = None;
+        }
+        Poll::Ready(true)
+    }
+
+    fn closed_error() -> io::Error {
+        io::Error::new(io::ErrorKind::ConnectionAborted, PeerConnectionError::SubstreamClosed)
+    }
+
+    fn poll_write_deadline<T>(&mut self, cx: &mut Context<'_>, poll: Poll<io::Result<T>>) -> Poll<io::Result<T>> {
+        if poll.is_ready() {
+            self.write_deadline = None;
+            return poll;
+        }
+        let timeout = match self.write_timeout {
+            Some(timeout) => timeout,
+            None => return Poll::Pending,
+        };
+        let deadline = self
+            .write_deadline
+            .get_or_insert_with(|| Box::pin(time::sleep(timeout)));
+        futures::ready!(deadline.as_mut().poll(cx));
+        self.write_deadline = None;
+        Poll::Ready(Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            PeerConnectionError::WriteTimeout,
+        )))
+    }
 }
 
 impl StreamId for Substream {
@@ -220,17 +355,36 @@ impl StreamId for Substream {
 
 impl tokio::io::AsyncRead for Substream {
     fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.stream).poll_read(cx, buf)
+        if futures::ready!(self.poll_is_closed(cx)) {
+            return Poll::Ready(Ok(()));
+        }
+        let filled_before = buf.filled().len();
+        let poll = Pin::new(&mut self.stream).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            self.byte_counters.add_received(buf.filled().len() - filled_before);
+        }
+        poll
     }
 }
 
 impl tokio::io::AsyncWrite for Substream {
     fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.stream).poll_write(cx, buf)
+        if futures::ready!(self.poll_is_closed(cx)) {
+            return Poll::Ready(Err(Self::closed_error()));
+        }
+        let poll = Pin::new(&mut self.stream).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll {
+            self.byte_counters.add_sent(n);
+        }
+        self.poll_write_deadline(cx, poll)
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.stream).poll_flush(cx)
+        if futures::ready!(self.poll_is_closed(cx)) {
+            return Poll::Ready(Err(Self::closed_error()));
+        }
+        let poll = Pin::new(&mut self.stream).poll_flush(cx);
+        self.poll_write_deadline(cx, poll)
     }
 
     fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
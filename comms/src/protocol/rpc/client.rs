@@ -65,7 +65,7 @@ use std::{
 use tari_shutdown::{Shutdown, ShutdownSignal};
 use tokio::{
     io::{AsyncRead, AsyncWrite},
-    sync::{mpsc, oneshot, Mutex},
+    sync::{mpsc, oneshot, Mutex, OwnedSemaphorePermit, Semaphore},
     time,
 };
 use tower::{Service, ServiceExt};
@@ -85,6 +85,19 @@ impl RpcClient {
         framed: CanonicalFraming<TSubstream>,
         protocol_name: ProtocolId,
     ) -> Result<Self, RpcError>
+    where
+        TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId + 'static,
+    {
+        Self::connect_with_session_permit(config, framed, protocol_name, None).await
+    }
+
+    /// Create a new RpcClient that holds the given session permit until the session ends.
+    async fn connect_with_session_permit<TSubstream>(
+        config: RpcClientConfig,
+        framed: CanonicalFraming<TSubstream>,
+        protocol_name: ProtocolId,
+        session_permit: Option<OwnedSemaphorePermit>,
+    ) -> Result<Self, RpcError>
     where
         TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId + 'static,
     {
@@ -99,6 +112,7 @@ impl RpcClient {
             span.follows_from(tracing_id);
 
             RpcClientWorker::new(config, request_rx, framed, ready_tx, protocol_name, shutdown_signal)
+                .with_session_permit(session_permit)
                 .run()
                 .instrument(span)
         });
@@ -175,10 +189,43 @@ impl fmt::Debug for RpcClient {
     }
 }
 
+/// A limit on the number of RPC client sessions that may be live at the same time. The limit is shared by every
+/// `RpcClientBuilder` it is given to, so a node can bound the total number of client sessions it holds across all of
+/// its peer connections by giving the same limit to all of its RPC clients.
+#[derive(Debug, Clone)]
+pub struct RpcSessionLimit {
+    semaphore: Arc<Semaphore>,
+    max_sessions: usize,
+}
+
+impl RpcSessionLimit {
+    pub fn new(max_sessions: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_sessions)),
+            max_sessions,
+        }
+    }
+
+    /// Returns the number of sessions that can still be established
+    pub fn available_sessions(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+
+    fn try_acquire(&self) -> Result<OwnedSemaphorePermit, RpcError> {
+        self.semaphore
+            .clone()
+            .try_acquire_owned()
+            .map_err(|_| RpcError::SessionLimitReached {
+                max_sessions: self.max_sessions,
+            })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RpcClientBuilder<TClient> {
     config: RpcClientConfig,
     protocol_id: Option<ProtocolId>,
+    session_limit: Option<RpcSessionLimit>,
     _client: PhantomData<TClient>,
 }
 
@@ -187,6 +234,7 @@ impl<TClient> Default for RpcClientBuilder<TClient> {
         Self {
             config: Default::default(),
             protocol_id: None,
+            session_limit: None,
             _client: PhantomData,
         }
     }
@@ -228,19 +276,68 @@ where TClient: From<RpcClient> + NamedProtocolService
         self
     }
 
+    /// Set the length of time that the client will wait for the peer to agree on the RPC protocol of the substream
+    /// before returning a timeout error. This is capped at the peer connection's protocol negotiation timeout.
+    /// Default: 10 seconds
+    pub fn with_negotiation_timeout(mut self, timeout: Duration) -> Self {
+        self.config.negotiation_timeout = timeout;
+        self
+    }
+
     /// Set the protocol ID associated with this client. This is used for logging purposes only.
     pub fn with_protocol_id(mut self, protocol_id: ProtocolId) -> Self {
         self.protocol_id = Some(protocol_id);
         self
     }
 
+    /// Share the given limit on the number of live RPC sessions with this client. Establishing a session fails with
+    /// `RpcError::SessionLimitReached` if the limit has been reached.
+    ///
+    /// Default: no limit
+    pub fn with_session_limit(mut self, session_limit: RpcSessionLimit) -> Self {
+        self.session_limit = Some(session_limit);
+        self
+    }
+
+    /// Uses the given session limit if this builder has not been given its own limit
+    pub(crate) fn with_default_session_limit(mut self, session_limit: Option<RpcSessionLimit>) -> Self {
+        if self.session_limit.is_none() {
+            self.session_limit = session_limit;
+        }
+        self
+    }
+
+    pub(crate) fn config(&self) -> &RpcClientConfig {
+        &self.config
+    }
+
+    /// Acquires a permit from the session limit, if one is set. The session holds the permit until it ends.
+    pub(crate) fn acquire_session_permit(&self) -> Result<Option<OwnedSemaphorePermit>, RpcError> {
+        self.session_limit.as_ref().map(|limit| limit.try_acquire()).transpose()
+    }
+
     /// Negotiates and establishes a session to the peer's RPC service
     pub async fn connect<TSubstream>(self, framed: CanonicalFraming<TSubstream>) -> Result<TClient, RpcError>
     where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId + 'static {
-        RpcClient::connect(
+        let session_permit = self.acquire_session_permit()?;
+        self.connect_with_session_permit(framed, session_permit).await
+    }
+
+    /// Negotiates and establishes a session to the peer's RPC service using a session permit that has already been
+    /// acquired with `acquire_session_permit`.
+    pub(crate) async fn connect_with_session_permit<TSubstream>(
+        self,
+        framed: CanonicalFraming<TSubstream>,
+        session_permit: Option<OwnedSemaphorePermit>,
+    ) -> Result<TClient, RpcError>
+    where
+        TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId + 'static,
+    {
+        RpcClient::connect_with_session_permit(
             self.config,
             framed,
             self.protocol_id.as_ref().cloned().unwrap_or_default(),
+            session_permit,
         )
         .await
         .map(Into::into)
@@ -252,6 +349,7 @@ pub struct RpcClientConfig {
     pub deadline: Option<Duration>,
     pub deadline_grace_period: Duration,
     pub handshake_timeout: Duration,
+    pub negotiation_timeout: Duration,
 }
 
 impl RpcClientConfig {
@@ -272,6 +370,7 @@ impl Default for RpcClientConfig {
             deadline: Some(Duration::from_secs(120)),
             deadline_grace_period: Duration::from_secs(60),
             handshake_timeout: Duration::from_secs(90),
+            negotiation_timeout: Duration::from_secs(10),
         }
     }
 }
@@ -362,6 +461,8 @@ struct RpcClientWorker<TSubstream> {
     last_request_latency: Option<Duration>,
     protocol_id: ProtocolId,
     shutdown_signal: ShutdownSignal,
+    // Held for the lifetime of the session to count it against the RpcSessionLimit, if any
+    _session_permit: Option<OwnedSemaphorePermit>,
 }
 
 impl<TSubstream> RpcClientWorker<TSubstream>
@@ -384,9 +485,15 @@ where TSubstream: AsyncRead + AsyncWrite + Unpin + Send + StreamId
             last_request_latency: None,
             protocol_id,
             shutdown_signal,
+            _session_permit: None,
         }
     }
 
+    pub(self) fn with_session_permit(mut self, session_permit: Option<OwnedSemaphorePermit>) -> Self {
+        self._session_permit = session_permit;
+        self
+    }
+
     fn protocol_name(&self) -> Cow<'_, str> {
         String::from_utf8_lossy(&self.protocol_id)
     }
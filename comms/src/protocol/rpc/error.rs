@@ -69,6 +69,8 @@ pub enum RpcError {
     ExceededMaxChunkCount { expected: usize },
     #[error("Request body was too large. Expected <= {expected} but got {got}")]
     MaxRequestSizeExceeded { got: usize, expected: usize },
+    #[error("The maximum number of live RPC sessions ({max_sessions}) has been reached")]
+    SessionLimitReached { max_sessions: usize },
     #[error(transparent)]
     UnknownError(#[from] anyhow::Error),
 }
@@ -64,7 +64,7 @@ mod server;
 pub use server::{mock, NamedProtocolService, RpcServer, RpcServerError, RpcServerHandle};
 
 mod client;
-pub use client::{RpcClient, RpcClientBuilder, RpcClientConfig};
+pub use client::{RpcClient, RpcClientBuilder, RpcClientConfig, RpcSessionLimit};
 
 mod either;
 
@@ -80,6 +80,9 @@ const LOG_TARGET: &str = "comms::rpc";
 
 pub trait NamedProtocolService {
     const PROTOCOL_NAME: &'static [u8];
+    /// Older versions of the protocol that a client of this service is able to speak, newest first. A client will
+    /// fall back to the newest of these that the peer supports if the peer does not support `PROTOCOL_NAME`.
+    const COMPATIBLE_PROTOCOL_NAMES: &'static [&'static [u8]] = &[];
 
     /// Default implementation that returns a pointer to the static protocol name.
     fn as_protocol_name(&self) -> &'static [u8] {
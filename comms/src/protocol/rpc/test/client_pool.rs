@@ -29,7 +29,10 @@ use crate::{
                 mock::create_mocked_rpc_context,
             },
             NamedProtocolService,
+            RpcClientBuilder,
+            RpcError,
             RpcServer,
+            RpcSessionLimit,
         },
         ProtocolEvent,
         ProtocolId,
@@ -170,4 +173,34 @@ mod lazy_pool {
         let err = pool.get_least_used_or_connect().await.unwrap_err();
         unpack_enum!(RpcClientPoolError::PeerConnectionDropped { .. } = err);
     }
+
+    #[runtime::test]
+    async fn it_respects_the_session_limit_across_connections() {
+        let (conn1, mock_state1, _shutdown1) = setup(2).await;
+        let (conn2, mock_state2, _shutdown2) = setup(2).await;
+        let session_limit = RpcSessionLimit::new(2);
+        let builder = RpcClientBuilder::new().with_session_limit(session_limit.clone());
+        let mut pool1 = LazyPool::<GreetingClient>::new(conn1, 2, builder.clone());
+        let mut pool2 = LazyPool::<GreetingClient>::new(conn2, 2, builder);
+
+        let mut client1 = pool1.get_least_used_or_connect().await.unwrap();
+        let _client2 = pool2.get_least_used_or_connect().await.unwrap();
+        assert_eq!(session_limit.available_sessions(), 0);
+
+        // The limit is reached, a substream is not opened for the session
+        let err = pool1.get_least_used_or_connect().await.unwrap_err();
+        unpack_enum!(RpcClientPoolError::FailedToConnect(err) = err);
+        unpack_enum!(RpcError::SessionLimitReached { max_sessions } = err);
+        assert_eq!(max_sessions, 2);
+        assert_eq!(mock_state1.num_open_substreams(), 1);
+        assert_eq!(mock_state2.num_open_substreams(), 1);
+
+        // Closing a session releases its slot
+        client1.close().await;
+        drop(client1);
+        async_assert_eventually!(session_limit.available_sessions(), expect = 1);
+        let _client3 = pool2.get_least_used_or_connect().await.unwrap();
+        assert_eq!(mock_state2.num_open_substreams(), 2);
+        assert_eq!(session_limit.available_sessions(), 0);
+    }
 }
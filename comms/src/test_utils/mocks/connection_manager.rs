@@ -26,6 +26,7 @@ use crate::{
         ConnectionManagerEvent,
         ConnectionManagerRequest,
         ConnectionManagerRequester,
+        DialFailure,
         PeerConnection,
     },
     peer_manager::NodeId,
@@ -38,7 +39,9 @@ use std::{
         Arc,
     },
 };
-use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
+
+type DialReplyTx = oneshot::Sender<Result<PeerConnection, ConnectionManagerError>>;
 
 pub fn create_connection_manager_mock() -> (ConnectionManagerRequester, ConnectionManagerMock) {
     let (tx, rx) = mpsc::channel(10);
@@ -54,6 +57,7 @@ pub struct ConnectionManagerMockState {
     call_count: Arc<AtomicUsize>,
     calls: Arc<Mutex<Vec<String>>>,
     active_conns: Arc<Mutex<HashMap<NodeId, PeerConnection>>>,
+    pending_conns: Arc<Mutex<HashMap<NodeId, Vec<DialReplyTx>>>>,
     event_tx: broadcast::Sender<Arc<ConnectionManagerEvent>>,
 }
 
@@ -64,6 +68,7 @@ impl ConnectionManagerMockState {
             calls: Arc::new(Mutex::new(Vec::new())),
             event_tx,
             active_conns: Arc::new(Mutex::new(HashMap::new())),
+            pending_conns: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -89,6 +94,12 @@ impl ConnectionManagerMockState {
         self.active_conns.lock().await.insert(node_id, conn);
     }
 
+    /// Dials to the given peer will remain pending until the dial is cancelled
+    #[allow(dead_code)]
+    pub async fn set_pending_connection(&self, node_id: NodeId) {
+        self.pending_conns.lock().await.entry(node_id).or_default();
+    }
+
     #[allow(dead_code)]
     pub fn publish_event(&self, event: ConnectionManagerEvent) {
         self.event_tx.send(Arc::new(event)).unwrap();
@@ -135,7 +146,11 @@ impl ConnectionManagerMock {
                 mut reply_tx,
                 tracing_id: _,
             } => {
-                // Send Ok(conn) if we have an active connection, otherwise Err(DialConnectFailedAllAddresses)
+                if let Some(replies) = self.state.pending_conns.lock().await.get_mut(&node_id) {
+                    replies.extend(reply_tx.take());
+                    return;
+                }
+                // Send Ok(conn) if we have an active connection, otherwise Err(DialFailed)
                 let result = self
                     .state
                     .active_conns
@@ -143,10 +158,16 @@ impl ConnectionManagerMock {
                     .await
                     .get(&node_id)
                     .map(Clone::clone)
-                    .ok_or(ConnectionManagerError::DialConnectFailedAllAddresses);
+                    .ok_or(ConnectionManagerError::DialFailed(DialFailure::NoAddresses));
                 let _ = reply_tx.take().map(|tx| tx.send(result));
             },
-            CancelDial(_) => {},
+            CancelDial(node_id) => {
+                if let Some(replies) = self.state.pending_conns.lock().await.remove(&node_id) {
+                    replies.into_iter().for_each(|reply| {
+                        let _ = reply.send(Err(ConnectionManagerError::DialCancelled));
+                    });
+                }
+            },
             NotifyListening(_reply_tx) => {},
         }
     }
@@ -21,7 +21,7 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
 use crate::{
-    connection_manager::{ConnectionManagerError, PeerConnection},
+    connection_manager::{ConnectionManagerError, DialFailure, PeerConnection},
     connectivity::{
         ConnectivityEvent,
         ConnectivityEventTx,
@@ -224,7 +224,7 @@ impl ConnectivityManagerMock {
                     return;
                 }
                 let reply_tx = reply_tx.unwrap();
-                // Send Ok(conn) if we have an active connection, otherwise Err(DialConnectFailedAllAddresses)
+                // Send Ok(conn) if we have an active connection, otherwise Err(DialFailed)
                 self.state
                     .with_state(|state| match state.pending_conns.get_mut(&node_id) {
                         Some(replies) => {
@@ -236,13 +236,24 @@ impl ConnectivityManagerMock {
                                     .active_conns
                                     .get(&node_id)
                                     .cloned()
-                                    .ok_or(ConnectionManagerError::DialConnectFailedAllAddresses)
+                                    .ok_or(ConnectionManagerError::DialFailed(DialFailure::NoAddresses))
                                     .map_err(Into::into),
                             );
                         },
                     })
                     .await;
             },
+            CancelDial(node_id) => {
+                self.state
+                    .with_state(|state| {
+                        if let Some(replies) = state.pending_conns.remove(&node_id) {
+                            replies.into_iter().for_each(|reply| {
+                                let _ = reply.send(Err(ConnectionManagerError::DialCancelled));
+                            });
+                        }
+                    })
+                    .await;
+            },
             GetConnectivityStatus(reply) => {
                 self.state
                     .with_state(|state| {
@@ -253,6 +264,16 @@ impl ConnectivityManagerMock {
             SelectConnections(_, reply) => {
                 reply.send(Ok(self.state.get_selected_connections().await)).unwrap();
             },
+            GetSelectionCandidates(_, reply) => {
+                let candidates = self
+                    .state
+                    .get_selected_connections()
+                    .await
+                    .iter()
+                    .map(|conn| conn.peer_node_id().clone())
+                    .collect();
+                reply.send(candidates).unwrap();
+            },
             GetConnection(node_id, reply) => {
                 self.state
                     .with_state(|state| {
@@ -261,7 +282,32 @@ impl ConnectivityManagerMock {
                     .await
             },
             GetAllConnectionStates(_) => unimplemented!(),
+            GetPeerStats(_) => unimplemented!(),
+            GetConnectionByAddress(address, reply) => {
+                self.state
+                    .with_state(|state| {
+                        let conn = state
+                            .active_conns
+                            .values()
+                            .find(|conn| *conn.address() == address)
+                            .cloned();
+                        reply.send(conn).unwrap();
+                    })
+                    .await
+            },
+            GetTieBreakStats(_) => unimplemented!(),
+            GetStatusHistory(_) => unimplemented!(),
+            GetBannedPeers(_) => unimplemented!(),
             BanPeer(_, _, _) => {},
+            ReportPeerFailure(_) => {},
+            SetAcceptInbound(_) => {},
+            NetworkChanged => {},
+            Shutdown { .. } => unimplemented!(),
+            SetStatusOverride(status) => {
+                if let Some(status) = status {
+                    self.state.set_connectivity_status(status).await;
+                }
+            },
             GetActiveConnections(reply) => {
                 self.state
                     .with_state(|state| reply.send(state.active_conns.values().cloned().collect()).unwrap())
@@ -76,6 +76,12 @@ pub async fn create_peer_connection_mock_pair(
     let (tx1, rx1) = mpsc::channel(1);
     let (tx2, rx2) = mpsc::channel(1);
     let (listen_addr, muxer_in, muxer_out) = transport::build_multiplexed_connections().await;
+    // The outbound side "dialed" the peer's primary address
+    let dialed_addr = peer1
+        .addresses
+        .first()
+        .map(|addr| addr.address.clone())
+        .unwrap_or_else(|| listen_addr.clone());
 
     // Start both mocks on current handle
     let mock = PeerConnectionMock::new(rx1, muxer_in);
@@ -102,7 +108,7 @@ pub async fn create_peer_connection_mock_pair(
             tx2,
             peer1.node_id,
             peer1.features,
-            listen_addr,
+            dialed_addr,
             ConnectionDirection::Outbound,
             mock_state_out.substream_counter(),
         ),
@@ -201,6 +207,7 @@ impl PeerConnectionMock {
                 protocol_id,
                 reply_tx,
                 tracing_id: _,
+                negotiation_timeout: _,
             } => match self.state.open_substream().await {
                 Ok(stream) => {
                     let negotiated_substream = NegotiatedSubstream {
@@ -217,6 +224,23 @@ impl PeerConnectionMock {
                 self.receiver.close();
                 reply_tx.send(self.state.disconnect().await).unwrap();
             },
+            GetProtocolsInCommon(reply_tx) => {
+                reply_tx.send(vec![]).unwrap();
+            },
+            GetTheirProtocols(reply_tx) => {
+                reply_tx.send(vec![]).unwrap();
+            },
+            GetNegotiatedProtocolsObserved(reply_tx) => {
+                reply_tx.send(vec![]).unwrap();
+            },
+            CloseSubstreamsForProtocol(_, reply_tx) => {
+                reply_tx.send(()).unwrap();
+            },
+            GetByteCounters(reply_tx) => {
+                reply_tx
+                    .send(self.state.mux_control.lock().await.byte_counts())
+                    .unwrap();
+            },
         }
     }
 }
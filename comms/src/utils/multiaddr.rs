@@ -21,8 +21,9 @@
 // USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 use crate::multiaddr::{Multiaddr, Protocol};
 use std::{
+    borrow::Cow,
     io,
-    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs},
 };
 
 /// Convert a multiaddr to a socket address required for `TcpStream`
@@ -77,6 +78,29 @@ pub fn socketaddr_to_multiaddr(socket_addr: &SocketAddr) -> Multiaddr {
     addr
 }
 
+/// Returns the canonical form of an address, so that different representations of the same host and port compare
+/// equal. IPv4-mapped IPv6 addresses are converted to IPv4, DNS names are lowercased and any `/p2p` component is
+/// removed.
+pub fn canonicalize_multiaddr(addr: &Multiaddr) -> Multiaddr {
+    addr.iter()
+        .filter(|protocol| !matches!(protocol, Protocol::P2p(_)))
+        .map(|protocol| match protocol {
+            Protocol::Ip6(ip) => {
+                let octets = ip.octets();
+                let is_ipv4_mapped = octets[..10].iter().all(|b| *b == 0) && octets[10] == 0xff && octets[11] == 0xff;
+                if is_ipv4_mapped {
+                    Protocol::Ip4(Ipv4Addr::new(octets[12], octets[13], octets[14], octets[15]))
+                } else {
+                    Protocol::Ip6(ip)
+                }
+            },
+            Protocol::Dns4(name) => Protocol::Dns4(Cow::Owned(name.to_lowercase())),
+            Protocol::Dns6(name) => Protocol::Dns6(Cow::Owned(name.to_lowercase())),
+            protocol => protocol,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -117,6 +141,22 @@ mod test {
         expect_fail("/dns4/doesntexist.theresnotldlikethis/tcp/1234")
     }
 
+    #[test]
+    fn canonicalize_multiaddr_equates_representations() {
+        fn canonical(addr: &str) -> String {
+            super::canonicalize_multiaddr(&addr.parse().unwrap()).to_string()
+        }
+
+        assert_eq!(canonical("/ip6/::ffff:10.0.0.1/tcp/18141"), "/ip4/10.0.0.1/tcp/18141");
+        assert_eq!(canonical("/ip6/::1/tcp/18141"), "/ip6/::1/tcp/18141");
+        assert_eq!(canonical("/dns4/Example.COM/tcp/18141"), "/dns4/example.com/tcp/18141");
+        assert_eq!(
+            canonical("/ip4/10.0.0.1/tcp/18141/p2p/QmcgpsyWgH8Y8ajJz1Cu72KnS5uo2Aa2LpzU7kinSupNKC"),
+            "/ip4/10.0.0.1/tcp/18141"
+        );
+        assert_eq!(canonical("/memory/1234"), "/memory/1234");
+    }
+
     #[test]
     fn multiaddr_from_components() {
         let ip: Ipv4Addr = "127.0.0.1".parse().unwrap();